@@ -0,0 +1,172 @@
+use crate::cancel::CancellationToken;
+use crate::grouper::Grouper;
+use crate::dupes::delete_duplicates;
+use crate::file::{delete_empty_directories, get_files_to_move, move_files, MoveReport, Plan};
+use crate::job::MoveJob;
+use crate::model::{enrich_arguments, get_reference_time, validate_arguments, Args, FileDateType, GroupBy};
+use crate::storage::LocalStorage;
+use chrono::{DateTime, Utc};
+use color_eyre::eyre::{bail, Result};
+use std::path::Path;
+use std::sync::Arc;
+
+/// Plans which files of the source folders move where in the destination folder, with the options of a run
+///
+/// ```no_run
+/// use chronomover_core::model::GroupBy;
+/// use chronomover_core::{Mover, Planner};
+///
+/// let planner = Planner::new(["/home/me/Downloads"], "/mnt/archive")?.group_by(GroupBy::Month);
+/// let plan = planner.plan()?;
+/// let report = Mover::new(&planner).execute(&plan)?;
+/// println!("Moved {} file(s)", report.moved_count());
+/// # Ok::<(), color_eyre::Report>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct Planner {
+    args: Args,
+}
+
+impl Planner {
+    /// Planner of the files of `sources` to move into `destination`, with the defaults of the command line. The
+    /// `CHRONOMOVER_*` environment variables are ignored, so the host program alone decides the options.
+    pub fn new<S: AsRef<Path>>(sources: impl IntoIterator<Item = S>, destination: impl AsRef<Path>) -> Result<Self> {
+        let sources = sources.into_iter().map(|source| source.as_ref().to_path_buf()).collect::<Vec<_>>();
+        if sources.is_empty() {
+            bail!("At least one source directory is required");
+        }
+        Ok(Self { args: Args { sources, destination: destination.as_ref().to_path_buf(), ..Args::default() } })
+    }
+
+    /// Planner of a run with every option of `args`, for the options without a method (e.g., `Args { max_depth:
+    /// Some(1), ..Args::default() }` with its sources and destination)
+    pub fn from_args(args: Args) -> Result<Self> {
+        if args.sources.is_empty() {
+            bail!("At least one source directory is required");
+        }
+        Ok(Self { args })
+    }
+
+    /// Planner of a job, with the defaults of the command line for the options the job doesn't define
+    pub fn from_job(job: &MoveJob) -> Result<Self> {
+        let mut args = Args::default();
+        job.apply_to(&mut args);
+        Ok(Self { args })
+    }
+
+    /// Group the files into period folders of this strategy
+    pub fn group_by(mut self, group_by: GroupBy) -> Self {
+        self.args.group_by = Some(group_by);
+        self
+    }
+
+    /// Group the files into the period folders of a custom scheme instead, whatever the grouping strategy of their folder
+    pub fn grouper(mut self, grouper: impl Grouper + 'static) -> Self {
+        self.args.grouper = Some(Arc::new(grouper));
+        self
+    }
+
+    /// Only plan the files older than this date
+    pub fn older_than(mut self, cutoff: DateTime<Utc>) -> Self {
+        self.args.older_than = Some(cutoff);
+        self
+    }
+
+    /// Date the files by the most recent of these timestamps
+    pub fn file_date_types(mut self, file_date_types: Vec<FileDateType>) -> Self {
+        self.args.file_date_types = file_date_types;
+        self
+    }
+
+    /// Stop the run cleanly between files once this token is cancelled, e.g. from another thread
+    pub fn cancellation(mut self, token: CancellationToken) -> Self {
+        self.args.cancellation = token;
+        self
+    }
+
+    /// Only plan the files of the periods before the current one, as `--previous-period-only` does
+    pub fn previous_period_only(mut self, previous_period_only: bool) -> Self {
+        self.args.previous_period_only = previous_period_only;
+        self
+    }
+
+    /// Validate the options, then scan the sources for the files to move. Nothing is moved.
+    pub fn plan(&self) -> Result<Plan> {
+        validate_arguments(&self.args)?;
+        let now = self.args.now.unwrap_or_else(get_reference_time);
+        get_files_to_move(&enrich_arguments(&self.args), &LocalStorage, now, None)
+    }
+}
+
+/// Moves the files of a plan, with the options of the planner that planned it
+#[derive(Debug, Clone)]
+pub struct Mover {
+    args: Args,
+}
+
+impl Mover {
+    pub fn new(planner: &Planner) -> Self {
+        Self { args: enrich_arguments(&planner.args) }
+    }
+
+    /// Only report what would be moved, as `--dry-run` does
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.args.dry_run = dry_run;
+        self
+    }
+
+    /// Move the planned files, recording the run manifest, then delete the source folders left empty (unless
+    /// `--keep-empty-folders` was set). Files that fail to move are listed in the report instead of failing the call,
+    /// and a run that stops early has `MoveReport::aborted` set.
+    pub fn execute(&self, plan: &Plan) -> Result<MoveReport> {
+        let mut move_report = move_files(&self.args, &LocalStorage, &plan.files_to_move, self.args.dry_run)?;
+        delete_duplicates(&self.args, &LocalStorage, &plan.duplicates, self.args.dry_run, &mut move_report);
+        delete_empty_directories(&self.args, &LocalStorage, &move_report.moved)?;
+        Ok(move_report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::job::{MoveJobBuilder, MoveMode};
+
+    #[test]
+    fn test_planner_new_uses_command_line_defaults() {
+        let planner = Planner::new(["/photos", "/notes"], "/archive").unwrap().group_by(GroupBy::Trimester);
+
+        assert_eq!(planner.args.sources, [Path::new("/photos"), Path::new("/notes")]);
+        assert_eq!(planner.args.destination, Path::new("/archive"));
+        assert_eq!(planner.args.group_by, Some(GroupBy::Trimester));
+        assert_eq!(planner.args.file_date_types, [FileDateType::Created, FileDateType::Modified]);
+        assert!(!planner.args.dry_run);
+        assert!(Planner::new(Vec::<&Path>::new(), "/archive").is_err());
+    }
+
+    #[test]
+    fn test_planner_from_args_keeps_every_option() {
+        let dir = std::env::temp_dir().join(format!("chronomover-api-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("source/nested")).unwrap();
+        std::fs::write(dir.join("source/top.txt"), "top").unwrap();
+        std::fs::write(dir.join("source/nested/deep.txt"), "deep").unwrap();
+        let args = Args { sources: vec![dir.join("source")], destination: dir.join("archive"), max_depth: Some(1), jobs: 2, ..Args::default() };
+
+        let planner = Planner::from_args(args).unwrap();
+        let plan = planner.plan().unwrap();
+
+        assert_eq!(planner.args.jobs, 2);
+        assert_eq!(plan.files_to_move.iter().map(|file| file.source.clone()).collect::<Vec<_>>(), [dir.join("source/top.txt")]);
+        assert!(Planner::from_args(Args::default()).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_planner_from_job() {
+        let job = MoveJobBuilder::new().source("/photos").destination("/archive").group_by(GroupBy::Year).mode(MoveMode::DryRun).build().unwrap();
+
+        let planner = Planner::from_job(&job).unwrap();
+
+        assert_eq!(MoveJob::from(&planner.args), job);
+        assert!(planner.args.dry_run);
+    }
+}
@@ -0,0 +1,145 @@
+use crate::file::{get_destination_roots, get_files_to_move, FileToMove};
+use crate::logging::SUMMARY_TARGET;
+use crate::model::{enrich_arguments, get_reference_time, Args, AuditArgs};
+use crate::period_index::{CSV_INDEX_FILE_NAME, MARKDOWN_INDEX_FILE_NAME};
+use crate::report::format_size;
+use crate::storage::{StorageBackend, StorageEntry, WalkOptions};
+use chrono::{DateTime, Utc};
+use color_eyre::eyre::{bail, Result};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+/// How the files a run would move compare with an existing archive
+#[derive(Debug, Default)]
+pub struct ArchiveAudit {
+    /// Planned files whose destination is already taken in the archive
+    pub existing: Vec<FileToMove>,
+    /// Planned files whose destination is free
+    pub new: Vec<FileToMove>,
+    /// Files of the archive no planned file would land on
+    pub orphans: Vec<PathBuf>,
+    /// Files of the sources whose date couldn't be read
+    pub failed: usize,
+}
+
+/// Compare the sources against an existing archive without moving anything: which files would land on a path the
+/// archive already holds, which would be new, and which archived files no source file maps to
+pub fn audit_archive(audit_args: &AuditArgs, storage: &dyn StorageBackend) -> Result<()> {
+    let args = enrich_arguments(&audit_args.args);
+    for source in &args.sources {
+        if !storage.is_dir(source) {
+            bail!("Source directory does not exist: {}", source.display());
+        }
+    }
+    if !storage.is_dir(&args.destination) {
+        bail!("Destination does not exist: {}", args.destination.display());
+    }
+
+    info!(target: SUMMARY_TARGET, "Comparing {} with {}...", args.sources.iter().map(|source| source.display().to_string()).collect::<Vec<_>>().join(", "), args.destination.display());
+    let audit = audit_sources(&args, storage, args.now.unwrap_or_else(get_reference_time))?;
+
+    for (heading, files, status) in [("Already in the archive:", &audit.existing, "existing"), ("New to the archive:", &audit.new, "new")] {
+        if !files.is_empty() {
+            info!(target: SUMMARY_TARGET, "\n{}", heading);
+        }
+        for (index, file) in files.iter().enumerate() {
+            info!(
+                target: SUMMARY_TARGET, path = %file.source.display(), destination = %file.destination.display(), status,
+                "{}. {}\n       ↳ {}", index + 1, file.source.display(), file.destination.display()
+            );
+        }
+    }
+    if !audit.orphans.is_empty() {
+        info!(target: SUMMARY_TARGET, "\nArchived files with no source file:");
+    }
+    for (index, path) in audit.orphans.iter().enumerate() {
+        info!(target: SUMMARY_TARGET, path = %path.display(), status = "orphan", "{}. {}", index + 1, path.display());
+    }
+
+    let size = |files: &[FileToMove]| format_size(files.iter().map(|file| file.size).sum());
+    info!(
+        target: SUMMARY_TARGET,
+        "\n{} source file(s) would land on a path already in the archive ({}), {} are new ({}), and {} archived file(s) have no source file",
+        audit.existing.len(), size(&audit.existing), audit.new.len(), size(&audit.new), audit.orphans.len()
+    );
+    if audit.failed > 0 {
+        info!(target: SUMMARY_TARGET, "Failed to read the date of {} file(s)", audit.failed);
+    }
+    Ok(())
+}
+
+/// Plan the sources as a dry run with the given options, then sort the planned files by whether their destination is
+/// taken and collect the archived files none of them lands on. Paths are compared ignoring case on case-insensitive
+/// destinations, and the period indexes ChronoMover writes into the archive aren't archived files.
+pub fn audit_sources(args: &Args, storage: &dyn StorageBackend, now: DateTime<Utc>) -> Result<ArchiveAudit> {
+    let mut args = args.clone();
+    args.dry_run = true;
+    let plan = get_files_to_move(&args, storage, now, None)?;
+
+    let roots = get_destination_roots(&args).into_iter().filter(|root| storage.is_dir(root)).collect::<Vec<_>>();
+    let fold = |path: &Path| {
+        let root = roots.iter().find(|root| path.starts_with(root));
+        match root {
+            Some(root) if storage.is_case_insensitive(root) => PathBuf::from(path.to_string_lossy().to_lowercase()),
+            _ => path.to_path_buf(),
+        }
+    };
+    let planned = plan.files_to_move.iter().map(|file| fold(&file.destination)).collect::<HashSet<_>>();
+
+    let mut audit = ArchiveAudit { failed: plan.errors.len(), ..ArchiveAudit::default() };
+    for file in plan.files_to_move {
+        if storage.stat(&file.destination).is_ok() {
+            audit.existing.push(file);
+        } else {
+            audit.new.push(file);
+        }
+    }
+    for root in &roots {
+        // A source inside the archive is not part of it
+        let keep = Box::new(|entry: &StorageEntry| !args.sources.iter().any(|source| entry.path.starts_with(source)));
+        for entry in storage.walk(root, WalkOptions { sorted: true, ..WalkOptions::default() }, keep) {
+            match entry {
+                Ok(entry) if entry.is_file => {
+                    let is_period_index = entry.path.file_name().is_some_and(|name| name == CSV_INDEX_FILE_NAME || name == MARKDOWN_INDEX_FILE_NAME);
+                    if !is_period_index && !planned.contains(&fold(&entry.path)) {
+                        audit.orphans.push(entry.path);
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => warn!(error = %e, "Failed to list a file of {}, skipping it: {}", root.display(), e),
+            }
+        }
+    }
+    Ok(audit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::Cli;
+    use crate::storage::MemoryStorage;
+    use clap::Parser;
+
+    #[test]
+    fn test_audit_sources() {
+        let storage = MemoryStorage::with_tree([
+            ("/src/report.pdf", "2025-01-10"),
+            ("/src/docs/notes.txt", "2025-02-03"),
+            ("/src/recent.txt", "2025-06-14"),
+            ("/archive/2025-01/report.pdf", "2025-01-10"),
+            ("/archive/2025-01/manifest.csv", "2025-01-31"),
+            ("/archive/2024-12/old.pdf", "2024-12-01"),
+        ]);
+        let now = "2025-06-15T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let args = Cli::parse_from(["chronomover", "-s", "/src", "-d", "/archive", "-g", "month", "--older-than", "2025-06-01"]).args.unwrap();
+
+        let audit = audit_sources(&enrich_arguments(&args), &storage, now).unwrap();
+
+        let sources = |files: &[FileToMove]| files.iter().map(|file| file.source.clone()).collect::<Vec<_>>();
+        assert_eq!(sources(&audit.existing), [PathBuf::from("/src/report.pdf")]);
+        assert_eq!(sources(&audit.new), [PathBuf::from("/src/docs/notes.txt")]);
+        assert_eq!(audit.orphans, [PathBuf::from("/archive/2024-12/old.pdf")]);
+        assert_eq!(storage.file_paths().len(), 6);
+    }
+}
@@ -0,0 +1,41 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Asks a run to stop cleanly. The scan stops before its next file, and the moves stop between files, so no rename is
+/// interrupted halfway, the run manifest holds every moved file, and the summary reports the partial run. Clones share
+/// the same state, so a token is cancelled from another thread (e.g., a Ctrl-C handler or the UI of an embedder).
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clones_share_cancellation() {
+        let token = CancellationToken::new();
+        let clone = token.clone();
+        assert!(!token.is_cancelled());
+
+        clone.cancel();
+
+        assert!(token.is_cancelled());
+        assert!(!CancellationToken::new().is_cancelled());
+    }
+}
@@ -0,0 +1,311 @@
+use crate::file::FileToMove;
+use crate::model::FileDateType;
+use crate::storage::FileStat;
+#[cfg(feature = "sqlite")]
+use chrono::SecondsFormat;
+use chrono::{DateTime, Utc};
+use color_eyre::eyre::{bail, Context, Result};
+#[cfg(feature = "sqlite")]
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::debug;
+
+/// Metadata of a file processed by a run, as recorded in the `--catalog`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CatalogEntry {
+    /// Run manifest of the move, none for dry runs
+    pub run_id: Option<String>,
+    pub processed_at: DateTime<Utc>,
+    /// "moved", "dry-run" or "failed"
+    pub status: String,
+    pub source: String,
+    pub destination: String,
+    pub size: u64,
+    pub created: Option<DateTime<Utc>>,
+    pub modified: Option<DateTime<Utc>>,
+    pub accessed: Option<DateTime<Utc>>,
+    /// Date the file was grouped by
+    pub date: DateTime<Utc>,
+    /// Timestamp the date was taken from
+    pub date_source: Option<FileDateType>,
+    pub period: Option<String>,
+    /// BLAKE3 hash of the content, for moved files and the sources of dry runs
+    pub hash: Option<String>,
+    pub error: Option<String>,
+}
+
+impl CatalogEntry {
+    /// Entry of a planned file, from the metadata read right after it was handled (before hashing it, which may update
+    /// its access time)
+    pub fn new(file: &FileToMove, stat: Option<&FileStat>, status: &str) -> Self {
+        let to_date = |time: Option<std::time::SystemTime>| time.map(DateTime::<Utc>::from);
+        let created = stat.and_then(|stat| to_date(stat.created));
+        let modified = stat.and_then(|stat| to_date(stat.modified));
+        let accessed = stat.and_then(|stat| to_date(stat.accessed));
+        let date_source = [(FileDateType::Created, created), (FileDateType::Modified, modified), (FileDateType::Accessed, accessed)]
+            .into_iter()
+            .find(|(_, timestamp)| *timestamp == Some(file.file_date))
+            .map(|(date_type, _)| date_type);
+        Self {
+            run_id: None,
+            processed_at: Utc::now(),
+            status: status.to_string(),
+            source: file.source.to_string_lossy().into_owned(),
+            destination: file.destination.to_string_lossy().into_owned(),
+            size: file.size,
+            created,
+            modified,
+            accessed,
+            date: file.file_date,
+            date_source,
+            period: file.period.clone(),
+            hash: None,
+            error: None,
+        }
+    }
+}
+
+/// Format of a catalog, from the extension of its path
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CatalogFormat {
+    Json,
+    #[cfg(feature = "sqlite")]
+    Sqlite,
+}
+
+fn get_catalog_format(path: &Path) -> Result<CatalogFormat> {
+    match path.extension().and_then(|extension| extension.to_str()).map(str::to_ascii_lowercase).as_deref() {
+        Some("json") => Ok(CatalogFormat::Json),
+        #[cfg(feature = "sqlite")]
+        Some("sqlite" | "sqlite3" | "db") => Ok(CatalogFormat::Sqlite),
+        #[cfg(not(feature = "sqlite"))]
+        Some("sqlite" | "sqlite3" | "db") => bail!("SQLite catalogs need the sqlite feature of chronomover-core: {}", path.display()),
+        _ => bail!("Unsupported catalog format: {} (use a .json or .sqlite file)", path.display()),
+    }
+}
+
+/// Parse `--catalog`, only accepting the paths of a supported format
+pub fn parse_catalog_path(value: &str) -> Result<PathBuf, String> {
+    let path = PathBuf::from(value);
+    get_catalog_format(&path).map_err(|e| e.to_string())?;
+    Ok(path)
+}
+
+/// Append entries to the catalog, creating it on the first run. Catalogs accumulate every run, so they can be searched
+/// and audited whatever the archive layout became since.
+pub fn append_to_catalog(path: &Path, entries: &[CatalogEntry]) -> Result<()> {
+    if entries.is_empty() {
+        return Ok(());
+    }
+    match get_catalog_format(path)? {
+        CatalogFormat::Json => append_to_json_catalog(path, entries),
+        #[cfg(feature = "sqlite")]
+        CatalogFormat::Sqlite => append_to_sqlite_catalog(path, entries),
+    }
+    .with_context(|| format!("Failed to update the catalog: {}", path.display()))?;
+    debug!(path = %path.display(), "Added {} file(s) to the catalog {}", entries.len(), path.display());
+    Ok(())
+}
+
+/// Read the entries of a catalog, oldest first
+pub fn load_catalog(path: &Path) -> Result<Vec<CatalogEntry>> {
+    match get_catalog_format(path)? {
+        CatalogFormat::Json => load_json_catalog(path),
+        #[cfg(feature = "sqlite")]
+        CatalogFormat::Sqlite => load_sqlite_catalog(path),
+    }
+    .with_context(|| format!("Failed to read the catalog: {}", path.display()))
+}
+
+/// A JSON catalog is a single array, rewritten through a temporary file so it stays valid if the run is interrupted
+fn append_to_json_catalog(path: &Path, entries: &[CatalogEntry]) -> Result<()> {
+    let mut catalog = if path.exists() { load_json_catalog(path)? } else { Vec::new() };
+    catalog.extend_from_slice(entries);
+    let temporary_path = PathBuf::from(format!("{}.tmp", path.display()));
+    fs::write(&temporary_path, serde_json::to_vec_pretty(&catalog)?)?;
+    fs::rename(&temporary_path, path)?;
+    Ok(())
+}
+
+fn load_json_catalog(path: &Path) -> Result<Vec<CatalogEntry>> {
+    Ok(serde_json::from_slice(&fs::read(path)?)?)
+}
+
+#[cfg(feature = "sqlite")]
+const CREATE_SQLITE_SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS files (
+        id INTEGER PRIMARY KEY,
+        run_id TEXT,
+        processed_at TEXT NOT NULL,
+        status TEXT NOT NULL,
+        source TEXT NOT NULL,
+        destination TEXT NOT NULL,
+        size INTEGER NOT NULL,
+        created TEXT,
+        modified TEXT,
+        accessed TEXT,
+        date TEXT NOT NULL,
+        date_source TEXT,
+        period TEXT,
+        hash TEXT,
+        error TEXT
+    );
+    CREATE INDEX IF NOT EXISTS files_source ON files (source);
+    CREATE INDEX IF NOT EXISTS files_destination ON files (destination);
+    CREATE INDEX IF NOT EXISTS files_hash ON files (hash);
+";
+
+#[cfg(feature = "sqlite")]
+fn append_to_sqlite_catalog(path: &Path, entries: &[CatalogEntry]) -> Result<()> {
+    let mut connection = Connection::open(path)?;
+    connection.execute_batch(CREATE_SQLITE_SCHEMA)?;
+    let transaction = connection.transaction()?;
+    {
+        let mut insert = transaction.prepare(
+            "INSERT INTO files (run_id, processed_at, status, source, destination, size, created, modified, accessed, date, date_source, period, hash, error)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+        )?;
+        for entry in entries {
+            insert.execute(params![
+                entry.run_id,
+                format_date(entry.processed_at),
+                entry.status,
+                entry.source,
+                entry.destination,
+                entry.size,
+                entry.created.map(format_date),
+                entry.modified.map(format_date),
+                entry.accessed.map(format_date),
+                format_date(entry.date),
+                entry.date_source.map(format_date_type),
+                entry.period,
+                entry.hash,
+                entry.error,
+            ])?;
+        }
+    }
+    transaction.commit()?;
+    Ok(())
+}
+
+#[cfg(feature = "sqlite")]
+fn load_sqlite_catalog(path: &Path) -> Result<Vec<CatalogEntry>> {
+    let connection = Connection::open(path)?;
+    let mut select = connection.prepare(
+        "SELECT run_id, processed_at, status, source, destination, size, created, modified, accessed, date, date_source, period, hash, error
+         FROM files ORDER BY id",
+    )?;
+    let rows = select.query_map([], |row| {
+        Ok((
+            (row.get::<_, Option<String>>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?, row.get::<_, String>(3)?),
+            (row.get::<_, String>(4)?, row.get::<_, u64>(5)?, row.get::<_, Option<String>>(6)?, row.get::<_, Option<String>>(7)?),
+            (row.get::<_, Option<String>>(8)?, row.get::<_, String>(9)?, row.get::<_, Option<String>>(10)?, row.get::<_, Option<String>>(11)?),
+            (row.get::<_, Option<String>>(12)?, row.get::<_, Option<String>>(13)?),
+        ))
+    })?;
+    let mut entries = Vec::new();
+    for row in rows {
+        let ((run_id, processed_at, status, source), (destination, size, created, modified), (accessed, date, date_source, period), (hash, error)) = row?;
+        let parse_optional_date = |date: Option<String>| date.as_deref().map(parse_date).transpose();
+        entries.push(CatalogEntry {
+            run_id,
+            processed_at: parse_date(&processed_at)?,
+            status,
+            source,
+            destination,
+            size,
+            created: parse_optional_date(created)?,
+            modified: parse_optional_date(modified)?,
+            accessed: parse_optional_date(accessed)?,
+            date: parse_date(&date)?,
+            date_source: date_source.as_deref().and_then(parse_date_type),
+            period,
+            hash,
+            error,
+        });
+    }
+    Ok(entries)
+}
+
+#[cfg(feature = "sqlite")]
+/// Dates are stored as RFC 3339 text, which SQLite's date functions understand and sorts chronologically
+fn format_date(date: DateTime<Utc>) -> String {
+    date.to_rfc3339_opts(SecondsFormat::Nanos, true)
+}
+
+#[cfg(feature = "sqlite")]
+fn parse_date(date: &str) -> Result<DateTime<Utc>> {
+    Ok(DateTime::parse_from_rfc3339(date)?.with_timezone(&Utc))
+}
+
+#[cfg(feature = "sqlite")]
+fn format_date_type(date_type: FileDateType) -> &'static str {
+    match date_type {
+        FileDateType::Created => "created",
+        FileDateType::Modified => "modified",
+        FileDateType::Accessed => "accessed",
+    }
+}
+
+#[cfg(feature = "sqlite")]
+fn parse_date_type(date_type: &str) -> Option<FileDateType> {
+    [FileDateType::Created, FileDateType::Modified, FileDateType::Accessed].into_iter().find(|candidate| format_date_type(*candidate) == date_type)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::SystemTime;
+
+    fn create_entries() -> Vec<CatalogEntry> {
+        let created = "2025-06-10T08:30:00Z".parse::<DateTime<Utc>>().unwrap();
+        let file = FileToMove {
+            source: PathBuf::from("/src/report.pdf"),
+            destination: PathBuf::from("/archive/2025-06/report.pdf"),
+            file_date: created,
+            size: 2048,
+            period: Some("2025-06".to_string()),
+        };
+        let stat = FileStat {
+            size: 2048,
+            created: Some(SystemTime::from(created)),
+            modified: Some(SystemTime::from(created - chrono::TimeDelta::days(1))),
+            accessed: None,
+            is_placeholder: false,
+        };
+        let mut moved = CatalogEntry::new(&file, Some(&stat), "moved");
+        moved.run_id = Some("20250701-120000".to_string());
+        moved.hash = Some("abc".to_string());
+        let mut failed = CatalogEntry::new(&file, None, "failed");
+        failed.error = Some("Permission denied".to_string());
+        vec![moved, failed]
+    }
+
+    #[test]
+    fn test_catalog_entry_records_date_source() {
+        let entries = create_entries();
+
+        assert_eq!(entries[0].date_source, Some(FileDateType::Created));
+        assert_eq!(entries[0].accessed, None);
+        assert_eq!(entries[1].date_source, None);
+    }
+
+    #[test]
+    fn test_append_to_catalog_accumulates_runs() {
+        let dir = std::env::temp_dir().join(format!("chronomover-catalog-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let entries = create_entries();
+
+        for name in ["catalog.json", "catalog.sqlite"] {
+            let path = dir.join(name);
+            append_to_catalog(&path, &entries[..1]).unwrap();
+            append_to_catalog(&path, &entries[1..]).unwrap();
+            assert_eq!(load_catalog(&path).unwrap(), entries, "{name}");
+        }
+        assert!(parse_catalog_path("catalog.csv").is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
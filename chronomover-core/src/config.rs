@@ -0,0 +1,171 @@
+use crate::model::{file_date_type_parser, parse_older_than, Args, FileDateType, GroupBy};
+use chrono::{DateTime, Utc};
+use color_eyre::eyre::{bail, eyre, Context, ContextCompat, Result};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+/// Name of the file overriding the grouping and filter options of the folder it's in (and of its subfolders)
+pub const DIR_CONFIG_FILE_NAME: &str = ".chronomover.toml";
+
+/// Options a folder config may override
+const DIR_CONFIG_OPTIONS: &[&str] = &["group_by", "previous_period_only", "older_than", "file_date_types"];
+
+/// Grouping and filter options applied to the files of a folder
+#[derive(Debug, Clone, PartialEq)]
+pub struct DirSettings {
+    pub group_by: Option<GroupBy>,
+    pub previous_period_only: bool,
+    pub older_than: Option<DateTime<Utc>>,
+    pub file_date_types: Vec<FileDateType>,
+}
+
+impl DirSettings {
+    pub fn from_args(args: &Args) -> Self {
+        Self {
+            group_by: args.group_by,
+            previous_period_only: args.previous_period_only,
+            older_than: args.older_than,
+            file_date_types: args.file_date_types.clone(),
+        }
+    }
+}
+
+/// Resolves the settings of the folders under the sources, applying every folder config found from their source down
+/// to the folder, so the closest one wins. Settings are cached, so each folder config is read only once per run.
+pub struct DirSettingsResolver {
+    sources: Vec<PathBuf>,
+    base: DirSettings,
+    has_grouper: bool,
+    cache: HashMap<PathBuf, Result<DirSettings, String>>,
+}
+
+impl DirSettingsResolver {
+    pub fn new(args: &Args) -> Self {
+        Self {
+            sources: args.sources.clone(),
+            base: DirSettings::from_args(args),
+            has_grouper: args.grouper.is_some(),
+            cache: HashMap::new(),
+        }
+    }
+
+    pub fn resolve(&mut self, dir: &Path) -> Result<DirSettings> {
+        self.resolve_cached(dir).map_err(|e| eyre!(e))
+    }
+
+    fn resolve_cached(&mut self, dir: &Path) -> Result<DirSettings, String> {
+        if !self.sources.iter().any(|source| dir.starts_with(source)) {
+            return Ok(self.base.clone());
+        }
+        if let Some(settings) = self.cache.get(dir) {
+            return settings.clone();
+        }
+
+        let inherited = match dir.parent() {
+            Some(parent) if !self.sources.iter().any(|source| source == dir) => self.resolve_cached(parent),
+            _ => Ok(self.base.clone()),
+        };
+        let dir_config_path = dir.join(DIR_CONFIG_FILE_NAME);
+        let settings = match inherited {
+            Ok(inherited) if dir_config_path.is_file() => load_dir_config(&dir_config_path, &inherited)
+                .inspect(|settings| {
+                    // Same rule as on the command line
+                    if settings.previous_period_only && settings.group_by.is_none() && !self.has_grouper {
+                        warn!(path = %dir_config_path.display(), "previous_period_only is only meaningful with group_by");
+                    }
+                })
+                .inspect_err(|e| warn!(path = %dir_config_path.display(), "{:#}, skipping the files under its folder", e))
+                .map_err(|e| format!("{e:#}")),
+            inherited => inherited,
+        };
+        self.cache.insert(dir.to_path_buf(), settings.clone());
+        settings
+    }
+}
+
+fn load_dir_config(path: &Path, inherited: &DirSettings) -> Result<DirSettings> {
+    let content = fs::read_to_string(path).with_context(|| format!("Failed to read folder config: {}", path.display()))?;
+    apply_dir_config(&content, inherited).with_context(|| format!("Invalid folder config: {}", path.display()))
+}
+
+/// Override the inherited settings with the options set in a folder config, given as in a rule of the config file
+fn apply_dir_config(content: &str, inherited: &DirSettings) -> Result<DirSettings> {
+    let options: toml::Table = toml::from_str(content)?;
+    let option_names = options.keys().map(|key| key.replace('-', "_")).collect::<Vec<_>>();
+    if let Some(unsupported) = option_names.iter().find(|name| !DIR_CONFIG_OPTIONS.contains(&name.as_str())) {
+        bail!("Option '{}' cannot be set per folder, only {} can", unsupported, DIR_CONFIG_OPTIONS.join(", "));
+    }
+
+    let mut settings = inherited.clone();
+    for ((key, value), name) in options.iter().zip(option_names) {
+        let invalid = || format!("Invalid option '{key}' in folder config");
+        match name.as_str() {
+            "group_by" => settings.group_by = Some(value.as_str().context("Expected a grouping strategy").with_context(invalid)?.parse().with_context(invalid)?),
+            "previous_period_only" => settings.previous_period_only = value.as_bool().context("Expected true or false").with_context(invalid)?,
+            "older_than" => {
+                let older_than = match value {
+                    toml::Value::String(value) => value.clone(),
+                    toml::Value::Datetime(value) => value.to_string(),
+                    _ => bail!("{}: expected a duration or date", invalid()),
+                };
+                settings.older_than = Some(parse_older_than(&older_than).with_context(invalid)?);
+            }
+            "file_date_types" => {
+                // A comma-separated string or a list, like on the command line
+                let types = match value {
+                    toml::Value::String(value) => value.split(',').map(str::to_string).collect::<Vec<_>>(),
+                    toml::Value::Array(values) => values.iter()
+                        .map(|value| value.as_str().map(str::to_string).context("Lists may only contain strings"))
+                        .collect::<Result<_>>()
+                        .with_context(invalid)?,
+                    _ => bail!("{}: expected a list of file date types", invalid()),
+                };
+                settings.file_date_types = types.iter()
+                    .map(|value| file_date_type_parser(value).map_err(|e| eyre!(e)))
+                    .collect::<Result<_>>()
+                    .with_context(invalid)?;
+            }
+            _ => unreachable!("unsupported options are rejected above"),
+        }
+    }
+    Ok(settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::Cli;
+    use clap::Parser;
+
+    #[test]
+    fn test_apply_dir_config_overrides_only_given_options() {
+        let args = Cli::parse_from(["chronomover", "-s", "/photos", "-d", "/archive", "--group-by", "year", "--older-than", "30d"]).args.unwrap();
+        let inherited = DirSettings::from_args(&args);
+
+        let settings = apply_dir_config("group_by = \"month\"\nfile-date-types = [\"modified\"]", &inherited).unwrap();
+        assert_eq!(settings.group_by, Some(GroupBy::Month));
+        assert_eq!(settings.file_date_types, vec![FileDateType::Modified]);
+        assert_eq!(settings.older_than, inherited.older_than);
+        assert!(!settings.previous_period_only);
+
+        assert_eq!(apply_dir_config("", &inherited).unwrap(), inherited);
+
+        let settings = apply_dir_config("previous-period-only = true\nolder_than = \"2025-01-15\"\nfile_date_types = \"c,m\"", &inherited).unwrap();
+        assert!(settings.previous_period_only);
+        assert_eq!(settings.older_than, Some(parse_older_than("2025-01-15").unwrap()));
+        assert_eq!(settings.file_date_types, vec![FileDateType::Created, FileDateType::Modified]);
+    }
+
+    #[test]
+    fn test_apply_dir_config_rejects_unsupported_options() {
+        let args = Cli::parse_from(["chronomover", "-s", "/photos", "-d", "/archive"]).args.unwrap();
+        let inherited = DirSettings::from_args(&args);
+
+        let error = apply_dir_config("destination = \"/elsewhere\"", &inherited).unwrap_err();
+        assert!(error.to_string().contains("Option 'destination' cannot be set per folder"));
+        assert!(apply_dir_config("group_by = \"decade\"", &inherited).is_err());
+        assert!(apply_dir_config("previous_period_only = \"yes\"", &inherited).is_err());
+    }
+}
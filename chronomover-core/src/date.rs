@@ -1,8 +1,10 @@
-use crate::model::FileDateType;
-use chrono::{DateTime, Datelike, Utc};
-use color_eyre::eyre::{Context, ContextCompat, Result};
-use std::fs;
+use crate::model::{FileDateType, GroupBy};
+use crate::storage::FileStat;
+use chrono::{DateTime, Datelike, Local, NaiveDate, NaiveTime, TimeDelta, Utc, Weekday};
+use color_eyre::eyre::{bail, ContextCompat, Result};
+use std::collections::BTreeSet;
 use std::path::Path;
+use tracing::trace;
 
 struct FileTimestamps {
     created: DateTime<Utc>,
@@ -10,12 +12,16 @@ struct FileTimestamps {
     accessed: DateTime<Utc>,
 }
 
-/// Get the most recent timestamp based on selected file date types
-pub fn get_file_date(path: &Path, date_types: &[FileDateType]) -> Result<DateTime<Utc>> {
-    let file_timestamps = get_file_timestamps(path)?;
+/// Get the most recent timestamp based on selected file date types, from the metadata read while scanning
+pub fn get_file_date(path: &Path, stat: &FileStat, date_types: &[FileDateType]) -> Result<DateTime<Utc>> {
+    let file_timestamps = get_file_timestamps(path, stat)?;
     let created = file_timestamps.created;
     let modified = file_timestamps.modified;
     let accessed = file_timestamps.accessed;
+    trace!(
+        path = %path.display(), %created, %modified, %accessed,
+        "{}: created {}, modified {}, accessed {}", path.display(), created, modified, accessed
+    );
 
     let timestamps = date_types.iter()
         .map(|t| match t {
@@ -28,15 +34,12 @@ pub fn get_file_date(path: &Path, date_types: &[FileDateType]) -> Result<DateTim
     timestamps.context("At least one file date type must be provided")
 }
 
-fn get_file_timestamps(path: &Path) -> Result<FileTimestamps> {
-    let metadata = fs::metadata(path)
-        .with_context(|| format!("Failed to get metadata for: {}", path.display()))?;
-
-    let created = metadata.created()
+fn get_file_timestamps(path: &Path, stat: &FileStat) -> Result<FileTimestamps> {
+    let created = stat.created
         .with_context(|| format!("Failed to get creation time for: {}", path.display()))?;
-    let modified = metadata.modified()
+    let modified = stat.modified
         .with_context(|| format!("Failed to get modified time for: {}", path.display()))?;
-    let accessed = metadata.accessed()
+    let accessed = stat.accessed
         .with_context(|| format!("Failed to get accessed time for: {}", path.display()))?;
 
     Ok(FileTimestamps {
@@ -220,9 +223,153 @@ pub fn get_biweekly_identifier(date: DateTime<Utc>) -> String {
     format!("{}-BW{:02}", iso_week.year(), biweekly)
 }
 
+/// Get the identifier of the period a date belongs to (e.g., "2025-11" when grouping by month)
+pub fn get_period_identifier(group_by: GroupBy, date: DateTime<Utc>) -> String {
+    match group_by {
+        GroupBy::Week => get_week_identifier(date),
+        GroupBy::Month => get_month_identifier(date),
+        GroupBy::Year => get_year_identifier(date),
+        GroupBy::Semester => get_semester_identifier(date),
+        GroupBy::Trimester => get_trimester_identifier(date),
+        GroupBy::Quadrimester => get_quadrimester_identifier(date),
+        GroupBy::Biweekly => get_biweekly_identifier(date),
+    }
+}
+
+/// Get the start of the period following the one a date belongs to, from when it counts as a previous period.
+/// Every period starts at midnight UTC, so this is the first midnight in another period.
+pub fn get_next_period_start(group_by: GroupBy, date: DateTime<Utc>) -> DateTime<Utc> {
+    let identifier = get_period_identifier(group_by, date);
+    let mut day = date.date_naive();
+    loop {
+        // Safe to unwrap: no period ends near the last date chrono can represent
+        day = day.succ_opt().unwrap();
+        let start = day.and_time(NaiveTime::MIN).and_utc();
+        if get_period_identifier(group_by, start) != identifier {
+            return start;
+        }
+    }
+}
+
+/// Get the first midnight of the period an identifier names, if it is an identifier of the grouping strategy
+/// (e.g., 2025-04-01 for "2025-Q2" when grouping by trimester)
+pub fn get_period_start(group_by: GroupBy, identifier: &str) -> Option<DateTime<Utc>> {
+    let (year, period) = identifier.split_once('-').unwrap_or((identifier, ""));
+    let year = year.parse::<i32>().ok()?;
+    let number = |prefix: &str| period.strip_prefix(prefix)?.parse::<u8>().ok().map(u32::from)?.checked_sub(1);
+    let start = match group_by {
+        GroupBy::Week => NaiveDate::from_isoywd_opt(year, number("W")? + 1, Weekday::Mon),
+        GroupBy::Biweekly => NaiveDate::from_isoywd_opt(year, number("BW")? * 2 + 1, Weekday::Mon),
+        GroupBy::Month => NaiveDate::from_ymd_opt(year, number("")? + 1, 1),
+        GroupBy::Trimester => NaiveDate::from_ymd_opt(year, number("Q")? * 3 + 1, 1),
+        GroupBy::Quadrimester => NaiveDate::from_ymd_opt(year, number("QD")? * 4 + 1, 1),
+        GroupBy::Semester => NaiveDate::from_ymd_opt(year, number("H")? * 6 + 1, 1),
+        GroupBy::Year => NaiveDate::from_ymd_opt(year, 1, 1).filter(|_| period.is_empty()),
+    }?.and_time(NaiveTime::MIN).and_utc();
+    // Names that merely parse, like "2025-Q5" or "2025-6", are no identifier
+    (get_period_identifier(group_by, start) == identifier).then_some(start)
+}
+
+/// Get the last second of the period an identifier of any grouping strategy names (e.g., 2025-06-30 23:59:59 for
+/// "2025-06"), none for the identifiers of other groupers
+pub fn get_period_end(identifier: &str) -> Option<DateTime<Utc>> {
+    GroupBy::ALL.into_iter().find_map(|group_by| {
+        let start = get_period_start(group_by, identifier)?;
+        Some(get_next_period_start(group_by, start) - TimeDelta::seconds(1))
+    })
+}
+
+/// Whether a folder name is a period identifier of any grouping strategy (e.g., "2025", "2025-06", "2025-W23", "2025-Q2")
+pub fn is_period_identifier(name: &str) -> bool {
+    let (year, period) = name.split_once('-').unwrap_or((name, ""));
+    if year.len() != 4 || !year.bytes().all(|byte| byte.is_ascii_digit()) {
+        return false;
+    }
+    let is_number_in = |digits: &str, length: usize, range: std::ops::RangeInclusive<u32>| {
+        digits.len() == length && digits.bytes().all(|byte| byte.is_ascii_digit()) && digits.parse().is_ok_and(|number| range.contains(&number))
+    };
+    // The quadrimester prefix must be tried before the trimester one
+    if let Some(number) = period.strip_prefix("W") {
+        is_number_in(number, 2, 1..=53)
+    } else if let Some(number) = period.strip_prefix("BW") {
+        is_number_in(number, 2, 1..=26)
+    } else if let Some(number) = period.strip_prefix("QD") {
+        is_number_in(number, 1, 1..=3)
+    } else if let Some(number) = period.strip_prefix("Q") {
+        is_number_in(number, 1, 1..=4)
+    } else if let Some(number) = period.strip_prefix("H") {
+        is_number_in(number, 1, 1..=2)
+    } else {
+        period.is_empty() || is_number_in(period, 2, 1..=12)
+    }
+}
+
+/// Days not worked, which `--older-than` durations in business days (e.g., "10bd") step over
+#[derive(Debug, Clone, PartialEq)]
+pub struct BusinessCalendar {
+    pub weekend_days: Vec<Weekday>,
+    pub holidays: BTreeSet<NaiveDate>,
+}
+
+impl Default for BusinessCalendar {
+    fn default() -> Self {
+        Self { weekend_days: vec![Weekday::Sat, Weekday::Sun], holidays: BTreeSet::new() }
+    }
+}
+
+impl BusinessCalendar {
+    pub fn new(weekend_days: Vec<Weekday>, holidays: BTreeSet<NaiveDate>) -> Result<Self> {
+        if (0..7).all(|day| weekend_days.iter().any(|weekend_day| weekend_day.num_days_from_monday() == day)) {
+            bail!("At least one day of the week must not be a weekend day");
+        }
+        Ok(Self { weekend_days, holidays })
+    }
+
+    pub fn is_business_day(&self, date: NaiveDate) -> bool {
+        !self.weekend_days.contains(&date.weekday()) && !self.holidays.contains(&date)
+    }
+
+    /// Same local time of day, `days` business days before `now`, counting back day by day (so 1 business day before a
+    /// Monday is the Friday before). Days are local, like the dates given to `--older-than`.
+    pub fn subtract_business_days(&self, now: DateTime<Utc>, days: u32) -> DateTime<Utc> {
+        let local_now = now.with_timezone(&Local);
+        let mut date = local_now.date_naive();
+        let mut counted = 0;
+        while counted < days {
+            // Safe to unwrap: far from the minimum date, as at least one day of each week is a business day
+            date = date.pred_opt().unwrap();
+            if self.is_business_day(date) {
+                counted += 1;
+            }
+        }
+        // A time skipped by a daylight saving change falls back to the same time in UTC
+        date.and_time(local_now.time())
+            .and_local_timezone(Local)
+            .earliest()
+            .map_or_else(|| date.and_time(local_now.time()).and_utc(), |cutoff| cutoff.to_utc())
+    }
+}
+
+/// Parse a holiday list: one `YYYY-MM-DD` date per line, blank lines and `#` comments being ignored
+pub fn parse_holidays(content: &str) -> Result<BTreeSet<NaiveDate>> {
+    let mut holidays = BTreeSet::new();
+    for (index, line) in content.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        if line.is_empty() {
+            continue;
+        }
+        let Ok(date) = NaiveDate::parse_from_str(line, "%Y-%m-%d") else {
+            bail!("Invalid holiday on line {}: '{}', use one YYYY-MM-DD date per line", index + 1, line);
+        };
+        holidays.insert(date);
+    }
+    Ok(holidays)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use chrono::{TimeDelta, TimeZone};
 
     // Period calculation tests
     #[test]
@@ -338,6 +485,16 @@ mod tests {
     }
 
     // Identifier formatting tests
+    #[test]
+    fn test_is_period_identifier() {
+        for name in ["2025", "2025-06", "2025-W23", "2025-BW12", "2025-Q2", "2025-QD3", "2025-H1"] {
+            assert!(is_period_identifier(name), "{name}");
+        }
+        for name in ["photos", "25-06", "2025-13", "2025-6", "2025-W54", "2025-QD4", "2025-H3", "2025-06-01"] {
+            assert!(!is_period_identifier(name), "{name}");
+        }
+    }
+
     #[test]
     fn test_get_week_identifier() {
         // Week 1 (with zero padding)
@@ -610,4 +767,84 @@ mod tests {
         let previous_year = "2024-12-25T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
         assert!(is_before_current_biweekly(previous_year, now));
     }
+    #[test]
+    fn test_get_next_period_start() {
+        let date = "2025-06-15T13:45:00Z".parse::<DateTime<Utc>>().unwrap(); // Sunday of week 24, the last of BW12
+        let expected = [
+            (GroupBy::Week, "2025-06-16T00:00:00Z"),
+            (GroupBy::Biweekly, "2025-06-16T00:00:00Z"),
+            (GroupBy::Month, "2025-07-01T00:00:00Z"),
+            (GroupBy::Trimester, "2025-07-01T00:00:00Z"),
+            (GroupBy::Quadrimester, "2025-09-01T00:00:00Z"),
+            (GroupBy::Semester, "2025-07-01T00:00:00Z"),
+            (GroupBy::Year, "2026-01-01T00:00:00Z"),
+        ];
+        for (group_by, next_period_start) in expected {
+            let next_period_start = next_period_start.parse::<DateTime<Utc>>().unwrap();
+            assert_eq!(get_next_period_start(group_by, date), next_period_start, "{group_by:?}");
+            // The next period start is exactly when the date becomes part of a previous period
+            assert!(get_period_identifier(group_by, next_period_start - TimeDelta::seconds(1)) == get_period_identifier(group_by, date));
+        }
+
+        // Weeks 51 to 53 share a biweekly period, which ends with the ISO year
+        let date = "2026-12-20T00:00:00Z".parse::<DateTime<Utc>>().unwrap(); // Week 51 of 2026
+        assert_eq!(get_next_period_start(GroupBy::Biweekly, date), "2027-01-04T00:00:00Z".parse::<DateTime<Utc>>().unwrap());
+    }
+
+    #[test]
+    fn test_get_period_start() {
+        let expected = [
+            (GroupBy::Week, "2025-W01", "2024-12-30T00:00:00Z"),
+            (GroupBy::Biweekly, "2025-BW26", "2025-12-15T00:00:00Z"),
+            (GroupBy::Month, "2025-06", "2025-06-01T00:00:00Z"),
+            (GroupBy::Trimester, "2025-Q2", "2025-04-01T00:00:00Z"),
+            (GroupBy::Quadrimester, "2025-QD3", "2025-09-01T00:00:00Z"),
+            (GroupBy::Semester, "2025-H2", "2025-07-01T00:00:00Z"),
+            (GroupBy::Year, "2025", "2025-01-01T00:00:00Z"),
+        ];
+        for (group_by, identifier, start) in expected {
+            assert_eq!(get_period_start(group_by, identifier), Some(start.parse::<DateTime<Utc>>().unwrap()), "{identifier}");
+        }
+
+        assert_eq!(get_period_start(GroupBy::Month, "2025-Q2"), None);
+        assert_eq!(get_period_start(GroupBy::Month, "2025-6"), None);
+        assert_eq!(get_period_start(GroupBy::Month, "2025-13"), None);
+        assert_eq!(get_period_start(GroupBy::Trimester, "2025-QD1"), None);
+        assert_eq!(get_period_start(GroupBy::Week, "2025-W53"), None); // 2025 has 52 ISO weeks
+        assert_eq!(get_period_start(GroupBy::Year, "2025-06"), None);
+    }
+
+    #[test]
+    fn test_get_period_end() {
+        let end = |identifier| get_period_end(identifier).map(|end| end.to_rfc3339());
+
+        assert_eq!(end("2025-W01").as_deref(), Some("2025-01-05T23:59:59+00:00"));
+        assert_eq!(end("2024-02").as_deref(), Some("2024-02-29T23:59:59+00:00"));
+        assert_eq!(end("2025-QD1").as_deref(), Some("2025-04-30T23:59:59+00:00"));
+        assert_eq!(end("2025").as_deref(), Some("2025-12-31T23:59:59+00:00"));
+        assert_eq!(end("sprint-42"), None);
+    }
+
+    #[test]
+    fn test_subtract_business_days_steps_over_weekends_and_holidays() {
+        let monday = Local.with_ymd_and_hms(2025, 6, 16, 12, 0, 0).unwrap().to_utc();
+        let local_date = |date: DateTime<Utc>| date.with_timezone(&Local).date_naive();
+        let calendar = BusinessCalendar::default();
+
+        assert_eq!(local_date(calendar.subtract_business_days(monday, 1)), NaiveDate::from_ymd_opt(2025, 6, 13).unwrap());
+        assert_eq!(local_date(calendar.subtract_business_days(monday, 10)), NaiveDate::from_ymd_opt(2025, 6, 2).unwrap());
+        assert_eq!(calendar.subtract_business_days(monday, 0), monday);
+
+        let holidays = parse_holidays("# Bank holidays\n2025-06-13\n\n2025-06-12  # Corpus Christi\n").unwrap();
+        let calendar = BusinessCalendar::new(vec![Weekday::Fri, Weekday::Sat], holidays).unwrap();
+        // Sunday is worked, then Saturday and Friday are the weekend and Thursday a holiday
+        assert_eq!(local_date(calendar.subtract_business_days(monday, 2)), NaiveDate::from_ymd_opt(2025, 6, 11).unwrap());
+    }
+
+    #[test]
+    fn test_business_calendar_rejects_invalid_settings() {
+        let every_day = vec![Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu, Weekday::Fri, Weekday::Sat, Weekday::Sun];
+        assert!(BusinessCalendar::new(every_day, BTreeSet::new()).is_err());
+        assert!(parse_holidays("2025-13-01").is_err());
+    }
 }
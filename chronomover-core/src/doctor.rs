@@ -0,0 +1,343 @@
+use crate::model::DoctorArgs;
+use color_eyre::eyre::{Context, Result};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::{Duration, SystemTime};
+use tracing::{info, warn};
+use walkdir::WalkDir;
+
+/// Outcome of a single environment check
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum CheckStatus {
+    Ok,
+    Warning,
+    Unknown,
+}
+
+impl fmt::Display for CheckStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            CheckStatus::Ok => "[ok]     ",
+            CheckStatus::Warning => "[warning]",
+            CheckStatus::Unknown => "[unknown]",
+        })
+    }
+}
+
+/// How the access times of a mount are updated, from its mount options
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum AtimeMode {
+    /// Updated on every read
+    Strict,
+    /// Updated at most once a day, or when older than the modification time (Linux default)
+    Relative,
+    /// Never updated
+    Disabled,
+}
+
+/// Report the filesystem capabilities that explain surprising dates or failed moves: creation time support,
+/// access time updates, timestamp precision, whether moves stay on one filesystem, and long path support
+pub fn run_doctor(doctor_args: &DoctorArgs) -> Result<()> {
+    let mounts = fs::read_to_string("/proc/self/mounts");
+    for source in &doctor_args.sources {
+        let canonical_source = fs::canonicalize(source)
+            .with_context(|| format!("Failed to resolve source directory: {}", source.display()))?;
+        info!("Source directory: {}", source.display());
+
+        let (status, detail) = match fs::metadata(&canonical_source).and_then(|metadata| metadata.created()) {
+            Ok(_) => (CheckStatus::Ok, "supported".to_string()),
+            Err(e) => (CheckStatus::Warning, format!("not supported ({}), so files cannot be dated by their creation time here", e)),
+        };
+        print_check(status, "Creation time", &detail);
+
+        let (status, detail) = match &mounts {
+            Ok(mounts) => match get_atime_mode(mounts, &canonical_source) {
+                Some(AtimeMode::Strict) => (CheckStatus::Ok, "updated on every read".to_string()),
+                Some(AtimeMode::Relative) => (CheckStatus::Warning, "updated at most once a day (relatime), so reading a file may not make it recent".to_string()),
+                Some(AtimeMode::Disabled) => (CheckStatus::Warning, "never updated (noatime), so access times only tell when a file was created or copied".to_string()),
+                None => (CheckStatus::Unknown, "the mount of the source directory was not found".to_string()),
+            },
+            Err(_) => (CheckStatus::Unknown, "mount options cannot be read on this platform".to_string()),
+        };
+        print_check(status, "Access time", &detail);
+
+        let (status, detail) = match get_filesystem_type(&mounts, &canonical_source) {
+            Some(filesystem_type) if is_fat_filesystem(&filesystem_type) => (
+                CheckStatus::Warning,
+                format!(
+                    "{} stores modification times in 2-second steps and in local time without a timezone, so files near \
+                     the --older-than cutoff may be misjudged (see --timestamp-tolerance, e.g., \"2s\", or \"1h\" across \
+                     daylight saving time changes)",
+                    filesystem_type
+                ),
+            ),
+            Some(filesystem_type) => (CheckStatus::Ok, format!("{} stores precise timestamps", filesystem_type)),
+            None => (CheckStatus::Unknown, "the filesystem of the source directory was not found".to_string()),
+        };
+        print_check(status, "Timestamps", &detail);
+
+        if let Some(destination) = &doctor_args.destination {
+            let (status, detail) = match (get_device_id(&canonical_source), get_device_id(&get_existing_ancestor(destination))) {
+                (Ok(source_device), Ok(destination_device)) if source_device == destination_device => {
+                    (CheckStatus::Ok, format!("same filesystem as {}, files are moved by renaming them", destination.display()))
+                }
+                (Ok(_), Ok(_)) => (
+                    CheckStatus::Warning,
+                    format!("different filesystem than {}, files cannot be renamed there and their moves will fail", destination.display()),
+                ),
+                (Err(e), _) | (_, Err(e)) => (CheckStatus::Unknown, format!("failed to compare the filesystems: {}", e)),
+            };
+            print_check(status, "Destination", &detail);
+        }
+    }
+
+    let (status, detail) = get_long_path_support();
+    print_check(status, "Long paths", &detail);
+    Ok(())
+}
+
+/// Warn about the sources whose access times aren't updated on every read, as files selected by their `accessed` date
+/// would then look older than they are. Mount options are read on Linux, other platforms sample the files instead.
+pub fn warn_about_unreliable_access_times(sources: &[PathBuf]) {
+    let mounts = fs::read_to_string("/proc/self/mounts");
+    for source in sources {
+        let canonical_source = fs::canonicalize(source).unwrap_or_else(|_| source.clone());
+        let reason = match &mounts {
+            Ok(mounts) => match get_atime_mode(mounts, &canonical_source) {
+                Some(AtimeMode::Relative) => Some("its filesystem is mounted with relatime, updating them at most once a day"),
+                Some(AtimeMode::Disabled) => Some("its filesystem is mounted with noatime, never updating them"),
+                Some(AtimeMode::Strict) | None => None,
+            },
+            Err(_) => is_access_time_stale(sample_file_times(source)).then_some("most of its files were last accessed when they were modified"),
+        };
+        if let Some(reason) = reason {
+            warn!(
+                path = %source.display(),
+                "Access times of {} are unreliable, as {}: recently read files may still be moved. Consider removing 'accessed' from --file-date-types",
+                source.display(), reason
+            );
+        }
+    }
+}
+
+/// Files sampled to tell whether access times are updated, when mount options are not available
+const ACCESS_TIME_SAMPLE_SIZE: usize = 100;
+
+/// Access and modification times of the first files of a source
+fn sample_file_times(source: &Path) -> Vec<(SystemTime, SystemTime)> {
+    WalkDir::new(source)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_file())
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok()?;
+            Some((metadata.accessed().ok()?, metadata.modified().ok()?))
+        })
+        .take(ACCESS_TIME_SAMPLE_SIZE)
+        .collect()
+}
+
+/// Whether nearly all sampled files were last accessed when they were modified, a sign that reading a file doesn't
+/// update its access time. Too few samples tell nothing.
+fn is_access_time_stale(samples: Vec<(SystemTime, SystemTime)>) -> bool {
+    let unchanged = samples.iter()
+        .filter(|(accessed, modified)| accessed.duration_since(*modified).unwrap_or_default() < Duration::from_secs(1))
+        .count();
+    samples.len() >= 10 && unchanged * 10 >= samples.len() * 9
+}
+
+fn print_check(status: CheckStatus, name: &str, detail: &str) {
+    info!("{} {}: {}", status, name, detail);
+}
+
+/// Access time mode of the mount containing `path`, from the content of `/proc/self/mounts`
+fn get_atime_mode(mounts: &str, path: &Path) -> Option<AtimeMode> {
+    let (_, options) = find_mount(mounts, path)?;
+    let options = options.split(',').collect::<Vec<_>>();
+    Some(if options.contains(&"noatime") {
+        AtimeMode::Disabled
+    } else if options.contains(&"strictatime") || !options.contains(&"relatime") {
+        AtimeMode::Strict
+    } else {
+        AtimeMode::Relative
+    })
+}
+
+/// Filesystem type and options of the mount containing `path`, from the content of `/proc/self/mounts`
+fn find_mount<'a>(mounts: &'a str, path: &Path) -> Option<(&'a str, &'a str)> {
+    mounts.lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let mount_point = PathBuf::from(unescape_mount_field(fields.nth(1)?));
+            let filesystem_type = fields.next()?;
+            let options = fields.next()?;
+            Some((mount_point, filesystem_type, options))
+        })
+        .filter(|(mount_point, _, _)| path.starts_with(mount_point))
+        // The last of the longest mount points wins, as later mounts hide earlier ones on the same point
+        .max_by_key(|(mount_point, _, _)| mount_point.components().count())
+        .map(|(_, filesystem_type, options)| (filesystem_type, options))
+}
+
+/// Whether a filesystem type is one of the FAT family, which stores modification times in 2-second steps and in local
+/// time without a timezone
+fn is_fat_filesystem(filesystem_type: &str) -> bool {
+    matches!(filesystem_type.to_ascii_lowercase().as_str(), "vfat" | "msdos" | "fat" | "fat12" | "fat16" | "fat32" | "exfat")
+}
+
+/// Filesystem type of the volume containing `path` (e.g., "NTFS", "FAT32", "exFAT")
+#[cfg(windows)]
+fn get_filesystem_type(_mounts: &io::Result<String>, path: &Path) -> Option<String> {
+    use std::os::windows::ffi::OsStrExt;
+    use windows_sys::Win32::Storage::FileSystem::{GetVolumeInformationW, GetVolumePathNameW};
+
+    let wide_path = path.as_os_str().encode_wide().chain([0]).collect::<Vec<_>>();
+    let mut volume_path = [0u16; 1024];
+    let mut filesystem_name = [0u16; 64];
+    // Safe: the path is NUL-terminated, and both buffers are passed with their length
+    let is_found = unsafe {
+        GetVolumePathNameW(wide_path.as_ptr(), volume_path.as_mut_ptr(), volume_path.len() as u32) != 0
+            && GetVolumeInformationW(
+                volume_path.as_ptr(),
+                std::ptr::null_mut(),
+                0,
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+                filesystem_name.as_mut_ptr(),
+                filesystem_name.len() as u32,
+            ) != 0
+    };
+    let length = filesystem_name.iter().position(|&c| c == 0).unwrap_or(filesystem_name.len());
+    is_found.then(|| String::from_utf16_lossy(&filesystem_name[..length]))
+}
+
+/// Filesystem type of the mount containing `path` (e.g., "ext4", "vfat", "exfat")
+#[cfg(not(windows))]
+fn get_filesystem_type(mounts: &io::Result<String>, path: &Path) -> Option<String> {
+    let (filesystem_type, _) = find_mount(mounts.as_ref().ok()?, path)?;
+    Some(filesystem_type.to_string())
+}
+
+/// Undo the octal escapes (`\040` for spaces) of a field of `/proc/self/mounts`
+fn unescape_mount_field(field: &str) -> String {
+    let mut unescaped = String::with_capacity(field.len());
+    let mut rest = field;
+    while let Some(index) = rest.find('\\') {
+        unescaped.push_str(&rest[..index]);
+        let escape = rest.get(index + 1..index + 4);
+        match escape.and_then(|digits| u8::from_str_radix(digits, 8).ok()) {
+            Some(byte) => {
+                unescaped.push(byte as char);
+                rest = &rest[index + 4..];
+            }
+            None => {
+                unescaped.push('\\');
+                rest = &rest[index + 1..];
+            }
+        }
+    }
+    unescaped.push_str(rest);
+    unescaped
+}
+
+/// Closest existing folder of a path, as the destination is only created by the first run
+pub(crate) fn get_existing_ancestor(path: &Path) -> PathBuf {
+    path.ancestors()
+        .find(|ancestor| ancestor.exists())
+        .map_or_else(|| PathBuf::from("."), Path::to_path_buf)
+}
+
+#[cfg(unix)]
+pub(crate) fn get_device_id(path: &Path) -> io::Result<String> {
+    use std::os::unix::fs::MetadataExt;
+    Ok(fs::metadata(path)?.dev().to_string())
+}
+
+/// Drive or share of the path, as the volume serial number is not readable with the standard library on Windows
+#[cfg(not(unix))]
+pub(crate) fn get_device_id(path: &Path) -> io::Result<String> {
+    let canonical_path = fs::canonicalize(path)?;
+    Ok(canonical_path.components().next().map(|prefix| prefix.as_os_str().to_string_lossy().to_lowercase()).unwrap_or_default())
+}
+
+fn get_long_path_support() -> (CheckStatus, String) {
+    if !cfg!(windows) {
+        return (CheckStatus::Ok, "no 260 character limit on this platform".to_string());
+    }
+    let query = process::Command::new("reg")
+        .args(["query", r"HKLM\SYSTEM\CurrentControlSet\Control\FileSystem", "/v", "LongPathsEnabled"])
+        .output();
+    match query {
+        Ok(output) if output.status.success() && String::from_utf8_lossy(&output.stdout).contains("0x1") => {
+            (CheckStatus::Ok, "enabled (LongPathsEnabled)".to_string())
+        }
+        Ok(_) => (
+            CheckStatus::Warning,
+            "disabled, ChronoMover still moves long paths but Explorer and other programs may fail to open files moved past 260 characters".to_string(),
+        ),
+        Err(e) => (CheckStatus::Unknown, format!("failed to run reg: {}", e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MOUNTS: &str = "\
+/dev/sda1 / ext4 rw,relatime 0 0
+/dev/sdb1 /mnt/archive ext4 rw,noatime 0 0
+/dev/sdc1 /mnt/my\\040photos xfs rw,strictatime 0 0
+tmpfs /tmp tmpfs rw,nosuid,nodev 0 0
+";
+
+    #[test]
+    fn test_get_atime_mode_longest_mount_point() {
+        assert_eq!(get_atime_mode(MOUNTS, Path::new("/home/user/Downloads")), Some(AtimeMode::Relative));
+        assert_eq!(get_atime_mode(MOUNTS, Path::new("/mnt/archive/2025")), Some(AtimeMode::Disabled));
+        assert_eq!(get_atime_mode(MOUNTS, Path::new("/mnt/my photos/2025")), Some(AtimeMode::Strict));
+        assert_eq!(get_atime_mode(MOUNTS, Path::new("/tmp/files")), Some(AtimeMode::Strict));
+        assert_eq!(get_atime_mode(MOUNTS, Path::new("/mnt/archived")), Some(AtimeMode::Relative));
+    }
+
+    #[test]
+    fn test_find_mount_filesystem_type() {
+        let mounts = format!("{}/dev/mmcblk0p1 /media/sd vfat rw,relatime,fmask=0022 0 0\n", MOUNTS);
+
+        assert_eq!(find_mount(&mounts, Path::new("/media/sd/DCIM")), Some(("vfat", "rw,relatime,fmask=0022")));
+        assert_eq!(find_mount(&mounts, Path::new("/mnt/my photos")), Some(("xfs", "rw,strictatime")));
+        assert_eq!(find_mount("", Path::new("/home")), None);
+    }
+
+    #[test]
+    fn test_is_fat_filesystem() {
+        assert!(is_fat_filesystem("vfat"));
+        assert!(is_fat_filesystem("exfat"));
+        assert!(is_fat_filesystem("FAT32"));
+        assert!(is_fat_filesystem("exFAT"));
+        assert!(!is_fat_filesystem("NTFS"));
+        assert!(!is_fat_filesystem("ext4"));
+    }
+
+    #[test]
+    fn test_is_access_time_stale() {
+        let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(1_750_000_000);
+        let unchanged = vec![(modified, modified); 10];
+        let mut read = unchanged.clone();
+        read[0].0 = modified + Duration::from_secs(3600);
+        read[1].0 = modified + Duration::from_secs(3600);
+
+        assert!(is_access_time_stale(unchanged.clone()));
+        assert!(!is_access_time_stale(read));
+        assert!(!is_access_time_stale(unchanged[..9].to_vec()));
+    }
+
+    #[test]
+    fn test_unescape_mount_field() {
+        assert_eq!(unescape_mount_field(r"/mnt/my\040photos\134raw"), r"/mnt/my photos\raw");
+        assert_eq!(unescape_mount_field(r"/mnt/trailing\"), r"/mnt/trailing\");
+    }
+}
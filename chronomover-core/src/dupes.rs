@@ -0,0 +1,307 @@
+use crate::file::{get_destination_root, run_in_order, FileError, FileToMove, MoveReport};
+use crate::logging::SUMMARY_TARGET;
+use crate::model::{Args, DupesArgs};
+use crate::report::format_size;
+use crate::storage::{StorageBackend, StorageEntry, WalkOptions};
+use color_eyre::eyre::{bail, Result};
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, PoisonError};
+use tracing::{error, info, warn};
+
+/// A source file whose content is already in the archive
+#[derive(Debug, Clone)]
+pub struct Duplicate {
+    pub path: PathBuf,
+    /// File of the archive with the same content
+    pub original: PathBuf,
+    pub size: u64,
+}
+
+/// Files of an archive by size, so only the archived files as large as a candidate are hashed, and each of them once.
+/// Shared by the threads looking for duplicates.
+pub struct ArchiveIndex {
+    files_by_size: HashMap<u64, Vec<PathBuf>>,
+    hashes: Mutex<HashMap<PathBuf, String>>,
+    file_count: usize,
+}
+
+impl ArchiveIndex {
+    /// Index the files of an archive, which holds no file yet when it doesn't exist
+    pub fn build(storage: &dyn StorageBackend, archive: &Path) -> Self {
+        let mut files_by_size = HashMap::<u64, Vec<PathBuf>>::new();
+        let mut file_count = 0;
+        if storage.is_dir(archive) {
+            for entry in storage.walk(archive, WalkOptions::default(), Box::new(|_| true)) {
+                let stat = entry.and_then(|entry| {
+                    let stat = entry.stat.map_or_else(|| storage.stat(&entry.path), Ok)?;
+                    Ok((entry, stat))
+                });
+                match stat {
+                    Ok((entry, stat)) if entry.is_file => {
+                        files_by_size.entry(stat.size).or_default().push(entry.path);
+                        file_count += 1;
+                    }
+                    Ok(_) => {}
+                    Err(e) => warn!(error = %e, "Failed to index a file of {}, duplicates of it won't be found: {}", archive.display(), e),
+                }
+            }
+        }
+        Self { files_by_size, hashes: Mutex::default(), file_count }
+    }
+
+    pub fn file_count(&self) -> usize {
+        self.file_count
+    }
+
+    /// File of the archive with the same content (same size and BLAKE3 hash) as `file`, if any. Empty files have no
+    /// content to compare, so they are never duplicates.
+    pub fn find_duplicate(&self, storage: &dyn StorageBackend, file: &Path, size: u64) -> io::Result<Option<PathBuf>> {
+        let Some(candidates) = self.files_by_size.get(&size).filter(|_| size > 0) else {
+            return Ok(None);
+        };
+        let hash = storage.hash_file(file)?;
+        for candidate in candidates.iter().filter(|candidate| *candidate != file) {
+            // Not held while hashing, so the other threads keep going (two of them may hash the same candidate)
+            let known_hash = self.hashes.lock().unwrap_or_else(PoisonError::into_inner).get(candidate).cloned();
+            let candidate_hash = match known_hash {
+                Some(candidate_hash) => candidate_hash,
+                None => match storage.hash_file(candidate) {
+                    Ok(candidate_hash) => {
+                        self.hashes.lock().unwrap_or_else(PoisonError::into_inner).insert(candidate.clone(), candidate_hash.clone());
+                        candidate_hash
+                    }
+                    Err(e) => {
+                        warn!(path = %candidate.display(), error = %e, "Failed to hash {}, skipping it: {}", candidate.display(), e);
+                        continue;
+                    }
+                },
+            };
+            if candidate_hash == hash {
+                return Ok(Some(candidate.clone()));
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// List the files of the sources whose content is already somewhere in the archive, whatever their name or folder
+pub fn report_duplicates(args: &DupesArgs, storage: &dyn StorageBackend) -> Result<()> {
+    for source in &args.sources {
+        if !storage.is_dir(source) {
+            bail!("Source directory does not exist: {}", source.display());
+        }
+    }
+    if !storage.is_dir(&args.destination) {
+        bail!("Destination does not exist: {}", args.destination.display());
+    }
+
+    if args.jobs == 0 {
+        bail!("Number of jobs must be greater than 0");
+    }
+
+    let index = ArchiveIndex::build(storage, &args.destination);
+    info!("Looking for the files of {} source(s) already in the {} file(s) of {}", args.sources.len(), index.file_count(), args.destination.display());
+
+    let mut scanned = 0;
+    let mut found = 0;
+    let mut found_bytes = 0;
+    let mut failed = 0;
+    for source in &args.sources {
+        // An archive inside a source is not part of it
+        let keep = Box::new(|entry: &StorageEntry| !entry.path.starts_with(&args.destination));
+        let entries = storage.walk(source, WalkOptions { sorted: true, ..WalkOptions::default() }, keep)
+            .filter(|entry| entry.as_ref().map_or(true, |entry| entry.is_file))
+            .collect::<Vec<_>>();
+        scanned += entries.iter().filter(|entry| entry.is_ok()).count();
+
+        // Files are hashed on the worker threads, while duplicates are reported in walk order
+        let check = |entry: &io::Result<StorageEntry>| -> Result<Option<Duplicate>, String> {
+            let entry = entry.as_ref().map_err(ToString::to_string)?;
+            let size = entry.stat.map_or_else(|| storage.stat(&entry.path), Ok).map_err(|e| e.to_string())?.size;
+            let original = index.find_duplicate(storage, &entry.path, size).map_err(|e| e.to_string())?;
+            Ok(original.map(|original| Duplicate { path: entry.path.clone(), original, size }))
+        };
+        run_in_order(&entries, args.jobs, check, |_, duplicate| {
+            match duplicate {
+                Ok(Some(duplicate)) => {
+                    found += 1;
+                    found_bytes += duplicate.size;
+                    info!(
+                        path = %duplicate.path.display(), original = %duplicate.original.display(), status = "duplicate",
+                        "{}. {}\n       = {}", found, duplicate.path.display(), duplicate.original.display()
+                    );
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    error!(error = %e, "Checking a file of {}: {}", source.display(), e);
+                    failed += 1;
+                }
+            }
+            Ok(())
+        })?;
+    }
+
+    let summary = format!("{} of the {} source file(s) are already in the archive ({})", found, scanned, format_size(found_bytes));
+    if failed > 0 {
+        bail!("{}, {} could not be checked", summary, failed);
+    }
+    info!(target: SUMMARY_TARGET, "{}", summary);
+    if found > 0 {
+        info!("Pass --duplicates skip or --duplicates delete to runs to leave them out of the moves");
+    }
+    Ok(())
+}
+
+/// Take the planned files whose content is already in the archive out of the plan, for `--duplicates`. Each file is
+/// looked for in the destination it is routed to.
+pub fn separate_duplicates(
+    args: &Args,
+    storage: &dyn StorageBackend,
+    files_to_move: Vec<FileToMove>,
+) -> (Vec<FileToMove>, Vec<Duplicate>, Vec<FileError>) {
+    let mut indexes = HashMap::<&Path, ArchiveIndex>::new();
+    for file in &files_to_move {
+        let archive = get_destination_root(args, &file.source);
+        indexes.entry(archive).or_insert_with(|| ArchiveIndex::build(storage, archive));
+    }
+
+    // Files are hashed on the `--jobs` threads, while the plan keeps its order
+    let mut results = Vec::with_capacity(files_to_move.len());
+    let find_duplicate = |file: &FileToMove| indexes[get_destination_root(args, &file.source)].find_duplicate(storage, &file.source, file.size);
+    // Safe to unwrap: the handler never fails
+    run_in_order(&files_to_move, args.jobs, find_duplicate, |_, result| {
+        results.push(result);
+        Ok(())
+    }).unwrap();
+
+    let mut kept_files = Vec::with_capacity(files_to_move.len());
+    let mut duplicates = Vec::new();
+    let mut errors = Vec::new();
+    for (file, result) in files_to_move.into_iter().zip(results) {
+        match result {
+            Ok(Some(original)) => duplicates.push(Duplicate { path: file.source, original, size: file.size }),
+            Ok(None) => kept_files.push(file),
+            Err(e) => {
+                error!(path = %file.source.display(), error = %e, "Hashing file {}: {}", file.source.display(), e);
+                errors.push(FileError { path: file.source, error: format!("Failed to compare with the archive: {e}") });
+            }
+        }
+    }
+    (kept_files, duplicates, errors)
+}
+
+/// Delete the source files already in the archive, for `--duplicates delete`. They are sent to the Recycle Bin first on
+/// Windows unless `--permanent` is set, and files that fail to be deleted are reported as errors of the run.
+pub fn delete_duplicates(args: &Args, storage: &dyn StorageBackend, duplicates: &[Duplicate], dry_run: bool, report: &mut MoveReport) {
+    let status = if dry_run { "dry-run" } else { "deleted" };
+    let mut deleted = 0;
+    let mut deleted_bytes = 0;
+    for duplicate in duplicates {
+        if !dry_run
+            && let Err(e) = delete_file(storage, &duplicate.path, args.permanent) {
+                error!(path = %duplicate.path.display(), error = %e, "Deleting duplicate {}: {}", duplicate.path.display(), e);
+                report.errors.push(FileError { path: duplicate.path.clone(), error: e.to_string() });
+                continue;
+            }
+        deleted += 1;
+        deleted_bytes += duplicate.size;
+        info!(
+            path = %duplicate.path.display(), original = %duplicate.original.display(), status,
+            "Deleted {}: duplicate of {}", duplicate.path.display(), duplicate.original.display()
+        );
+    }
+    if deleted > 0 {
+        let verb = if dry_run { "would have been deleted" } else { "deleted" };
+        info!(target: SUMMARY_TARGET, status, "{} duplicate(s) of archived files {} ({})", deleted, verb, format_size(deleted_bytes));
+    }
+}
+
+/// Recycling only takes the file away on Windows, so it is removed for good wherever it is still there
+fn delete_file(storage: &dyn StorageBackend, path: &Path, permanent: bool) -> io::Result<()> {
+    if !permanent {
+        storage.recycle_file(path)?;
+    }
+    if storage.stat(path).is_ok() {
+        storage.remove_file(path)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file::get_files_to_move;
+    use crate::model::Cli;
+    use crate::storage::MemoryStorage;
+    use chrono::{DateTime, Utc};
+    use clap::Parser;
+
+    fn create_storage() -> MemoryStorage {
+        let storage = MemoryStorage::with_tree([
+            ("/src/IMG_0001.jpg", "2025-01-10"),
+            ("/src/renamed/beach.jpg", "2025-01-11"),
+            ("/src/new.jpg", "2025-01-12"),
+            ("/src/empty.txt", "2025-01-12"),
+            ("/archive/2024-12/IMG_0001.jpg", "2024-12-01"),
+            ("/archive/2025-01/empty.txt", "2025-01-01"),
+        ]);
+        storage.set_size("/src/IMG_0001.jpg", 100);
+        storage.set_size("/src/renamed/beach.jpg", 100);
+        storage.set_size("/src/new.jpg", 200);
+        storage.set_size("/archive/2024-12/IMG_0001.jpg", 100);
+        storage
+    }
+
+    #[test]
+    fn test_archive_index_finds_same_content_under_any_name() {
+        let storage = create_storage();
+        let index = ArchiveIndex::build(&storage, Path::new("/archive"));
+
+        let original = Some(PathBuf::from("/archive/2024-12/IMG_0001.jpg"));
+        assert_eq!(index.file_count(), 2);
+        assert_eq!(index.find_duplicate(&storage, Path::new("/src/IMG_0001.jpg"), 100).unwrap(), original);
+        assert_eq!(index.find_duplicate(&storage, Path::new("/src/renamed/beach.jpg"), 100).unwrap(), original);
+        assert_eq!(index.find_duplicate(&storage, Path::new("/src/new.jpg"), 200).unwrap(), None);
+        assert_eq!(index.find_duplicate(&storage, Path::new("/src/empty.txt"), 0).unwrap(), None);
+        assert!(ArchiveIndex::build(&storage, Path::new("/missing")).find_duplicate(&storage, Path::new("/src/new.jpg"), 200).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_report_duplicates_leaves_files_in_place() {
+        let storage = create_storage();
+        let args = DupesArgs { sources: vec![PathBuf::from("/src")], destination: PathBuf::from("/archive"), jobs: 2 };
+
+        report_duplicates(&args, &storage).unwrap();
+
+        assert_eq!(storage.file_paths().len(), 6);
+        let missing_source = DupesArgs { sources: vec![PathBuf::from("/missing")], ..args };
+        assert!(report_duplicates(&missing_source, &storage).is_err());
+    }
+
+    #[test]
+    fn test_run_skips_or_deletes_duplicates() {
+        let now = "2025-06-15T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let plan_with = |storage: &MemoryStorage, policy: &str| {
+            let args = Cli::parse_from(["chronomover", "-s", "/src", "-d", "/archive", "--duplicates", policy, "--permanent"]).args.unwrap();
+            let plan = get_files_to_move(&args, storage, now, None).unwrap();
+            (args, plan)
+        };
+
+        let storage = create_storage();
+        let (_, plan) = plan_with(&storage, "skip");
+        assert_eq!((plan.planned, plan.skipped, plan.duplicates.len()), (2, 2, 0));
+
+        let (args, plan) = plan_with(&storage, "delete");
+        let mut report = MoveReport::default();
+        assert_eq!((plan.planned, plan.skipped, plan.duplicates.len()), (2, 0, 2));
+        delete_duplicates(&args, &storage, &plan.duplicates, true, &mut report);
+        assert!(storage.is_file("/src/IMG_0001.jpg"));
+        delete_duplicates(&args, &storage, &plan.duplicates, false, &mut report);
+        assert!(report.errors.is_empty());
+        assert!(!storage.is_file("/src/IMG_0001.jpg"));
+        assert!(!storage.is_file("/src/renamed/beach.jpg"));
+        assert!(storage.is_file("/archive/2024-12/IMG_0001.jpg"));
+    }
+}
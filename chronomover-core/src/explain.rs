@@ -0,0 +1,212 @@
+use crate::config::{DirSettings, DirSettingsResolver, DIR_CONFIG_FILE_NAME};
+use crate::date::get_file_date;
+use crate::file::{calculate_dest_path, find_in_sources, get_absolute_sources, get_excluding_filter, get_grouper, get_source_root, get_tolerant_cutoff, is_macos_metadata, is_sync_conflict_copy, ExcludingFilter};
+use crate::grouper::Grouper;
+use crate::hooks::run_filter_cmd;
+use crate::obsidian::is_obsidian_folder;
+use crate::model::{enrich_arguments, get_reference_time, Args, ExplainArgs, FileDateType, PlaceholderPolicy, SyncConflictPolicy};
+use crate::storage::{is_cloud_placeholder, is_hidden_or_system, FileStat};
+use chrono::{DateTime, Utc};
+use color_eyre::eyre::{bail, Context, ContextCompat, Result};
+use std::fs::{self, Metadata};
+use std::io;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+use tracing::info;
+
+/// Explain how a run with the given options would handle a single file: the timestamps read, the date picked from
+/// them, its period and destination, and what would keep it in place
+pub fn explain_file(explain_args: &ExplainArgs) -> Result<()> {
+    let args = enrich_arguments(&explain_args.args);
+    let now = args.now.unwrap_or_else(get_reference_time);
+
+    let absolute_path = std::path::absolute(&explain_args.file)
+        .with_context(|| format!("Failed to resolve file: {}", explain_args.file.display()))?;
+    let path = find_in_sources(&get_absolute_sources(&args)?, &absolute_path)
+        .with_context(|| format!("Not inside a source directory: {}", explain_args.file.display()))?;
+    let metadata = fs::metadata(&path).with_context(|| format!("Failed to get metadata for: {}", path.display()))?;
+    if !metadata.is_file() {
+        bail!("Not a file: {}", path.display());
+    }
+    let source_root = get_source_root(&args, &path);
+    info!("File: {}", path.display());
+    info!("Source directory: {}", source_root.display());
+
+    // The closest folder config may override the options of the run
+    let dir = path.parent().unwrap_or(source_root);
+    let settings = DirSettingsResolver::new(&args).resolve(dir)?;
+    info!("Grouping by: {}", settings.group_by.map_or("None".to_string(), |e| format!("{e:?}")));
+
+    info!("Timestamps:");
+    for (date_type, timestamp) in read_timestamps(&metadata) {
+        let selected = if settings.file_date_types.contains(&date_type) { "" } else { " (not selected)" };
+        match timestamp {
+            Ok(timestamp) => info!("   {:?}: {}{}", date_type, DateTime::<Utc>::from(timestamp), selected),
+            Err(e) => info!("   {:?}: unavailable ({}){}", date_type, e, selected),
+        }
+    }
+    let file_date = get_file_date(&path, &FileStat::from(&metadata), &settings.file_date_types)?;
+    info!("File date: {} (the most recent of {:?}, from {:?})", file_date, settings.file_date_types, get_date_sources(&metadata, &settings.file_date_types, file_date));
+
+    let group_folder = get_grouper(&args, &settings).map(|grouper| grouper.identifier(file_date));
+    if let Some(group_folder) = &group_folder {
+        info!("Period: {}", group_folder);
+    }
+    let destination = calculate_dest_path(&path, source_root, &args.destination, group_folder.as_deref(), args.prefix_source_name, args.normalize_unicode, args.sanitize_names)?;
+    info!("Destination: {}", destination.display());
+
+    let skip_reason = get_skip_reason(&args, &path, source_root)
+        .or_else(|| (!args.include_hidden && is_hidden_or_system(&metadata)).then(|| "hidden or system file, see --include-hidden".to_string()))
+        .or_else(|| {
+            (args.cloud_placeholders == PlaceholderPolicy::Skip && is_cloud_placeholder(&metadata))
+                .then(|| "online-only cloud placeholder, see --cloud-placeholders".to_string())
+        })
+        .or_else(|| get_filter_reason(&settings, args.timestamp_tolerance, file_date, now));
+    let skip_reason = match (skip_reason, &args.filter_cmd) {
+        (None, Some(filter_cmd)) => (!run_filter_cmd(filter_cmd, &path)?).then(|| "excluded by --filter-cmd".to_string()),
+        (skip_reason, _) => skip_reason,
+    };
+    match skip_reason {
+        Some(reason) => info!("Result: stays in place, {}", reason),
+        None => {
+            info!("Result: would be moved");
+            if args.keep_newest_per_dir {
+                info!("   unless it is the newest file of its folder, kept by --keep-newest-per-dir");
+            }
+            if let Some(keep_min) = args.keep_min_per_dir {
+                info!("   unless it is among the {} most recent file(s) of its folder kept by --keep-min-per-dir", keep_min);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn read_timestamps(metadata: &Metadata) -> [(FileDateType, io::Result<SystemTime>); 3] {
+    [
+        (FileDateType::Created, metadata.created()),
+        (FileDateType::Modified, metadata.modified()),
+        (FileDateType::Accessed, metadata.accessed()),
+    ]
+}
+
+/// Selected date types whose timestamp is the file date
+fn get_date_sources(metadata: &Metadata, date_types: &[FileDateType], file_date: DateTime<Utc>) -> Vec<FileDateType> {
+    read_timestamps(metadata).into_iter()
+        .filter(|(date_type, timestamp)| {
+            date_types.contains(date_type)
+                && timestamp.as_ref().is_ok_and(|&timestamp| DateTime::<Utc>::from(timestamp) == file_date)
+        })
+        .map(|(date_type, _)| date_type)
+        .collect()
+}
+
+/// Why the walk would not consider the file at all, if it would not
+fn get_skip_reason(args: &Args, path: &Path, source_root: &Path) -> Option<String> {
+    if path.file_name() == Some(DIR_CONFIG_FILE_NAME.as_ref()) {
+        return Some("folder configs are never moved".to_string());
+    }
+    if !args.include_macos_metadata {
+        let relative_path = path.strip_prefix(source_root).unwrap_or(path);
+        let is_inside_metadata_dir = relative_path.parent()
+            .is_some_and(|parent| parent.iter().any(|name| is_macos_metadata(name, true)));
+        if is_inside_metadata_dir || path.file_name().is_some_and(|name| is_macos_metadata(name, false)) {
+            return Some("macOS metadata, see --include-macos-metadata".to_string());
+        }
+    }
+    if args.sync_conflicts == SyncConflictPolicy::Skip && path.file_name().is_some_and(is_sync_conflict_copy) {
+        return Some("conflicted copy of a cloud-sync client, see --sync-conflicts".to_string());
+    }
+    if args.obsidian {
+        let relative_path = path.strip_prefix(source_root).unwrap_or(path);
+        if relative_path.parent().is_some_and(|parent| parent.iter().any(is_obsidian_folder)) {
+            return Some("inside the settings or trash folder of an Obsidian vault".to_string());
+        }
+    }
+    if let Some(ignored_path) = args.ignored_paths.iter().flatten().find(|ignored_path| path.starts_with(ignored_path)) {
+        return Some(format!("inside the ignored path {}", ignored_path.display()));
+    }
+    // Files directly inside their source folder are at depth 1
+    let depth = path.strip_prefix(source_root).map_or(0, |relative| relative.components().count());
+    if args.min_depth.is_some_and(|min_depth| depth < min_depth) {
+        return Some(format!("at depth {}, below --min-depth", depth));
+    }
+    if args.max_depth.is_some_and(|max_depth| depth > max_depth) {
+        return Some(format!("at depth {}, beyond --max-depth", depth));
+    }
+    None
+}
+
+/// Which filter would keep the file in place, if any
+fn get_filter_reason(settings: &DirSettings, tolerance: Option<Duration>, file_date: DateTime<Utc>, now: DateTime<Utc>) -> Option<String> {
+    let cutoff = get_tolerant_cutoff(settings.older_than, tolerance);
+    let grouper = settings.group_by.as_ref().map(|group_by| group_by as &dyn Grouper);
+    let filter = get_excluding_filter(file_date, grouper, settings.previous_period_only, cutoff, now)?;
+    Some(match filter {
+        ExcludingFilter::OlderThan => {
+            // Safe to unwrap: the filter only applies with a cutoff
+            format!("excluded by --older-than: dated {}, not before {}", file_date, cutoff.unwrap())
+        }
+        ExcludingFilter::PreviousPeriodOnly => format!("excluded by --previous-period-only: dated {}, in the current period", file_date),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::Cli;
+    use clap::Parser;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_get_skip_reason_depth() {
+        let args = Cli::parse_from(["chronomover", "-s", "/src", "-d", "/dst", "--min-depth", "2", "--max-depth", "3"]).args.unwrap();
+        let source_root = PathBuf::from("/src");
+
+        assert_eq!(get_skip_reason(&args, Path::new("/src/a.txt"), &source_root).unwrap(), "at depth 1, below --min-depth");
+        assert_eq!(get_skip_reason(&args, Path::new("/src/a/b.txt"), &source_root), None);
+        assert_eq!(get_skip_reason(&args, Path::new("/src/a/b/c/d.txt"), &source_root).unwrap(), "at depth 4, beyond --max-depth");
+    }
+
+    #[test]
+    fn test_get_skip_reason_macos_metadata() {
+        let args = Cli::parse_from(["chronomover", "-s", "/src", "-d", "/dst"]).args.unwrap();
+        let including_args = Cli::parse_from(["chronomover", "-s", "/src", "-d", "/dst", "--include-macos-metadata"]).args.unwrap();
+        let source_root = Path::new("/src");
+
+        assert!(get_skip_reason(&args, Path::new("/src/photos/._IMG_0001.jpg"), source_root).is_some());
+        assert!(get_skip_reason(&args, Path::new("/src/.Trashes/501/a.txt"), source_root).is_some());
+        assert_eq!(get_skip_reason(&including_args, Path::new("/src/photos/.DS_Store"), source_root), None);
+    }
+
+    #[test]
+    fn test_get_skip_reason_obsidian_folder() {
+        let args = Cli::parse_from(["chronomover", "-s", "/vault", "-d", "/vault/Archive", "--obsidian"]).args.unwrap();
+        let source_root = Path::new("/vault");
+
+        assert!(get_skip_reason(&args, Path::new("/vault/.obsidian/workspace.json"), source_root).is_some());
+        assert!(get_skip_reason(&args, Path::new("/vault/.trash/Old.md"), source_root).is_some());
+        assert_eq!(get_skip_reason(&args, Path::new("/vault/Notes/Plan.md"), source_root), None);
+    }
+
+    #[test]
+    fn test_get_skip_reason_ignored_path() {
+        let args = Cli::parse_from(["chronomover", "-s", "/src", "-d", "/dst", "--ignored-paths", "/src/keep"]).args.unwrap();
+
+        let reason = get_skip_reason(&args, Path::new("/src/keep/a.txt"), Path::new("/src"));
+
+        assert_eq!(reason.unwrap(), "inside the ignored path /src/keep");
+    }
+
+    #[test]
+    fn test_get_filter_reason() {
+        let now = "2025-06-15T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let file_date = "2025-06-01T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let args = Cli::parse_from(["chronomover", "-s", "/src", "-d", "/dst", "-g", "month", "--previous-period-only"]).args.unwrap();
+        let settings = DirSettings::from_args(&args);
+
+        let reason = get_filter_reason(&settings, None, file_date, now);
+
+        assert_eq!(reason.unwrap(), "excluded by --previous-period-only: dated 2025-06-01 00:00:00 UTC, in the current period");
+        assert_eq!(get_filter_reason(&settings, None, "2025-05-31T00:00:00Z".parse::<DateTime<Utc>>().unwrap(), now), None);
+    }
+}
@@ -0,0 +1,3207 @@
+use crate::catalog::{append_to_catalog, CatalogEntry};
+use crate::config::{DirSettings, DirSettingsResolver, DIR_CONFIG_FILE_NAME};
+use crate::manifest::{hash_file, ManifestWriter};
+use crate::glob::matches_path_glob;
+use crate::grouper::Grouper;
+use crate::model::{Args, CaseCollisionPolicy, DuplicatePolicy, JunctionPolicy, MoveOrder, PlaceholderPolicy, PreviewStyle, RetryableError, SortOrder, SyncConflictPolicy, TouchDestination, UnicodeNormalization};
+use crate::output::{emit_event, Event};
+use crate::preview::print_destination_tree;
+use crate::scan_state::ScanState;
+use crate::shorten::{shorten_dest_path, ShortenedPath, DEFAULT_MAX_PATH_LENGTH};
+use crate::spill::{estimate_memory, PlanSpill};
+use crate::storage::{FileStat, LinkKind, StorageBackend, StorageEntry, WalkOptions};
+use crate::period_index::append_to_period_indexes;
+use crate::report::{format_size, MirrorStats, PeriodStats, PhaseTimings, UNGROUPED_PERIOD};
+use crate::date;
+use crate::dupes::{separate_duplicates, Duplicate};
+use crate::hooks::{run_filter_cmd, run_post_file_hook};
+use crate::logging::SUMMARY_TARGET;
+use crate::obsidian::is_obsidian_folder;
+use chrono::{DateTime, Utc};
+use color_eyre::eyre::{bail, eyre, Context, ContextCompat, Result};
+use serde::{Deserialize, Serialize};
+use date::get_file_date;
+use std::cmp::Ordering as CmpOrdering;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::ffi::OsStr;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::iter;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, Mutex, PoisonError};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+use tracing::{debug, error, info, info_span, warn, Span};
+use unicode_normalization::UnicodeNormalization as _;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileToMove {
+    pub source: PathBuf,
+    pub destination: PathBuf,
+    pub file_date: DateTime<Utc>,
+    /// Size in bytes, read while scanning
+    pub size: u64,
+    /// Period folder the file is grouped into, if its folder is grouped
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub period: Option<String>,
+}
+
+/// A file that could not be evaluated or moved
+#[derive(Debug, Clone, Serialize)]
+pub struct FileError {
+    pub path: PathBuf,
+    pub error: String,
+}
+
+/// Files selected to be moved, plus the files that could not be evaluated while scanning
+#[derive(Debug, Default)]
+pub struct Plan {
+    pub files_to_move: Vec<FileToMove>,
+    /// Number of files planned to move, which batched runs keep after dropping the file lists
+    pub planned: usize,
+    pub errors: Vec<FileError>,
+    /// Files left in place by the ignored paths, date filters, `--keep-min-per-dir` or `--duplicates skip`
+    pub skipped: usize,
+    /// Files whose content is already in the destination, deleted by `delete_duplicates()` with `--duplicates delete`
+    pub duplicates: Vec<Duplicate>,
+    /// Destinations shortened by `--shorten-paths`
+    pub shortened: Vec<ShortenedPath>,
+    /// Duration of the scanning, date extraction and planning phases
+    pub timings: PhaseTimings,
+}
+
+/// Outcome of executing a plan (in dry-run mode, `moved` holds the files that would have been moved)
+#[derive(Debug, Default, Serialize)]
+pub struct MoveReport {
+    pub moved: Vec<FileToMove>,
+    pub errors: Vec<FileError>,
+    pub run_id: Option<String>,
+    /// Files and bytes moved into each destination period folder
+    pub periods: BTreeMap<String, PeriodStats>,
+    /// The same by destination root, so the period folders of different destinations are told apart
+    pub destination_periods: BTreeMap<PathBuf, BTreeMap<String, PeriodStats>>,
+    /// Files copied into each `--mirror` ahead of their move
+    pub mirrors: BTreeMap<PathBuf, MirrorStats>,
+    /// Planned files left in place because the run was cancelled
+    pub cancelled: usize,
+    /// Why the run stopped before moving every planned file, after `--max-errors` errors or the destination staying
+    /// unavailable. The report still describes the files handled until then.
+    pub aborted: Option<String>,
+    /// Planned files an aborted run left in place without trying to move them
+    pub left_in_place: Vec<PathBuf>,
+}
+
+impl MoveReport {
+    /// Number of files moved, still known after batched runs dropped the `moved` list
+    pub fn moved_count(&self) -> usize {
+        self.periods.values().map(|stats| stats.files).sum()
+    }
+}
+
+/// Longest path most Windows programs can open (MAX_PATH, including the terminating NUL character)
+const WINDOWS_MAX_PATH: usize = 260;
+
+/// How close to MAX_PATH a destination gets before a warning
+const LONG_PATH_WARNING_MARGIN: usize = 20;
+
+pub fn get_files_to_move(
+    args: &Args,
+    storage: &dyn StorageBackend,
+    now: DateTime<Utc>,
+    mut scan_state: Option<&mut ScanState>,
+) -> Result<Plan> {
+    info!("Finding files to move in target folder...");
+
+    let mut plan = Plan::default();
+    plan_files(args, storage, now, None, scan_state.as_deref_mut(), |whole_plan| {
+        plan = whole_plan;
+        Ok(())
+    })?;
+
+    let _plan_span = info_span!("plan").entered();
+    let plan_started_at = Instant::now();
+    if args.keep_newest_per_dir {
+        let kept_files;
+        (plan.files_to_move, kept_files) = retain_newest_file_per_dir(args, storage, plan.files_to_move);
+        plan.skipped += kept_files.len();
+        for file in &kept_files {
+            if let Some(scan_state) = scan_state.as_deref_mut() {
+                scan_state.mark_unsettled(&file.source);
+            }
+            info!(
+                path = %file.source.display(), status = "skipped",
+                "Keeping {} in place as the newest file of its folder", file.source.display()
+            );
+        }
+    }
+    if let Some(keep_min) = args.keep_min_per_dir {
+        let kept_files;
+        (plan.files_to_move, kept_files) = retain_min_files_per_dir(plan.files_to_move, keep_min, |dir| count_files_in_dir(storage, dir));
+        plan.skipped += kept_files.len();
+        for file in &kept_files {
+            if let Some(scan_state) = scan_state.as_deref_mut() {
+                scan_state.mark_unsettled(&file.source);
+            }
+            info!(
+                path = %file.source.display(), status = "skipped",
+                "Keeping {} in place to leave at least {} file(s) in its folder", file.source.display(), keep_min
+            );
+        }
+    }
+    if args.duplicates != DuplicatePolicy::Move && !plan.files_to_move.is_empty() {
+        let (files_to_move, duplicates, errors) = separate_duplicates(args, storage, std::mem::take(&mut plan.files_to_move));
+        plan.files_to_move = files_to_move;
+        plan.errors.extend(errors);
+        if args.duplicates == DuplicatePolicy::Skip {
+            plan.skipped += duplicates.len();
+            for duplicate in &duplicates {
+                if let Some(scan_state) = scan_state.as_deref_mut() {
+                    scan_state.mark_unsettled(&duplicate.path);
+                }
+                info!(
+                    path = %duplicate.path.display(), status = "skipped",
+                    "Keeping {} in place: duplicate of {}", duplicate.path.display(), duplicate.original.display()
+                );
+            }
+        } else {
+            plan.duplicates = duplicates;
+        }
+    }
+    if !plan.files_to_move.is_empty() && !is_over_thresholds(args, &plan.files_to_move) {
+        info!(
+            "Leaving the {} file(s) to move ({}) in place until they go over the --only-if-count-over or --only-if-size-over threshold",
+            plan.files_to_move.len(), format_size(plan.files_to_move.iter().map(|file| file.size).sum())
+        );
+        plan.skipped += plan.files_to_move.len();
+        if let Some(scan_state) = scan_state.as_deref_mut() {
+            for file in &plan.files_to_move {
+                scan_state.mark_unsettled(&file.source);
+            }
+        }
+        plan.files_to_move.clear();
+    }
+    if let Some(free_at_least) = args.free_at_least {
+        let left_files;
+        (plan.files_to_move, left_files) = select_oldest_files(plan.files_to_move, free_at_least);
+        plan.skipped += left_files.len();
+        if let Some(scan_state) = &mut scan_state {
+            for file in &left_files {
+                scan_state.mark_unsettled(&file.source);
+            }
+        }
+        let freed_bytes = plan.files_to_move.iter().map(|file| file.size).sum::<u64>();
+        if freed_bytes < free_at_least {
+            warn!(
+                "Moving all {} file(s) frees {}, short of the {} of --free-at-least",
+                plan.files_to_move.len(), format_size(freed_bytes), format_size(free_at_least)
+            );
+        } else {
+            info!(
+                "Moving the {} oldest file(s) frees {}, leaving {} newer file(s) in place",
+                plan.files_to_move.len(), format_size(freed_bytes), left_files.len()
+            );
+        }
+    }
+    if let Some(sort) = args.sort {
+        sort_files_to_move(&mut plan.files_to_move, sort);
+    }
+    resolve_case_collisions(args, storage, &mut plan.files_to_move, &mut ClaimedDestinations::new())?;
+    plan.planned = plan.files_to_move.len();
+    emit_plan_events(&plan);
+
+    info!(count = plan.planned, "Found {} file(s) to move", plan.planned);
+
+    plan.timings.plan = plan_started_at.elapsed();
+    Ok(plan)
+}
+
+/// Plan and move the files in batches of `batch_size` candidate files, so memory use stays bounded and moving starts
+/// before the whole source folder was scanned. Returns the totals of the run, without the file lists.
+pub fn plan_and_move_in_batches(
+    args: &Args,
+    storage: &dyn StorageBackend,
+    now: DateTime<Utc>,
+    batch_size: usize,
+    scan_state: Option<&mut ScanState>,
+) -> Result<(Plan, MoveReport)> {
+    info!("Finding and moving files in batches of {} file(s)...", batch_size);
+
+    let mut totals = Plan::default();
+    let mut report = MoveReport::default();
+    let mut manifest = None;
+    let mut claimed_destinations = ClaimedDestinations::new();
+    // The size of the run is only known once every batch was planned
+    let mut progress = MoveProgress::new(None);
+    let planned = plan_files(args, storage, now, Some(batch_size), scan_state, |mut batch| {
+        emit_plan_events(&batch);
+
+        let _move_span = info_span!("move", dry_run = args.dry_run).entered();
+        let move_started_at = Instant::now();
+        if manifest.is_none() && !args.dry_run && !batch.files_to_move.is_empty() {
+            let created_manifest = ManifestWriter::create(Utc::now())?;
+            report.run_id = Some(created_manifest.run_id.clone());
+            manifest = Some(created_manifest);
+        }
+        resolve_case_collisions(args, storage, &mut batch.files_to_move, &mut claimed_destinations)?;
+        move_batch(args, storage, &batch.files_to_move, args.dry_run, &mut progress, (manifest.as_mut(), &mut report), None)?;
+        // Only the counts of the moved files are kept
+        report.moved.clear();
+
+        totals.planned += batch.files_to_move.len();
+        totals.errors.append(&mut batch.errors);
+        totals.skipped += batch.skipped;
+        totals.shortened.append(&mut batch.shortened);
+        totals.timings.scan += batch.timings.scan;
+        totals.timings.date_extraction += batch.timings.date_extraction;
+        totals.timings.move_files += move_started_at.elapsed();
+        match &report.aborted {
+            // Stops the scan, the sources left are not planned
+            Some(abort_reason) => Err(eyre!(abort_reason.clone())),
+            None => Ok(()),
+        }
+    });
+    if report.aborted.is_none() {
+        planned?;
+    }
+
+    log_move_summary(args.dry_run, &report, manifest.as_ref());
+    Ok((totals, report))
+}
+
+/// Number of candidate files scanned between two checks of the `--max-memory` cap
+const MEMORY_CHECK_BATCH_SIZE: usize = 1000;
+
+/// Number of walked files between two `ScanProgress` events
+const SCAN_PROGRESS_INTERVAL: usize = 1000;
+
+/// Number of entries a subfolder walked by `walk_source_folder_in_parallel()` may get ahead of the scan
+const PARALLEL_WALK_BUFFER: usize = 1024;
+
+/// Plan the whole source folder like `get_files_to_move()`, but spill the planned files to a temporary file once they
+/// would take more than `max_memory` bytes, then move them in chunks read back from it. Returns the totals of the run,
+/// without the file lists.
+pub fn plan_and_move_with_memory_cap(
+    args: &Args,
+    storage: &dyn StorageBackend,
+    now: DateTime<Utc>,
+    max_memory: u64,
+    scan_state: Option<&mut ScanState>,
+) -> Result<(Plan, MoveReport)> {
+    info!("Finding files to move in target folder...");
+
+    let mut totals = Plan::default();
+    let mut files_in_memory = Vec::new();
+    let mut memory_used = 0;
+    let mut planned_bytes = 0;
+    let mut spill: Option<PlanSpill> = None;
+    plan_files(args, storage, now, Some(MEMORY_CHECK_BATCH_SIZE), scan_state, |mut batch| {
+        emit_plan_events(&batch);
+        totals.planned += batch.files_to_move.len();
+        planned_bytes += batch.files_to_move.iter().map(|file| file.size).sum::<u64>();
+        totals.errors.append(&mut batch.errors);
+        totals.skipped += batch.skipped;
+        totals.shortened.append(&mut batch.shortened);
+        totals.timings.scan += batch.timings.scan;
+        totals.timings.date_extraction += batch.timings.date_extraction;
+
+        if let Some(spill) = spill.as_mut() {
+            return spill.append(&batch.files_to_move);
+        }
+        memory_used += batch.files_to_move.iter().map(estimate_memory).sum::<u64>();
+        files_in_memory.append(&mut batch.files_to_move);
+        if memory_used > max_memory {
+            let mut created_spill = PlanSpill::create()?;
+            info!("Planned files exceed the memory cap, spilling them to {}", created_spill.path.display());
+            created_spill.append(&files_in_memory)?;
+            files_in_memory = Vec::new();
+            spill = Some(created_spill);
+        }
+        Ok(())
+    })?;
+    info!(count = totals.planned, "Found {} file(s) to move", totals.planned);
+
+    let _move_span = info_span!("move", dry_run = args.dry_run).entered();
+    let move_started_at = Instant::now();
+    if totals.planned > 0 {
+        info!("\nMoving files{}...", if args.dry_run { " (DRY RUN)" } else { "" } );
+    }
+    let mut manifest = if args.dry_run || totals.planned == 0 {
+        None
+    } else {
+        Some(ManifestWriter::create(Utc::now())?)
+    };
+    let mut report = MoveReport {
+        run_id: manifest.as_ref().map(|manifest| manifest.run_id.clone()),
+        ..MoveReport::default()
+    };
+    let mut progress = MoveProgress::new(Some((totals.planned, planned_bytes)));
+    match spill {
+        Some(spill) => {
+            let mut claimed_destinations = ClaimedDestinations::new();
+            spill.read_chunks(max_memory, |mut chunk| {
+                resolve_case_collisions(args, storage, &mut chunk, &mut claimed_destinations)?;
+                move_batch(args, storage, &chunk, args.dry_run, &mut progress, (manifest.as_mut(), &mut report), None)?;
+                // As in `plan_and_move_in_batches()`
+                report.moved.clear();
+                Ok(())
+            })?;
+        }
+        None => {
+            resolve_case_collisions(args, storage, &mut files_in_memory, &mut ClaimedDestinations::new())?;
+            move_batch(args, storage, &files_in_memory, args.dry_run, &mut progress, (manifest.as_mut(), &mut report), None)?;
+            report.moved.clear();
+        }
+    }
+    totals.timings.move_files = move_started_at.elapsed();
+
+    log_move_summary(args.dry_run, &report, manifest.as_ref());
+    Ok((totals, report))
+}
+
+/// Walk the source folder (or read the `--files-from` list) and plan the files to move, handing a plan to `handle_plan` every `batch_size` candidate
+/// files, or a single plan once the whole source folder was scanned without a batch size
+fn plan_files(
+    args: &Args,
+    storage: &dyn StorageBackend,
+    now: DateTime<Utc>,
+    batch_size: Option<usize>,
+    mut scan_state: Option<&mut ScanState>,
+    mut handle_plan: impl FnMut(Plan) -> Result<()>,
+) -> Result<()> {
+    let scan_span = info_span!("scan", sources = ?args.sources);
+    // The walkers stop once the walk is dropped, at the end of the scope
+    thread::scope(|scope| {
+        let walk: Box<dyn Iterator<Item = Result<StorageEntry, FileError>>> = match &args.files_from {
+            Some(files_from) => Box::new(read_listed_files(args, storage, open_file_list(files_from)?)?),
+            None if args.jobs > 1 => Box::new(keep_source_files(walk_source_folder_in_parallel(scope, args, storage)).map(Ok)),
+            None => Box::new(walk_source_files(args, storage).map(Ok)),
+        };
+        let mut scanned = 0;
+        let mut walk = walk.inspect(|_| {
+            scanned += 1;
+            if scanned % SCAN_PROGRESS_INTERVAL == 0 {
+                emit_event(Event::ScanProgress { scanned });
+            }
+        });
+        let mut dir_settings_resolver = DirSettingsResolver::new(args);
+        let mut planned = 0;
+        loop {
+            let (plan, is_walk_over) = scan_span.in_scope(|| {
+                let batch_size = batch_size.unwrap_or(usize::MAX);
+                scan_batch(args, storage, now, &mut walk, &mut dir_settings_resolver, scan_state.as_deref_mut(), (batch_size, planned))
+            });
+            planned += plan.files_to_move.len();
+            handle_plan(plan)?;
+            if is_walk_over {
+                return Ok(());
+            }
+        }
+    })
+}
+
+/// Plan only the first `candidate_count` files of the source folder, e.g. to show a few example moves
+pub fn get_sample_files_to_move(args: &Args, storage: &dyn StorageBackend, now: DateTime<Utc>, candidate_count: usize) -> Plan {
+    let mut dir_settings_resolver = DirSettingsResolver::new(args);
+    let mut walk = walk_source_files(args, storage).map(Ok);
+    scan_batch(args, storage, now, &mut walk, &mut dir_settings_resolver, None, (candidate_count, 0)).0
+}
+
+/// Files of the source folder that may be moved, skipping unreadable entries and the folder configs
+fn walk_source_files<'a>(args: &'a Args, storage: &'a dyn StorageBackend) -> impl Iterator<Item = StorageEntry> + 'a {
+    keep_source_files(walk_source_folder(args, storage))
+}
+
+fn keep_source_files(walk: impl Iterator<Item = Result<StorageEntry>>) -> impl Iterator<Item = StorageEntry> {
+    walk.filter_map(Result::ok)
+        .filter(|e| e.is_file && e.path.file_name() != Some(DIR_CONFIG_FILE_NAME.as_ref()))
+}
+
+fn open_file_list(files_from: &Path) -> Result<Box<dyn BufRead>> {
+    if files_from == Path::new("-") {
+        return Ok(Box::new(io::stdin().lock()));
+    }
+    let file = File::open(files_from)
+        .with_context(|| format!("Failed to open file list: {}", files_from.display()))?;
+    Ok(Box::new(BufReader::new(file)))
+}
+
+/// Files of the `--files-from` list, resolved against the source folder they are inside of. Listed paths outside
+/// every source folder, missing or not regular files are reported as errors, and the folder configs are skipped.
+fn read_listed_files<'a>(
+    args: &'a Args,
+    storage: &'a dyn StorageBackend,
+    reader: impl BufRead + 'a,
+) -> Result<impl Iterator<Item = Result<StorageEntry, FileError>> + 'a> {
+    let absolute_sources = get_absolute_sources(args)?;
+    let files = parse_file_list(reader)?
+        .filter(|path| path.as_ref().map_or(true, |path| path.file_name() != Some(DIR_CONFIG_FILE_NAME.as_ref())))
+        .map(move |path| {
+            let path = path?;
+            let to_error = |error: &str| FileError { path: path.clone(), error: error.to_string() };
+            let absolute_path = std::path::absolute(&path).map_err(|e| to_error(&e.to_string()))?;
+            let source_path = find_in_sources(&absolute_sources, &absolute_path)
+                .ok_or_else(|| to_error("Not inside a source directory"))?;
+            let options = WalkOptions { max_depth: Some(0), follow_links: args.follow_symbolic_links, sorted: false };
+            let entry = storage.walk(&source_path, options, Box::new(|_| true))
+                .next()
+                // Safe to unwrap: a walk always yields its root, or the error reading it
+                .unwrap()
+                .map_err(|e| to_error(&e.to_string()))?;
+            if !entry.is_file {
+                return Err(to_error("Not a file"));
+            }
+            Ok(entry)
+        });
+    Ok(files)
+}
+
+/// The source folders as given, paired with their absolute path
+pub fn get_absolute_sources(args: &Args) -> Result<Vec<(&PathBuf, PathBuf)>> {
+    args.sources.iter()
+        .map(|source| Ok((source, std::path::absolute(source)?)))
+        .collect::<io::Result<Vec<_>>>()
+        .context("Failed to resolve the source directories")
+}
+
+/// Rebuild an absolute path from the source folder containing it as given, so it is grouped and reported like a
+/// walked file
+pub fn find_in_sources(absolute_sources: &[(&PathBuf, PathBuf)], absolute_path: &Path) -> Option<PathBuf> {
+    absolute_sources.iter().find_map(|(source, absolute_source)| {
+        absolute_path.strip_prefix(absolute_source).ok().map(|relative| source.join(relative))
+    })
+}
+
+/// Split a file list into paths, one per line, or one per NUL byte when the list contains any in its first buffer
+/// (as written by `find -print0` or `fd -0`, which allows newlines in file names). Stops at the first read error.
+fn parse_file_list(mut reader: impl BufRead) -> Result<impl Iterator<Item = Result<PathBuf, FileError>>> {
+    let separator = if reader.fill_buf().context("Failed to read file list")?.contains(&0) { b'\0' } else { b'\n' };
+    let mut has_failed = false;
+    let paths = reader.split(separator)
+        .map_while(move |line| {
+            if has_failed {
+                return None;
+            }
+            has_failed = line.is_err();
+            Some(line)
+        })
+        .filter_map(move |line| {
+            let mut line = match line {
+                Ok(line) => line,
+                Err(e) => return Some(Err(FileError { path: PathBuf::from("-"), error: format!("Failed to read file list: {e}") })),
+            };
+            if separator == b'\n' && line.last() == Some(&b'\r') {
+                line.pop();
+            }
+            if line.is_empty() {
+                return None;
+            }
+            Some(String::from_utf8(line)
+                .map(PathBuf::from)
+                .map_err(|e| FileError {
+                    path: PathBuf::from(String::from_utf8_lossy(e.as_bytes()).into_owned()),
+                    error: "File name is not valid UTF-8".to_string(),
+                }))
+        });
+    Ok(paths)
+}
+
+/// Plan the next `batch_size` files of the walk, returning the plan and whether the walk is over
+fn scan_batch(
+    args: &Args,
+    storage: &dyn StorageBackend,
+    now: DateTime<Utc>,
+    walk: &mut impl Iterator<Item = Result<StorageEntry, FileError>>,
+    dir_settings_resolver: &mut DirSettingsResolver,
+    mut scan_state: Option<&mut ScanState>,
+    (batch_size, planned_before): (usize, usize),
+) -> (Plan, bool) {
+    let mut plan = Plan::default();
+    let scan_started_at = Instant::now();
+    let mut candidates = Vec::new();
+    let mut is_walk_over = true;
+    for entry in walk.by_ref() {
+        // The files walked so far are still planned, for the summary of the partial run
+        if args.cancellation.is_cancelled() {
+            break;
+        }
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(error) => {
+                warn!(path = %error.path.display(), error = %error.error, "Skipping listed file {}: {}", error.path.display(), error.error);
+                plan.errors.push(error);
+                continue;
+            }
+        };
+        let path = entry.path.as_path();
+
+        // Skip files in ignored paths
+        let is_inside_ignored_folder = args.ignored_paths.as_ref()
+            .is_some_and(|ignored_paths| ignored_paths.iter().any(|ignored_path| path.starts_with(ignored_path)));
+        if is_inside_ignored_folder {
+            debug!(path = %path.display(), status = "skipped", "Skipping {}: inside an ignored path", path.display());
+            plan.skipped += 1;
+            continue;
+        }
+
+        // Get the grouping and filters of the file's folder, which its folder configs may override
+        let dir = path.parent().unwrap_or(get_source_root(args, path));
+        match dir_settings_resolver.resolve(dir) {
+            Ok(settings) => {
+                if let Some(scan_state) = scan_state.as_deref_mut()
+                    && scan_state.is_unchanged(dir, &settings, now) {
+                        debug!(path = %path.display(), status = "skipped", "Skipping {}: folder unchanged since the last incremental run", path.display());
+                        plan.skipped += 1;
+                        continue;
+                    }
+                candidates.push((entry, settings));
+            }
+            Err(e) => plan.errors.push(FileError { path: path.to_path_buf(), error: format!("{e:#}") }),
+        }
+        if candidates.len() >= batch_size {
+            is_walk_over = false;
+            break;
+        }
+    }
+    plan.timings.scan = scan_started_at.elapsed();
+
+    // Reading timestamps is latency-bound on network filesystems, and so is the `--filter-cmd`, so they run on the
+    // `--jobs` threads while the results are still evaluated in walk order. The command only runs for the files the
+    // date filters keep.
+    let date_extraction_started_at = Instant::now();
+    let evaluate_candidate = |(entry, settings): &(StorageEntry, DirSettings)| {
+        let file_date_and_stat = read_file_date_and_stat(storage, entry, settings);
+        let filter_cmd_verdict = match (&args.filter_cmd, &file_date_and_stat) {
+            (Some(filter_cmd), Ok((file_datetime, stat)))
+                if !is_skipped_placeholder(args, stat) && passes_date_filters(args, settings, *file_datetime, now) => {
+                Some(run_filter_cmd(filter_cmd, &entry.path))
+            }
+            _ => None,
+        };
+        (file_date_and_stat, filter_cmd_verdict)
+    };
+    // Safe to unwrap: the handler never fails
+    run_in_order(&candidates, args.jobs, evaluate_candidate, |index, (file_date_and_stat, filter_cmd_verdict)| {
+        let (entry, settings) = &candidates[index];
+        let path = entry.path.as_path();
+        let source_root = get_source_root(args, path);
+        match (file_date_and_stat, filter_cmd_verdict) {
+            (Ok((_, stat)), _) if is_skipped_placeholder(args, &stat) => {
+                debug!(path = %path.display(), status = "skipped", "Skipping {}: online-only cloud placeholder", path.display());
+                plan.skipped += 1;
+                // The file may be downloaded by next time, whatever the folder
+                if let Some(scan_state) = scan_state.as_deref_mut() {
+                    scan_state.mark_unsettled(path);
+                }
+            }
+            (Ok(_), Some(Err(e))) => {
+                warn!(path = %path.display(), error = %e, "Failed to run the filter command for {}: {}", path.display(), e);
+                plan.errors.push(FileError { path: path.to_path_buf(), error: format!("{e:#}") });
+            }
+            (Ok(_), Some(Ok(false))) => {
+                debug!(path = %path.display(), status = "skipped", "Skipping {}: excluded by --filter-cmd", path.display());
+                plan.skipped += 1;
+                // The command may decide otherwise next time, whatever the folder
+                if let Some(scan_state) = scan_state.as_deref_mut() {
+                    scan_state.mark_unsettled(path);
+                }
+            }
+            (Ok((file_datetime, FileStat { size, .. })), _) => {
+                // Determine if file should be moved
+                if passes_date_filters(args, settings, file_datetime, now) {
+                    // Get the group identifier if grouping is enabled
+                    let group_folder = get_grouper(args, settings).map(|grouper| grouper.identifier(file_datetime));
+
+                    // Calculate destination path
+                    match calculate_dest_path(
+                        path,
+                        source_root,
+                        get_destination_root(args, path),
+                        group_folder.as_deref(),
+                        args.prefix_source_name,
+                        args.normalize_unicode,
+                        args.sanitize_names,
+                    ) {
+                        Ok(dest_path) => {
+                            let dest_path = match shorten_planned_dest_path(args, path, group_folder.as_deref(), &dest_path) {
+                                Some(shortened) => {
+                                    debug!(path = %path.display(), destination = %shortened.display(), "Shortened the destination of {} to {}", path.display(), shortened.display());
+                                    plan.shortened.push(ShortenedPath { source: path.to_path_buf(), original: dest_path, shortened: shortened.clone() });
+                                    shortened
+                                }
+                                None => dest_path,
+                            };
+                            info!(path = %path.display(), "{}. {}",
+                                planned_before + plan.files_to_move.len() + 1,
+                                path.display()
+                            );
+                            debug!(
+                                path = %path.display(), file_date = %file_datetime, destination = %dest_path.display(),
+                                "   dated {}, destination: {}", file_datetime, dest_path.display()
+                            );
+
+                            let file_to_move = FileToMove {
+                                source: path.to_path_buf(),
+                                destination: dest_path,
+                                file_date: file_datetime,
+                                size,
+                                period: group_folder,
+                            };
+                            plan.files_to_move.push(file_to_move);
+                            if let Some(scan_state) = scan_state.as_deref_mut() {
+                                scan_state.record_planned(path.parent().unwrap_or(source_root), settings, now);
+                            }
+                        }
+                        Err(e) => {
+                            warn!(path = %path.display(), error = %e, "Failed to calculate destination for {}: {}", path.display(), e);
+                            plan.errors.push(FileError { path: path.to_path_buf(), error: format!("{e:#}") });
+                        }
+                    }
+                } else {
+                    debug!(
+                        path = %path.display(), file_date = %file_datetime, status = "skipped",
+                        "Skipping {}: dated {}, excluded by filters", path.display(), file_datetime
+                    );
+                    plan.skipped += 1;
+                    if let Some(scan_state) = scan_state.as_deref_mut() {
+                        scan_state.record_skipped(path.parent().unwrap_or(source_root), settings, file_datetime, now);
+                    }
+                }
+            }
+            (Err(e), _) => {
+                warn!(path = %path.display(), error = %e, "Failed to get file date for {}: {}", path.display(), e);
+                plan.errors.push(FileError { path: path.to_path_buf(), error: format!("{e:#}") });
+            }
+        }
+        Ok(())
+    }).unwrap();
+    plan.timings.date_extraction = date_extraction_started_at.elapsed();
+    if let Some(scan_state) = scan_state {
+        for error in &plan.errors {
+            scan_state.mark_unsettled(&error.path);
+        }
+    }
+
+    (plan, is_walk_over)
+}
+
+/// Read the date and stat of a candidate file, from a single metadata read (read with the walked entry on Windows)
+fn read_file_date_and_stat(storage: &dyn StorageBackend, entry: &StorageEntry, settings: &DirSettings) -> Result<(DateTime<Utc>, FileStat)> {
+    let stat = match entry.stat {
+        Some(stat) => stat,
+        None => storage.stat(&entry.path).with_context(|| format!("Failed to get metadata for: {}", entry.path.display()))?,
+    };
+    let file_date = get_file_date(&entry.path, &stat, &settings.file_date_types)?;
+    Ok((file_date, stat))
+}
+
+/// Whether a file is a cloud placeholder left in place by `--cloud-placeholders skip`
+fn is_skipped_placeholder(args: &Args, stat: &FileStat) -> bool {
+    stat.is_placeholder && args.cloud_placeholders == PlaceholderPolicy::Skip
+}
+
+/// Whether the planned files go over both `--only-if-count-over` and `--only-if-size-over`, when set
+fn is_over_thresholds(args: &Args, files_to_move: &[FileToMove]) -> bool {
+    let is_over_count = args.only_if_count_over.is_none_or(|count| files_to_move.len() > count);
+    let is_over_size = args.only_if_size_over.is_none_or(|bytes| files_to_move.iter().map(|file| file.size).sum::<u64>() > bytes);
+    is_over_count && is_over_size
+}
+
+/// Keep the oldest files until their sizes add up to `bytes`, for `--free-at-least`. Returns them oldest first, along
+/// with the files left out.
+fn select_oldest_files(mut files_to_move: Vec<FileToMove>, bytes: u64) -> (Vec<FileToMove>, Vec<FileToMove>) {
+    sort_files_to_move(&mut files_to_move, SortOrder::Date);
+    let mut selected_bytes = 0;
+    let selected_count = files_to_move.iter()
+        .take_while(|file| {
+            let is_needed = selected_bytes < bytes;
+            selected_bytes += file.size;
+            is_needed
+        })
+        .count();
+    let left_files = files_to_move.split_off(selected_count);
+    (files_to_move, left_files)
+}
+
+/// Sort the plan, breaking ties by source path so the order never depends on the filesystem
+fn sort_files_to_move(files_to_move: &mut [FileToMove], sort: SortOrder) {
+    files_to_move.sort_by(|a, b| {
+        let ordering = match sort {
+            SortOrder::Date => a.file_date.cmp(&b.file_date),
+            SortOrder::Name => a.source.file_name().cmp(&b.source.file_name()),
+            SortOrder::Size => a.size.cmp(&b.size),
+            SortOrder::Path => CmpOrdering::Equal,
+        };
+        ordering.then_with(|| a.source.cmp(&b.source))
+    });
+}
+
+/// Files of a batch in the `--order` they are moved in. The sort is stable, so files of the same size keep the plan
+/// (and `--sort`) order.
+fn order_files_to_move(files_to_move: &[FileToMove], order: MoveOrder) -> Vec<FileToMove> {
+    let mut ordered_files = files_to_move.to_vec();
+    match order {
+        MoveOrder::SmallestFirst => ordered_files.sort_by_key(|file| file.size),
+        MoveOrder::LargestFirst => ordered_files.sort_by_key(|file| std::cmp::Reverse(file.size)),
+    }
+    ordered_files
+}
+
+fn emit_plan_events(plan: &Plan) {
+    for file in &plan.files_to_move {
+        emit_event(Event::FilePlanned(file));
+    }
+    for error in &plan.errors {
+        emit_event(Event::FileFailed(error));
+    }
+}
+
+/// Split the plan so that no source directory ends up with fewer than `keep_min` files, keeping the most recent
+/// candidates in place. Returns the files still to move and the files kept in place.
+fn retain_min_files_per_dir(
+    files_to_move: Vec<FileToMove>,
+    keep_min: usize,
+    count_files_in_dir: impl Fn(&Path) -> usize,
+) -> (Vec<FileToMove>, Vec<FileToMove>) {
+    let mut candidates_by_dir: HashMap<&Path, Vec<usize>> = HashMap::new();
+    for (index, file) in files_to_move.iter().enumerate() {
+        if let Some(parent) = file.source.parent() {
+            candidates_by_dir.entry(parent).or_default().push(index);
+        }
+    }
+
+    let mut indexes_to_keep = HashSet::new();
+    for (dir, mut candidates) in candidates_by_dir {
+        let files_left_behind = count_files_in_dir(dir).saturating_sub(candidates.len());
+        let keep_count = keep_min.saturating_sub(files_left_behind).min(candidates.len());
+
+        candidates.sort_by_key(|&index| std::cmp::Reverse(files_to_move[index].file_date));
+        indexes_to_keep.extend(candidates.into_iter().take(keep_count));
+    }
+
+    let (to_move, kept): (Vec<_>, Vec<_>) = files_to_move.into_iter()
+        .enumerate()
+        .partition(|(index, _)| !indexes_to_keep.contains(index));
+
+    (
+        to_move.into_iter().map(|(_, file)| file).collect(),
+        kept.into_iter().map(|(_, file)| file).collect(),
+    )
+}
+
+/// Split the plan so that every source directory keeps its newest file, for `--keep-newest-per-dir`: the most recent
+/// candidate of a directory stays, unless a file staying anyway is newer. Returns the files still to move and the files
+/// kept in place.
+fn retain_newest_file_per_dir(args: &Args, storage: &dyn StorageBackend, files_to_move: Vec<FileToMove>) -> (Vec<FileToMove>, Vec<FileToMove>) {
+    let mut newest_by_dir: HashMap<&Path, usize> = HashMap::new();
+    for (index, file) in files_to_move.iter().enumerate() {
+        if let Some(parent) = file.source.parent() {
+            let newest = newest_by_dir.entry(parent).or_insert(index);
+            if file.file_date > files_to_move[*newest].file_date {
+                *newest = index;
+            }
+        }
+    }
+
+    let candidates = files_to_move.iter().map(|file| file.source.as_path()).collect::<HashSet<_>>();
+    let mut resolver = DirSettingsResolver::new(args);
+    let indexes_to_keep = newest_by_dir.into_iter()
+        .filter(|&(dir, index)| {
+            // Files the walk leaves out, like macOS metadata, are no anchor
+            let file_date_types = resolver.resolve(dir).map_or_else(|_| args.file_date_types.clone(), |settings| settings.file_date_types);
+            let Ok(entries) = storage.list_dir(dir) else {
+                return true;
+            };
+            !entries.filter_map(Result::ok)
+                .filter(|entry| entry.is_file && !candidates.contains(entry.path.as_path()))
+                .filter(|entry| args.include_macos_metadata || !entry.path.file_name().is_some_and(|name| is_macos_metadata(name, false)))
+                .filter_map(|entry| {
+                    let stat = entry.stat.map_or_else(|| storage.stat(&entry.path), Ok).ok()?;
+                    get_file_date(&entry.path, &stat, &file_date_types).ok()
+                })
+                .any(|file_date| file_date > files_to_move[index].file_date)
+        })
+        .map(|(_, index)| index)
+        .collect::<HashSet<_>>();
+
+    let (to_move, kept): (Vec<_>, Vec<_>) = files_to_move.into_iter()
+        .enumerate()
+        .partition(|(index, _)| !indexes_to_keep.contains(index));
+
+    (
+        to_move.into_iter().map(|(_, file)| file).collect(),
+        kept.into_iter().map(|(_, file)| file).collect(),
+    )
+}
+
+fn count_files_in_dir(storage: &dyn StorageBackend, dir: &Path) -> usize {
+    storage.list_dir(dir)
+        .map(|entries| entries
+            .filter_map(Result::ok)
+            .filter(|entry| entry.is_file)
+            .count())
+        .unwrap_or(0)
+}
+
+/// Walk the source folders one after the other
+fn walk_source_folder<'a>(args: &'a Args, storage: &'a dyn StorageBackend) -> impl Iterator<Item = Result<StorageEntry>> + 'a {
+    args.sources.iter()
+        .flat_map(|source| {
+            // A sorted plan is also scanned in a stable order, so the scan output doesn't differ between runs either
+            let options = WalkOptions { max_depth: args.max_depth, sorted: args.sort.is_some(), ..WalkOptions::default() };
+            walk_following_links(args, storage, source, options, args.min_depth.unwrap_or(0))
+        })
+        .map(|e| e.map_err(Into::into))
+}
+
+/// Like `walk_source_folder()`, but walking each subfolder of the source folders on one of the `--jobs` threads, which
+/// speeds up listing network filesystems. The entries still come in the order of a single-threaded walk, as each
+/// subfolder is walked ahead by at most `PARALLEL_WALK_BUFFER` entries. Walks following links stay single-threaded,
+/// since a link looping back above a subfolder is only detected within the walk of its source folder.
+fn walk_source_folder_in_parallel<'scope, 'env>(
+    scope: &'scope thread::Scope<'scope, 'env>,
+    args: &'env Args,
+    storage: &'env dyn StorageBackend,
+) -> Box<dyn Iterator<Item = Result<StorageEntry>> + 'scope> {
+    let follows_any_link = args.follow_symbolic_links || args.junctions == JunctionPolicy::Follow;
+    if follows_any_link || args.max_depth.is_some_and(|max_depth| max_depth <= 1) {
+        return Box::new(walk_source_folder(args, storage));
+    }
+    let min_depth = args.min_depth.unwrap_or(0);
+    let sorted = args.sort.is_some();
+
+    // The source folders and their direct entries are listed here, the subfolders being walked by the threads
+    let mut top_entries = Vec::new();
+    let mut subfolders = Vec::new();
+    for source in &args.sources {
+        let options = WalkOptions { max_depth: Some(1), sorted, ..WalkOptions::default() };
+        for entry in walk_following_links(args, storage, source, options, 0) {
+            let is_subfolder = entry.as_ref().is_ok_and(|entry| entry.depth == 1 && entry.is_dir);
+            if let (true, Ok(entry)) = (is_subfolder, &entry) {
+                subfolders.push(entry.path.clone());
+            }
+            top_entries.push((entry, is_subfolder));
+        }
+    }
+
+    let (senders, receivers): (Vec<_>, Vec<_>) = subfolders.iter()
+        .map(|_| {
+            let (sender, receiver) = mpsc::sync_channel(PARALLEL_WALK_BUFFER);
+            (Mutex::new(Some(sender)), receiver)
+        })
+        .unzip();
+    let (subfolders, senders, next_index) = (Arc::new(subfolders), Arc::new(senders), Arc::new(AtomicUsize::new(0)));
+    for _ in 0..args.jobs.min(subfolders.len()) {
+        let (subfolders, senders, next_index, span) = (Arc::clone(&subfolders), Arc::clone(&senders), Arc::clone(&next_index), Span::current());
+        scope.spawn(move || {
+            let _span = span.enter();
+            loop {
+                let index = next_index.fetch_add(1, Ordering::Relaxed);
+                let Some(subfolder) = subfolders.get(index) else { break };
+                // Safe to unwrap: each subfolder is taken by a single thread
+                let sender = senders[index].lock().unwrap_or_else(PoisonError::into_inner).take().unwrap();
+                let options = WalkOptions { max_depth: args.max_depth.map(|max_depth| max_depth - 1), sorted, ..WalkOptions::default() };
+                // The subfolder itself was listed with its parent
+                for entry in walk_following_links(args, storage, subfolder, options, min_depth.saturating_sub(1).max(1)) {
+                    let entry = entry.map(|entry| StorageEntry { depth: entry.depth + 1, ..entry });
+                    // The scan is over once the walk is dropped
+                    if sender.send(entry).is_err() {
+                        return;
+                    }
+                }
+            }
+        });
+    }
+
+    let mut receivers = receivers.into_iter();
+    Box::new(top_entries.into_iter().flat_map(move |(entry, is_subfolder)| {
+        let subfolder_entries = if is_subfolder { receivers.next() } else { None };
+        iter::once(entry)
+            .filter(move |entry| entry.as_ref().map_or(true, |entry| entry.depth >= min_depth))
+            .chain(subfolder_entries.into_iter().flatten())
+            .map(|entry| entry.map_err(Into::into))
+    }))
+}
+
+/// Walk `root` from `min_depth` on, descending into the symbolic links and junctions allowed by
+/// `--follow-symbolic-links` and `--junctions`. Links that are not followed are left out, as a symbolic link to a file
+/// is never moved either, and so are hidden and system files and folders on Windows, unless `--include-hidden` is passed.
+fn walk_following_links<'a>(
+    args: &'a Args,
+    storage: &'a dyn StorageBackend,
+    root: &Path,
+    options: WalkOptions,
+    min_depth: usize,
+) -> impl Iterator<Item = io::Result<StorageEntry>> + 'a {
+    let follows_any_link = args.follow_symbolic_links || args.junctions == JunctionPolicy::Follow;
+    // The walked folder itself is always entered, even if it is a link or hidden
+    let keep = move |entry: &StorageEntry| {
+        entry.depth == 0
+            || (entry.link.is_none_or(|link| is_link_followed(args, link))
+                && !is_excluded_hidden_entry(args, entry)
+                && !is_excluded_macos_metadata(args, entry)
+                && !is_excluded_sync_conflict(args, entry)
+                && !is_excluded_obsidian_folder(args, entry))
+    };
+    storage.walk(root, WalkOptions { follow_links: follows_any_link, ..options }, Box::new(keep))
+        // Not a minimum depth of the walk, which would also keep the shallower links from the filter above
+        .filter(move |entry| entry.as_ref().map_or(true, |entry| entry.depth >= min_depth))
+}
+
+fn is_link_followed(args: &Args, link: LinkKind) -> bool {
+    match link {
+        LinkKind::Junction => args.junctions == JunctionPolicy::Follow,
+        LinkKind::Symbolic => args.follow_symbolic_links,
+    }
+}
+
+/// Whether a walked entry is a macOS metadata file or folder left out by the walk, logging it if so
+fn is_excluded_macos_metadata(args: &Args, entry: &StorageEntry) -> bool {
+    let is_excluded = !args.include_macos_metadata
+        && entry.path.file_name().is_some_and(|name| is_macos_metadata(name, entry.is_dir));
+    if is_excluded {
+        debug!(path = %entry.path.display(), status = "skipped", "Skipping {}: macOS metadata", entry.path.display());
+    }
+    is_excluded
+}
+
+/// Whether a file or folder name is metadata macOS leaves on the drives it uses: Finder's `.DS_Store`, the `._*`
+/// AppleDouble files holding resource forks on non-Apple filesystems, and the Spotlight index and trash folders
+pub fn is_macos_metadata(name: &OsStr, is_dir: bool) -> bool {
+    let Some(name) = name.to_str() else {
+        return false;
+    };
+    if is_dir {
+        name == ".Spotlight-V100" || name == ".Trashes"
+    } else {
+        name == ".DS_Store" || name.starts_with("._")
+    }
+}
+
+/// Whether a walked entry is a conflicted copy of a cloud-sync client left out by the walk, logging it if so
+fn is_excluded_sync_conflict(args: &Args, entry: &StorageEntry) -> bool {
+    let is_excluded = args.sync_conflicts == SyncConflictPolicy::Skip
+        && !entry.is_dir
+        && entry.path.file_name().is_some_and(is_sync_conflict_copy);
+    if is_excluded {
+        debug!(path = %entry.path.display(), status = "skipped", "Skipping {}: cloud-sync conflicted copy", entry.path.display());
+    }
+    is_excluded
+}
+
+/// Whether a file name is the conflicted copy a cloud-sync client creates when a file changed on two devices at once:
+/// `name (conflicted copy 2025-06-01).ext` or `name (Alice's conflicted copy 2025-06-01).ext` from Dropbox and
+/// Nextcloud, `name.sync-conflict-20250601-120000-ABCDEFG.ext` from Syncthing and `name_conflict-20250601-120000.ext`
+/// from ownCloud
+pub fn is_sync_conflict_copy(name: &OsStr) -> bool {
+    let Some(name) = name.to_str() else {
+        return false;
+    };
+    let lowercase_name = name.to_lowercase();
+    let is_conflicted_copy = lowercase_name.match_indices("conflicted copy").any(|(start, _)| {
+        let before = &lowercase_name[..start];
+        before.rfind(" (").is_some_and(|open| !before[open..].contains(')'))
+    });
+    let is_owncloud_conflict = lowercase_name.match_indices("_conflict-")
+        .any(|(start, pattern)| {
+            let date = &lowercase_name[start + pattern.len()..];
+            date.len() >= 8 && date.bytes().take(8).all(|byte| byte.is_ascii_digit())
+        });
+    is_conflicted_copy || is_owncloud_conflict || lowercase_name.contains(".sync-conflict-")
+}
+
+/// Whether a walked entry is the settings or trash folder of an Obsidian vault left out by the walk, logging it if so
+fn is_excluded_obsidian_folder(args: &Args, entry: &StorageEntry) -> bool {
+    let is_excluded = args.obsidian && entry.is_dir && entry.path.file_name().is_some_and(is_obsidian_folder);
+    if is_excluded {
+        debug!(path = %entry.path.display(), status = "skipped", "Skipping {}: Obsidian folder", entry.path.display());
+    }
+    is_excluded
+}
+
+/// Whether a walked entry is a hidden or system file or folder left out by the walk, logging it if so
+fn is_excluded_hidden_entry(args: &Args, entry: &StorageEntry) -> bool {
+    let is_excluded = !args.include_hidden && entry.is_hidden;
+    if is_excluded {
+        debug!(path = %entry.path.display(), status = "skipped", "Skipping {}: hidden or system file", entry.path.display());
+    }
+    is_excluded
+}
+
+/// Source folder a walked path was found in
+pub fn get_source_root<'a>(args: &'a Args, path: &Path) -> &'a Path {
+    // Safe to unwrap: walked paths start with the source they were walked from
+    args.sources.iter().find(|source| path.starts_with(source)).unwrap()
+}
+
+/// Destination root of a file: the destination of the first `--route-path` matching its path within its source, else
+/// of the first `--route` taking its extension, else the main destination
+pub fn get_destination_root<'a>(args: &'a Args, path: &Path) -> &'a Path {
+    let relative_path = args.sources.iter().find_map(|source| path.strip_prefix(source).ok());
+    if let Some(relative_path) = relative_path
+        && let Some(route) = args.path_routes.iter().find(|route| matches_path_glob(&route.pattern, relative_path)) {
+            return &route.destination;
+        }
+    let extension = path.extension().map(|extension| extension.to_string_lossy().to_lowercase());
+    extension
+        .and_then(|extension| args.routes.iter().find(|route| route.extensions.contains(&extension)))
+        .map_or(&args.destination, |route| &route.destination)
+}
+
+/// The main destination, then the destinations of the `--route-path` and `--route` rules, each once
+pub fn get_destination_roots(args: &Args) -> Vec<&Path> {
+    let mut roots = vec![args.destination.as_path()];
+    let route_destinations = args.path_routes.iter().map(|route| route.destination.as_path())
+        .chain(args.routes.iter().map(|route| route.destination.as_path()));
+    for destination in route_destinations {
+        if !roots.contains(&destination) {
+            roots.push(destination);
+        }
+    }
+    roots
+}
+
+/// Whether a file dated `file_date` passes the date filters of its folder
+fn passes_date_filters(args: &Args, settings: &DirSettings, file_date: DateTime<Utc>, now: DateTime<Utc>) -> bool {
+    should_move_file(
+        file_date,
+        get_grouper(args, settings),
+        settings.previous_period_only,
+        get_tolerant_cutoff(settings.older_than, args.timestamp_tolerance),
+        now,
+    )
+}
+
+/// Period scheme of the files of a folder: the custom grouper of a library run, or the grouping strategy of the folder
+pub fn get_grouper<'a>(args: &'a Args, settings: &'a DirSettings) -> Option<&'a dyn Grouper> {
+    match &args.grouper {
+        Some(grouper) => Some(grouper.as_ref()),
+        None => settings.group_by.as_ref().map(|group_by| group_by as &dyn Grouper),
+    }
+}
+
+/// Filter keeping a file in place
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ExcludingFilter {
+    OlderThan,
+    PreviousPeriodOnly,
+}
+
+/// `--older-than` cutoff pushed later by the `--timestamp-tolerance`, so files whose coarse timestamps (2-second steps
+/// on FAT32 and exFAT) land just after the cutoff are still moved
+pub fn get_tolerant_cutoff(older_than: Option<DateTime<Utc>>, tolerance: Option<Duration>) -> Option<DateTime<Utc>> {
+    let cutoff = older_than?;
+    match tolerance.and_then(|tolerance| chrono::TimeDelta::from_std(tolerance).ok()) {
+        Some(tolerance) => Some(cutoff.checked_add_signed(tolerance).unwrap_or(DateTime::<Utc>::MAX_UTC)),
+        None => Some(cutoff),
+    }
+}
+
+/// Determine if a file should be moved based on filters
+fn should_move_file(
+    file_datetime: DateTime<Utc>,
+    grouper: Option<&dyn Grouper>,
+    previous_period_only: bool,
+    older_than: Option<DateTime<Utc>>,
+    now: DateTime<Utc>,
+) -> bool {
+    get_excluding_filter(file_datetime, grouper, previous_period_only, older_than, now).is_none()
+}
+
+/// First filter that keeps a file in place, if any
+pub fn get_excluding_filter(
+    file_datetime: DateTime<Utc>,
+    grouper: Option<&dyn Grouper>,
+    previous_period_only: bool,
+    older_than: Option<DateTime<Utc>>,
+    now: DateTime<Utc>,
+) -> Option<ExcludingFilter> {
+    // Check older_than filter if specified
+    if let Some(cutoff) = older_than
+        && file_datetime >= cutoff {
+            return Some(ExcludingFilter::OlderThan);
+        }
+
+    // Check previous_period_only filter if specified
+    if previous_period_only {
+        if let Some(grouper) = grouper {
+            let is_before_current = grouper.is_previous_period(file_datetime, now);
+            if !is_before_current {
+                return Some(ExcludingFilter::PreviousPeriodOnly);
+            }
+        } else {
+            // previous_period_only without grouping doesn't make sense, but we'll allow it
+            // and just ignore the flag
+        }
+    }
+
+    // If no filters apply, move the file
+    None
+}
+
+/// Whether the absolute form of a path is close to MAX_PATH, past which only programs using extended-length paths
+/// (as `move_files()` does) can open it
+fn is_near_path_limit(path: &Path) -> bool {
+    let absolute_path = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    absolute_path.as_os_str().to_string_lossy().chars().count() >= WINDOWS_MAX_PATH - LONG_PATH_WARNING_MARGIN
+}
+
+/// Destination of a planned file shortened by `--shorten-paths`, if it is past the path limits. Only the folders and
+/// file below its period folder are shortened.
+fn shorten_planned_dest_path(args: &Args, path: &Path, group_folder: Option<&str>, dest_path: &Path) -> Option<PathBuf> {
+    let dest_root = get_destination_root(args, path);
+    let base = group_folder.map_or_else(|| dest_root.to_path_buf(), |group_folder| dest_root.join(group_folder));
+    let max_length = args.max_path_length.unwrap_or(DEFAULT_MAX_PATH_LENGTH);
+    shorten_dest_path(args.shorten_paths, max_length, &base, dest_path)
+}
+
+/// Calculate destination path for a file, optionally inside a folder named after its source root
+pub fn calculate_dest_path(
+    source_path: &Path,
+    source_root: &Path,
+    dest_root: &Path,
+    group_folder: Option<&str>,
+    prefix_source_name: bool,
+    normalization: UnicodeNormalization,
+    sanitize_names: bool,
+) -> Result<PathBuf> {
+    // Get the relative path from the source root
+    let mut relative_path = source_path
+        .strip_prefix(source_root)
+        .context("Failed to compute relative path")?
+        .to_path_buf();
+    if prefix_source_name {
+        let source_name = source_root.file_name()
+            .with_context(|| format!("Source directory has no name: {}", source_root.display()))?;
+        relative_path = Path::new(source_name).join(relative_path);
+    }
+    let mut relative_path = normalize_path(&relative_path, normalization);
+    if sanitize_names {
+        relative_path = sanitize_path(&relative_path);
+    }
+
+    // Construct the destination path
+    let dest_path = if let Some(group) = group_folder {
+        // Add grouping folder between destination root and relative path
+        dest_root.join(group).join(relative_path)
+    } else {
+        // No grouping, just append relative path
+        dest_root.join(relative_path)
+    };
+
+    if cfg!(windows) && is_near_path_limit(&dest_path) {
+        warn!(
+            path = %dest_path.display(),
+            "Destination {} is close to the {} character path limit of Windows, Explorer and other programs may fail to open it",
+            dest_path.display(), WINDOWS_MAX_PATH
+        );
+    }
+    Ok(dest_path)
+}
+
+/// Rewrite each component of a relative path in the `--normalize-unicode` form, keeping the names that are not valid
+/// UTF-8 as they are
+fn normalize_path(path: &Path, normalization: UnicodeNormalization) -> PathBuf {
+    if normalization == UnicodeNormalization::None {
+        return path.to_path_buf();
+    }
+    path.components()
+        .map(|component| match component.as_os_str().to_str() {
+            Some(name) if normalization == UnicodeNormalization::Nfd => name.nfd().collect::<String>().into(),
+            Some(name) => name.nfc().collect::<String>().into(),
+            None => component.as_os_str().to_os_string(),
+        })
+        .collect::<PathBuf>()
+}
+
+/// Characters Windows, NTFS and exFAT don't allow in names, on top of the control characters
+const UNSAFE_NAME_CHARACTERS: [char; 9] = ['<', '>', ':', '"', '/', '\\', '|', '?', '*'];
+
+/// Device names Windows reserves, whatever their extension
+const RESERVED_WINDOWS_NAMES: [&str; 22] = [
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8", "COM9",
+    "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Rewrite each component of a relative path with `--sanitize-names`, so it can be created on any filesystem
+fn sanitize_path(path: &Path) -> PathBuf {
+    path.components()
+        .map(|component| match component.as_os_str().to_str() {
+            Some(name) => sanitize_name(name).into(),
+            None => component.as_os_str().to_os_string(),
+        })
+        .collect::<PathBuf>()
+}
+
+/// Replace the characters Windows doesn't allow in a name with `_`, drop the dots and spaces it would strip from its
+/// end, and suffix the device names it reserves (e.g., "CON.txt" becomes "CON_.txt")
+fn sanitize_name(name: &str) -> String {
+    let mut sanitized = name
+        .chars()
+        .map(|character| if character.is_control() || UNSAFE_NAME_CHARACTERS.contains(&character) { '_' } else { character })
+        .collect::<String>();
+    sanitized.truncate(sanitized.trim_end_matches(['.', ' ']).len());
+    if sanitized.is_empty() {
+        return "_".to_string();
+    }
+    let (stem, extension) = sanitized.split_once('.').unwrap_or((&sanitized, ""));
+    if RESERVED_WINDOWS_NAMES.iter().any(|reserved| reserved.eq_ignore_ascii_case(stem)) {
+        sanitized = if extension.is_empty() { format!("{stem}_") } else { format!("{stem}_.{extension}") };
+    }
+    sanitized
+}
+
+/// Execute the move plan (or preview in dry-run mode)
+pub fn move_files(
+    args: &Args,
+    storage: &dyn StorageBackend,
+    files_to_move: &[FileToMove],
+    dry_run: bool,
+) -> Result<MoveReport> {
+    let _move_span = info_span!("move", dry_run).entered();
+    if !files_to_move.is_empty() {
+        info!("\nMoving files{}...", if dry_run { " (DRY RUN)" } else { "" } );
+    }
+
+    let mut manifest = if dry_run || files_to_move.is_empty() {
+        None
+    } else {
+        Some(ManifestWriter::create(Utc::now())?)
+    };
+    let mut report = MoveReport {
+        run_id: manifest.as_ref().map(|manifest| manifest.run_id.clone()),
+        ..MoveReport::default()
+    };
+    let mut tree_files = (dry_run && args.preview == PreviewStyle::Tree).then(Vec::new);
+
+    move_batch(args, storage, files_to_move, dry_run, &mut MoveProgress::for_files(files_to_move), (manifest.as_mut(), &mut report), tree_files.as_mut())?;
+
+    if let Some(tree_files) = &tree_files {
+        print_destination_tree(&args.destination, tree_files);
+    }
+    log_move_summary(dry_run, &report, manifest.as_ref());
+
+    Ok(report)
+}
+
+/// Numbering of the progress lines across the batches of a run, and the estimate of the time it has left once its
+/// size is known
+struct MoveProgress {
+    /// Number of the next file of the run
+    next_number: usize,
+    /// Files and bytes of the whole run
+    total: Option<(usize, u64)>,
+    /// Bytes of the files handled so far, moved or failed
+    handled_bytes: u64,
+    started_at: Instant,
+}
+
+impl MoveProgress {
+    fn new(total: Option<(usize, u64)>) -> Self {
+        Self { next_number: 1, total, handled_bytes: 0, started_at: Instant::now() }
+    }
+
+    fn for_files(files_to_move: &[FileToMove]) -> Self {
+        Self::new(Some((files_to_move.len(), files_to_move.iter().map(|file| file.size).sum())))
+    }
+
+    /// Prefix of the progress line of a file (e.g., "3/10 (about 2m 5s left)"). Real runs estimate the time left from
+    /// the average rate so far; dry runs move nothing, so they have none.
+    fn describe(&self, number: usize, dry_run: bool) -> String {
+        let Some((total_files, total_bytes)) = self.total else {
+            return number.to_string();
+        };
+        let time_left = (!dry_run && self.handled_bytes > 0)
+            .then(|| {
+                let bytes_left = total_bytes.saturating_sub(self.handled_bytes);
+                self.started_at.elapsed().mul_f64(bytes_left as f64 / self.handled_bytes as f64)
+            })
+            .filter(|time_left| time_left.as_secs() > 0);
+        match time_left {
+            Some(time_left) => format!(
+                "{}/{} (about {} left)", number, total_files, humantime::format_duration(Duration::from_secs(time_left.as_secs()))
+            ),
+            None => format!("{}/{}", number, total_files),
+        }
+    }
+}
+
+/// Move the files into `report`, numbering the progress lines where `progress` is at. With `tree_files`, the
+/// destinations are collected for the tree preview instead of being printed.
+fn move_batch(
+    args: &Args,
+    storage: &dyn StorageBackend,
+    files_to_move: &[FileToMove],
+    dry_run: bool,
+    progress: &mut MoveProgress,
+    (mut manifest, report): (Option<&mut ManifestWriter>, &mut MoveReport),
+    mut tree_files: Option<&mut Vec<(PathBuf, u64)>>,
+) -> Result<()> {
+    let first_number = progress.next_number;
+    progress.next_number += files_to_move.len();
+    if args.cancellation.is_cancelled() {
+        report.cancelled += files_to_move.len();
+        return Ok(());
+    }
+    if report.aborted.is_some() {
+        report.left_in_place.extend(files_to_move.iter().map(|item| item.source.clone()));
+        return Ok(());
+    }
+    let ordered_files;
+    let files_to_move = match args.order {
+        Some(order) => {
+            ordered_files = order_files_to_move(files_to_move, order);
+            &ordered_files[..]
+        }
+        None => files_to_move,
+    };
+    if !dry_run {
+        create_destination_dirs(args, storage, files_to_move)?;
+    }
+    let moved_before = report.moved.len();
+
+    let status = if dry_run { "dry-run" } else { "moved" };
+    // Hashing and the `--post-file` hook happen on the workers right after each move, so they don't serialize the run
+    let with_manifest_hash = args.record_hashes && manifest.is_some() && !dry_run;
+    let mut catalog_entries = args.catalog.is_some().then(Vec::new);
+    let with_catalog = catalog_entries.is_some();
+    let destination_monitor = DestinationMonitor::new(storage, args.destination_wait);
+    // Cancelling stops between files, so a move already started always completes
+    let move_file = |item: &FileToMove| {
+        if args.cancellation.is_cancelled() {
+            return None;
+        }
+        let mut mirrored = Vec::new();
+        let attempt = hydrate_placeholder(args, storage, item, dry_run)
+            .and_then(|()| {
+                // A file is only moved once every mirror holds a copy of it
+                mirrored = copy_to_mirrors(args, storage, item, dry_run);
+                match mirrored.iter().find_map(|(mirror, copied)| copied.as_ref().err().map(|e| (mirror, e))) {
+                    Some((mirror, e)) => Err(io::Error::new(e.kind(), format!("Failed to copy it into the mirror {}: {}", mirror.display(), e))),
+                    None => Ok(()),
+                }
+            })
+            .and_then(|()| attempt_move_with_retries(args, storage, item, dry_run, &destination_monitor));
+        if !dry_run && attempt.is_ok() {
+            touch_destination(args, storage, item);
+        }
+        // The file is where it ended up: at its destination once moved, still at its source otherwise
+        let current_path = if dry_run || attempt.is_err() { &item.source } else { &item.destination };
+        // Read before hashing, which may update the access time
+        let stat = with_catalog.then(|| storage.stat(current_path).ok()).flatten();
+        let hash = ((with_manifest_hash || with_catalog) && attempt.is_ok()).then(|| hash_file(current_path));
+        run_post_file_hook(args, item, dry_run, attempt.as_ref().err());
+        Some((attempt, stat, hash, mirrored))
+    };
+    let mut handled = 0;
+    let result = run_in_order(files_to_move, args.jobs, move_file, |index, result| {
+        handled += 1;
+        let Some((attempt, stat, hash, mirrored)) = result else {
+            report.cancelled += 1;
+            return Ok(());
+        };
+        let item = &files_to_move[index];
+        progress.handled_bytes += item.size;
+        let source_path = &item.source;
+        let dest_path = &item.destination;
+        for (mirror, copied) in mirrored {
+            let stats = report.mirrors.entry(mirror.to_path_buf()).or_default();
+            if copied.is_ok() {
+                stats.files += 1;
+                stats.bytes += item.size;
+            } else {
+                stats.failed += 1;
+            }
+        }
+        if let Err(e) = attempt {
+            error!(path = %source_path.display(), error = %e, "Moving file {}: {}", source_path.display(), e);
+            let error = FileError { path: source_path.clone(), error: e.to_string() };
+            emit_event(Event::FileFailed(&error));
+            if let Some(catalog_entries) = catalog_entries.as_mut() {
+                let mut entry = CatalogEntry::new(item, stat.as_ref(), "failed");
+                entry.run_id.clone_from(&report.run_id);
+                entry.error = Some(error.error.clone());
+                catalog_entries.push(entry);
+            }
+            report.errors.push(error);
+            // Files already handed to the workers when the run aborted are still handled here
+            let abort_reason = if report.aborted.is_some() {
+                None
+            } else if let Some(destination) = destination_monitor.given_up_destination() {
+                Some(format!(
+                    "Aborting as the destination {} stayed unavailable for {}, {} file(s) were moved successfully before that",
+                    destination.display(), humantime::format_duration(args.destination_wait), report.moved_count()
+                ))
+            } else if args.max_errors.is_some_and(|max_errors| report.errors.len() >= max_errors) {
+                Some(format!("Aborting after {} error(s), {} file(s) were moved successfully before that", report.errors.len(), report.moved_count()))
+            } else {
+                None
+            };
+            if let Some(abort_reason) = abort_reason {
+                // Stops the workers, the caller finds the reason in the report
+                report.aborted = Some(abort_reason.clone());
+                return Err(eyre!(abort_reason));
+            }
+            return Ok(());
+        }
+
+        let hash = match hash {
+            Some(Ok(hash)) => Some(hash),
+            Some(Err(e)) => {
+                warn!(path = %dest_path.display(), error = %e, "Failed to hash {}, recording it without a hash: {}", dest_path.display(), e);
+                None
+            }
+            None => None,
+        };
+        if let Some(catalog_entries) = catalog_entries.as_mut() {
+            let mut entry = CatalogEntry::new(item, stat.as_ref(), status);
+            entry.run_id.clone_from(&report.run_id);
+            entry.hash.clone_from(&hash);
+            catalog_entries.push(entry);
+        }
+        if let Some(manifest) = manifest.as_deref_mut() {
+            let hash = hash.filter(|_| with_manifest_hash);
+            if let Err(e) = manifest.record(source_path, dest_path, get_destination_root(args, source_path), item.size, hash) {
+                warn!(path = %dest_path.display(), error = %e, "Failed to record {} in the run manifest: {}", dest_path.display(), e);
+            }
+        }
+
+        if let Some(tree_files) = tree_files.as_mut() {
+            tree_files.push((dest_path.clone(), item.size));
+        } else {
+            info!(
+                path = %source_path.display(), destination = %dest_path.display(), status,
+                "{}. {}\n       ↳ {}",
+                progress.describe(first_number + index, dry_run),
+                source_path.display(),
+                dest_path.parent().map(|it| it.display()).unwrap_or(dest_path.display())
+            );
+        }
+        emit_event(Event::FileMoved(item));
+        let period_name = item.period.clone().unwrap_or_else(|| UNGROUPED_PERIOD.to_string());
+        let destination_periods = report.destination_periods.entry(get_destination_root(args, source_path).to_path_buf()).or_default();
+        for period in [destination_periods.entry(period_name.clone()).or_default(), report.periods.entry(period_name).or_default()] {
+            period.files += 1;
+            period.bytes += item.size;
+        }
+        report.moved.push(item.clone());
+        Ok(())
+    });
+    // The files handled before an abort are described too
+    if !dry_run && !args.period_index.is_empty() {
+        append_to_period_indexes(args, &report.moved[moved_before..], Utc::now());
+    }
+    if !dry_run {
+        set_period_dir_times(args, storage, &report.moved[moved_before..]);
+    }
+    if let (Some(catalog), Some(catalog_entries)) = (&args.catalog, &catalog_entries)
+        && let Err(e) = append_to_catalog(catalog, catalog_entries) {
+            warn!("{:#}", e);
+        }
+    match report.aborted {
+        Some(_) => report.left_in_place.extend(files_to_move[handled..].iter().map(|item| item.source.clone())),
+        None => result?,
+    }
+
+    Ok(())
+}
+
+/// Date the period folders that received files to the end of their period, so listings of the archive sorted by date
+/// follow the periods rather than the runs. Moving files into a folder updates its modification time, so this follows
+/// the moves (and the period indexes written into the folders). Folders of a custom grouper are left as they are.
+fn set_period_dir_times(args: &Args, storage: &dyn StorageBackend, moved: &[FileToMove]) {
+    let period_dirs = moved.iter()
+        .filter_map(|file| Some((get_destination_root(args, &file.source).join(file.period.as_deref()?), file.period.as_deref()?)))
+        .collect::<BTreeMap<_, _>>();
+    for (period_dir, period) in period_dirs {
+        let Some(period_end) = date::get_period_end(period) else { continue };
+        if let Err(e) = storage.set_modified(&period_dir, SystemTime::from(period_end)) {
+            warn!(path = %period_dir.display(), error = %e, "Failed to set the modification time of {}: {}", period_dir.display(), e);
+        }
+    }
+}
+
+/// Set the modification time of a moved file as `--touch-destination` asks. Failing to only warns, the file is moved.
+fn touch_destination(args: &Args, storage: &dyn StorageBackend, item: &FileToMove) {
+    let modified = match args.touch_destination {
+        TouchDestination::Preserve => return,
+        TouchDestination::Now => SystemTime::now(),
+        TouchDestination::PeriodEnd => match item.period.as_deref().and_then(date::get_period_end) {
+            Some(period_end) => SystemTime::from(period_end),
+            // Ungrouped files, or the periods of a custom grouper, have no known end
+            None => return,
+        },
+    };
+    if let Err(e) = storage.set_modified(&item.destination, modified) {
+        warn!(path = %item.destination.display(), error = %e, "Failed to set the modification time of {}: {}", item.destination.display(), e);
+    }
+}
+
+fn log_move_summary(dry_run: bool, report: &MoveReport, manifest: Option<&ManifestWriter>) {
+    let status = if dry_run { "dry-run" } else { "moved" };
+    if dry_run {
+        info!(target: SUMMARY_TARGET, status, "DRY RUN: {} file(s) would have been moved successfully", report.moved_count());
+    } else {
+        info!(target: SUMMARY_TARGET, status, "Finished moving files, {} file(s) moved successfully", report.moved_count());
+    }
+    if report.cancelled > 0 {
+        warn!(target: SUMMARY_TARGET, "Cancelled, {} planned file(s) were left in place", report.cancelled);
+    }
+    if report.aborted.is_some() {
+        warn!(target: SUMMARY_TARGET, "Aborted, {} planned file(s) were left in place", report.left_in_place.len());
+    }
+    if let Some(manifest) = manifest {
+        info!(run_id = %manifest.run_id, "Run manifest saved as '{}': {}", manifest.run_id, manifest.path.display());
+    }
+}
+
+/// Create the destination folders of the files up front, once per folder rather than once per file, since each call
+/// is a round trip on network mounts. Failing to create one aborts the run.
+fn create_destination_dirs(args: &Args, storage: &dyn StorageBackend, files_to_move: &[FileToMove]) -> Result<()> {
+    let dirs = files_to_move.iter()
+        .filter_map(|item| item.destination.parent())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect::<Vec<_>>();
+    let create_dir = |dir: &&Path| storage.create_dir_all(dir)
+        .with_context(|| format!("Failed to create directory: {}", dir.display()));
+    run_in_order(&dirs, args.jobs, create_dir, |_, result| result)
+}
+
+/// Case-folded destinations planned so far in a run, each with the destination planned there first, so batched runs
+/// also find the collisions between their batches
+type ClaimedDestinations = HashMap<String, PathBuf>;
+
+/// Apply `--case-collisions` to the planned files whose destinations only differ in case from an earlier one (of this
+/// batch, or of `claimed`), when the destination ignores case: warn that they replace it, rename them with a numeric
+/// suffix (e.g., "report (1).pdf"), or fail before anything is moved. The destinations end up in `claimed`.
+fn resolve_case_collisions(
+    args: &Args,
+    storage: &dyn StorageBackend,
+    files_to_move: &mut [FileToMove],
+    claimed: &mut ClaimedDestinations,
+) -> Result<()> {
+    let collisions = find_case_collisions(files_to_move, claimed);
+    // Only probe the destination when it matters, as probing writes a file there
+    if collisions.is_empty() || !storage.is_case_insensitive(&args.destination) {
+        claim_destinations(files_to_move, claimed);
+        return Ok(());
+    }
+    match args.case_collisions {
+        CaseCollisionPolicy::Replace => {
+            for (replaced, replacing) in &collisions {
+                warn!(
+                    path = %replacing.display(), replaced = %replaced.display(),
+                    "{} {} {}, as they only differ in case and the destination filesystem ignores case",
+                    replacing.display(), if args.dry_run { "would replace" } else { "replaces" }, replaced.display()
+                );
+            }
+            claim_destinations(files_to_move, claimed);
+            return Ok(());
+        }
+        CaseCollisionPolicy::Fail => {
+            let pairs = collisions.iter()
+                .map(|(earlier, later)| format!("\n  {}\n  {}", earlier.display(), later.display()))
+                .collect::<String>();
+            bail!("{} planned destination(s) only differ in case from another one, which the destination filesystem ignores:{}", collisions.len(), pairs);
+        }
+        CaseCollisionPolicy::Rename => {}
+    }
+
+    // Each destination is kept by the first file planned there, and renamed files avoid every planned destination
+    claim_destinations(files_to_move, claimed);
+    let mut taken = claimed.keys().cloned().collect::<HashSet<_>>();
+    for file in files_to_move.iter_mut() {
+        let first_destination = &claimed[&fold_case(&file.destination)];
+        if *first_destination == file.destination {
+            continue;
+        }
+        // Safe to unwrap: only finitely many destinations are taken
+        let renamed = (1..).map(|number| add_number_suffix(&file.destination, number))
+            .find(|candidate| !taken.contains(&fold_case(candidate)))
+            .unwrap();
+        warn!(
+            path = %file.source.display(), destination = %renamed.display(),
+            "Renaming the destination {} to {}, as it only differs in case from {} and the destination filesystem ignores case",
+            file.destination.display(), renamed.display(), first_destination.display()
+        );
+        taken.insert(fold_case(&renamed));
+        file.destination = renamed;
+    }
+    claim_destinations(files_to_move, claimed);
+    Ok(())
+}
+
+/// Record the destinations of the files in `claimed`, keeping the first destination planned at each case-folded path
+fn claim_destinations(files_to_move: &[FileToMove], claimed: &mut ClaimedDestinations) {
+    for file in files_to_move {
+        claimed.entry(fold_case(&file.destination)).or_insert_with(|| file.destination.clone());
+    }
+}
+
+fn fold_case(path: &Path) -> String {
+    path.to_string_lossy().to_lowercase()
+}
+
+/// Path with ` (<number>)` added before the extension of its name (e.g., "report (1).pdf")
+fn add_number_suffix(path: &Path, number: usize) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match path.extension() {
+        Some(extension) => format!("{stem} ({number}).{}", extension.to_string_lossy()),
+        None => format!("{stem} ({number})"),
+    };
+    path.with_file_name(name)
+}
+
+/// Pairs of planned destinations that only differ in case, each destination planned first (in `claimed`, or earlier
+/// in the files) with the one replacing it
+fn find_case_collisions<'a>(files_to_move: &'a [FileToMove], claimed: &'a ClaimedDestinations) -> Vec<(&'a Path, &'a Path)> {
+    let mut first_by_folded_path = HashMap::new();
+    files_to_move.iter()
+        .filter_map(|item| {
+            let destination = item.destination.as_path();
+            let folded = fold_case(destination);
+            let first = match claimed.get(&folded) {
+                Some(first) => first.as_path(),
+                None => *first_by_folded_path.entry(folded).or_insert(destination),
+            };
+            (first != destination).then_some((first, destination))
+        })
+        .collect()
+}
+
+/// Delay before the first retry of a move, doubled before each next one
+const RETRY_BASE_DELAY: Duration = Duration::from_secs(1);
+
+/// How often a vanished destination is checked for being back
+const DESTINATION_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Download the content of a cloud placeholder before it is moved, with `--cloud-placeholders hydrate`, so the file
+/// moved to the destination is stored locally
+fn hydrate_placeholder(args: &Args, storage: &dyn StorageBackend, item: &FileToMove, dry_run: bool) -> io::Result<()> {
+    if dry_run || args.cloud_placeholders != PlaceholderPolicy::Hydrate {
+        return Ok(());
+    }
+    match storage.hydrate(&item.source) {
+        Ok(is_placeholder) => {
+            if is_placeholder {
+                debug!(path = %item.source.display(), "Downloaded the content of the placeholder {}", item.source.display());
+            }
+            Ok(())
+        }
+        Err(e) => Err(io::Error::new(e.kind(), format!("Failed to download the content of the placeholder: {e}"))),
+    }
+}
+
+/// Move a file, retrying the transient errors of network shares (`--retry-errors`) up to `--retries` times and pausing
+/// while its destination root is gone, as then every move would fail whatever its error
+fn attempt_move_with_retries<'a>(
+    args: &'a Args,
+    storage: &dyn StorageBackend,
+    item: &FileToMove,
+    dry_run: bool,
+    destination_monitor: &DestinationMonitor<'a>,
+) -> io::Result<()> {
+    let destination_root = get_destination_root(args, &item.source);
+    let mut retries = 0;
+    loop {
+        let error = match attempt_move(storage, item, dry_run, args.permanent) {
+            Ok(()) => return Ok(()),
+            Err(error) => error,
+        };
+        if !storage.is_dir(destination_root) {
+            if destination_monitor.wait_until_available(destination_root) {
+                continue;
+            }
+            return Err(error);
+        }
+        if retries >= args.retries || !is_retryable(&args.retry_errors, &error) {
+            return Err(error);
+        }
+        let delay = RETRY_BASE_DELAY * 2u32.saturating_pow(retries);
+        retries += 1;
+        warn!(
+            path = %item.source.display(), error = %error,
+            "Moving file {} failed with a transient error ({}), retrying in {} ({}/{})",
+            item.source.display(), error, humantime::format_duration(delay), retries, args.retries
+        );
+        thread::sleep(delay);
+    }
+}
+
+/// Whether a move error is one of the `--retry-errors`
+fn is_retryable(retry_errors: &[RetryableError], error: &io::Error) -> bool {
+    retry_errors.iter().any(|retry_error| match retry_error {
+        RetryableError::Stale => error.kind() == io::ErrorKind::StaleNetworkFileHandle,
+        RetryableError::TimedOut => error.kind() == io::ErrorKind::TimedOut,
+        RetryableError::Network => matches!(
+            error.kind(),
+            io::ErrorKind::NetworkDown
+                | io::ErrorKind::NetworkUnreachable
+                | io::ErrorKind::HostUnreachable
+                | io::ErrorKind::ConnectionReset
+                | io::ErrorKind::ConnectionAborted
+                | io::ErrorKind::NotConnected
+        ),
+        RetryableError::Code(code) => error.raw_os_error() == Some(*code),
+    })
+}
+
+/// Pauses the moves while a destination root (the main destination or a `--route`/`--route-path` one) is gone (e.g., a
+/// disconnected network share), shared by the `--jobs` threads so only one of them waits and logs. Once a pause lasted
+/// `--destination-wait`, the batch gives up.
+struct DestinationMonitor<'a> {
+    storage: &'a dyn StorageBackend,
+    max_wait: Duration,
+    /// Set to the destination root that stayed unavailable for `max_wait`, so the next moves fail right away
+    gave_up: Mutex<Option<&'a Path>>,
+}
+
+impl<'a> DestinationMonitor<'a> {
+    fn new(storage: &'a dyn StorageBackend, max_wait: Duration) -> Self {
+        Self { storage, max_wait, gave_up: Mutex::new(None) }
+    }
+
+    /// Wait for the destination root to be back, returning whether it is
+    fn wait_until_available(&self, destination: &'a Path) -> bool {
+        let mut gave_up = self.gave_up.lock().unwrap_or_else(PoisonError::into_inner);
+        if gave_up.is_some() {
+            return false;
+        }
+        // Another thread may have waited for it while this one was blocked on the lock
+        if self.storage.is_dir(destination) {
+            return true;
+        }
+
+        warn!(path = %destination.display(), "Destination {} is unavailable, pausing the run until it's back", destination.display());
+        let paused_at = Instant::now();
+        while !self.storage.is_dir(destination) {
+            let waited = paused_at.elapsed();
+            if waited >= self.max_wait {
+                *gave_up = Some(destination);
+                return false;
+            }
+            thread::sleep(DESTINATION_POLL_INTERVAL.min(self.max_wait - waited));
+        }
+        info!(path = %destination.display(), "Destination {} is back, resuming the run", destination.display());
+        true
+    }
+
+    fn given_up_destination(&self) -> Option<&'a Path> {
+        *self.gave_up.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Copy a file into each `--mirror` before it is moved, at the same path below the mirror as below its destination, so
+/// the mirrors follow the layout of the archive. Returns whether each copy succeeded; dry runs copy nothing.
+fn copy_to_mirrors<'a>(args: &'a Args, storage: &dyn StorageBackend, item: &FileToMove, dry_run: bool) -> Vec<(&'a Path, io::Result<()>)> {
+    let destination_root = get_destination_root(args, &item.source);
+    let relative_path = item.destination.strip_prefix(destination_root)
+        .unwrap_or_else(|_| Path::new(item.destination.file_name().unwrap_or_default()));
+    args.mirrors.iter()
+        .map(|mirror| {
+            let mirror_path = mirror.join(relative_path);
+            let copied = if dry_run {
+                Ok(())
+            } else {
+                mirror_path.parent()
+                    .map_or(Ok(()), |parent| storage.create_dir_all(parent))
+                    .and_then(|()| storage.copy_file(&item.source, &mirror_path))
+            };
+            if copied.is_ok() {
+                debug!(path = %item.source.display(), destination = %mirror_path.display(), "Copied {} to {}", item.source.display(), mirror_path.display());
+            }
+            (mirror.as_path(), copied)
+        })
+        .collect()
+}
+
+/// Move a file into its already created destination folder, doing nothing in dry-run mode. A file already at the
+/// destination is replaced, then sent to the Recycle Bin on Windows unless `permanent` is set. Until the move
+/// succeeded, the replaced file waits next to its path and is put back if the move fails, so it's never lost.
+fn attempt_move(storage: &dyn StorageBackend, item: &FileToMove, dry_run: bool, permanent: bool) -> io::Result<()> {
+    if dry_run {
+        return Ok(());
+    }
+    if permanent || storage.stat(&item.destination).is_err() {
+        return storage.rename(&item.source, &item.destination);
+    }
+
+    let mut replaced_path = item.destination.as_os_str().to_owned();
+    replaced_path.push(format!(".{}.replaced", std::process::id()));
+    let replaced_path = PathBuf::from(replaced_path);
+    storage.rename(&item.destination, &replaced_path)?;
+    if let Err(e) = storage.rename(&item.source, &item.destination) {
+        if let Err(restore_error) = storage.rename(&replaced_path, &item.destination) {
+            error!(path = %replaced_path.display(), error = %restore_error, "Failed to put the replaced file {} back: {}", replaced_path.display(), restore_error);
+        }
+        return Err(e);
+    }
+
+    // The file is moved by now, so failing to get rid of the replaced one only warns. Recycling only takes it away on
+    // Windows, so it is removed for good wherever it is still there.
+    let removed = storage.recycle_file(&replaced_path)
+        .and_then(|()| if storage.stat(&replaced_path).is_ok() { storage.remove_file(&replaced_path) } else { Ok(()) });
+    if let Err(e) = removed {
+        warn!(path = %replaced_path.display(), error = %e, "Failed to remove the replaced file {}: {}", replaced_path.display(), e);
+    }
+    Ok(())
+}
+
+/// Run `work` on every item using up to `jobs` threads, while `handle_result` receives the results one at a time and
+/// in the order of the items, so progress output never interleaves. The first error from `handle_result` stops the
+/// workers from starting new items and is returned once the items already started were handled too, so no finished
+/// move goes unrecorded.
+pub fn run_in_order<T: Sync, R: Send>(
+    items: &[T],
+    jobs: usize,
+    work: impl Fn(&T) -> R + Sync,
+    mut handle_result: impl FnMut(usize, R) -> Result<()>,
+) -> Result<()> {
+    let next_index = AtomicUsize::new(0);
+    let stopped = AtomicBool::new(false);
+    let (next_index, stopped, work) = (&next_index, &stopped, &work);
+
+    // Workers log inside the caller's span, so their log lines keep their phase
+    let span = &Span::current();
+
+    thread::scope(|scope| {
+        let (sender, receiver) = mpsc::channel();
+        for _ in 0..jobs.clamp(1, items.len().max(1)) {
+            let sender = sender.clone();
+            scope.spawn(move || {
+                let _span = span.enter();
+                while !stopped.load(Ordering::Relaxed) {
+                    let index = next_index.fetch_add(1, Ordering::Relaxed);
+                    let Some(item) = items.get(index) else { break };
+                    if sender.send((index, work(item))).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(sender);
+
+        // Results arrive in completion order, hold them back until every previous item was handled
+        let mut pending_results = BTreeMap::new();
+        let mut next_to_handle = 0;
+        let mut first_error = None;
+        for (index, result) in receiver {
+            pending_results.insert(index, result);
+            while let Some(result) = pending_results.remove(&next_to_handle) {
+                if let Err(e) = handle_result(next_to_handle, result)
+                    && first_error.is_none() {
+                        stopped.store(true, Ordering::Relaxed);
+                        first_error = Some(e);
+                    }
+                next_to_handle += 1;
+            }
+        }
+        first_error.map_or(Ok(()), Err)
+    })
+}
+
+/// Delete empty directories recursively in every source folder, returning the deleted directories. Dry runs delete
+/// nothing, and return the directories that would be deleted once the `moved` files are gone from the sources.
+pub fn delete_empty_directories(args: &Args, storage: &dyn StorageBackend, moved: &[FileToMove]) -> Result<Vec<PathBuf>> {
+    if args.cancellation.is_cancelled() {
+        return Ok(Vec::new());
+    }
+    let mut simulation = args.dry_run.then(|| DryRunCleanup {
+        gone: moved.iter().map(|file| file.source.clone()).collect(),
+        created: moved.iter().map(|file| file.destination.clone()).collect(),
+    });
+
+    let mut deleted_dirs = Vec::new();
+    if !args.keep_empty_folders {
+        for source in &args.sources {
+            deleted_dirs.extend(delete_empty_directories_under(args, storage, source, simulation.as_mut())?);
+        }
+    }
+    if args.clean_destination_empty_dirs {
+        for destination in get_destination_roots(args) {
+            if storage.is_dir(destination) {
+                deleted_dirs.extend(delete_empty_destination_directories(args, storage, destination, simulation.as_mut())?);
+            }
+        }
+    }
+    Ok(deleted_dirs)
+}
+
+/// State of the folders after a dry run, had it moved its files
+struct DryRunCleanup {
+    /// Files moved away and folders deleted
+    gone: HashSet<PathBuf>,
+    /// Files moved into the destination
+    created: Vec<PathBuf>,
+}
+
+/// The macOS metadata files of a folder that holds nothing else, or `None` if it holds anything else or can't be read.
+/// Metadata files count as regular files with `--include-macos-metadata`.
+fn get_metadata_files_if_empty(
+    args: &Args,
+    storage: &dyn StorageBackend,
+    dir: &Path,
+    simulation: Option<&DryRunCleanup>,
+) -> Option<Vec<PathBuf>> {
+    if simulation.is_some_and(|simulation| simulation.created.iter().any(|created| created.starts_with(dir))) {
+        return None;
+    }
+    let mut metadata_files = Vec::new();
+    for entry in storage.list_dir(dir).ok()? {
+        let entry = entry.ok()?;
+        if simulation.is_some_and(|simulation| simulation.gone.contains(&entry.path)) {
+            continue;
+        }
+        let is_metadata_file = !args.include_macos_metadata
+            && entry.is_file
+            && entry.path.file_name().is_some_and(|name| is_macos_metadata(name, false));
+        if !is_metadata_file {
+            return None;
+        }
+        metadata_files.push(entry.path);
+    }
+    Some(metadata_files)
+}
+
+fn delete_empty_directories_under(
+    args: &Args,
+    storage: &dyn StorageBackend,
+    root: &Path,
+    simulation: Option<&mut DryRunCleanup>,
+) -> Result<Vec<PathBuf>> {
+    let _cleanup_span = info_span!("cleanup", root = %root.display()).entered();
+
+    // The root itself is never deleted, and directories outside of the scanned depth range are left alone
+    let options = WalkOptions { max_depth: args.max_depth, ..WalkOptions::default() };
+    let dirs = walk_following_links(args, storage, root, options, args.min_depth.unwrap_or(1).max(1))
+        .filter_map(Result::ok)
+        .filter(|e| e.is_dir)
+        .map(|e| e.path)
+        // Skip ignored paths
+        .filter(|path| {
+            args.ignored_paths.as_ref()
+                .is_none_or(|ignored_paths| !ignored_paths.iter().any(|ignored_path| path.starts_with(ignored_path)))
+        })
+        .collect::<Vec<_>>();
+    delete_empty_dirs(args, storage, &dirs, simulation)
+}
+
+/// Delete the empty folders of the destination, for `--clean-destination-empty-dirs`, e.g. period folders left empty
+/// when every file planned into them was skipped as a conflict or restored since. The destination itself, links and
+/// the folders of sources inside the destination (cleaned by the rules of the sources) are left alone.
+fn delete_empty_destination_directories(
+    args: &Args,
+    storage: &dyn StorageBackend,
+    destination: &Path,
+    simulation: Option<&mut DryRunCleanup>,
+) -> Result<Vec<PathBuf>> {
+    let _cleanup_span = info_span!("cleanup", root = %destination.display()).entered();
+
+    let keep = |entry: &StorageEntry| entry.depth == 0 || (entry.link.is_none() && !args.sources.iter().any(|source| entry.path.starts_with(source)));
+    let dirs = storage.walk(destination, WalkOptions::default(), Box::new(keep))
+        .filter_map(Result::ok)
+        .filter(|e| e.is_dir && e.depth >= 1)
+        .map(|e| e.path)
+        .collect::<Vec<_>>();
+    delete_empty_dirs(args, storage, &dirs, simulation)
+}
+
+/// Delete the folders that are empty, given in walk order. With a dry-run simulation, they are only recorded as gone.
+fn delete_empty_dirs(
+    args: &Args,
+    storage: &dyn StorageBackend,
+    dirs: &[PathBuf],
+    mut simulation: Option<&mut DryRunCleanup>,
+) -> Result<Vec<PathBuf>> {
+    let mut deleted_dirs = Vec::new();
+
+    // Deepest first, so a directory is checked once its subdirectories were deleted, in a single pass
+    for path in dirs.iter().rev() {
+        let path = path.as_path();
+
+        // Check if directory is empty, but for the macOS metadata files left behind by Finder
+        let Some(metadata_files) = get_metadata_files_if_empty(args, storage, path, simulation.as_deref()) else {
+            continue;
+        };
+        if let Some(simulation) = simulation.as_deref_mut() {
+            simulation.gone.insert(path.to_path_buf());
+            deleted_dirs.push(path.to_path_buf());
+            continue;
+        }
+        for metadata_file in metadata_files {
+            storage.remove_file(&metadata_file)
+                .with_context(|| format!("Failed to delete macOS metadata file: {}", metadata_file.display()))?;
+        }
+        // Directory is empty, delete it
+        storage.remove_dir(path)
+            .with_context(|| format!("Failed to delete empty directory: {}", path.display()))?;
+        emit_event(Event::DirDeleted { path });
+        deleted_dirs.push(path.to_path_buf());
+    }
+
+    if !deleted_dirs.is_empty() {
+        let (heading, verb, status) = match simulation {
+            Some(_) => ("\nEmpty directories that would be deleted (DRY RUN):", "Would delete", "dry-run"),
+            None => ("\nCleaning up empty directories...", "Deleted", "deleted"),
+        };
+        info!("{}", heading);
+        for (index, dir) in deleted_dirs.iter().enumerate() {
+            info!(path = %dir.display(), status, "{}/{}. {} empty directory: {}", index + 1, deleted_dirs.len(), verb, dir.display());
+        }
+    }
+
+    Ok(deleted_dirs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::Cli;
+    use crate::model::GroupBy;
+    use crate::storage::{LocalStorage, MemoryStorage};
+    use std::sync::Arc;
+    use clap::Parser;
+    use std::time::Duration;
+    use std::fs;
+
+    #[test]
+    fn test_get_excluding_filter_older_than_first() {
+        let now = "2025-06-15T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let file_datetime = "2025-06-10T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let cutoff = "2025-06-01T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+
+        assert_eq!(get_excluding_filter(file_datetime, Some(&GroupBy::Month), true, Some(cutoff), now), Some(ExcludingFilter::OlderThan));
+        assert_eq!(get_excluding_filter(file_datetime, Some(&GroupBy::Month), true, None, now), Some(ExcludingFilter::PreviousPeriodOnly));
+        assert_eq!(get_excluding_filter(file_datetime, Some(&GroupBy::Month), false, None, now), None);
+    }
+
+    // should_move_file tests
+    #[test]
+    fn test_should_move_file_no_filters() {
+        let now = "2025-06-15T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let file_datetime = "2025-01-01T12:00:00Z".parse::<DateTime<Utc>>().unwrap();
+
+        // With no filters, should always move
+        assert!(should_move_file(file_datetime, None, false, None, now));
+    }
+
+    #[test]
+    fn test_should_move_file_older_than_filter() {
+        let now = "2025-06-15T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let cutoff = "2025-03-01T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+
+        // File before cutoff - should move
+        let before_cutoff = "2025-02-15T12:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        assert!(should_move_file(before_cutoff, None, false, Some(cutoff), now));
+
+        // File after cutoff - should not move
+        let after_cutoff = "2025-03-15T12:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        assert!(!should_move_file(after_cutoff, None, false, Some(cutoff), now));
+
+        // File exactly at cutoff - should not move (>= comparison)
+        let at_cutoff = "2025-03-01T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        assert!(!should_move_file(at_cutoff, None, false, Some(cutoff), now));
+    }
+
+    #[test]
+    fn test_get_tolerant_cutoff_moves_files_within_tolerance() {
+        let now = "2025-06-15T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let cutoff = "2025-03-01T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let tolerant_cutoff = get_tolerant_cutoff(Some(cutoff), Some(Duration::from_secs(2)));
+
+        // FAT rounds the modification time up to the next even second
+        let rounded_up = "2025-03-01T00:00:01Z".parse::<DateTime<Utc>>().unwrap();
+        assert!(!should_move_file(rounded_up, None, false, Some(cutoff), now));
+        assert!(should_move_file(rounded_up, None, false, tolerant_cutoff, now));
+
+        let beyond_tolerance = "2025-03-01T00:00:02Z".parse::<DateTime<Utc>>().unwrap();
+        assert!(!should_move_file(beyond_tolerance, None, false, tolerant_cutoff, now));
+
+        assert_eq!(get_tolerant_cutoff(Some(cutoff), None), Some(cutoff));
+        assert_eq!(get_tolerant_cutoff(None, Some(Duration::from_secs(2))), None);
+    }
+
+    #[test]
+    fn test_should_move_file_previous_period_only_week() {
+        let now = "2025-06-15T00:00:00Z".parse::<DateTime<Utc>>().unwrap(); // Week 24
+
+        // Previous week - should move
+        let previous_week = "2025-06-08T12:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        assert!(should_move_file(previous_week, Some(&GroupBy::Week), true, None, now));
+
+        // Current week - should not move
+        let current_week = "2025-06-16T12:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        assert!(!should_move_file(current_week, Some(&GroupBy::Week), true, None, now));
+
+        // Next week - should not move
+        let next_week = "2025-06-22T12:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        assert!(!should_move_file(next_week, Some(&GroupBy::Week), true, None, now));
+    }
+
+    #[test]
+    fn test_should_move_file_previous_period_only_month() {
+        let now = "2025-06-15T00:00:00Z".parse::<DateTime<Utc>>().unwrap(); // June
+
+        // Previous month - should move
+        let previous_month = "2025-05-31T12:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        assert!(should_move_file(previous_month, Some(&GroupBy::Month), true, None, now));
+
+        // Current month - should not move
+        let current_month = "2025-06-01T12:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        assert!(!should_move_file(current_month, Some(&GroupBy::Month), true, None, now));
+
+        // Next month - should not move
+        let next_month = "2025-07-01T12:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        assert!(!should_move_file(next_month, Some(&GroupBy::Month), true, None, now));
+    }
+
+    #[test]
+    fn test_should_move_file_previous_period_only_year() {
+        let now = "2025-06-15T00:00:00Z".parse::<DateTime<Utc>>().unwrap(); // 2025
+
+        // Previous year - should move
+        let previous_year = "2024-12-31T12:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        assert!(should_move_file(previous_year, Some(&GroupBy::Year), true, None, now));
+
+        // Current year - should not move
+        let current_year = "2025-01-01T12:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        assert!(!should_move_file(current_year, Some(&GroupBy::Year), true, None, now));
+
+        // Next year - should not move
+        let next_year = "2026-01-01T12:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        assert!(!should_move_file(next_year, Some(&GroupBy::Year), true, None, now));
+    }
+
+    #[test]
+    fn test_should_move_file_previous_period_only_semester() {
+        let now = "2025-08-15T00:00:00Z".parse::<DateTime<Utc>>().unwrap(); // H2
+
+        // Previous semester (H1) - should move
+        let previous_semester = "2025-06-30T12:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        assert!(should_move_file(previous_semester, Some(&GroupBy::Semester), true, None, now));
+
+        // Current semester (H2) - should not move
+        let current_semester = "2025-08-01T12:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        assert!(!should_move_file(current_semester, Some(&GroupBy::Semester), true, None, now));
+    }
+
+    #[test]
+    fn test_should_move_file_previous_period_only_trimester() {
+        let now = "2025-05-15T00:00:00Z".parse::<DateTime<Utc>>().unwrap(); // Q2
+
+        // Previous trimester (Q1) - should move
+        let previous_trimester = "2025-03-31T12:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        assert!(should_move_file(previous_trimester, Some(&GroupBy::Trimester), true, None, now));
+
+        // Current trimester (Q2) - should not move
+        let current_trimester = "2025-05-01T12:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        assert!(!should_move_file(current_trimester, Some(&GroupBy::Trimester), true, None, now));
+    }
+
+    #[test]
+    fn test_should_move_file_previous_period_only_quadrimester() {
+        let now = "2025-06-15T00:00:00Z".parse::<DateTime<Utc>>().unwrap(); // QD2
+
+        // Previous quadrimester (QD1) - should move
+        let previous_qd = "2025-04-30T12:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        assert!(should_move_file(previous_qd, Some(&GroupBy::Quadrimester), true, None, now));
+
+        // Current quadrimester (QD2) - should not move
+        let current_qd = "2025-05-01T12:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        assert!(!should_move_file(current_qd, Some(&GroupBy::Quadrimester), true, None, now));
+    }
+
+    #[test]
+    fn test_should_move_file_previous_period_only_biweekly() {
+        let now = "2025-06-15T00:00:00Z".parse::<DateTime<Utc>>().unwrap(); // Week 24 -> BW12
+
+        // Previous biweekly period - should move
+        let previous_bw = "2025-06-01T12:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        assert!(should_move_file(previous_bw, Some(&GroupBy::Biweekly), true, None, now));
+
+        // Current biweekly period - should not move
+        let current_bw = "2025-06-16T12:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        assert!(!should_move_file(current_bw, Some(&GroupBy::Biweekly), true, None, now));
+    }
+
+    #[test]
+    fn test_should_move_file_combined_filters() {
+        let now = "2025-06-15T00:00:00Z".parse::<DateTime<Utc>>().unwrap(); // Week 24
+        let cutoff = "2025-06-10T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+
+        // Passes both filters: before cutoff (June 8) AND previous period (Week 23)
+        let passes_both = "2025-06-08T12:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        assert!(should_move_file(passes_both, Some(&GroupBy::Week), true, Some(cutoff), now));
+
+        // Fails older_than: after cutoff (June 14) but in previous period (Week 23)
+        // Note: June 14 is actually in Week 24, so let me use Week 23 date after cutoff
+        // Week 23 is June 2-8, so June 14 is Week 24. Let me use a date from previous week that's after cutoff
+        // Actually, if cutoff is June 10, then all dates in Week 23 (June 2-8) are before cutoff
+        // Let's use Month grouping instead for this test case
+        let now_month = "2025-06-15T00:00:00Z".parse::<DateTime<Utc>>().unwrap(); // June
+        let cutoff_month = "2025-05-15T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+
+        // Fails older_than: after cutoff (May 20) but in previous period (May)
+        let fails_older_than = "2025-05-20T12:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        assert!(!should_move_file(fails_older_than, Some(&GroupBy::Month), true, Some(cutoff_month), now_month));
+
+        // Fails previous_period_only: before cutoff (June 5) but in current period (June)
+        let fails_period = "2025-06-05T12:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        assert!(!should_move_file(fails_period, Some(&GroupBy::Month), true, Some(cutoff_month), now_month));
+
+        // Fails both filters: after cutoff AND in current period
+        let fails_both = "2025-06-16T12:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        assert!(!should_move_file(fails_both, Some(&GroupBy::Month), true, Some(cutoff_month), now_month));
+    }
+
+    #[test]
+    fn test_should_move_file_previous_period_only_without_group_by() {
+        let now = "2025-06-15T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let file_datetime = "2025-01-01T12:00:00Z".parse::<DateTime<Utc>>().unwrap();
+
+        // previous_period_only without group_by should be ignored, file should move
+        assert!(should_move_file(file_datetime, None, true, None, now));
+    }
+
+    // retain_min_files_per_dir tests
+    fn create_file_to_move(source: &str, file_date: &str) -> FileToMove {
+        FileToMove {
+            source: PathBuf::from(source),
+            destination: PathBuf::from("/dest").join(source.trim_start_matches('/')),
+            file_date: file_date.parse::<DateTime<Utc>>().unwrap(),
+            size: 0,
+            period: None,
+        }
+    }
+
+    #[test]
+    fn test_is_link_followed_symbolic_link() {
+        let args = Cli::parse_from(["chronomover", "-s", "/src", "-d", "/dst", "--junctions", "follow"]).args.unwrap();
+        let following_args = Cli::parse_from(["chronomover", "-s", "/src", "-d", "/dst", "--follow-symbolic-links"]).args.unwrap();
+
+        assert!(!is_link_followed(&args, LinkKind::Symbolic));
+        assert!(is_link_followed(&args, LinkKind::Junction));
+        assert!(is_link_followed(&following_args, LinkKind::Symbolic));
+        assert!(!is_link_followed(&following_args, LinkKind::Junction));
+    }
+
+    #[test]
+    fn test_is_macos_metadata() {
+        assert!(is_macos_metadata(OsStr::new(".DS_Store"), false));
+        assert!(is_macos_metadata(OsStr::new("._IMG_0001.jpg"), false));
+        assert!(is_macos_metadata(OsStr::new(".Spotlight-V100"), true));
+        assert!(!is_macos_metadata(OsStr::new(".Trashes"), false));
+        assert!(!is_macos_metadata(OsStr::new(".DS_Store"), true));
+        assert!(!is_macos_metadata(OsStr::new("_notes.txt"), false));
+    }
+
+    #[test]
+    fn test_is_sync_conflict_copy() {
+        assert!(is_sync_conflict_copy(OsStr::new("report (conflicted copy 2025-06-01).docx")));
+        assert!(is_sync_conflict_copy(OsStr::new("report (Alice's Conflicted Copy 2025-06-01).docx")));
+        assert!(is_sync_conflict_copy(OsStr::new("report.sync-conflict-20250601-120000-ABCDEFG.docx")));
+        assert!(is_sync_conflict_copy(OsStr::new("report_conflict-20250601-120000.docx")));
+        assert!(!is_sync_conflict_copy(OsStr::new("report (final) conflicted copy notes.docx")));
+        assert!(!is_sync_conflict_copy(OsStr::new("merge_conflict-notes.txt")));
+        assert!(!is_sync_conflict_copy(OsStr::new("report.docx")));
+    }
+
+    #[test]
+    fn test_is_near_path_limit() {
+        let root = std::path::absolute("/").unwrap();
+
+        assert!(!is_near_path_limit(&root.join("a".repeat(200))));
+        assert!(is_near_path_limit(&root.join("a".repeat(240))));
+    }
+
+    #[test]
+    fn test_sort_files_to_move() {
+        let mut files = vec![
+            create_file_to_move("/source/b/a.txt", "2025-03-01T00:00:00Z"),
+            create_file_to_move("/source/a/c.txt", "2025-01-01T00:00:00Z"),
+            create_file_to_move("/source/a/a.txt", "2025-03-01T00:00:00Z"),
+        ];
+        files[0].size = 5;
+        files[1].size = 10;
+        files[2].size = 5;
+        let sorted_sources = |files: &[FileToMove]| files.iter().map(|file| file.source.display().to_string()).collect::<Vec<_>>();
+
+        sort_files_to_move(&mut files, SortOrder::Date);
+        assert_eq!(sorted_sources(&files), ["/source/a/c.txt", "/source/a/a.txt", "/source/b/a.txt"]);
+        sort_files_to_move(&mut files, SortOrder::Name);
+        assert_eq!(sorted_sources(&files), ["/source/a/a.txt", "/source/b/a.txt", "/source/a/c.txt"]);
+        sort_files_to_move(&mut files, SortOrder::Size);
+        assert_eq!(sorted_sources(&files), ["/source/a/a.txt", "/source/b/a.txt", "/source/a/c.txt"]);
+        sort_files_to_move(&mut files, SortOrder::Path);
+        assert_eq!(sorted_sources(&files), ["/source/a/a.txt", "/source/a/c.txt", "/source/b/a.txt"]);
+    }
+
+    #[test]
+    fn test_order_files_to_move() {
+        let mut files = vec![
+            create_file_to_move("/source/a.txt", "2025-01-01T00:00:00Z"),
+            create_file_to_move("/source/b.txt", "2025-01-01T00:00:00Z"),
+            create_file_to_move("/source/c.txt", "2025-01-01T00:00:00Z"),
+        ];
+        files[0].size = 5;
+        files[1].size = 10;
+        files[2].size = 5;
+        let ordered_sources = |order| order_files_to_move(&files, order).iter().map(|file| file.source.display().to_string()).collect::<Vec<_>>();
+
+        assert_eq!(ordered_sources(MoveOrder::SmallestFirst), ["/source/a.txt", "/source/c.txt", "/source/b.txt"]);
+        assert_eq!(ordered_sources(MoveOrder::LargestFirst), ["/source/b.txt", "/source/a.txt", "/source/c.txt"]);
+    }
+
+    #[test]
+    fn test_retain_min_files_per_dir_keeps_most_recent() {
+        let files = vec![
+            create_file_to_move("/source/reports/jan.pdf", "2025-01-31T12:00:00Z"),
+            create_file_to_move("/source/reports/mar.pdf", "2025-03-31T12:00:00Z"),
+            create_file_to_move("/source/reports/feb.pdf", "2025-02-28T12:00:00Z"),
+        ];
+
+        // All 3 files of the folder are candidates, so the 2 most recent must stay
+        let (to_move, kept) = retain_min_files_per_dir(files, 2, |_| 3);
+
+        assert_eq!(to_move.iter().map(|f| f.source.clone()).collect::<Vec<_>>(), vec![PathBuf::from("/source/reports/jan.pdf")]);
+        assert_eq!(kept.iter().map(|f| f.source.clone()).collect::<Vec<_>>(), vec![
+            PathBuf::from("/source/reports/mar.pdf"),
+            PathBuf::from("/source/reports/feb.pdf"),
+        ]);
+    }
+
+    #[test]
+    fn test_retain_min_files_per_dir_counts_files_left_behind() {
+        let files = vec![
+            create_file_to_move("/source/a/old.md", "2025-01-01T12:00:00Z"),
+            create_file_to_move("/source/b/old.md", "2025-01-01T12:00:00Z"),
+        ];
+
+        // Folder "a" keeps 5 non-candidate files, folder "b" has nothing else
+        let count_files = |dir: &Path| if dir.ends_with("a") { 6 } else { 1 };
+        let (to_move, kept) = retain_min_files_per_dir(files, 1, count_files);
+
+        assert_eq!(to_move.len(), 1);
+        assert_eq!(to_move[0].source, PathBuf::from("/source/a/old.md"));
+        assert_eq!(kept.len(), 1);
+        assert_eq!(kept[0].source, PathBuf::from("/source/b/old.md"));
+    }
+
+    #[test]
+    fn test_get_files_to_move_keeps_newest_file_per_dir() {
+        let storage = MemoryStorage::with_tree([
+            ("/src/reports/report-v1.pdf", "2025-01-10"),
+            ("/src/reports/report-v3.pdf", "2025-03-10"),
+            ("/src/reports/report-v2.pdf", "2025-02-10"),
+            ("/src/notes/old.txt", "2025-01-10"),
+            ("/src/notes/recent.txt", "2025-06-10"),
+            ("/dest/", ""),
+        ]);
+        let args = Cli::parse_from(["chronomover", "-s", "/src", "-d", "/dest", "--older-than", "2025-05-01", "--keep-newest-per-dir"]).args.unwrap();
+
+        let plan = get_files_to_move(&args, &storage, "2025-06-15T00:00:00Z".parse().unwrap(), None).unwrap();
+
+        // The notes folder already keeps a newer file than its candidates
+        let mut sources = plan.files_to_move.iter().map(|file| file.source.clone()).collect::<Vec<_>>();
+        sources.sort();
+        assert_eq!(sources, ["/src/notes/old.txt", "/src/reports/report-v1.pdf", "/src/reports/report-v2.pdf"].map(PathBuf::from));
+        assert_eq!(plan.skipped, 2);
+    }
+
+    // calculate_dest_path tests
+    #[test]
+    fn test_calculate_dest_path_without_grouping() {
+        // Root-level file
+        let source_root = PathBuf::from("/source");
+        let dest_root = PathBuf::from("/dest");
+        let source_path = source_root.join("file.md");
+
+        let result = calculate_dest_path(&source_path, &source_root, &dest_root, None, false, UnicodeNormalization::None, false).unwrap();
+        assert_eq!(result, dest_root.join("file.md"));
+
+        // Nested file
+        let nested_source = source_root.join("folder1").join("folder2").join("file.md");
+        let result = calculate_dest_path(&nested_source, &source_root, &dest_root, None, false, UnicodeNormalization::None, false).unwrap();
+        assert_eq!(result, dest_root.join("folder1").join("folder2").join("file.md"));
+    }
+
+    #[test]
+    fn test_calculate_dest_path_with_grouping() {
+        let source_root = PathBuf::from("/source");
+        let dest_root = PathBuf::from("/dest");
+        let group_folder = "2025-24";
+
+        // Root-level file
+        let source_path = source_root.join("file.md");
+        let result = calculate_dest_path(&source_path, &source_root, &dest_root, Some(group_folder), false, UnicodeNormalization::None, false).unwrap();
+        assert_eq!(result, dest_root.join(group_folder).join("file.md"));
+
+        // Nested file
+        let nested_source = source_root.join("folder1").join("folder2").join("file.md");
+        let result = calculate_dest_path(&nested_source, &source_root, &dest_root, Some(group_folder), false, UnicodeNormalization::None, false).unwrap();
+        assert_eq!(result, dest_root.join(group_folder).join("folder1").join("folder2").join("file.md"));
+    }
+
+    #[test]
+    fn test_calculate_dest_path_preserves_structure() {
+        let source_root = PathBuf::from("/notes");
+        let dest_root = PathBuf::from("/archive");
+
+        // Test with various nesting levels
+        let paths = vec![
+            "daily.md",
+            "work/meeting.md",
+            "work/projects/project1.md",
+            "personal/journal/2025/january.md",
+        ];
+
+        for path in paths {
+            let source_path = source_root.join(path);
+            let result = calculate_dest_path(&source_path, &source_root, &dest_root, None, false, UnicodeNormalization::None, false).unwrap();
+            assert_eq!(result, dest_root.join(path));
+        }
+    }
+
+    #[test]
+    fn test_calculate_dest_path_with_grouping_preserves_structure() {
+        let source_root = PathBuf::from("/notes");
+        let dest_root = PathBuf::from("/archive");
+        let group = "2025-W24";
+
+        let paths = vec![
+            "daily.md",
+            "work/meeting.md",
+            "work/projects/project1.md",
+        ];
+
+        for path in paths {
+            let source_path = source_root.join(path);
+            let result = calculate_dest_path(&source_path, &source_root, &dest_root, Some(group), false, UnicodeNormalization::None, false).unwrap();
+            assert_eq!(result, dest_root.join(group).join(path));
+        }
+    }
+
+    #[test]
+    fn test_calculate_dest_path_with_source_name_prefix() {
+        let dest_root = PathBuf::from("/archive");
+        let downloads = calculate_dest_path(Path::new("/home/me/Downloads/a.pdf"), Path::new("/home/me/Downloads"), &dest_root, Some("2025-06"), true, UnicodeNormalization::None, false).unwrap();
+        let desktop = calculate_dest_path(Path::new("/home/me/Desktop/notes/a.pdf"), Path::new("/home/me/Desktop"), &dest_root, None, true, UnicodeNormalization::None, false).unwrap();
+
+        assert_eq!(downloads, PathBuf::from("/archive/2025-06/Downloads/a.pdf"));
+        assert_eq!(desktop, PathBuf::from("/archive/Desktop/notes/a.pdf"));
+        assert!(calculate_dest_path(Path::new("/a.pdf"), Path::new("/"), &dest_root, None, true, UnicodeNormalization::None, false).is_err());
+    }
+
+    #[test]
+    fn test_calculate_dest_path_normalizes_unicode() {
+        // The destination root already exists, so only the names below it are normalized
+        let dest_root = PathBuf::from("/archive/Cafe\u{301}");
+        let source_path = PathBuf::from("/source/Cafe\u{301}/re\u{301}sume\u{301}.pdf");
+        let source_root = PathBuf::from("/source");
+
+        let nfc = calculate_dest_path(&source_path, &source_root, &dest_root, None, false, UnicodeNormalization::Nfc, false).unwrap();
+        let nfd = calculate_dest_path(&PathBuf::from("/source/Caf\u{e9}.pdf"), &source_root, &dest_root, None, false, UnicodeNormalization::Nfd, false).unwrap();
+        let none = calculate_dest_path(&source_path, &source_root, &dest_root, None, false, UnicodeNormalization::None, false).unwrap();
+
+        assert_eq!(nfc, PathBuf::from("/archive/Cafe\u{301}/Caf\u{e9}/r\u{e9}sum\u{e9}.pdf"));
+        assert_eq!(nfd, PathBuf::from("/archive/Cafe\u{301}/Cafe\u{301}.pdf"));
+        assert_eq!(none, PathBuf::from("/archive/Cafe\u{301}/Cafe\u{301}/re\u{301}sume\u{301}.pdf"));
+    }
+
+    #[test]
+    fn test_is_retryable() {
+        let retry_errors = [RetryableError::Stale, RetryableError::TimedOut, RetryableError::Code(64)];
+
+        assert!(is_retryable(&retry_errors, &io::Error::from(io::ErrorKind::StaleNetworkFileHandle)));
+        assert!(is_retryable(&retry_errors, &io::Error::from(io::ErrorKind::TimedOut)));
+        assert!(is_retryable(&retry_errors, &io::Error::from_raw_os_error(64)));
+        assert!(!is_retryable(&retry_errors, &io::Error::from(io::ErrorKind::ConnectionReset)));
+        assert!(!is_retryable(&[RetryableError::Network], &io::Error::from(io::ErrorKind::PermissionDenied)));
+        assert!(is_retryable(&[RetryableError::Network], &io::Error::from(io::ErrorKind::ConnectionReset)));
+    }
+
+    #[test]
+    fn test_destination_monitor_gives_up() {
+        let storage = MemoryStorage::with_tree([("/archive/photos/", "")]);
+        let monitor = DestinationMonitor::new(&storage, Duration::ZERO);
+
+        assert!(monitor.wait_until_available(Path::new("/archive/photos")));
+        assert!(!monitor.wait_until_available(Path::new("/archive/videos")));
+        assert_eq!(monitor.given_up_destination(), Some(Path::new("/archive/videos")));
+        assert!(!monitor.wait_until_available(Path::new("/archive/photos")));
+    }
+
+    #[test]
+    fn test_attempt_move_with_retries_waits_for_the_route_destination() {
+        let storage = MemoryStorage::with_tree([("/src/beach.jpg", "2025-04-20"), ("/dest/", "")]);
+        let args = Cli::parse_from([
+            "chronomover", "-s", "/src", "-d", "/dest", "--route", "jpg=>/photos", "--destination-wait", "0s",
+        ]).args.unwrap();
+        let file = FileToMove { destination: PathBuf::from("/photos/2025-04/beach.jpg"), ..create_file_to_move("/src/beach.jpg", "2025-04-20T00:00:00Z") };
+        let monitor = DestinationMonitor::new(&storage, args.destination_wait);
+
+        assert!(attempt_move_with_retries(&args, &storage, &file, false, &monitor).is_err());
+        assert_eq!(monitor.given_up_destination(), Some(Path::new("/photos")));
+    }
+
+    #[test]
+    fn test_get_files_to_move_from_memory_storage() {
+        let now = "2025-06-15T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let storage = MemoryStorage::default();
+        storage.add_file("/src/notes/old.txt", "2025-01-10T00:00:00Z".parse::<DateTime<Utc>>().unwrap());
+        storage.add_file("/src/notes/.DS_Store", "2025-01-10T00:00:00Z".parse::<DateTime<Utc>>().unwrap());
+        storage.add_file("/src/new.txt", "2025-06-10T00:00:00Z".parse::<DateTime<Utc>>().unwrap());
+        let args = Cli::parse_from(["chronomover", "-s", "/src", "-d", "/dest", "--group-by", "month", "--older-than", "2025-03-01"]).args.unwrap();
+
+        let plan = get_files_to_move(&args, &storage, now, None).unwrap();
+
+        let moves = plan.files_to_move.iter().map(|file| (file.source.as_path(), file.destination.as_path())).collect::<Vec<_>>();
+        assert_eq!(moves, vec![(Path::new("/src/notes/old.txt"), Path::new("/dest/2025-01/notes/old.txt"))]);
+        assert_eq!(plan.skipped, 1);
+    }
+
+    #[test]
+    fn test_walk_source_folder_in_parallel_keeps_the_walk_order() {
+        let storage = MemoryStorage::with_tree([
+            ("/src/a.txt", "2025-01-01"),
+            ("/src/docs/b.txt", "2025-01-02"),
+            ("/src/docs/deep/c.txt", "2025-01-03"),
+            ("/src/docs/deep/.DS_Store", "2025-01-03"),
+            ("/src/music/d.mp3", "2025-01-04"),
+            ("/src/music/albums/e.mp3", "2025-01-05"),
+            ("/src/z.txt", "2025-01-06"),
+            ("/other/f.txt", "2025-01-07"),
+        ]);
+        let walked_paths = |walk: &mut dyn Iterator<Item = Result<StorageEntry>>| {
+            walk.map(|entry| (entry.as_ref().unwrap().path.clone(), entry.unwrap().depth)).collect::<Vec<_>>()
+        };
+
+        for extra_args in [&[][..], &["--min-depth", "2"], &["--max-depth", "2"], &["--sort", "name"]] {
+            let command_line = ["chronomover", "-s", "/src", "-s", "/other", "-d", "/dest", "--jobs", "3"].iter().chain(extra_args);
+            let args = Cli::parse_from(command_line).args.unwrap();
+
+            let in_parallel = thread::scope(|scope| walked_paths(&mut walk_source_folder_in_parallel(scope, &args, &storage)));
+
+            assert_eq!(in_parallel, walked_paths(&mut walk_source_folder(&args, &storage)), "with {extra_args:?}");
+        }
+    }
+
+    #[test]
+    fn test_get_files_to_move_frees_at_least_with_oldest_files() {
+        let now = "2025-06-15T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let storage = MemoryStorage::with_tree([
+            ("/src/2025/march.iso", "2025-03-01"),
+            ("/src/2024/june.iso", "2024-06-01"),
+            ("/src/2025/may.iso", "2025-05-01"),
+            ("/src/2024/july.iso", "2024-07-01"),
+        ]);
+        for (path, size) in [("/src/2025/march.iso", 300), ("/src/2024/june.iso", 100), ("/src/2025/may.iso", 400), ("/src/2024/july.iso", 100)] {
+            storage.set_size(path, size << 20);
+        }
+        let get_moved_files = |free_at_least: &str| {
+            let args = Cli::parse_from(["chronomover", "-s", "/src", "-d", "/dest", "--free-at-least", free_at_least]).args.unwrap();
+            let plan = get_files_to_move(&args, &storage, now, None).unwrap();
+            (plan.files_to_move.into_iter().map(|file| file.source).collect::<Vec<_>>(), plan.skipped)
+        };
+
+        // 100 + 100 MB fall short of 250 MB, so the 300 MB file is needed too
+        let (moved, skipped) = get_moved_files("250M");
+        assert_eq!(moved, ["/src/2024/june.iso", "/src/2024/july.iso", "/src/2025/march.iso"].map(PathBuf::from));
+        assert_eq!(skipped, 1);
+        assert_eq!(get_moved_files("200M").0.len(), 2);
+        assert_eq!(get_moved_files("10G").0.len(), 4);
+    }
+
+    #[test]
+    fn test_get_files_to_move_waits_for_thresholds() {
+        let now = "2025-06-15T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let storage = MemoryStorage::with_tree([("/src/a.log", "2025-01-01"), ("/src/b.log", "2025-01-02"), ("/src/c.log", "2025-01-03")]);
+        storage.set_size("/src/a.log", 1 << 20);
+        let get_plan = |thresholds: &[&str]| {
+            let args = Cli::parse_from(["chronomover", "-s", "/src", "-d", "/dest"].iter().chain(thresholds)).args.unwrap();
+            let plan = get_files_to_move(&args, &storage, now, None).unwrap();
+            (plan.planned, plan.skipped)
+        };
+
+        assert_eq!(get_plan(&["--only-if-count-over", "3"]), (0, 3));
+        assert_eq!(get_plan(&["--only-if-count-over", "2"]), (3, 0));
+        assert_eq!(get_plan(&["--only-if-size-over", "1M"]), (0, 3));
+        assert_eq!(get_plan(&["--only-if-size-over", "512K"]), (3, 0));
+        assert_eq!(get_plan(&["--only-if-count-over", "2", "--only-if-size-over", "1M"]), (0, 3));
+    }
+
+    #[test]
+    fn test_get_files_to_move_with_custom_grouper() {
+        /// Two-week sprints starting on Monday 2025-01-06
+        #[derive(Debug)]
+        struct SprintGrouper;
+
+        impl SprintGrouper {
+            fn sprint(date: DateTime<Utc>) -> i64 {
+                let sprint_start = "2025-01-06T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+                (date - sprint_start).num_days().div_euclid(14) + 1
+            }
+        }
+
+        impl Grouper for SprintGrouper {
+            fn identifier(&self, date: DateTime<Utc>) -> String {
+                format!("sprint-{:02}", Self::sprint(date))
+            }
+
+            fn is_previous_period(&self, date: DateTime<Utc>, now: DateTime<Utc>) -> bool {
+                Self::sprint(date) < Self::sprint(now)
+            }
+        }
+
+        let now = "2025-02-04T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let storage = MemoryStorage::default();
+        storage.add_file("/src/a.txt", "2025-01-10T00:00:00Z".parse::<DateTime<Utc>>().unwrap());
+        storage.add_file("/src/b.txt", "2025-01-21T00:00:00Z".parse::<DateTime<Utc>>().unwrap());
+        storage.add_file("/src/c.txt", "2025-02-03T00:00:00Z".parse::<DateTime<Utc>>().unwrap());
+        let mut args = Cli::parse_from(["chronomover", "-s", "/src", "-d", "/dest", "--group-by", "month", "--previous-period-only"]).args.unwrap();
+        args.grouper = Some(Arc::new(SprintGrouper));
+
+        let plan = get_files_to_move(&args, &storage, now, None).unwrap();
+
+        let mut destinations = plan.files_to_move.iter().map(|file| file.destination.as_path()).collect::<Vec<_>>();
+        destinations.sort();
+        assert_eq!(destinations, [Path::new("/dest/sprint-01/a.txt"), Path::new("/dest/sprint-02/b.txt")]);
+    }
+
+    #[test]
+    fn test_get_files_to_move_skips_sync_conflicts_and_placeholders() {
+        let now = "2025-06-15T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let date = "2025-01-10T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let storage = MemoryStorage::default();
+        storage.add_file("/src/report.docx", date);
+        storage.add_file("/src/report (conflicted copy 2025-01-10).docx", date);
+        storage.add_placeholder("/src/video.mp4", date);
+        let get_moved_files = |extra_args: &[&str]| {
+            let args = Cli::parse_from(["chronomover", "-s", "/src", "-d", "/dest"].iter().chain(extra_args)).args.unwrap();
+            let plan = get_files_to_move(&args, &storage, now, None).unwrap();
+            plan.files_to_move.into_iter().map(|file| file.source).collect::<Vec<_>>()
+        };
+
+        assert_eq!(get_moved_files(&[]), vec![PathBuf::from("/src/report.docx")]);
+        assert_eq!(get_moved_files(&["--sync-conflicts", "include", "--cloud-placeholders", "hydrate"]).len(), 3);
+    }
+
+    #[test]
+    fn test_cancelled_run_leaves_files_in_place() {
+        let storage = MemoryStorage::default();
+        storage.add_file("/src/a.txt", "2025-01-10T00:00:00Z".parse::<DateTime<Utc>>().unwrap());
+        storage.add_dir("/dest");
+        let args = Cli::parse_from(["chronomover", "-s", "/src", "-d", "/dest"]).args.unwrap();
+        let files = vec![create_file_to_move("/src/a.txt", "2025-01-10T00:00:00Z")];
+        let mut report = MoveReport::default();
+
+        args.cancellation.cancel();
+        let plan = get_files_to_move(&args, &storage, Utc::now(), None).unwrap();
+        move_batch(&args, &storage, &files, false, &mut MoveProgress::for_files(&files), (None, &mut report), None).unwrap();
+
+        assert!(plan.files_to_move.is_empty());
+        assert_eq!((report.moved_count(), report.cancelled), (0, 1));
+        assert!(storage.stat(Path::new("/src/a.txt")).is_ok());
+    }
+
+    #[test]
+    fn test_move_progress_estimates_time_left() {
+        let mut progress = MoveProgress::new(Some((10, 1000)));
+        progress.started_at = Instant::now().checked_sub(Duration::from_secs(10)).unwrap();
+        assert_eq!(progress.describe(1, false), "1/10");
+
+        progress.handled_bytes = 250;
+        assert_eq!(progress.describe(3, false), "3/10 (about 30s left)");
+        assert_eq!(progress.describe(3, true), "3/10");
+        assert_eq!(MoveProgress::new(None).describe(3, false), "3");
+    }
+
+    #[test]
+    fn test_max_errors_aborts_with_the_report() {
+        let storage = MemoryStorage::default();
+        storage.add_file("/src/c.txt", "2025-01-10T00:00:00Z".parse::<DateTime<Utc>>().unwrap());
+        storage.add_dir("/dest");
+        let args = Cli::parse_from(["chronomover", "-s", "/src", "-d", "/dest", "--max-errors", "2", "--jobs", "1"]).args.unwrap();
+        // The first two files are missing, so they fail to move
+        let files = ["/src/a.txt", "/src/b.txt", "/src/c.txt"].map(|path| create_file_to_move(path, "2025-01-10T00:00:00Z")).to_vec();
+        let mut report = MoveReport::default();
+
+        move_batch(&args, &storage, &files, false, &mut MoveProgress::for_files(&files), (None, &mut report), None).unwrap();
+
+        assert!(report.aborted.as_deref().is_some_and(|reason| reason.starts_with("Aborting after 2 error(s)")));
+        assert_eq!(report.errors.len(), 2);
+        // The worker may have moved the last file before the abort stopped it
+        assert_eq!(report.moved_count() + report.left_in_place.len(), 1);
+        assert_eq!(storage.is_file("/src/c.txt"), !report.left_in_place.is_empty());
+    }
+
+    #[test]
+    fn test_move_batch_hydrates_placeholders() {
+        let storage = MemoryStorage::default();
+        storage.add_placeholder("/src/video.mp4", "2025-01-10T00:00:00Z".parse::<DateTime<Utc>>().unwrap());
+        storage.add_dir("/dest");
+        let args = Cli::parse_from(["chronomover", "-s", "/src", "-d", "/dest", "--cloud-placeholders", "hydrate"]).args.unwrap();
+        let files = vec![create_file_to_move("/src/video.mp4", "2025-01-10T00:00:00Z")];
+        let mut report = MoveReport::default();
+
+        move_batch(&args, &storage, &files, false, &mut MoveProgress::for_files(&files), (None, &mut report), None).unwrap();
+
+        assert_eq!(report.moved_count(), 1);
+        assert!(!storage.stat(Path::new("/dest/src/video.mp4")).unwrap().is_placeholder);
+    }
+
+    #[test]
+    fn test_attempt_move_keeps_the_replaced_file_when_the_move_fails() {
+        let storage = MemoryStorage::with_tree([
+            ("/src/report.pdf", "2025-01-10"),
+            ("/dest/src/report.pdf", "2024-12-01"),
+            ("/dest/src/missing.pdf", "2024-12-01"),
+        ]);
+        let file = create_file_to_move("/src/report.pdf", "2025-01-10T00:00:00Z");
+        let missing_file = create_file_to_move("/src/missing.pdf", "2025-01-10T00:00:00Z");
+
+        assert!(attempt_move(&storage, &missing_file, false, false).is_err());
+        assert!(storage.is_file("/dest/src/missing.pdf"));
+
+        attempt_move(&storage, &file, false, false).unwrap();
+        assert_eq!(storage.file_paths(), [PathBuf::from("/dest/src/missing.pdf"), PathBuf::from("/dest/src/report.pdf")]);
+        assert_eq!(storage.stat(Path::new("/dest/src/report.pdf")).unwrap().modified, Some(SystemTime::from("2025-01-10T00:00:00Z".parse::<DateTime<Utc>>().unwrap())));
+    }
+
+    #[test]
+    fn test_move_batch_and_delete_empty_directories_in_memory_storage() {
+        let storage = MemoryStorage::default();
+        storage.add_file("/src/notes/old.txt", "2025-01-10T00:00:00Z".parse::<DateTime<Utc>>().unwrap());
+        storage.add_file("/src/notes/.DS_Store", "2025-01-10T00:00:00Z".parse::<DateTime<Utc>>().unwrap());
+        storage.add_file("/src/kept/new.txt", "2025-06-10T00:00:00Z".parse::<DateTime<Utc>>().unwrap());
+        storage.add_dir("/dest");
+        let args = Cli::parse_from(["chronomover", "-s", "/src", "-d", "/dest"]).args.unwrap();
+        let files = vec![create_file_to_move("/src/notes/old.txt", "2025-01-10T00:00:00Z")];
+        let mut report = MoveReport::default();
+
+        move_batch(&args, &storage, &files, false, &mut MoveProgress::for_files(&files), (None, &mut report), None).unwrap();
+        let deleted_dirs = delete_empty_directories(&args, &storage, &report.moved).unwrap();
+
+        assert_eq!(report.moved_count(), 1);
+        assert!(storage.is_file("/dest/src/notes/old.txt"));
+        assert!(!storage.is_file("/src/notes/old.txt"));
+        assert_eq!(deleted_dirs, vec![PathBuf::from("/src/notes")]);
+        assert!(!storage.is_file("/src/notes/.DS_Store"));
+        assert!(storage.is_file("/src/kept/new.txt"));
+    }
+
+    /// Plan, move and clean up like a run does, all in `storage`, without the run manifest (written to the disk)
+    fn run_in_memory(args: &Args, storage: &MemoryStorage, now: DateTime<Utc>) -> (Plan, MoveReport, Vec<PathBuf>) {
+        let plan = get_files_to_move(args, storage, now, None).unwrap();
+        let mut report = MoveReport::default();
+        move_batch(args, storage, &plan.files_to_move, false, &mut MoveProgress::for_files(&plan.files_to_move), (None, &mut report), None).unwrap();
+        let deleted_dirs = delete_empty_directories(args, storage, &report.moved).unwrap();
+        (plan, report, deleted_dirs)
+    }
+
+    #[test]
+    fn test_run_in_memory_storage_groups_by_latest_timestamp() {
+        let now = "2025-06-15T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let storage = MemoryStorage::with_tree([
+            ("/src/photos/beach.jpg", "2025-04-20"),
+            ("/src/photos/edited.jpg", "2025-04-21"),
+            ("/src/docs/current.txt", "2025-06-01"),
+            ("/src/docs/no-created.txt", "2025-05-02"),
+            ("/src/empty/", ""),
+            ("/dest/", ""),
+        ]);
+        // Created in April but edited in June, so it belongs to the current period
+        let edited_at = "2025-06-02T00:00:00Z".parse().ok();
+        storage.set_dates("/src/photos/edited.jpg", "2025-04-21T00:00:00Z".parse().ok(), edited_at, edited_at);
+        // Missing a creation time, so it can't be dated by the default timestamps
+        storage.set_dates("/src/docs/no-created.txt", None, "2025-05-02T00:00:00Z".parse().ok(), "2025-05-02T00:00:00Z".parse().ok());
+        let args = Cli::parse_from(["chronomover", "-s", "/src", "-d", "/dest", "--group-by", "month", "--previous-period-only"]).args.unwrap();
+
+        let (plan, report, deleted_dirs) = run_in_memory(&args, &storage, now);
+
+        assert_eq!((plan.planned, plan.skipped, plan.errors.len()), (1, 2, 1));
+        assert_eq!(plan.errors[0].path, PathBuf::from("/src/docs/no-created.txt"));
+        assert_eq!((report.moved_count(), report.errors.len()), (1, 0));
+        assert_eq!(deleted_dirs, vec![PathBuf::from("/src/empty")]);
+        assert_eq!(storage.file_paths(), [
+            "/dest/2025-04/photos/beach.jpg",
+            "/src/docs/current.txt",
+            "/src/docs/no-created.txt",
+            "/src/photos/edited.jpg",
+        ].map(PathBuf::from));
+    }
+
+    #[test]
+    fn test_run_in_memory_storage_with_conflicting_destinations() {
+        let now = "2025-06-15T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let storage = MemoryStorage::with_tree([
+            ("/src/a/report.txt", "2025-01-10"),
+            ("/src/b/notes.txt", "2025-01-11"),
+            ("/dest/2025/a/report.txt", "2024-12-01"),
+            ("/dest/2025/b/notes.txt/", ""),
+        ]);
+        storage.set_size("/src/a/report.txt", 42);
+        let args = Cli::parse_from(["chronomover", "-s", "/src", "-d", "/dest", "--group-by", "year"]).args.unwrap();
+
+        let (plan, report, deleted_dirs) = run_in_memory(&args, &storage, now);
+
+        // The existing file is replaced, while the folder in the way fails the move and keeps its source folder
+        assert_eq!(plan.planned, 2);
+        assert_eq!(report.moved_count(), 1);
+        assert_eq!(report.errors.iter().map(|error| error.path.as_path()).collect::<Vec<_>>(), [Path::new("/src/b/notes.txt")]);
+        assert_eq!(deleted_dirs, vec![PathBuf::from("/src/a")]);
+        assert_eq!(storage.stat(Path::new("/dest/2025/a/report.txt")).unwrap().size, 42);
+        assert_eq!(storage.file_paths(), ["/dest/2025/a/report.txt", "/src/b/notes.txt"].map(PathBuf::from));
+    }
+
+    #[test]
+    fn test_run_in_memory_storage_copies_into_mirrors() {
+        let now = "2025-06-15T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let storage = MemoryStorage::with_tree([
+            ("/src/a/report.txt", "2025-01-10"),
+            ("/src/notes.txt", "2025-01-11"),
+            ("/usb/", ""),
+            ("/nas/2025/notes.txt/", ""),
+        ]);
+        storage.set_size("/src/a/report.txt", 42);
+        let args = Cli::parse_from(["chronomover", "-s", "/src", "-d", "/dest", "-g", "year", "--mirror", "/usb", "--mirror", "/nas"]).args.unwrap();
+
+        let (_, report, _) = run_in_memory(&args, &storage, now);
+
+        // The folder in the way of a copy keeps the file in place, with its copy in the other mirror
+        assert_eq!(report.moved_count(), 1);
+        assert_eq!(report.errors.iter().map(|error| error.path.as_path()).collect::<Vec<_>>(), [Path::new("/src/notes.txt")]);
+        assert_eq!(storage.stat(Path::new("/usb/2025/a/report.txt")).unwrap().size, 42);
+        assert!(storage.is_file("/nas/2025/a/report.txt") && storage.is_file("/usb/2025/notes.txt"));
+        assert_eq!(report.mirrors[Path::new("/usb")], MirrorStats { files: 2, bytes: 42, failed: 0 });
+        assert_eq!(report.mirrors[Path::new("/nas")], MirrorStats { files: 1, bytes: 42, failed: 1 });
+    }
+
+    #[test]
+    fn test_run_in_memory_storage_cleans_destination_empty_dirs() {
+        let now = "2025-06-15T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let storage = MemoryStorage::with_tree([
+            ("/src/report.txt", "2025-01-10"),
+            ("/src/keep/", ""),
+            ("/dest/2024-11/", ""),
+            ("/dest/2024-12/empty/", ""),
+            ("/dest/2024-12/notes.txt", "2024-12-01"),
+            ("/dest/inbox/", ""),
+        ]);
+        let args = Cli::parse_from([
+            "chronomover", "-s", "/src", "-s", "/dest/inbox", "-d", "/dest", "--group-by", "month",
+            "--keep-empty-folders", "--clean-destination-empty-dirs",
+        ]).args.unwrap();
+
+        let (_, report, deleted_dirs) = run_in_memory(&args, &storage, now);
+
+        assert_eq!(report.moved_count(), 1);
+        // Sources inside the destination keep their own rules, here --keep-empty-folders
+        assert_eq!(deleted_dirs, vec![PathBuf::from("/dest/2024-12/empty"), PathBuf::from("/dest/2024-11")]);
+        assert!(storage.is_dir(Path::new("/src/keep")));
+        assert!(storage.is_dir(Path::new("/dest/inbox")));
+        assert!(storage.is_dir(Path::new("/dest/2025-01")));
+    }
+
+    #[test]
+    fn test_run_in_memory_storage_routes_extensions_to_destinations() {
+        let now = "2025-06-15T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let storage = MemoryStorage::with_tree([
+            ("/src/beach.JPG", "2025-04-20"),
+            ("/src/scan.png", "2025-04-21"),
+            ("/src/invoice.pdf", "2025-05-02"),
+            ("/src/notes.txt", "2025-05-03"),
+            ("/dest/", ""),
+            ("/photos/", ""),
+            ("/docs/", ""),
+        ]);
+        let args = Cli::parse_from([
+            "chronomover", "-s", "/src", "-d", "/dest", "--group-by", "month",
+            "--route", "jpg,png=>/photos", "--route", "pdf=>/docs",
+        ]).args.unwrap();
+
+        let (plan, report, _) = run_in_memory(&args, &storage, now);
+
+        assert_eq!((plan.planned, report.moved_count()), (4, 4));
+        assert_eq!(storage.file_paths(), [
+            "/dest/2025-05/notes.txt",
+            "/docs/2025-05/invoice.pdf",
+            "/photos/2025-04/beach.JPG",
+            "/photos/2025-04/scan.png",
+        ].map(PathBuf::from));
+    }
+
+    #[test]
+    fn test_run_in_memory_storage_routes_source_subpaths_to_destinations() {
+        let now = "2025-06-15T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let storage = MemoryStorage::with_tree([
+            ("/src/Camera Uploads/2025/beach.jpg", "2025-04-20"),
+            ("/src/Work/reports/q1.pdf", "2025-04-21"),
+            ("/src/Work/logo.png", "2025-05-02"),
+            ("/src/Workshop/plan.pdf", "2025-05-03"),
+            ("/src/scan.png", "2025-05-04"),
+            ("/dest/", ""),
+            ("/pictures/", ""),
+            ("/work/", ""),
+        ]);
+        let args = Cli::parse_from([
+            "chronomover", "-s", "/src", "-d", "/dest", "--group-by", "month",
+            "--route-path", "camera uploads/**=>/pictures", "--route-path", "Work=>/work", "--route", "png=>/pictures",
+        ]).args.unwrap();
+
+        let (plan, report, _) = run_in_memory(&args, &storage, now);
+
+        // Path routes are checked before the extension ones, so Work's logo stays with the work files
+        assert_eq!((plan.planned, report.moved_count()), (5, 5));
+        assert_eq!(storage.file_paths(), [
+            "/dest/2025-05/Workshop/plan.pdf",
+            "/pictures/2025-04/Camera Uploads/2025/beach.jpg",
+            "/pictures/2025-05/scan.png",
+            "/work/2025-04/Work/reports/q1.pdf",
+            "/work/2025-05/Work/logo.png",
+        ].map(PathBuf::from));
+    }
+
+    #[test]
+    fn test_run_in_memory_storage_touches_destination() {
+        let now = "2025-06-15T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let storage = MemoryStorage::with_tree([("/src/a.txt", "2025-04-20"), ("/src/b.txt", "2025-05-02"), ("/dest/", "")]);
+        let args = Cli::parse_from([
+            "chronomover", "-s", "/src", "-d", "/dest", "--group-by", "month", "--touch-destination", "period-end",
+        ]).args.unwrap();
+
+        run_in_memory(&args, &storage, now);
+
+        let modified = |path: &str| DateTime::<Utc>::from(storage.stat(Path::new(path)).unwrap().modified.unwrap()).to_rfc3339();
+        assert_eq!(modified("/dest/2025-04/a.txt"), "2025-04-30T23:59:59+00:00");
+        assert_eq!(modified("/dest/2025-05/b.txt"), "2025-05-31T23:59:59+00:00");
+    }
+
+    #[test]
+    fn test_calculate_dest_path_sanitizes_names() {
+        let source_root = PathBuf::from("/source");
+        let dest_root = PathBuf::from("/dest");
+        let source_path = source_root.join("Meeting: 10\\30?").join("notes*draft .").join("con.txt");
+
+        let result = calculate_dest_path(&source_path, &source_root, &dest_root, Some("2025-06"), false, UnicodeNormalization::None, true).unwrap();
+
+        assert_eq!(result, dest_root.join("2025-06").join("Meeting_ 10_30_").join("notes_draft").join("con_.txt"));
+        assert_eq!(sanitize_name("tab\there..."), "tab_here");
+        assert_eq!(sanitize_name(". "), "_");
+        assert_eq!(sanitize_name("console.log"), "console.log");
+    }
+
+    #[test]
+    fn test_get_files_to_move_shortens_long_destinations() {
+        let long_folder = format!("/src/{}", "Quarterly planning ".repeat(4).trim_end());
+        let storage = MemoryStorage::with_tree([
+            (format!("{long_folder}/agenda.md").as_str(), "2025-05-02"),
+            ("/src/short.md", "2025-05-03"),
+            ("/dest/", ""),
+        ]);
+        let args = Cli::parse_from([
+            "chronomover", "-s", "/src", "-d", "/dest", "--group-by", "month", "--shorten-paths", "hash-suffix", "--max-path-length", "60",
+        ]).args.unwrap();
+
+        let plan = get_files_to_move(&args, &storage, "2025-06-15T00:00:00Z".parse().unwrap(), None).unwrap();
+
+        assert_eq!(plan.shortened.len(), 1);
+        let shortened = &plan.shortened[0];
+        assert_eq!(shortened.source, PathBuf::from(format!("{long_folder}/agenda.md")));
+        assert!(shortened.shortened.starts_with("/dest/2025-05") && shortened.shortened.ends_with("agenda.md"));
+        assert!(shortened.shortened.as_os_str().len() <= 60);
+        assert!(plan.files_to_move.iter().any(|file| file.destination == shortened.shortened));
+    }
+
+    #[test]
+    fn test_set_period_dir_times() {
+        let destination = std::env::temp_dir().join(format!("chronomover-period-dir-times-{}", std::process::id()));
+        let destination_arg = destination.to_string_lossy().to_string();
+        let args = Cli::parse_from(["chronomover", "-s", "/src", "-d", &destination_arg, "--group-by", "month"]).args.unwrap();
+        let file = |name: &str, period: &str| FileToMove {
+            source: PathBuf::from("/src").join(name),
+            destination: destination.join(period).join(name),
+            file_date: Utc::now(),
+            size: 0,
+            period: Some(period.to_string()),
+        };
+        for period in ["2025-05", "sprint-42"] {
+            fs::create_dir_all(destination.join(period)).unwrap();
+        }
+        let modified = |period: &str| DateTime::<Utc>::from(fs::metadata(destination.join(period)).unwrap().modified().unwrap());
+
+        set_period_dir_times(&args, &LocalStorage, &[file("a.txt", "2025-05"), file("b.txt", "2025-05"), file("c.txt", "sprint-42")]);
+
+        assert_eq!(modified("2025-05").to_rfc3339(), "2025-05-31T23:59:59+00:00");
+        assert!(modified("sprint-42") > "2025-06-01T00:00:00Z".parse::<DateTime<Utc>>().unwrap());
+        fs::remove_dir_all(&destination).unwrap();
+    }
+
+    #[test]
+    fn test_dry_run_lists_directories_that_would_be_deleted() {
+        let storage = MemoryStorage::with_tree([
+            ("/src/notes/2024/old.txt", "2024-01-10"),
+            ("/src/notes/.DS_Store", "2024-01-10"),
+            ("/src/kept/old.txt", "2024-01-10"),
+            ("/src/kept/new.txt", "2025-06-10"),
+            ("/dest/2023/", ""),
+            ("/dest/2024/", ""),
+        ]);
+        let args = Cli::parse_from([
+            "chronomover", "-s", "/src", "-d", "/dest", "--group-by", "year", "--older-than", "2025-01-01",
+            "--clean-destination-empty-dirs", "--dry-run",
+        ]).args.unwrap();
+        let plan = get_files_to_move(&args, &storage, "2025-06-15T00:00:00Z".parse().unwrap(), None).unwrap();
+        let report = move_files(&args, &storage, &plan.files_to_move, true).unwrap();
+
+        let deleted_dirs = delete_empty_directories(&args, &storage, &report.moved).unwrap();
+
+        // /dest/2024 would receive the moved files, so only /dest/2023 would stay empty
+        assert_eq!(deleted_dirs, ["/src/notes/2024", "/src/notes", "/dest/2023"].map(PathBuf::from));
+        assert!(storage.is_file("/src/notes/2024/old.txt"));
+        assert!(storage.is_dir(Path::new("/dest/2023")));
+    }
+
+    #[test]
+    fn test_find_case_collisions() {
+        let files = vec![
+            create_file_to_move("/src/Report.pdf", "2025-01-01T00:00:00Z"),
+            create_file_to_move("/src/notes.txt", "2025-01-01T00:00:00Z"),
+            create_file_to_move("/src/report.pdf", "2025-01-01T00:00:00Z"),
+            create_file_to_move("/src/notes.txt", "2025-01-01T00:00:00Z"),
+        ];
+
+        let nothing_claimed = ClaimedDestinations::new();
+        let collisions = find_case_collisions(&files, &nothing_claimed);
+        assert_eq!(collisions, vec![(Path::new("/dest/src/Report.pdf"), Path::new("/dest/src/report.pdf"))]);
+
+        // Destinations claimed by an earlier batch come first
+        let claimed = ClaimedDestinations::from([("/dest/src/notes.txt".to_string(), PathBuf::from("/dest/src/NOTES.txt"))]);
+        let collisions = find_case_collisions(&files[1..2], &claimed);
+        assert_eq!(collisions, vec![(Path::new("/dest/src/NOTES.txt"), Path::new("/dest/src/notes.txt"))]);
+    }
+
+    #[test]
+    fn test_resolve_case_collisions() {
+        let files = || vec![
+            create_file_to_move("/src/Report.pdf", "2025-01-01T00:00:00Z"),
+            create_file_to_move("/src/report.pdf", "2025-01-01T00:00:00Z"),
+            create_file_to_move("/src/report (1).pdf", "2025-01-01T00:00:00Z"),
+            create_file_to_move("/src/REPORT.PDF", "2025-01-01T00:00:00Z"),
+        ];
+        let args = |policy| Cli::parse_from(["chronomover", "-s", "/src", "-d", "/dest", "--case-collisions", policy]).args.unwrap();
+        let storage = MemoryStorage::default().reporting_case_insensitive();
+
+        let mut renamed = files();
+        resolve_case_collisions(&args("rename"), &storage, &mut renamed, &mut ClaimedDestinations::new()).unwrap();
+        let destinations = renamed.iter().map(|file| file.destination.clone()).collect::<Vec<_>>();
+        assert_eq!(destinations, [
+            "/dest/src/Report.pdf", "/dest/src/report (2).pdf", "/dest/src/report (1).pdf", "/dest/src/REPORT (3).PDF",
+        ].map(PathBuf::from));
+
+        assert!(resolve_case_collisions(&args("fail"), &storage, &mut files(), &mut ClaimedDestinations::new()).is_err());
+        let mut replaced = files();
+        resolve_case_collisions(&args("replace"), &storage, &mut replaced, &mut ClaimedDestinations::new()).unwrap();
+        assert_eq!(replaced[1].destination, PathBuf::from("/dest/src/report.pdf"));
+
+        // Batches are resolved against the destinations claimed by the previous ones, but can't avoid the destinations
+        // of the batches to come
+        let mut claimed = ClaimedDestinations::new();
+        let mut batches = files().into_iter().map(|file| vec![file]).collect::<Vec<_>>();
+        for batch in &mut batches {
+            resolve_case_collisions(&args("rename"), &storage, batch, &mut claimed).unwrap();
+        }
+        let destinations = batches.concat().into_iter().map(|file| file.destination).collect::<Vec<_>>();
+        assert_eq!(destinations, [
+            "/dest/src/Report.pdf", "/dest/src/report (1).pdf", "/dest/src/report (1).pdf", "/dest/src/REPORT (2).PDF",
+        ].map(PathBuf::from));
+    }
+
+    #[test]
+    fn test_calculate_dest_path_different_group_formats() {
+        let source_root = PathBuf::from("/source");
+        let dest_root = PathBuf::from("/dest");
+        let source_path = source_root.join("file.md");
+
+        // Test with different grouping formats
+        let groups = vec![
+            "2025-24",      // Week
+            "2025-06",      // Month
+            "2025",         // Year
+            "2025-H1",      // Semester
+            "2025-Q2",      // Trimester
+            "2025-QD2",     // Quadrimester
+            "2025-BW12",    // Biweekly
+        ];
+
+        for group in groups {
+            let result = calculate_dest_path(&source_path, &source_root, &dest_root, Some(group), false, UnicodeNormalization::None, false).unwrap();
+            assert_eq!(result, dest_root.join(group).join("file.md"));
+        }
+    }
+    // run_in_order tests
+    #[test]
+    fn test_run_in_order_handles_results_in_item_order() {
+        let items = (0..50).collect::<Vec<u64>>();
+        let mut handled = Vec::new();
+
+        run_in_order(&items, 8, |&item| {
+            // Finish later items first, so results arrive out of order
+            thread::sleep(Duration::from_micros((50 - item) * 20));
+            item * 2
+        }, |index, result| {
+            handled.push((index, result));
+            Ok(())
+        }).unwrap();
+
+        assert_eq!(handled, items.iter().map(|&item| (item as usize, item * 2)).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_run_in_order_stops_on_first_error() {
+        let items = (0..1000).collect::<Vec<usize>>();
+        let mut handled = Vec::new();
+
+        let result = run_in_order(&items, 4, |&item| {
+            thread::sleep(Duration::from_millis(1));
+            item
+        }, |index, _| {
+            handled.push(index);
+            if index >= 10 { bail!("Failed at {}", index) } else { Ok(()) }
+        });
+
+        assert_eq!(result.unwrap_err().to_string(), "Failed at 10");
+        // Items already started when the error happened are still handled, in order, but no new ones are started
+        assert!(handled.len() < items.len());
+        assert_eq!(handled, (0..handled.len()).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_parse_file_list_newline_separated() {
+        let list = "/src/a.txt\r\n\n/src/with space.txt\n".as_bytes();
+
+        let paths = parse_file_list(list).unwrap().map(Result::unwrap).collect::<Vec<_>>();
+
+        assert_eq!(paths, vec![PathBuf::from("/src/a.txt"), PathBuf::from("/src/with space.txt")]);
+    }
+
+    #[test]
+    fn test_parse_file_list_nul_separated() {
+        let list = "/src/a.txt\0/src/line\nbreak.txt\0".as_bytes();
+
+        let paths = parse_file_list(list).unwrap().map(Result::unwrap).collect::<Vec<_>>();
+
+        assert_eq!(paths, vec![PathBuf::from("/src/a.txt"), PathBuf::from("/src/line\nbreak.txt")]);
+    }
+
+    #[test]
+    fn test_parse_file_list_invalid_utf8() {
+        let list: &[u8] = b"/src/a.txt\n/src/\xff.txt\n";
+
+        let paths = parse_file_list(list).unwrap().collect::<Vec<_>>();
+
+        assert_eq!(paths.len(), 2);
+        assert_eq!(paths[1].as_ref().unwrap_err().error, "File name is not valid UTF-8");
+    }
+}
@@ -0,0 +1,82 @@
+use std::path::{Component, Path};
+
+/// Whether `text` matches a glob where `*` is any run of characters and `?` any single one
+pub fn matches_glob(pattern: &str, text: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let text = text.chars().collect::<Vec<_>>();
+    let (mut pattern_index, mut text_index) = (0, 0);
+    // Position of the last `*` and of the text it was matched up to, to backtrack to
+    let mut backtrack = None;
+    while text_index < text.len() {
+        match pattern.get(pattern_index) {
+            Some('*') => {
+                backtrack = Some((pattern_index, text_index));
+                pattern_index += 1;
+            }
+            Some(&character) if character == '?' || character == text[text_index] => {
+                pattern_index += 1;
+                text_index += 1;
+            }
+            _ => match backtrack {
+                Some((star_index, matched_to)) => {
+                    pattern_index = star_index + 1;
+                    text_index = matched_to + 1;
+                    backtrack = Some((star_index, matched_to + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[pattern_index..].iter().all(|&character| character == '*')
+}
+
+/// Whether a path relative to a source matches a glob of `/`-separated segments, where `**` is any number of folders,
+/// and `*` and `?` stay within a segment. Case is ignored. A pattern naming a folder also matches everything under it,
+/// so `Camera Uploads` is the same as `Camera Uploads/**`.
+pub fn matches_path_glob(pattern: &str, relative_path: &Path) -> bool {
+    let pattern = pattern.to_lowercase().replace('\\', "/");
+    let mut pattern_segments = pattern.split('/').filter(|segment| !segment.is_empty()).collect::<Vec<_>>();
+    pattern_segments.push("**");
+    let path_segments = relative_path.components()
+        .filter_map(|component| match component {
+            Component::Normal(name) => Some(name.to_string_lossy().to_lowercase()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    matches_segments(&pattern_segments, &path_segments)
+}
+
+fn matches_segments(pattern: &[&str], path: &[String]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((&"**", rest)) => (0..=path.len()).any(|skipped| matches_segments(rest, &path[skipped..])),
+        Some((segment, rest)) => path.split_first()
+            .is_some_and(|(name, path_rest)| matches_glob(segment, name) && matches_segments(rest, path_rest)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches_glob() {
+        assert!(matches_glob("*.pdf", "invoice.pdf"));
+        assert!(matches_glob("inv?ice*", "invoice-2025.pdf"));
+        assert!(matches_glob("*/2025-06/*", "/archive/2025-06/docs/a.txt"));
+        assert!(matches_glob("a*b*c", "aXbYbZc"));
+        assert!(!matches_glob("*.pdf", "invoice.pdf.bak"));
+        assert!(!matches_glob("?", ""));
+    }
+
+    #[test]
+    fn test_matches_path_glob() {
+        assert!(matches_path_glob("Camera Uploads/**", Path::new("camera uploads/2025/IMG_0001.jpg")));
+        assert!(matches_path_glob("Work", Path::new("Work/reports/q1.pdf")));
+        assert!(matches_path_glob("**/*.raw", Path::new("shoots/day1/DSC_0001.RAW")));
+        assert!(matches_path_glob("*/screenshots/*.png", Path::new("phone/screenshots/a.png")));
+        assert!(!matches_path_glob("*/screenshots/*.png", Path::new("screenshots/a.png")));
+        assert!(!matches_path_glob("Work/*", Path::new("Workshop/notes.txt")));
+        assert!(!matches_path_glob("*.pdf", Path::new("docs/a.pdf")));
+    }
+}
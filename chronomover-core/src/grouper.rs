@@ -0,0 +1,53 @@
+use crate::date;
+use crate::model::GroupBy;
+use chrono::{DateTime, Utc};
+use std::fmt::Debug;
+
+/// Period scheme grouping the moved files into folders. Implemented by `GroupBy` for the built-in strategies; library
+/// consumers implement it for their own schemes (sprints, pay periods, school terms) and plan with it through
+/// `Planner::grouper()`.
+pub trait Grouper: Debug + Send + Sync {
+    /// Name of the period folder a date belongs to (e.g., "2025-11" when grouping by month)
+    fn identifier(&self, date: DateTime<Utc>) -> String;
+
+    /// Whether a date belongs to a period before the current one, for `--previous-period-only`
+    fn is_previous_period(&self, date: DateTime<Utc>, now: DateTime<Utc>) -> bool;
+}
+
+impl Grouper for GroupBy {
+    fn identifier(&self, date: DateTime<Utc>) -> String {
+        date::get_period_identifier(*self, date)
+    }
+
+    fn is_previous_period(&self, date: DateTime<Utc>, now: DateTime<Utc>) -> bool {
+        match self {
+            GroupBy::Week => date::is_before_current_week(date, now),
+            GroupBy::Month => date::is_before_current_month(date, now),
+            GroupBy::Year => date::is_before_current_year(date, now),
+            GroupBy::Semester => date::is_before_current_semester(date, now),
+            GroupBy::Trimester => date::is_before_current_trimester(date, now),
+            GroupBy::Quadrimester => date::is_before_current_quadrimester(date, now),
+            GroupBy::Biweekly => date::is_before_current_biweekly(date, now),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_date(date: &str) -> DateTime<Utc> {
+        date.parse().unwrap()
+    }
+
+    #[test]
+    fn test_group_by_grouper() {
+        let date = parse_date("2025-06-15T00:00:00Z");
+        let now = parse_date("2025-07-01T00:00:00Z");
+
+        assert_eq!(GroupBy::Month.identifier(date), "2025-06");
+        assert_eq!(GroupBy::Trimester.identifier(date), "2025-Q2");
+        assert!(GroupBy::Month.is_previous_period(date, now));
+        assert!(!GroupBy::Year.is_previous_period(date, now));
+    }
+}
@@ -0,0 +1,195 @@
+use crate::file::{FileToMove, MoveReport, Plan};
+use crate::model::Args;
+use crate::report::RunOutcome;
+use color_eyre::eyre::{bail, Context, Result};
+use std::env;
+use std::ffi::OsString;
+use std::io;
+use std::path::Path;
+use std::process::{Command, Output, Stdio};
+use tracing::{debug, warn};
+
+/// Prefix of the environment variables describing the run or the file to a hook
+const HOOK_VARIABLE_PREFIX: &str = "CHRONOMOVER_HOOK_";
+
+/// Run the `--pre-run` hook before the source folders are scanned. A failing hook aborts the run.
+pub fn run_pre_run_hook(args: &Args) -> Result<()> {
+    let Some(command) = &args.pre_run else {
+        return Ok(());
+    };
+    run_hook("--pre-run", command, &get_run_variables(args, "pre-run")).context("Aborting the run")
+}
+
+/// Run the `--post-file` hook once a file was moved (or failed to, or would have been in dry-run mode). Failures are
+/// only logged, as the file was already handled.
+pub fn run_post_file_hook(args: &Args, item: &FileToMove, dry_run: bool, error: Option<&io::Error>) {
+    let Some(command) = &args.post_file else {
+        return;
+    };
+    if let Err(e) = run_hook("--post-file", command, &get_file_variables(item, dry_run, error)) {
+        warn!(path = %item.source.display(), error = %e, "{:#}", e);
+    }
+}
+
+/// Run the `--post-run` hook once the run finished, with its outcome and counts. Failures are only logged.
+pub fn run_post_run_hook(args: &Args, plan: &Plan, move_report: &MoveReport, outcome: RunOutcome) {
+    let Some(command) = &args.post_run else {
+        return;
+    };
+    let failed = plan.errors.len() + move_report.errors.len();
+    let mut variables = get_run_variables(args, "post-run");
+    variables.extend([
+        ("STATUS", OsString::from(get_outcome_status(outcome))),
+        ("RUN_ID", OsString::from(move_report.run_id.as_deref().unwrap_or_default())),
+        ("PLANNED", OsString::from(plan.planned.to_string())),
+        ("MOVED", OsString::from(move_report.moved_count().to_string())),
+        ("SKIPPED", OsString::from(plan.skipped.to_string())),
+        ("FAILED", OsString::from(failed.to_string())),
+        ("BYTES", OsString::from(move_report.periods.values().map(|stats| stats.bytes).sum::<u64>().to_string())),
+    ]);
+    if let Err(e) = run_hook("--post-run", command, &variables) {
+        warn!("{:#}", e);
+    }
+}
+
+/// Variables shared by the run hooks. The sources are joined like `PATH`, with `:` (`;` on Windows).
+fn get_run_variables(args: &Args, event: &str) -> Vec<(&'static str, OsString)> {
+    vec![
+        ("EVENT", OsString::from(event)),
+        ("DRY_RUN", OsString::from(args.dry_run.to_string())),
+        ("SOURCES", env::join_paths(&args.sources).unwrap_or_default()),
+        ("DESTINATION", args.destination.clone().into_os_string()),
+    ]
+}
+
+/// Variables of the `--post-file` hook, whose status is the one of the move log lines
+fn get_file_variables(item: &FileToMove, dry_run: bool, error: Option<&io::Error>) -> Vec<(&'static str, OsString)> {
+    let status = match error {
+        Some(_) => "failed",
+        None if dry_run => "dry-run",
+        None => "moved",
+    };
+    vec![
+        ("EVENT", OsString::from("post-file")),
+        ("DRY_RUN", OsString::from(dry_run.to_string())),
+        ("SOURCE", item.source.clone().into_os_string()),
+        ("DESTINATION", item.destination.clone().into_os_string()),
+        ("PERIOD", OsString::from(item.period.as_deref().unwrap_or_default())),
+        ("SIZE", OsString::from(item.size.to_string())),
+        ("STATUS", OsString::from(status)),
+        ("ERROR", OsString::from(error.map(ToString::to_string).unwrap_or_default())),
+    ]
+}
+
+/// Ask the `--filter-cmd` whether a file passing the other filters should be moved, passing its path as the last
+/// argument: exit code 0 moves it, 1 keeps it in place (like `grep` or `clamscan`) and any other is an error
+pub fn run_filter_cmd(command: &str, path: &Path) -> Result<bool> {
+    let variables = [("EVENT", OsString::from("filter")), ("SOURCE", path.as_os_str().to_os_string())];
+    let output = run_shell("--filter-cmd", command, Some(path), &variables)?;
+    match output.status.code() {
+        Some(0) => Ok(true),
+        Some(1) => Ok(false),
+        _ => bail!("The --filter-cmd exited with {}: {}", output.status, String::from_utf8_lossy(&output.stderr).trim()),
+    }
+}
+
+fn get_outcome_status(outcome: RunOutcome) -> &'static str {
+    match outcome {
+        RunOutcome::Success => "success",
+        RunOutcome::CompletedWithFailures => "completed-with-failures",
+        RunOutcome::NothingMatched => "nothing-matched",
+        RunOutcome::Cancelled => "cancelled",
+    }
+}
+
+/// Run a hook, failing if it exits with a non-zero code
+fn run_hook(option: &str, command: &str, variables: &[(&str, OsString)]) -> Result<()> {
+    let output = run_shell(option, command, None, variables)?;
+    if !output.status.success() {
+        bail!("The {} hook exited with {}: {}", option, output.status, String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(())
+}
+
+/// Run a command through the shell with its variables set, and `argument` appended. Its output is only logged at debug
+/// level, so it never mixes with the machine-readable output of the run.
+fn run_shell(option: &str, command: &str, argument: Option<&Path>, variables: &[(&str, OsString)]) -> Result<Output> {
+    let mut shell = get_shell_command(command, argument);
+    for (name, value) in variables {
+        shell.env(format!("{HOOK_VARIABLE_PREFIX}{name}"), value);
+    }
+    let output = shell.stdin(Stdio::null())
+        .output()
+        .with_context(|| format!("Failed to run the {} command: {}", option, command))?;
+
+    for line in String::from_utf8_lossy(&output.stdout).lines().chain(String::from_utf8_lossy(&output.stderr).lines()) {
+        debug!("{}: {}", option, line);
+    }
+    Ok(output)
+}
+
+#[cfg(windows)]
+fn get_shell_command(command: &str, argument: Option<&Path>) -> Command {
+    use std::os::windows::process::CommandExt;
+
+    // cmd parses the rest of its command line itself, so quoting the command would change its meaning. Paths can't
+    // contain quotes on Windows, so quoting the argument is enough.
+    let mut command_line = OsString::from(command);
+    if let Some(argument) = argument {
+        command_line.push(" \"");
+        command_line.push(argument);
+        command_line.push("\"");
+    }
+    let mut shell = Command::new("cmd");
+    shell.arg("/C").raw_arg(command_line);
+    shell
+}
+
+#[cfg(not(windows))]
+fn get_shell_command(command: &str, argument: Option<&Path>) -> Command {
+    let mut shell = Command::new("sh");
+    match argument {
+        // Passed as a positional parameter, so the shell never interprets the characters of the path
+        Some(argument) => shell.arg("-c").arg(format!("{command} \"$@\"")).arg("sh").arg(argument),
+        None => shell.arg("-c").arg(command),
+    };
+    shell
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_get_file_variables() {
+        let item = FileToMove {
+            source: PathBuf::from("/photos/a.jpg"),
+            destination: PathBuf::from("/archive/2025-06/a.jpg"),
+            file_date: "2025-06-01T00:00:00Z".parse().unwrap(),
+            size: 42,
+            period: Some("2025-06".to_string()),
+        };
+
+        let variables = get_file_variables(&item, false, Some(&io::Error::from(io::ErrorKind::PermissionDenied)));
+
+        assert!(variables.contains(&("DESTINATION", OsString::from("/archive/2025-06/a.jpg"))));
+        assert!(variables.contains(&("PERIOD", OsString::from("2025-06"))));
+        assert!(variables.contains(&("STATUS", OsString::from("failed"))));
+        assert!(variables.contains(&("ERROR", OsString::from("permission denied"))));
+        assert!(get_file_variables(&item, true, None).contains(&("STATUS", OsString::from("dry-run"))));
+    }
+
+    #[test]
+    fn test_run_filter_cmd() {
+        assert!(run_filter_cmd("exit 0 ||", Path::new("a.txt")).unwrap());
+        assert!(!run_filter_cmd("exit 1 ||", Path::new("a.txt")).unwrap());
+        assert!(run_filter_cmd("exit 2 ||", Path::new("a.txt")).is_err());
+    }
+
+    #[test]
+    fn test_run_hook_fails_on_non_zero_exit_code() {
+        assert!(run_hook("--post-run", "exit 0", &[]).is_ok());
+        assert!(run_hook("--post-run", "exit 3", &[]).is_err());
+    }
+}
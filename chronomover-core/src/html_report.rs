@@ -0,0 +1,190 @@
+use crate::file::{FileError, FileToMove, MoveReport, Plan};
+use crate::model::Args;
+use crate::report::{format_size, PeriodStats};
+use color_eyre::eyre::{Context, Result};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+use tracing::info;
+
+/// Styles and the table sorting script are inlined, so the page can be attached anywhere without its assets
+const STYLE: &str = r#"
+body { font-family: system-ui, sans-serif; margin: 2rem; color: #222; }
+h1 { margin-bottom: 0.25rem; }
+.subtitle { color: #666; margin-top: 0; }
+table { border-collapse: collapse; width: 100%; margin-bottom: 2rem; font-size: 0.9rem; }
+th, td { border-bottom: 1px solid #ddd; padding: 0.4rem 0.6rem; text-align: left; }
+th { background: #f4f4f4; cursor: pointer; user-select: none; }
+th:hover { background: #e8e8e8; }
+td.number { text-align: right; white-space: nowrap; }
+.chart { display: grid; grid-template-columns: max-content 1fr max-content; gap: 0.3rem 0.75rem; align-items: center; margin-bottom: 2rem; max-width: 60rem; }
+.bar { height: 1rem; background: #4a90d9; border-radius: 2px; }
+.bar.size { background: #7bb662; }
+.error { color: #b00020; }
+"#;
+
+const SCRIPT: &str = r#"
+document.querySelectorAll("table.sortable th").forEach((header, column) => {
+  header.addEventListener("click", () => {
+    const body = header.closest("table").tBodies[0];
+    const ascending = header.dataset.order !== "asc";
+    header.dataset.order = ascending ? "asc" : "desc";
+    const value = row => row.cells[column].dataset.value ?? row.cells[column].textContent;
+    const rows = Array.from(body.rows).sort((a, b) => {
+      const [x, y] = [value(a), value(b)];
+      const order = isNaN(x) || isNaN(y) ? x.localeCompare(y) : x - y;
+      return ascending ? order : -order;
+    });
+    rows.forEach(row => body.appendChild(row));
+  });
+});
+"#;
+
+/// Write a self-contained HTML page describing the run (`--report-html`)
+pub fn write_html_report(path: &Path, args: &Args, plan: &Plan, move_report: &MoveReport) -> Result<()> {
+    let errors = plan.errors.iter().chain(&move_report.errors).collect::<Vec<_>>();
+
+    let html = render_html_report(args, &move_report.moved, &move_report.periods, &errors);
+    fs::write(path, html).with_context(|| format!("Failed to write HTML report: {}", path.display()))?;
+
+    info!("HTML report saved: {}", path.display());
+    Ok(())
+}
+
+fn render_html_report(
+    args: &Args,
+    moved: &[FileToMove],
+    periods: &BTreeMap<String, PeriodStats>,
+    errors: &[&FileError],
+) -> String {
+    let mut html = String::new();
+    let title = if args.dry_run { "ChronoMover dry run report" } else { "ChronoMover run report" };
+
+    // Writing to a String cannot fail
+    let _ = write!(
+        html,
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n<style>{STYLE}</style>\n</head>\n<body>\n\
+         <h1>{title}</h1>\n<p class=\"subtitle\">{} &rarr; {}</p>\n\
+         <p>{} file(s) {}, {} error(s)</p>\n",
+        escape_html(&args.sources.iter().map(|source| source.display().to_string()).collect::<Vec<_>>().join(", ")),
+        escape_html(&args.destination.display().to_string()),
+        moved.len(),
+        if args.dry_run { "would be moved" } else { "moved" },
+        errors.len(),
+    );
+
+    render_period_charts(&mut html, periods);
+    render_moved_files(&mut html, moved);
+    render_errors(&mut html, errors);
+
+    let _ = write!(html, "<script>{SCRIPT}</script>\n</body>\n</html>\n");
+    html
+}
+
+fn render_period_charts(html: &mut String, periods: &BTreeMap<String, PeriodStats>) {
+    if periods.is_empty() {
+        return;
+    }
+    let max_files = periods.values().map(|stats| stats.files).max().unwrap_or_default().max(1);
+    let max_bytes = periods.values().map(|stats| stats.bytes).max().unwrap_or_default().max(1);
+
+    html.push_str("<h2>Files per period</h2>\n<div class=\"chart\">\n");
+    for (period, stats) in periods {
+        let width = stats.files as f64 / max_files as f64 * 100.0;
+        let _ = writeln!(
+            html,
+            "<span>{}</span><div class=\"bar\" style=\"width: {width:.1}%\"></div><span>{}</span>",
+            escape_html(period),
+            stats.files
+        );
+    }
+    html.push_str("</div>\n<h2>Size per period</h2>\n<div class=\"chart\">\n");
+    for (period, stats) in periods {
+        let width = stats.bytes as f64 / max_bytes as f64 * 100.0;
+        let _ = writeln!(
+            html,
+            "<span>{}</span><div class=\"bar size\" style=\"width: {width:.1}%\"></div><span>{}</span>",
+            escape_html(period),
+            format_size(stats.bytes)
+        );
+    }
+    html.push_str("</div>\n");
+}
+
+fn render_moved_files(html: &mut String, moved: &[FileToMove]) {
+    html.push_str("<h2>Moved files</h2>\n<table class=\"sortable\">\n<thead><tr><th>Source</th><th>Destination</th><th>Date</th><th>Size</th></tr></thead>\n<tbody>\n");
+    for file in moved {
+        let _ = writeln!(
+            html,
+            "<tr><td>{}</td><td>{}</td><td data-value=\"{}\">{}</td><td class=\"number\" data-value=\"{}\">{}</td></tr>",
+            escape_html(&file.source.display().to_string()),
+            escape_html(&file.destination.display().to_string()),
+            file.file_date.timestamp(),
+            file.file_date.format("%Y-%m-%d %H:%M:%S"),
+            file.size,
+            format_size(file.size),
+        );
+    }
+    html.push_str("</tbody>\n</table>\n");
+}
+
+fn render_errors(html: &mut String, errors: &[&FileError]) {
+    if errors.is_empty() {
+        return;
+    }
+    html.push_str("<h2>Errors</h2>\n<table class=\"sortable\">\n<thead><tr><th>Path</th><th>Error</th></tr></thead>\n<tbody>\n");
+    for error in errors {
+        let _ = writeln!(
+            html,
+            "<tr><td>{}</td><td class=\"error\">{}</td></tr>",
+            escape_html(&error.path.display().to_string()),
+            escape_html(&error.error)
+        );
+    }
+    html.push_str("</tbody>\n</table>\n");
+}
+
+fn escape_html(value: &str) -> String {
+    value.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::Cli;
+    use chrono::{DateTime, Utc};
+    use clap::Parser;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_escape_html() {
+        assert_eq!(escape_html(r#"<a href="x">Tom & Jerry's</a>"#), "&lt;a href=&quot;x&quot;&gt;Tom &amp; Jerry&#39;s&lt;/a&gt;");
+    }
+
+    #[test]
+    fn test_render_html_report() {
+        let args = Cli::parse_from(["chronomover", "-s", "/notes", "-d", "/archive"]).args.unwrap();
+        let file = FileToMove {
+            source: PathBuf::from("/notes/<draft>.md"),
+            destination: PathBuf::from("/archive/2025-06/<draft>.md"),
+            file_date: "2025-06-15T00:00:00Z".parse::<DateTime<Utc>>().unwrap(),
+            size: 2048,
+            period: Some("2025-06".to_string()),
+        };
+        let periods = BTreeMap::from([("2025-06".to_string(), PeriodStats { files: 1, bytes: 2048 })]);
+        let error = FileError { path: PathBuf::from("/notes/b.md"), error: "Permission denied".to_string() };
+
+        let html = render_html_report(&args, &[file], &periods, &[&error]);
+
+        assert!(html.contains("<td>/notes/&lt;draft&gt;.md</td>"));
+        assert!(html.contains("data-value=\"2048\">2.0 KiB</td>"));
+        assert!(html.contains("<span>2025-06</span><div class=\"bar\" style=\"width: 100.0%\"></div><span>1</span>"));
+        assert!(html.contains("<td class=\"error\">Permission denied</td>"));
+        assert!(html.contains("1 file(s) moved, 1 error(s)"));
+    }
+}
@@ -0,0 +1,201 @@
+use crate::model::{Args, FileDateType, GroupBy};
+use chrono::{DateTime, Utc};
+use color_eyre::eyre::{bail, Result};
+use std::path::PathBuf;
+
+/// Whether a job moves the files or only reports what it would move
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MoveMode {
+    #[default]
+    Move,
+    DryRun,
+}
+
+/// Which files of the sources a job moves
+#[derive(Debug, Clone, PartialEq)]
+pub struct JobFilters {
+    /// Only move files dated before this
+    pub older_than: Option<DateTime<Utc>>,
+    /// Timestamps the files are dated by, the most recent one winning
+    pub file_date_types: Vec<FileDateType>,
+    /// Files and folders never moved
+    pub ignored_paths: Vec<PathBuf>,
+    /// Only move files of the periods before the current one
+    pub previous_period_only: bool,
+}
+
+impl Default for JobFilters {
+    fn default() -> Self {
+        Self {
+            older_than: None,
+            file_date_types: vec![FileDateType::Created, FileDateType::Modified],
+            ignored_paths: Vec::new(),
+            previous_period_only: false,
+        }
+    }
+}
+
+/// A validated job definition: what to move where, grouped how. Built with `MoveJobBuilder`, or converted from the
+/// `Args` of the command line.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MoveJob {
+    pub sources: Vec<PathBuf>,
+    pub destination: PathBuf,
+    pub group_by: Option<GroupBy>,
+    pub filters: JobFilters,
+    pub mode: MoveMode,
+}
+
+/// Builds a `MoveJob` without going through the command line, its definition being checked by `build()`
+#[derive(Debug, Clone, Default)]
+pub struct MoveJobBuilder {
+    sources: Vec<PathBuf>,
+    destination: Option<PathBuf>,
+    group_by: Option<GroupBy>,
+    filters: JobFilters,
+    mode: MoveMode,
+}
+
+impl MoveJobBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a source folder, can be called several times
+    pub fn source(mut self, source: impl Into<PathBuf>) -> Self {
+        self.sources.push(source.into());
+        self
+    }
+
+    pub fn destination(mut self, destination: impl Into<PathBuf>) -> Self {
+        self.destination = Some(destination.into());
+        self
+    }
+
+    pub fn group_by(mut self, group_by: GroupBy) -> Self {
+        self.group_by = Some(group_by);
+        self
+    }
+
+    pub fn older_than(mut self, cutoff: DateTime<Utc>) -> Self {
+        self.filters.older_than = Some(cutoff);
+        self
+    }
+
+    pub fn file_date_types(mut self, file_date_types: Vec<FileDateType>) -> Self {
+        self.filters.file_date_types = file_date_types;
+        self
+    }
+
+    /// Add a file or folder never moved, can be called several times
+    pub fn ignore(mut self, path: impl Into<PathBuf>) -> Self {
+        self.filters.ignored_paths.push(path.into());
+        self
+    }
+
+    pub fn previous_period_only(mut self, previous_period_only: bool) -> Self {
+        self.filters.previous_period_only = previous_period_only;
+        self
+    }
+
+    pub fn mode(mut self, mode: MoveMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Check the definition of the job. The folders themselves are checked when the job is planned.
+    pub fn build(self) -> Result<MoveJob> {
+        if self.sources.is_empty() {
+            bail!("At least one source is required");
+        }
+        let Some(destination) = self.destination else {
+            bail!("A destination is required");
+        };
+        if self.sources.contains(&destination) {
+            bail!("Destination can't be one of the sources: {}", destination.display());
+        }
+        if self.filters.file_date_types.is_empty() {
+            bail!("At least one file date type is required");
+        }
+        if self.filters.previous_period_only && self.group_by.is_none() {
+            bail!("Previous period only requires a grouping strategy");
+        }
+        Ok(MoveJob { sources: self.sources, destination, group_by: self.group_by, filters: self.filters, mode: self.mode })
+    }
+}
+
+impl MoveJob {
+    /// Set the options of the job on the `Args` of a run, leaving the others as they are
+    pub fn apply_to(&self, args: &mut Args) {
+        args.sources = self.sources.clone();
+        args.destination = self.destination.clone();
+        args.group_by = self.group_by;
+        args.older_than = self.filters.older_than;
+        args.file_date_types = self.filters.file_date_types.clone();
+        args.ignored_paths = (!self.filters.ignored_paths.is_empty()).then(|| self.filters.ignored_paths.clone());
+        args.previous_period_only = self.filters.previous_period_only;
+        args.dry_run = self.mode == MoveMode::DryRun;
+    }
+}
+
+impl From<&Args> for MoveJob {
+    fn from(args: &Args) -> Self {
+        Self {
+            sources: args.sources.clone(),
+            destination: args.destination.clone(),
+            group_by: args.group_by,
+            filters: JobFilters {
+                older_than: args.older_than,
+                file_date_types: args.file_date_types.clone(),
+                ignored_paths: args.ignored_paths.clone().unwrap_or_default(),
+                previous_period_only: args.previous_period_only,
+            },
+            mode: if args.dry_run { MoveMode::DryRun } else { MoveMode::Move },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::Cli;
+    use clap::Parser;
+
+    #[test]
+    fn test_build_checks_job_definition() {
+        let builder = MoveJobBuilder::new().source("/photos").destination("/archive");
+        assert!(builder.clone().build().is_ok());
+
+        assert!(MoveJobBuilder::new().destination("/archive").build().is_err());
+        assert!(MoveJobBuilder::new().source("/photos").build().is_err());
+        assert!(builder.clone().source("/archive").build().is_err());
+        assert!(builder.clone().file_date_types(Vec::new()).build().is_err());
+        assert!(builder.clone().previous_period_only(true).build().is_err());
+        assert!(builder.group_by(GroupBy::Month).previous_period_only(true).build().is_ok());
+    }
+
+    #[test]
+    fn test_args_convert_into_job() {
+        let command_line = ["chronomover", "-s", "/photos", "-d", "/archive", "-g", "week", "--previous-period-only", "--dry-run", "--ignored-paths", "/photos/keep"];
+        let mut args = Cli::parse_from(command_line).args.unwrap();
+
+        let job = MoveJob::from(&args);
+
+        let expected = MoveJobBuilder::new()
+            .source("/photos")
+            .destination("/archive")
+            .group_by(GroupBy::Week)
+            .previous_period_only(true)
+            .ignore("/photos/keep")
+            .mode(MoveMode::DryRun)
+            .build()
+            .unwrap();
+        assert_eq!(job, expected);
+
+        // Applying the job back leaves the other options alone
+        args.prefix_source_name = true;
+        job.apply_to(&mut args);
+        assert_eq!(MoveJob::from(&args), job);
+        assert!(args.prefix_source_name);
+    }
+}
@@ -0,0 +1,49 @@
+//! Date-grouping logic of ChronoMover: planning which files move into which period folder, and moving them. The
+//! `chronomover` binary is the command line on top of it; other programs can embed it through `MoveJobBuilder`,
+//! `Planner` and `Mover`. The `cli` feature derives the clap parsers of the argument types, and `sqlite` adds SQLite
+//! catalogs.
+
+mod api;
+pub mod audit;
+pub mod cancel;
+pub mod catalog;
+pub mod config;
+pub mod date;
+pub mod doctor;
+pub mod dupes;
+pub mod explain;
+pub mod file;
+pub mod glob;
+pub mod grouper;
+pub mod hooks;
+pub mod html_report;
+pub mod job;
+pub mod links;
+pub mod locate;
+pub mod logging;
+pub mod manifest;
+pub mod merge;
+pub mod model;
+pub mod obsidian;
+pub mod output;
+pub mod period_index;
+pub mod period_links;
+pub mod preview;
+pub mod prune;
+pub mod regroup;
+pub mod report;
+pub mod restore;
+pub mod rsync;
+pub mod scan_state;
+pub mod shorten;
+pub mod spill;
+pub mod stats;
+pub mod storage;
+pub mod verify;
+
+pub use api::{Mover, Planner};
+pub use cancel::CancellationToken;
+pub use file::{MoveReport, Plan};
+pub use grouper::Grouper;
+pub use job::{JobFilters, MoveJob, MoveJobBuilder, MoveMode};
+pub use output::{add_event_sink, EventSink, EventSinkRegistration};
@@ -0,0 +1,441 @@
+use crate::manifest::ManifestEntry;
+use crate::model::{Args, LinkFormat};
+use color_eyre::eyre::{Context, Result};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use tracing::{debug, info, warn};
+use walkdir::WalkDir;
+
+/// Where the links of a file are resolved from, and where the moved files went
+pub struct LinkContext<'a> {
+    /// Folder the relative links of the file were written from, its original folder if the file was moved
+    pub base_dir: &'a Path,
+    /// Folder the file is in now
+    pub file_dir: &'a Path,
+    /// Root of the Obsidian vault of the file, which wikilinks with a folder and links not found relative to the file
+    /// start from, `None` outside of `--obsidian`
+    pub vault_root: Option<&'a Path>,
+    /// New location of each moved file by original location, both absolute and normalized
+    pub moves: &'a HashMap<PathBuf, PathBuf>,
+}
+
+/// Where a rewritten link points from
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum LinkBase {
+    File,
+    Vault,
+}
+
+/// Rewrite the relative links to the moved files in the `--rewrite-links` files left in the source folders and, with
+/// `--rewrite-links-in-destination`, in the destination folder, where the links of the moved files themselves are
+/// rewritten from their original folder. Returns the rewritten files.
+pub fn rewrite_moved_links(args: &Args, moved: &[ManifestEntry]) -> Result<Vec<PathBuf>> {
+    let moves = get_moves(moved)?;
+    let original_dirs = moves.iter()
+        .filter_map(|(source, destination)| Some((destination.clone(), source.parent()?.to_path_buf())))
+        .collect::<HashMap<_, _>>();
+
+    let mut roots = args.sources.clone();
+    if args.rewrite_links_in_destination {
+        roots.push(args.destination.clone());
+    }
+    let mut rewritten_files = Vec::new();
+    for root in &roots {
+        // The destination is walked on its own, and so are the ignored paths inside it
+        let is_walking_destination = root == &args.destination;
+        let files = WalkDir::new(root)
+            .follow_links(args.follow_symbolic_links)
+            .into_iter()
+            .filter_entry(|entry| is_walking_destination || !args.ignored_paths.iter().flatten().any(|ignored_path| entry.path().starts_with(ignored_path)))
+            .filter_map(Result::ok)
+            .filter(|entry| entry.file_type().is_file())
+            .filter_map(|entry| Some((get_link_format(entry.path(), &args.rewrite_links)?, entry.into_path())));
+        for (format, file) in files {
+            let Ok(absolute_file) = to_absolute(&file) else {
+                continue;
+            };
+            // Safe to unwrap: walked files have a parent
+            let file_dir = absolute_file.parent().unwrap();
+            let base_dir = match original_dirs.get(&absolute_file) {
+                Some(_) if !is_walking_destination => continue,
+                Some(original_dir) => original_dir.as_path(),
+                None => file_dir,
+            };
+            let context = LinkContext { base_dir, file_dir, vault_root: None, moves: &moves };
+            match rewrite_file_links(&file, format, &context) {
+                Ok(true) => {
+                    info!(path = %file.display(), "Rewrote the links of: {}", file.display());
+                    rewritten_files.push(file);
+                }
+                Ok(false) => {}
+                Err(e) => warn!(path = %file.display(), "Failed to rewrite the links of {}: {:#}", file.display(), e),
+            }
+        }
+    }
+    Ok(rewritten_files)
+}
+
+/// New location of each file of the manifest by original location, both absolute and normalized
+pub fn get_moves(moved: &[ManifestEntry]) -> Result<HashMap<PathBuf, PathBuf>> {
+    moved.iter()
+        .map(|entry| Ok((to_absolute(&entry.source)?, to_absolute(&entry.destination)?)))
+        .collect()
+}
+
+fn get_link_format(path: &Path, formats: &[LinkFormat]) -> Option<LinkFormat> {
+    let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+    let format = match extension.as_str() {
+        "md" | "markdown" => LinkFormat::Md,
+        "html" | "htm" => LinkFormat::Html,
+        _ => return None,
+    };
+    formats.contains(&format).then_some(format)
+}
+
+/// Rewrite the links of a single file, returning whether any changed
+pub fn rewrite_file_links(file: &Path, format: LinkFormat, context: &LinkContext) -> Result<bool> {
+    let Ok(content) = fs::read_to_string(file) else {
+        debug!(path = %file.display(), "Not rewriting the links of {}: not a UTF-8 text file", file.display());
+        return Ok(false);
+    };
+    let rewritten = match format {
+        LinkFormat::Md => rewrite_markdown(&content, context),
+        LinkFormat::Html => rewrite_html(&content, context),
+    };
+    if rewritten == content {
+        return Ok(false);
+    }
+    fs::write(file, rewritten).with_context(|| format!("Failed to write file: {}", file.display()))?;
+    Ok(true)
+}
+
+/// Rewrite the markdown links (`[text](folder/Note.md)`) of a markdown file and, in a vault, its wikilinks
+/// (`[[folder/Note]]`, `![[image.png]]`), leaving fenced code blocks alone. Wikilinks made of a bare name are resolved
+/// by name in the whole vault, so they keep working and are left as is.
+pub fn rewrite_markdown(content: &str, context: &LinkContext) -> String {
+    let mut rewritten = String::with_capacity(content.len());
+    let mut fence = None;
+    for line in content.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        let marker = ["```", "~~~"].into_iter().find(|marker| trimmed.starts_with(marker));
+        match (fence, marker) {
+            (None, Some(marker)) => fence = Some(marker),
+            (Some(open), Some(marker)) if open == marker => fence = None,
+            _ => {}
+        }
+        if fence.is_some() || marker.is_some() {
+            rewritten.push_str(line);
+        } else if context.vault_root.is_some() {
+            rewritten.push_str(&rewrite_markdown_links(&rewrite_wikilinks(line, context), context));
+        } else {
+            rewritten.push_str(&rewrite_markdown_links(line, context));
+        }
+    }
+    rewritten
+}
+
+fn rewrite_wikilinks(line: &str, context: &LinkContext) -> String {
+    let mut rewritten = String::with_capacity(line.len());
+    let mut rest = line;
+    while let Some(start) = rest.find("[[") {
+        let (before, link) = rest.split_at(start + 2);
+        rewritten.push_str(before);
+        let Some(end) = link.find("]]") else {
+            rest = link;
+            break;
+        };
+        let inner = &link[..end];
+        // The target ends at the heading or the alias, whose pipe is escaped inside tables
+        let mut target = &inner[..inner.find(['#', '|']).unwrap_or(inner.len())];
+        target = target.strip_suffix('\\').unwrap_or(target);
+        match rewrite_wikilink_target(target, context) {
+            Some(new_target) => {
+                rewritten.push_str(&new_target);
+                rewritten.push_str(&inner[target.len()..]);
+            }
+            None => rewritten.push_str(inner),
+        }
+        rest = &link[end..];
+    }
+    rewritten.push_str(rest);
+    rewritten
+}
+
+fn rewrite_wikilink_target(target: &str, context: &LinkContext) -> Option<String> {
+    let base = if target.starts_with("./") || target.starts_with("../") {
+        LinkBase::File
+    } else if target.contains('/') {
+        LinkBase::Vault
+    } else {
+        return None;
+    };
+    let path = resolve_link(context, base, target)?;
+    // The `.md` extension of notes is usually left out
+    let (new_location, had_extension) = match get_new_location(context, &path) {
+        Some(new_location) => (new_location, true),
+        None => (get_new_location(context, &with_md_extension(&path))?, false),
+    };
+    let new_target = format_link_target(context, base, &new_location)?;
+    Some(if had_extension { new_target } else { new_target.strip_suffix(".md").map(str::to_string).unwrap_or(new_target) })
+}
+
+fn rewrite_markdown_links(line: &str, context: &LinkContext) -> String {
+    let mut rewritten = String::with_capacity(line.len());
+    let mut rest = line;
+    while let Some(start) = rest.find("](") {
+        let (before, link) = rest.split_at(start + 2);
+        rewritten.push_str(before);
+        // `[text](<folder/My Note.md>)` allows spaces, otherwise the target ends at a space before a title
+        let (target, is_bracketed) = match link.strip_prefix('<') {
+            Some(bracketed) => (&bracketed[..bracketed.find('>').unwrap_or(bracketed.len())], true),
+            None => (&link[..link.find([')', ' ']).unwrap_or(link.len())], false),
+        };
+        let skipped = if is_bracketed { 1 } else { 0 };
+        match rewrite_link_target(target, !is_bracketed, context) {
+            Some(new_target) => {
+                rewritten.push_str(&link[..skipped]);
+                rewritten.push_str(&new_target);
+            }
+            None => rewritten.push_str(&link[..skipped + target.len()]),
+        }
+        rest = &link[skipped + target.len()..];
+    }
+    rewritten.push_str(rest);
+    rewritten
+}
+
+/// Rewrite the `href` and `src` attributes of an HTML file
+pub fn rewrite_html(content: &str, context: &LinkContext) -> String {
+    // ASCII lowercasing keeps the byte offsets
+    let lowercase = content.to_ascii_lowercase();
+    let mut rewritten = String::with_capacity(content.len());
+    let mut offset = 0;
+    while let Some(value_start) = find_link_attribute(&lowercase, offset) {
+        let quote = char::from(content.as_bytes()[value_start]);
+        let Some(length) = content[value_start + 1..].find(quote) else {
+            break;
+        };
+        let value_end = value_start + 1 + length;
+        let target = &content[value_start + 1..value_end];
+        // Entities such as `&amp;` are rare in paths, so targets holding one are left alone
+        let new_target = (!target.contains('&')).then(|| rewrite_link_target(target, true, context)).flatten();
+        rewritten.push_str(&content[offset..value_start + 1]);
+        rewritten.push_str(new_target.as_deref().unwrap_or(target));
+        offset = value_end;
+    }
+    rewritten.push_str(&content[offset..]);
+    rewritten
+}
+
+/// Position of the opening quote of the next quoted `href=` or `src=` attribute, from `offset` on
+fn find_link_attribute(lowercase: &str, offset: usize) -> Option<usize> {
+    ["href=\"", "href='", "src=\"", "src='"].into_iter()
+        .filter_map(|attribute| {
+            let mut from = offset;
+            while let Some(position) = lowercase[from..].find(attribute).map(|position| from + position) {
+                // Not the end of another attribute name, such as `data-src=`
+                if position == 0 || lowercase.as_bytes()[position - 1].is_ascii_whitespace() {
+                    return Some(position + attribute.len() - 1);
+                }
+                from = position + 1;
+            }
+            None
+        })
+        .min()
+}
+
+/// Rewrite a relative link target of a markdown or HTML link, keeping its `?query` and `#fragment`. Escaped targets
+/// have their `%XX` escapes decoded, and spaces escaped again once rewritten.
+fn rewrite_link_target(target: &str, is_escaped: bool, context: &LinkContext) -> Option<String> {
+    if target.is_empty() || target.starts_with(['#', '/', '?']) || target.contains(':') {
+        return None;
+    }
+    let (path, suffix) = target.split_at(target.find(['#', '?']).unwrap_or(target.len()));
+    let path = if is_escaped { percent_decode(path)? } else { path.to_string() };
+
+    // Links are relative to the file, or to the vault root when Obsidian is set to use absolute paths in the vault
+    let (base, new_location) = [LinkBase::File, LinkBase::Vault].into_iter()
+        .find_map(|base| Some((base, get_new_location(context, &resolve_link(context, base, &path)?)?)))?;
+    let new_path = format_link_target(context, base, &new_location)?;
+    let new_path = if is_escaped { new_path.replace('%', "%25").replace(' ', "%20") } else { new_path };
+    Some(format!("{new_path}{suffix}"))
+}
+
+fn resolve_link(context: &LinkContext, base: LinkBase, target: &str) -> Option<PathBuf> {
+    let path = match base {
+        LinkBase::File => context.base_dir.join(target),
+        LinkBase::Vault => context.vault_root?.join(target.trim_start_matches('/')),
+    };
+    Some(normalize_path(&path))
+}
+
+/// Where the target of a link is now, if the link needs rewriting: the target moved, or the file holding the link did
+fn get_new_location(context: &LinkContext, target: &Path) -> Option<PathBuf> {
+    match context.moves.get(target) {
+        Some(destination) => Some(destination.clone()),
+        None if context.base_dir != context.file_dir && target.exists() => Some(target.to_path_buf()),
+        None => None,
+    }
+}
+
+/// Link target of a file, as a path relative to the base with `/` separators. In a vault, only the files inside it
+/// can be linked to.
+fn format_link_target(context: &LinkContext, base: LinkBase, location: &Path) -> Option<String> {
+    if let Some(vault_root) = context.vault_root {
+        location.strip_prefix(vault_root).ok()?;
+    }
+    let relative_path = match base {
+        LinkBase::File => get_relative_path(context.file_dir, location),
+        LinkBase::Vault => location.strip_prefix(context.vault_root?).ok()?.to_path_buf(),
+    };
+    let components = relative_path.components()
+        .map(|component| component.as_os_str().to_str())
+        .collect::<Option<Vec<_>>>()?;
+    Some(components.join("/"))
+}
+
+fn with_md_extension(path: &Path) -> PathBuf {
+    let mut path = path.as_os_str().to_os_string();
+    path.push(".md");
+    PathBuf::from(path)
+}
+
+/// Decode the `%XX` escapes of a link target, `None` if they don't decode to UTF-8
+fn percent_decode(value: &str) -> Option<String> {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        let escaped = (bytes[index] == b'%')
+            .then(|| value.get(index + 1..index + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                index += 3;
+            }
+            None => {
+                decoded.push(bytes[index]);
+                index += 1;
+            }
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+pub fn to_absolute(path: &Path) -> Result<PathBuf> {
+    let absolute_path = std::path::absolute(path).with_context(|| format!("Failed to resolve path: {}", path.display()))?;
+    Ok(normalize_path(&absolute_path))
+}
+
+/// Resolve the `.` and `..` components of a path without touching the filesystem, as moved files are gone
+pub fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+fn get_relative_path(from_dir: &Path, to: &Path) -> PathBuf {
+    let from_components = from_dir.components().collect::<Vec<_>>();
+    let to_components = to.components().collect::<Vec<_>>();
+    let common = from_components.iter().zip(&to_components).take_while(|(from, to)| from == to).count();
+    let mut relative_path = PathBuf::new();
+    for _ in common..from_components.len() {
+        relative_path.push("..");
+    }
+    relative_path.extend(&to_components[common..]);
+    relative_path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_test_moves() -> HashMap<PathBuf, PathBuf> {
+        HashMap::from([
+            (PathBuf::from("/vault/Projects/Old Plan.md"), PathBuf::from("/vault/Archive/2025-06/Projects/Old Plan.md")),
+            (PathBuf::from("/vault/Projects/diagram.png"), PathBuf::from("/vault/Archive/2025-06/Projects/diagram.png")),
+            (PathBuf::from("/vault/Projects/outside.md"), PathBuf::from("/archive/Projects/outside.md")),
+        ])
+    }
+
+    fn rewrite_in_vault(content: &str, file_dir: &str) -> String {
+        let moves = get_test_moves();
+        let context = LinkContext { base_dir: Path::new(file_dir), file_dir: Path::new(file_dir), vault_root: Some(Path::new("/vault")), moves: &moves };
+        rewrite_markdown(content, &context)
+    }
+
+    #[test]
+    fn test_rewrite_wikilinks() {
+        assert_eq!(rewrite_in_vault("See [[Projects/Old Plan]].", "/vault"), "See [[Archive/2025-06/Projects/Old Plan]].");
+        assert_eq!(rewrite_in_vault("[[Projects/Old Plan.md#Goals|plan]]", "/vault"), "[[Archive/2025-06/Projects/Old Plan.md#Goals|plan]]");
+        assert_eq!(rewrite_in_vault("![[../Projects/diagram.png]]", "/vault/Daily"), "![[../Archive/2025-06/Projects/diagram.png]]");
+        assert_eq!(rewrite_in_vault("| [[Projects/Old Plan\\|plan]] |", "/vault"), "| [[Archive/2025-06/Projects/Old Plan\\|plan]] |");
+        // Bare names still resolve, and files moved out of the vault can't be linked to
+        assert_eq!(rewrite_in_vault("[[Old Plan]] [[Projects/outside]]", "/vault"), "[[Old Plan]] [[Projects/outside]]");
+    }
+
+    #[test]
+    fn test_rewrite_markdown_links() {
+        assert_eq!(rewrite_in_vault("[plan](Old%20Plan.md)", "/vault/Projects"), "[plan](../Archive/2025-06/Projects/Old%20Plan.md)");
+        assert_eq!(rewrite_in_vault("[plan](<Projects/Old Plan.md#Goals> \"title\")", "/vault/Daily"), "[plan](<Archive/2025-06/Projects/Old Plan.md#Goals> \"title\")");
+        assert_eq!(rewrite_in_vault("![](diagram.png)", "/vault/Projects"), "![](../Archive/2025-06/Projects/diagram.png)");
+        assert_eq!(rewrite_in_vault("[site](https://example.com/Old%20Plan.md) [top](#Goals)", "/vault/Projects"), "[site](https://example.com/Old%20Plan.md) [top](#Goals)");
+    }
+
+    #[test]
+    fn test_rewrite_markdown_keeps_unclosed_wikilinks() {
+        assert_eq!(rewrite_in_vault("[[Projects/Old Plan", "/vault"), "[[Projects/Old Plan");
+    }
+
+    #[test]
+    fn test_rewrite_markdown_skips_code_blocks() {
+        let content = "[[Projects/Old Plan]]\n```\n[[Projects/Old Plan]]\n```\n";
+        assert_eq!(rewrite_in_vault(content, "/vault"), "[[Archive/2025-06/Projects/Old Plan]]\n```\n[[Projects/Old Plan]]\n```\n");
+    }
+
+    #[test]
+    fn test_rewrite_markdown_outside_of_vault() {
+        let moves = get_test_moves();
+        let context = LinkContext { base_dir: Path::new("/vault"), file_dir: Path::new("/vault"), vault_root: None, moves: &moves };
+
+        assert_eq!(rewrite_markdown("[[Projects/Old Plan]] [out](Projects/outside.md)", &context), "[[Projects/Old Plan]] [out](../archive/Projects/outside.md)");
+    }
+
+    #[test]
+    fn test_rewrite_html() {
+        let moves = get_test_moves();
+        let context = LinkContext { base_dir: Path::new("/vault/Projects"), file_dir: Path::new("/vault/Projects"), vault_root: None, moves: &moves };
+
+        assert_eq!(
+            rewrite_html("<a HREF=\"Old%20Plan.md?v=1#Goals\">plan</a><img data-src='x' src='diagram.png'>", &context),
+            "<a HREF=\"../Archive/2025-06/Projects/Old%20Plan.md?v=1#Goals\">plan</a><img data-src='x' src='../Archive/2025-06/Projects/diagram.png'>"
+        );
+        assert_eq!(rewrite_html("<a href=\"https://example.com/diagram.png\">", &context), "<a href=\"https://example.com/diagram.png\">");
+    }
+
+    #[test]
+    fn test_rewrite_links_of_moved_file() {
+        let moves = get_test_moves();
+        // A file moved into the archive folder links to a file left in its original folder
+        let original_dir = std::env::current_dir().unwrap();
+        let context = LinkContext { base_dir: &original_dir, file_dir: &original_dir.join("archive"), vault_root: None, moves: &moves };
+
+        assert_eq!(rewrite_markdown("[manifest](Cargo.toml) [missing](missing.md)", &context), "[manifest](../Cargo.toml) [missing](missing.md)");
+    }
+
+    #[test]
+    fn test_normalize_path() {
+        assert_eq!(normalize_path(Path::new("/vault/Daily/../Projects/./Plan.md")), PathBuf::from("/vault/Projects/Plan.md"));
+    }
+}
@@ -0,0 +1,137 @@
+use crate::catalog::load_catalog;
+use crate::glob::matches_glob;
+use crate::logging::SUMMARY_TARGET;
+use crate::manifest::{get_manifest_dir, list_run_ids, load_manifest};
+use crate::model::WhereArgs;
+use chrono::{DateTime, Local, Utc};
+use color_eyre::eyre::{bail, Result};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+/// A move recorded by a run manifest or a catalog
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordedMove {
+    pub source: PathBuf,
+    pub destination: PathBuf,
+    pub moved_at: DateTime<Utc>,
+    pub run_id: Option<String>,
+}
+
+/// Tell where the files matching a name or glob were moved to and when, from the manifests of the past runs and the
+/// catalog, most recent move last
+pub fn locate_moved_files(args: &WhereArgs) -> Result<()> {
+    let mut moves = load_manifest_moves()?;
+    if let Some(catalog) = &args.catalog {
+        add_catalog_moves(&mut moves, catalog)?;
+    }
+    if moves.is_empty() {
+        bail!("No moves recorded yet: runs record their moves in {} (and with --catalog in a catalog)", get_manifest_dir()?.display());
+    }
+
+    let found = find_moves(&moves, &args.pattern);
+    for (index, recorded) in found.iter().enumerate() {
+        let run = recorded.run_id.as_deref().map(|run_id| format!(" by run '{run_id}'")).unwrap_or_default();
+        let gone = if recorded.destination.exists() { "" } else { ", no longer there" };
+        info!(
+            path = %recorded.source.display(), destination = %recorded.destination.display(), status = "found",
+            "{}. {}\n       ↳ {} (moved {}{}{})",
+            index + 1, recorded.source.display(), recorded.destination.display(),
+            recorded.moved_at.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S"), run, gone
+        );
+    }
+    if found.is_empty() {
+        info!(target: SUMMARY_TARGET, "No moved file matches '{}' among the {} recorded move(s)", args.pattern, moves.len());
+    } else {
+        info!(target: SUMMARY_TARGET, "{} of the {} recorded move(s) match '{}'", found.len(), moves.len(), args.pattern);
+    }
+    Ok(())
+}
+
+/// Moves of every run manifest, oldest run first. A manifest that can't be read is skipped with a warning.
+fn load_manifest_moves() -> Result<Vec<RecordedMove>> {
+    let manifest_dir = get_manifest_dir()?;
+    if !manifest_dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut moves = Vec::new();
+    for run_id in list_run_ids(&manifest_dir)? {
+        match load_manifest(Some(&run_id)) {
+            Ok((_, entries)) => moves.extend(entries.into_iter().map(|entry| RecordedMove {
+                source: entry.source,
+                destination: entry.destination,
+                moved_at: entry.moved_at,
+                run_id: Some(run_id.clone()),
+            })),
+            Err(e) => warn!("Skipping the manifest of run '{}': {:#}", run_id, e),
+        }
+    }
+    Ok(moves)
+}
+
+/// Add the moves of the catalog the manifests don't already have, e.g. once old manifests were deleted
+fn add_catalog_moves(moves: &mut Vec<RecordedMove>, catalog: &Path) -> Result<()> {
+    let known = moves.iter().map(|recorded| (recorded.run_id.clone(), recorded.source.clone())).collect::<HashSet<_>>();
+    let catalog_moves = load_catalog(catalog)?
+        .into_iter()
+        .filter(|entry| entry.status == "moved")
+        .map(|entry| RecordedMove {
+            source: PathBuf::from(entry.source),
+            destination: PathBuf::from(entry.destination),
+            moved_at: entry.processed_at,
+            run_id: entry.run_id,
+        })
+        .filter(|recorded| !known.contains(&(recorded.run_id.clone(), recorded.source.clone())))
+        .collect::<Vec<_>>();
+    moves.extend(catalog_moves);
+    moves.sort_by_key(|recorded| recorded.moved_at);
+    Ok(())
+}
+
+/// Moves of the files matching the pattern. A pattern with `*` or `?` is a glob, matched against the whole source path
+/// when it has a path separator and against the file name otherwise; any other pattern matches the file names holding
+/// it. Matching ignores case, and the name may be the one before or after the move.
+pub fn find_moves<'a>(moves: &'a [RecordedMove], pattern: &str) -> Vec<&'a RecordedMove> {
+    let pattern = pattern.to_lowercase();
+    let is_glob = pattern.contains(['*', '?']);
+    let is_path_pattern = pattern.contains(['/', '\\']);
+    let matches = |path: &Path| {
+        let candidate = if is_path_pattern {
+            path.to_string_lossy().replace('\\', "/").to_lowercase()
+        } else {
+            path.file_name().map(|name| name.to_string_lossy().to_lowercase()).unwrap_or_default()
+        };
+        if is_glob {
+            matches_glob(&pattern.replace('\\', "/"), &candidate)
+        } else {
+            candidate.contains(&pattern.replace('\\', "/"))
+        }
+    };
+    moves.iter().filter(|recorded| matches(&recorded.source) || matches(&recorded.destination)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_moves() {
+        let recorded = |source: &str, destination: &str| RecordedMove {
+            source: PathBuf::from(source),
+            destination: PathBuf::from(destination),
+            moved_at: Utc::now(),
+            run_id: None,
+        };
+        let moves = [
+            recorded("/src/Invoice-June.pdf", "/archive/2025-06/Invoice-June.pdf"),
+            recorded("/src/photos/beach.jpg", "/archive/2025-07/photos/beach.jpg"),
+            recorded("/src/invoices.txt", "/archive/2025-07/invoices.txt"),
+        ];
+        let sources = |pattern| find_moves(&moves, pattern).iter().map(|recorded| recorded.source.clone()).collect::<Vec<_>>();
+
+        assert_eq!(sources("invoice"), [PathBuf::from("/src/Invoice-June.pdf"), PathBuf::from("/src/invoices.txt")]);
+        assert_eq!(sources("*.PDF"), [PathBuf::from("/src/Invoice-June.pdf")]);
+        assert_eq!(sources("*/2025-07/*"), [PathBuf::from("/src/photos/beach.jpg"), PathBuf::from("/src/invoices.txt")]);
+        assert!(sources("src").is_empty());
+    }
+}
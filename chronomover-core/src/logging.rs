@@ -0,0 +1,16 @@
+/// Target of the final summary lines, which stay visible in quiet mode
+pub const SUMMARY_TARGET: &str = "chronomover::summary";
+
+/// How much human-readable output is printed, from nothing at all up to per-file metadata debugging
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Verbosity {
+    /// No human-readable output (e.g. when a machine-readable output format is selected)
+    Silent,
+    /// Only errors and the final summary
+    Quiet,
+    Normal,
+    /// Per-file details
+    Verbose,
+    /// Per-file metadata debugging
+    Debug,
+}
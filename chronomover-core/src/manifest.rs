@@ -0,0 +1,205 @@
+use chrono::{DateTime, Utc};
+use color_eyre::eyre::{bail, Context, ContextCompat, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+const MANIFEST_EXTENSION: &str = "jsonl";
+
+/// A single file moved during a run, as recorded in the run manifest
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    pub source: PathBuf,
+    pub destination: PathBuf,
+    pub archive_root: PathBuf,
+    pub size: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hash: Option<String>,
+    pub moved_at: DateTime<Utc>,
+}
+
+/// Appends entries to the manifest of the current run as files are moved, so an aborted run still leaves a record
+pub struct ManifestWriter {
+    pub run_id: String,
+    pub path: PathBuf,
+    file: File,
+}
+
+impl ManifestWriter {
+    pub fn create(now: DateTime<Utc>) -> Result<Self> {
+        let manifest_dir = get_manifest_dir()?;
+        fs::create_dir_all(&manifest_dir)
+            .with_context(|| format!("Failed to create manifest directory: {}", manifest_dir.display()))?;
+
+        let base_run_id = generate_run_id(now);
+        let mut attempt = 1;
+        loop {
+            // Two runs may start within the same second, so suffix the id instead of overwriting the older manifest
+            let run_id = if attempt == 1 { base_run_id.clone() } else { format!("{base_run_id}-{attempt}") };
+            let path = get_manifest_path(&manifest_dir, &run_id);
+
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(file) => {
+                    return Ok(Self { run_id, path, file });
+                }
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => attempt += 1,
+                Err(e) => {
+                    return Err(e).with_context(|| format!("Failed to create run manifest: {}", path.display()));
+                }
+            }
+        }
+    }
+
+    /// Record a moved file into the archive at `archive_root`, with its size read while scanning (and optionally its
+    /// hash, read from the destination)
+    pub fn record(&mut self, source: &Path, destination: &Path, archive_root: &Path, size: u64, hash: Option<String>) -> Result<()> {
+        let entry = ManifestEntry {
+            source: source.to_path_buf(),
+            destination: destination.to_path_buf(),
+            archive_root: archive_root.to_path_buf(),
+            size,
+            hash,
+            moved_at: Utc::now(),
+        };
+        let line = serde_json::to_string(&entry).context("Failed to serialize manifest entry")?;
+        writeln!(self.file, "{line}")
+            .with_context(|| format!("Failed to write to run manifest: {}", self.path.display()))
+    }
+}
+
+/// Directory where run manifests are stored, inside the user's local data folder
+pub fn get_manifest_dir() -> Result<PathBuf> {
+    let data_dir = dirs::data_local_dir().context("Failed to find the local data directory of the current user")?;
+    Ok(data_dir.join("chronomover").join("runs"))
+}
+
+pub fn get_manifest_path(manifest_dir: &Path, run_id: &str) -> PathBuf {
+    manifest_dir.join(format!("{run_id}.{MANIFEST_EXTENSION}"))
+}
+
+/// Generate a sortable run identifier from the run start time (e.g., "20250615T083000Z")
+pub fn generate_run_id(now: DateTime<Utc>) -> String {
+    now.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/// Load every entry of a run manifest, or of the most recent run when no id is given
+pub fn load_manifest(run_id: Option<&str>) -> Result<(String, Vec<ManifestEntry>)> {
+    let manifest_dir = get_manifest_dir()?;
+    let run_id = match run_id {
+        Some(run_id) => run_id.to_string(),
+        None => find_latest_run_id(&manifest_dir)?,
+    };
+    let path = get_manifest_path(&manifest_dir, &run_id);
+    if !path.exists() {
+        bail!("No manifest found for run '{}' in {}", run_id, manifest_dir.display());
+    }
+
+    let file = File::open(&path).with_context(|| format!("Failed to open run manifest: {}", path.display()))?;
+    let entries = BufReader::new(file)
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| line.as_ref().map_or(true, |line| !line.trim().is_empty()))
+        .map(|(index, line)| {
+            let line = line.with_context(|| format!("Failed to read run manifest: {}", path.display()))?;
+            serde_json::from_str(&line)
+                .with_context(|| format!("Invalid entry at line {} of run manifest: {}", index + 1, path.display()))
+        })
+        .collect::<Result<Vec<ManifestEntry>>>()?;
+
+    Ok((run_id, entries))
+}
+
+fn find_latest_run_id(manifest_dir: &Path) -> Result<String> {
+    // Run ids start with a UTC timestamp, so the lexicographically greatest is the most recent
+    list_run_ids(manifest_dir)?.pop().with_context(|| format!("No run manifests found in {}", manifest_dir.display()))
+}
+
+/// Identifiers of the runs with a manifest, oldest first
+pub fn list_run_ids(manifest_dir: &Path) -> Result<Vec<String>> {
+    let mut run_ids = fs::read_dir(manifest_dir)
+        .with_context(|| format!("Failed to read manifest directory: {}", manifest_dir.display()))?
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == MANIFEST_EXTENSION))
+        .filter_map(|path| path.file_stem().map(|stem| stem.to_string_lossy().into_owned()))
+        .collect::<Vec<_>>();
+    run_ids.sort();
+    Ok(run_ids)
+}
+
+/// Compute the BLAKE3 hash of a file's content as a hex string. The file is memory-mapped, which keeps hashing close to
+/// the disk's sequential throughput; small files and those that can't be mapped are read instead.
+pub fn hash_file(path: &Path) -> Result<String> {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update_mmap(path).with_context(|| format!("Failed to read file for hashing: {}", path.display()))?;
+    Ok(hasher.finalize().to_hex().to_string())
+}
+
+/// Translate a recorded destination to its location under a relocated archive root
+pub fn reroot_destination(entry: &ManifestEntry, new_archive_root: &Path) -> PathBuf {
+    match entry.destination.strip_prefix(&entry.archive_root) {
+        Ok(relative_path) => new_archive_root.join(relative_path),
+        Err(_) => entry.destination.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_entry(destination: &str, archive_root: &str) -> ManifestEntry {
+        ManifestEntry {
+            source: PathBuf::from("/notes/file.md"),
+            destination: PathBuf::from(destination),
+            archive_root: PathBuf::from(archive_root),
+            size: 42,
+            hash: None,
+            moved_at: "2025-06-15T08:30:00Z".parse::<DateTime<Utc>>().unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_generate_run_id() {
+        let now = "2025-06-15T08:30:05Z".parse::<DateTime<Utc>>().unwrap();
+        assert_eq!(generate_run_id(now), "20250615T083005Z");
+    }
+
+    #[test]
+    fn test_reroot_destination() {
+        let entry = create_entry("/archive/2025-06/work/file.md", "/archive");
+        assert_eq!(reroot_destination(&entry, Path::new("/mnt/backup")), PathBuf::from("/mnt/backup/2025-06/work/file.md"));
+    }
+
+    #[test]
+    fn test_reroot_destination_outside_archive_root() {
+        let entry = create_entry("/elsewhere/file.md", "/archive");
+        assert_eq!(reroot_destination(&entry, Path::new("/mnt/backup")), PathBuf::from("/elsewhere/file.md"));
+    }
+
+    #[test]
+    fn test_hash_file_small_and_mapped_files() {
+        let dir = std::env::temp_dir().join(format!("chronomover-hash-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        // Files this small are read, the larger ones memory-mapped
+        for content in [b"notes".to_vec(), vec![7; 256 * 1024]] {
+            let path = dir.join("file.bin");
+            fs::write(&path, &content).unwrap();
+            assert_eq!(hash_file(&path).unwrap(), blake3::hash(&content).to_hex().to_string());
+        }
+        assert!(hash_file(&dir.join("missing.bin")).is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_manifest_entry_round_trip() {
+        let entry = create_entry("/archive/2025-06/file.md", "/archive");
+        let line = serde_json::to_string(&entry).unwrap();
+
+        assert!(!line.contains("hash"));
+        assert_eq!(serde_json::from_str::<ManifestEntry>(&line).unwrap(), entry);
+    }
+}
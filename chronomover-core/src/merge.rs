@@ -0,0 +1,260 @@
+use crate::logging::SUMMARY_TARGET;
+use crate::model::{MergeArgs, MergeConflictPolicy};
+use crate::restore::delete_empty_dirs;
+use crate::storage::{StorageBackend, WalkOptions};
+use color_eyre::eyre::{bail, Result};
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::io;
+use std::path::{Path, PathBuf};
+use tracing::{error, info, warn};
+
+/// What happened to the files of the merged archives
+#[derive(Debug, Default)]
+struct MergeCounts {
+    moved: usize,
+    renamed: usize,
+    overwritten: usize,
+    duplicates: usize,
+    kept: usize,
+    failed: usize,
+}
+
+/// Archives being merged, and where the files merged so far are. On dry runs, nothing moves, so a file placed at a path
+/// is still at its path in its own archive.
+struct Merge<'a> {
+    args: &'a MergeArgs,
+    storage: &'a dyn StorageBackend,
+    placed: HashMap<PathBuf, PathBuf>,
+    counts: MergeCounts,
+}
+
+/// Move the files of the archives into `--into`, at the same path. A file whose path is already taken by a file of the
+/// same content is a duplicate, and is deleted. Any other taken path is handled according to `--on-conflict`. The
+/// folders the merged archives leave empty are deleted.
+pub fn merge_archives(args: &MergeArgs, storage: &dyn StorageBackend) -> Result<()> {
+    for archive in &args.archives {
+        if !storage.is_dir(archive) {
+            bail!("Archive does not exist: {}", archive.display());
+        }
+        if archive != &args.into && (args.into.starts_with(archive) || archive.starts_with(&args.into)) {
+            bail!("Archives can't be inside one another: {} and {}", archive.display(), args.into.display());
+        }
+    }
+    info!("Merging {} archive(s) into {}", args.archives.len(), args.into.display());
+
+    let mut merge = Merge { args, storage, placed: HashMap::new(), counts: MergeCounts::default() };
+    for archive in &args.archives {
+        if archive == &args.into {
+            info!("Keeping the files of {} in place: it is the archive merged into", archive.display());
+            continue;
+        }
+        merge.merge_archive(archive);
+    }
+
+    let counts = merge.counts;
+    let verb = if args.dry_run { "would have been merged" } else { "merged" };
+    let summary = format!(
+        "{} file(s) {} into {} ({} renamed, {} overwritten), {} duplicate(s) deleted, {} kept in place",
+        counts.moved, verb, args.into.display(), counts.renamed, counts.overwritten, counts.duplicates, counts.kept
+    );
+    if counts.failed > 0 {
+        bail!("{}, {} failed", summary, counts.failed);
+    }
+    info!(target: SUMMARY_TARGET, "{}", summary);
+    Ok(())
+}
+
+impl Merge<'_> {
+    fn merge_archive(&mut self, archive: &Path) {
+        let walk = self.storage.walk(archive, WalkOptions { sorted: true, ..WalkOptions::default() }, Box::new(|_| true));
+        for entry in walk {
+            match entry {
+                Ok(entry) if entry.is_dir => {}
+                // Safe to unwrap: walked entries are inside the walked folder
+                Ok(entry) => self.merge_file(&entry.path, &self.args.into.join(entry.path.strip_prefix(archive).unwrap())),
+                Err(e) => {
+                    error!(error = %e, "Failed to walk {}: {}", archive.display(), e);
+                    self.counts.failed += 1;
+                }
+            }
+        }
+        if !self.args.dry_run {
+            delete_empty_dirs(self.storage, archive);
+        }
+    }
+
+    fn merge_file(&mut self, source: &Path, destination: &Path) {
+        if self.storage.is_dir(destination) {
+            warn!(path = %source.display(), "Keeping {} in place: {} is a folder", source.display(), destination.display());
+            self.counts.kept += 1;
+            return;
+        }
+        let Some(existing) = self.find_file(destination) else {
+            self.place(source, destination);
+            return;
+        };
+        match self.has_same_content(source, &existing) {
+            Ok(true) => self.delete_duplicate(source, &existing),
+            Ok(false) => match self.args.on_conflict {
+                MergeConflictPolicy::Rename => {
+                    let renamed = self.find_free_name(destination);
+                    info!(path = %source.display(), "Renaming {} to {}: {} has another content", source.display(), renamed.display(), destination.display());
+                    if self.place(source, &renamed) {
+                        self.counts.renamed += 1;
+                    }
+                }
+                MergeConflictPolicy::Skip => {
+                    warn!(path = %source.display(), "Keeping {} in place: {} has another content", source.display(), destination.display());
+                    self.counts.kept += 1;
+                }
+                MergeConflictPolicy::Overwrite => {
+                    info!(path = %source.display(), "Overwriting {} with {}", destination.display(), source.display());
+                    let recycled = if self.args.dry_run { Ok(()) } else { self.storage.recycle_file(destination) };
+                    match recycled {
+                        Ok(()) if self.place(source, destination) => self.counts.overwritten += 1,
+                        Ok(()) => {}
+                        Err(e) => {
+                            error!(path = %source.display(), error = %e, "Overwriting file {}: {}", destination.display(), e);
+                            self.counts.failed += 1;
+                        }
+                    }
+                }
+            },
+            Err(e) => {
+                error!(path = %source.display(), error = %e, "Comparing file {} with {}: {}", source.display(), existing.display(), e);
+                self.counts.failed += 1;
+            }
+        }
+    }
+
+    /// Where the file at a path of the merged archive currently is, if there is one
+    fn find_file(&self, path: &Path) -> Option<PathBuf> {
+        match self.placed.get(path) {
+            Some(location) => Some(location.clone()),
+            None => self.storage.stat(path).is_ok().then(|| path.to_path_buf()),
+        }
+    }
+
+    /// Sizes are compared first, so files are only hashed when they may be duplicates
+    fn has_same_content(&self, file: &Path, other_file: &Path) -> io::Result<bool> {
+        if self.storage.stat(file)?.size != self.storage.stat(other_file)?.size {
+            return Ok(false);
+        }
+        Ok(self.storage.hash_file(file)? == self.storage.hash_file(other_file)?)
+    }
+
+    /// Move a file to a free path of the merged archive, returning whether it was
+    fn place(&mut self, source: &Path, destination: &Path) -> bool {
+        if !self.args.dry_run {
+            let moved = destination.parent().map_or(Ok(()), |parent| self.storage.create_dir_all(parent))
+                .and_then(|_| self.storage.rename(source, destination));
+            if let Err(e) = moved {
+                error!(path = %source.display(), error = %e, "Moving file {}: {}", source.display(), e);
+                self.counts.failed += 1;
+                return false;
+            }
+        }
+        let location = if self.args.dry_run { source } else { destination };
+        self.placed.insert(destination.to_path_buf(), location.to_path_buf());
+        self.counts.moved += 1;
+        let status = if self.args.dry_run { "dry-run" } else { "moved" };
+        info!(
+            path = %source.display(), destination = %destination.display(), status,
+            "{}. {}\n       ↳ {}", self.counts.moved, source.display(), destination.parent().unwrap_or(destination).display()
+        );
+        true
+    }
+
+    fn delete_duplicate(&mut self, duplicate: &Path, original: &Path) {
+        if !self.args.dry_run
+            && let Err(e) = self.storage.remove_file(duplicate) {
+                error!(path = %duplicate.display(), error = %e, "Deleting duplicate {}: {}", duplicate.display(), e);
+                self.counts.failed += 1;
+                return;
+            }
+        info!(path = %duplicate.display(), "Deleted {}: duplicate of {}", duplicate.display(), original.display());
+        self.counts.duplicates += 1;
+    }
+
+    /// First path of the form `name (N).ext` that no file or folder takes, starting from 2
+    fn find_free_name(&self, path: &Path) -> PathBuf {
+        let stem = path.file_stem().unwrap_or_default();
+        (2..)
+            .map(|number| {
+                let mut name = OsString::from(stem);
+                name.push(format!(" ({number})"));
+                if let Some(extension) = path.extension() {
+                    name.push(".");
+                    name.push(extension);
+                }
+                path.with_file_name(name)
+            })
+            .find(|candidate| !self.storage.is_dir(candidate) && self.find_file(candidate).is_none())
+            // Safe to unwrap: the numbers never run out
+            .unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::MemoryStorage;
+    use chrono::{DateTime, Utc};
+
+    fn create_args(on_conflict: MergeConflictPolicy, dry_run: bool) -> MergeArgs {
+        MergeArgs { archives: vec![PathBuf::from("/laptop"), PathBuf::from("/desktop")], into: PathBuf::from("/archive"), on_conflict, dry_run }
+    }
+
+    fn create_storage() -> MemoryStorage {
+        let date = "2025-01-10T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let storage = MemoryStorage::default();
+        storage.add_file("/laptop/2025-01/a.jpg", date);
+        storage.add_file("/laptop/2025-01/report.pdf", date);
+        storage.set_size("/laptop/2025-01/report.pdf", 10);
+        storage.add_file("/desktop/2025-01/a.jpg", date);
+        storage.add_file("/desktop/2025-01/report.pdf", date);
+        storage.set_size("/desktop/2025-01/report.pdf", 20);
+        storage.add_file("/desktop/2025-02/b.jpg", date);
+        storage
+    }
+
+    #[test]
+    fn test_merge_archives_renames_conflicts() {
+        let storage = create_storage();
+
+        merge_archives(&create_args(MergeConflictPolicy::Rename, false), &storage).unwrap();
+
+        assert!(storage.is_file("/archive/2025-01/a.jpg"));
+        assert_eq!(storage.stat(Path::new("/archive/2025-01/report.pdf")).unwrap().size, 10);
+        assert_eq!(storage.stat(Path::new("/archive/2025-01/report (2).pdf")).unwrap().size, 20);
+        assert!(storage.is_file("/archive/2025-02/b.jpg"));
+        // The duplicate was deleted, and the emptied archives with it
+        assert!(!storage.is_dir(Path::new("/laptop")));
+        assert!(!storage.is_dir(Path::new("/desktop")));
+    }
+
+    #[test]
+    fn test_merge_archives_skips_or_overwrites_conflicts() {
+        let storage = create_storage();
+        merge_archives(&create_args(MergeConflictPolicy::Skip, false), &storage).unwrap();
+        assert_eq!(storage.stat(Path::new("/archive/2025-01/report.pdf")).unwrap().size, 10);
+        assert!(storage.is_file("/desktop/2025-01/report.pdf"));
+
+        let storage = create_storage();
+        merge_archives(&create_args(MergeConflictPolicy::Overwrite, false), &storage).unwrap();
+        assert_eq!(storage.stat(Path::new("/archive/2025-01/report.pdf")).unwrap().size, 20);
+        assert!(!storage.is_file("/archive/2025-01/report (2).pdf"));
+    }
+
+    #[test]
+    fn test_merge_archives_dry_run_moves_nothing() {
+        let storage = create_storage();
+
+        merge_archives(&create_args(MergeConflictPolicy::Rename, true), &storage).unwrap();
+
+        assert!(storage.is_file("/laptop/2025-01/a.jpg"));
+        assert!(storage.is_file("/desktop/2025-01/a.jpg"));
+        assert!(!storage.is_dir(Path::new("/archive")));
+    }
+}
@@ -0,0 +1,1618 @@
+use crate::cancel::CancellationToken;
+#[cfg(feature = "cli")]
+use crate::catalog::parse_catalog_path;
+use crate::date::BusinessCalendar;
+use crate::doctor::{get_device_id, get_existing_ancestor, warn_about_unreliable_access_times};
+use crate::file::get_destination_roots;
+use crate::grouper::Grouper;
+use crate::logging::Verbosity;
+use crate::obsidian::warn_about_vaults;
+use crate::report::format_size;
+use crate::shorten::DEFAULT_MAX_PATH_LENGTH;
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, Offset, Utc, Weekday};
+#[cfg(feature = "cli")]
+use clap::builder::BoolishValueParser;
+#[cfg(feature = "cli")]
+use clap::ArgAction;
+use color_eyre::eyre;
+use color_eyre::eyre::{bail, Context};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{self, IsTerminal};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tracing::{info, warn};
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "cli", derive(clap::Args))]
+pub struct StatsArgs {
+    #[cfg_attr(feature = "cli", arg(short, long = "source", id = "source", required = true, action = ArgAction::Append, value_name = "PATH", help = "Source directory to scan, can be repeated"))]
+    pub sources: Vec<PathBuf>,
+
+    #[cfg_attr(feature = "cli", arg(short, long, value_enum, default_value = "month", value_name = "STRATEGY", help = "Grouping strategy of the periods"))]
+    pub group_by: GroupBy,
+
+    #[cfg_attr(feature = "cli", arg(long, default_value = "created,modified", value_delimiter = ',', value_parser = file_date_type_parser, value_name = "TYPES", help = "Which timestamps to check (created, modified, accessed). Can use short forms (c, m, a)"))]
+    pub file_date_types: Vec<FileDateType>,
+
+    #[cfg_attr(feature = "cli", arg(short, long, env = "CHRONOMOVER_JOBS", default_value = "1", value_name = "COUNT", help = "Number of threads reading file dates"))]
+    pub jobs: usize,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "cli", derive(clap::Args))]
+pub struct DoctorArgs {
+    #[cfg_attr(feature = "cli", arg(short, long = "source", id = "source", required = true, action = ArgAction::Append, value_name = "PATH", help = "Source directory to check, can be repeated"))]
+    pub sources: Vec<PathBuf>,
+
+    #[cfg_attr(feature = "cli", arg(short, long, value_name = "PATH", help = "Destination directory, to check whether files can be renamed into it"))]
+    pub destination: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "cli", derive(clap::Args))]
+pub struct ExplainArgs {
+    #[cfg_attr(feature = "cli", arg(value_name = "FILE", help = "File to explain, inside one of the source directories"))]
+    pub file: PathBuf,
+
+    /// The options of the run to explain the file for, boxed to keep `Command` small
+    #[cfg_attr(feature = "cli", command(flatten))]
+    pub args: Box<Args>,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "cli", derive(clap::Args))]
+pub struct AuditArgs {
+    /// The options of the run to audit, boxed to keep `Command` small
+    #[cfg_attr(feature = "cli", command(flatten))]
+    pub args: Box<Args>,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "cli", derive(clap::Args))]
+pub struct VerifyArgs {
+    #[cfg_attr(feature = "cli", arg(long, value_name = "ID", help = "Identifier of the run to verify (defaults to the most recent run)"))]
+    pub run: Option<String>,
+
+    #[cfg_attr(feature = "cli", arg(short, long, value_name = "PATH", help = "Current archive location, if it was relocated since the run"))]
+    pub destination: Option<PathBuf>,
+
+    #[cfg_attr(feature = "cli", arg(short, long, env = "CHRONOMOVER_JOBS", default_value = "1", value_name = "COUNT", help = "Number of threads checking and hashing files"))]
+    pub jobs: usize,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "cli", derive(clap::Args))]
+pub struct RestoreArgs {
+    #[cfg_attr(feature = "cli", arg(long, value_name = "PATH", help = "Archive holding the period folders (e.g., 2025-06, 2025-Q2) to restore"))]
+    pub from: PathBuf,
+
+    #[cfg_attr(feature = "cli", arg(long, value_name = "PATH", help = "Folder to move the files back into, at their path below their period folder"))]
+    pub to: PathBuf,
+
+    #[cfg_attr(feature = "cli", arg(long, default_value = "false", help = "Preview what would be restored without moving files"))]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "cli", derive(clap::Args))]
+pub struct RegroupArgs {
+    #[cfg_attr(feature = "cli", arg(long, value_name = "PATH", help = "Archive holding the period folders to regroup"))]
+    pub dir: PathBuf,
+
+    #[cfg_attr(feature = "cli", arg(long, value_enum, value_name = "STRATEGY", help = "Grouping strategy the period folders were created with"))]
+    pub from: GroupBy,
+
+    #[cfg_attr(feature = "cli", arg(long, value_enum, value_name = "STRATEGY", help = "Grouping strategy to regroup the period folders into"))]
+    pub to: GroupBy,
+
+    #[cfg_attr(feature = "cli", arg(long, default_value = "false", help = "Preview what would be regrouped without moving files"))]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "cli", derive(clap::Args))]
+pub struct MergeArgs {
+    #[cfg_attr(feature = "cli", arg(required = true, num_args = 2.., value_name = "ARCHIVE", help = "Archives to merge, whose files are moved at the same path into --into"))]
+    pub archives: Vec<PathBuf>,
+
+    #[cfg_attr(feature = "cli", arg(long, value_name = "PATH", help = "Archive to merge into, which may be one of the merged archives"))]
+    pub into: PathBuf,
+
+    #[cfg_attr(feature = "cli", arg(long, value_enum, default_value = "rename", value_name = "POLICY", help = "What to do with a file whose path is taken by a file with another content"))]
+    pub on_conflict: MergeConflictPolicy,
+
+    #[cfg_attr(feature = "cli", arg(long, default_value = "false", help = "Preview what would be merged without moving or deleting files"))]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "cli", derive(clap::Args))]
+pub struct WhereArgs {
+    #[cfg_attr(feature = "cli", arg(value_name = "NAME_OR_GLOB", help = "Part of the file name to look for, or a glob like \"*.pdf\" (matched against the whole path when it has a path separator, e.g. \"*/2025-06/*\")"))]
+    pub pattern: String,
+
+    #[cfg_attr(feature = "cli", arg(long, env = "CHRONOMOVER_CATALOG", value_name = "PATH", value_parser = parse_catalog_path, help = "Also search this catalog written by --catalog, which keeps the moves of runs whose manifest was deleted"))]
+    pub catalog: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "cli", derive(clap::Args))]
+pub struct DupesArgs {
+    #[cfg_attr(feature = "cli", arg(short, long = "source", id = "source", required = true, action = ArgAction::Append, value_name = "PATH", help = "Source directory whose files are checked, can be repeated"))]
+    pub sources: Vec<PathBuf>,
+
+    #[cfg_attr(feature = "cli", arg(short, long, value_name = "PATH", help = "Archive to look for the same content in"))]
+    pub destination: PathBuf,
+
+    #[cfg_attr(feature = "cli", arg(short, long, env = "CHRONOMOVER_JOBS", default_value = "1", value_name = "COUNT", help = "Number of threads hashing files"))]
+    pub jobs: usize,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "cli", derive(clap::Args))]
+pub struct PruneArgs {
+    #[cfg_attr(feature = "cli", arg(short, long, value_name = "PATH", help = "Archive holding the period folders to prune"))]
+    pub destination: PathBuf,
+
+    #[cfg_attr(feature = "cli", arg(short, long, value_enum, default_value = "month", value_name = "STRATEGY", help = "Grouping strategy the period folders were created with, other folders are left alone"))]
+    pub group_by: GroupBy,
+
+    #[cfg_attr(feature = "cli", arg(long, required_unless_present = "older_than", value_name = "COUNT", help = "Keep this many of the most recent period folders"))]
+    pub keep: Option<usize>,
+
+    #[cfg_attr(feature = "cli", arg(long, value_name = "DURATION_OR_DATE", value_parser = parse_older_than, help = "Prune the periods that ended before this duration ago or date (e.g., \"2y\", \"2023-01-01\"); with --keep, a folder either one keeps is kept"))]
+    pub older_than: Option<DateTime<Utc>>,
+
+    #[cfg_attr(feature = "cli", arg(long, default_value = "false", help = "Delete the pruned folders for good instead of sending them to the Recycle Bin, required outside Windows"))]
+    pub permanent: bool,
+
+    #[cfg_attr(feature = "cli", arg(long, default_value = "false", help = "Preview which period folders would be pruned without deleting them"))]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[cfg_attr(feature = "cli", derive(clap::Args))]
+pub struct Args {
+    #[cfg_attr(feature = "cli", arg(short, long = "source", id = "source", env = "CHRONOMOVER_SOURCE", required = true, action = ArgAction::Append, value_name = "PATH", help = "Source directory containing files to organize, can be repeated to archive several directories into the same destination"))]
+    pub sources: Vec<PathBuf>,
+
+    #[cfg_attr(feature = "cli", arg(short, long, env = "CHRONOMOVER_DESTINATION", required = true, value_name = "PATH", help = "Destination directory where files will be moved"))]
+    pub destination: PathBuf,
+
+    #[cfg_attr(feature = "cli", arg(long = "route", env = "CHRONOMOVER_ROUTE", action = ArgAction::Append, value_delimiter = ';', value_parser = parse_route, value_name = "EXTENSIONS=>PATH", help = "Move the files with these extensions into another destination, still grouped by period (e.g., \"jpg,png=>/archive/pictures\"), can be repeated; other files go to --destination"))]
+    pub routes: Vec<Route>,
+
+    #[cfg_attr(feature = "cli", arg(long = "route-path", env = "CHRONOMOVER_ROUTE_PATH", action = ArgAction::Append, value_delimiter = ';', value_parser = parse_path_route, value_name = "GLOB=>PATH", help = "Move the files under the source subpaths matching this glob into another destination, still grouped by period (e.g., \"Camera Uploads/**=>/archive/pictures\"), can be repeated; checked before --route"))]
+    pub path_routes: Vec<PathRoute>,
+
+    #[cfg_attr(feature = "cli", arg(long = "mirror", env = "CHRONOMOVER_MIRROR", action = ArgAction::Append, value_delimiter = ';', value_name = "PATH", help = "Also copy each file into this archive before moving it, at the same path below it as below its destination (e.g., an external drive), can be repeated; a file whose copy fails is left in place"))]
+    pub mirrors: Vec<PathBuf>,
+
+    #[cfg_attr(feature = "cli", arg(long, env = "CHRONOMOVER_PREFIX_SOURCE_NAME", default_value = "false", value_parser = BoolishValueParser::new(), help = "Move the files of each source directory into a folder named after it (e.g., \"Archive/2025-06/Downloads\"), to tell apart the files of several sources"))]
+    pub prefix_source_name: bool,
+
+    #[cfg_attr(feature = "cli", arg(long, env = "CHRONOMOVER_NORMALIZE_UNICODE", value_enum, default_value = "none", value_name = "FORM", help = "Unicode normalization form of the folder and file names created at the destination, so names from macOS (NFD) don't end up next to visually identical NFC names on Linux and Windows"))]
+    pub normalize_unicode: UnicodeNormalization,
+
+    #[cfg_attr(feature = "cli", arg(long, env = "CHRONOMOVER_SANITIZE_NAMES", default_value = "false", value_parser = BoolishValueParser::new(), help = "Replace the characters Windows, NTFS and exFAT don't allow in names (< > : \" \\ | ? * and control characters) with _ in the folder and file names created at the destination, drop their trailing dots and spaces, and suffix reserved names like CON or NUL"))]
+    pub sanitize_names: bool,
+
+    #[cfg_attr(feature = "cli", arg(long, env = "CHRONOMOVER_SHORTEN_PATHS", value_enum, default_value = "off", value_name = "STRATEGY", help = "Shorten the destinations longer than the platform allows instead of failing to move them: truncate the folders in the middle of the path first, or the longest names first, ending shortened names with a hash of the original"))]
+    pub shorten_paths: ShortenPaths,
+
+    #[cfg_attr(feature = "cli", arg(long, env = "CHRONOMOVER_CASE_COLLISIONS", value_enum, default_value = "replace", value_name = "POLICY", help = "What to do with planned files whose destinations only differ in case (A.txt, a.txt) when the destination filesystem ignores case: let the later one replace the earlier one with a warning, rename the later one with a numeric suffix, or fail before moving anything"))]
+    pub case_collisions: CaseCollisionPolicy,
+
+    #[cfg_attr(feature = "cli", arg(long, env = "CHRONOMOVER_MAX_PATH_LENGTH", value_name = "LENGTH", help = "Longest destination path --shorten-paths allows [default: 259 on Windows, 4095 elsewhere]"))]
+    pub max_path_length: Option<usize>,
+
+    #[cfg_attr(feature = "cli", arg(long, env = "CHRONOMOVER_FILES_FROM", value_name = "PATH", conflicts_with = "incremental", help = "Only consider the files listed in this file ('-' for stdin), one per line or NUL-separated (e.g., from `find -print0`), instead of walking the source directories"))]
+    pub files_from: Option<PathBuf>,
+
+    #[cfg_attr(feature = "cli", arg(short, long, env = "CHRONOMOVER_GROUP_BY", value_enum, value_name = "STRATEGY", help = "Optional grouping strategy"))]
+    pub group_by: Option<GroupBy>,
+
+    /// Period scheme of a library run, replacing the grouping strategy of every folder
+    #[cfg_attr(feature = "cli", arg(skip))]
+    #[serde(skip)]
+    pub grouper: Option<Arc<dyn Grouper>>,
+
+    #[cfg_attr(feature = "cli", arg(long, env = "CHRONOMOVER_PREVIOUS_PERIOD_ONLY", default_value = "false", value_parser = BoolishValueParser::new(), help = "Only move files from previous periods (not current period). Only valid with --group-by"))]
+    pub previous_period_only: bool,
+
+    #[cfg_attr(feature = "cli", arg(long, env = "CHRONOMOVER_PERIOD_LINKS", default_value = "false", value_parser = BoolishValueParser::new(), help = "After each run, point the `latest-<group-by>` and `previous-<group-by>` symlinks of the destination at the newest period folder and the one before the current period (e.g., latest-month -> 2025-06). Only valid with --group-by"))]
+    pub period_links: bool,
+
+    #[cfg_attr(feature = "cli", arg(long, env = "CHRONOMOVER_PERIOD_INDEX", value_enum, value_delimiter = ',', value_name = "FORMATS", help = "Append the moved files, their original location and date to an index in each destination period folder, in these formats (csv for manifest.csv, md for index.md)"))]
+    pub period_index: Vec<PeriodIndexFormat>,
+
+    #[cfg_attr(feature = "cli", arg(long, env = "CHRONOMOVER_CATALOG", value_name = "PATH", value_parser = parse_catalog_path, help = "Append the metadata of every file the run processes (timestamps, size, hash, date source, period) to this catalog, a .json or .sqlite file kept across runs for later search and audits"))]
+    pub catalog: Option<PathBuf>,
+
+    #[cfg_attr(feature = "cli", arg(long, env = "CHRONOMOVER_OLDER_THAN", value_name = "DURATION_OR_DATE", value_parser = parse_older_than, help = "Only move files older than specified duration or date (e.g., \"30d\", \"1y6M\", \"10bd\" for 10 business days, \"2025-01-15\", \"2025-01-15T06:30:53\")"))]
+    pub older_than: Option<DateTime<Utc>>,
+
+    #[cfg_attr(feature = "cli", arg(long, env = "CHRONOMOVER_WEEKEND_DAYS", default_value = "sat,sun", value_delimiter = ',', value_parser = parse_weekday, value_name = "DAYS", help = "Days of the week that business-day durations (e.g., --older-than 10bd) don't count"))]
+    pub weekend_days: Vec<Weekday>,
+
+    #[cfg_attr(feature = "cli", arg(long, env = "CHRONOMOVER_HOLIDAYS", value_name = "PATH", help = "File of the holidays that business-day durations don't count, one YYYY-MM-DD date per line"))]
+    pub holidays: Option<PathBuf>,
+
+    #[cfg_attr(feature = "cli", arg(long, env = "CHRONOMOVER_TIMESTAMP_TOLERANCE", value_name = "DURATION", value_parser = humantime::parse_duration, help = "Also count files dated up to this much after the --older-than cutoff as older, for filesystems storing coarse or local timestamps (e.g., \"2s\" for FAT32 and exFAT, \"1h\" across daylight saving time changes)"))]
+    pub timestamp_tolerance: Option<Duration>,
+
+    #[cfg_attr(feature = "cli", arg(long, env = "CHRONOMOVER_NOW", value_name = "DATE_OR_DATETIME", value_parser = parse_reference_time, help = "Run as if it were this date instead of now, for the periods and the --older-than durations (e.g., \"2025-07-01\", \"2025-07-01T09:00:00\")"))]
+    pub now: Option<DateTime<Utc>>,
+
+    #[cfg_attr(feature = "cli", arg(
+        long, env = "CHRONOMOVER_FILE_DATE_TYPES",
+        default_value = "created,modified",
+        value_delimiter = ',',
+        value_parser = file_date_type_parser,
+        value_name = "TYPES",
+        help = "Which timestamps to check (created, modified, accessed). Can use short forms (c, m, a)"
+    ))]
+    pub file_date_types: Vec<FileDateType>,
+
+    #[cfg_attr(feature = "cli", arg(long, env = "CHRONOMOVER_IGNORED_PATHS", value_name = "PATHS", value_delimiter = ',', help = "Comma-separated list of files/folders to ignore (absolute paths)"))]
+    pub ignored_paths: Option<Vec<PathBuf>>,
+
+    #[cfg_attr(feature = "cli", arg(long, env = "CHRONOMOVER_FILTER_CMD", value_name = "COMMAND", help = "Shell command deciding whether each file passing the other filters is moved, given its path as last argument: exit code 0 moves it, 1 keeps it in place"))]
+    pub filter_cmd: Option<String>,
+
+    #[cfg_attr(feature = "cli", arg(long, env = "CHRONOMOVER_MIN_DEPTH", value_name = "DEPTH", help = "Minimum directory depth to search"))]
+    pub min_depth: Option<usize>,
+
+    #[cfg_attr(feature = "cli", arg(long, env = "CHRONOMOVER_MAX_DEPTH", value_name = "DEPTH", help = "Maximum directory depth to search"))]
+    pub max_depth: Option<usize>,
+
+    #[cfg_attr(feature = "cli", arg(long, env = "CHRONOMOVER_KEEP_MIN_PER_DIR", value_name = "COUNT", help = "Never leave fewer than this many files in a source folder (the most recent files stay)"))]
+    pub keep_min_per_dir: Option<usize>,
+
+    #[cfg_attr(feature = "cli", arg(long, env = "CHRONOMOVER_KEEP_NEWEST_PER_DIR", default_value = "false", value_parser = BoolishValueParser::new(), help = "Leave the newest file of every source folder in place as its current version, archiving only the older ones"))]
+    pub keep_newest_per_dir: bool,
+
+    #[cfg_attr(feature = "cli", arg(long, env = "CHRONOMOVER_KEEP_EMPTY_FOLDERS", default_value = "false", value_parser = BoolishValueParser::new(), help = "Keep empty folders after moving files"))]
+    pub keep_empty_folders: bool,
+
+    #[cfg_attr(feature = "cli", arg(long, env = "CHRONOMOVER_CLEAN_DESTINATION_EMPTY_DIRS", default_value = "false", value_parser = BoolishValueParser::new(), help = "Also delete the empty folders of the destination after moving, like period folders left empty by skipped conflicts or restores"))]
+    pub clean_destination_empty_dirs: bool,
+
+    #[cfg_attr(feature = "cli", arg(long, env = "CHRONOMOVER_FOLLOW_SYMBOLIC_LINKS", default_value = "false", value_parser = BoolishValueParser::new(), help = "Follow symbolic links while traversing"))]
+    pub follow_symbolic_links: bool,
+
+    #[cfg_attr(feature = "cli", arg(long, env = "CHRONOMOVER_JUNCTIONS", value_enum, default_value = "skip", value_name = "POLICY", help = "Whether to walk into Windows junctions (e.g., OneDrive or Dropbox folders), independently of --follow-symbolic-links"))]
+    pub junctions: JunctionPolicy,
+
+    #[cfg_attr(feature = "cli", arg(long, env = "CHRONOMOVER_PERMANENT", default_value = "false", value_parser = BoolishValueParser::new(), help = "Overwrite the files already at a destination for good, instead of sending them to the Recycle Bin first (Windows only)"))]
+    pub permanent: bool,
+
+    #[cfg_attr(feature = "cli", arg(long, env = "CHRONOMOVER_INCLUDE_HIDDEN", default_value = "false", value_parser = BoolishValueParser::new(), help = "Also move the files and walk the folders Windows marks as hidden or system (e.g., desktop.ini, Thumbs.db)"))]
+    pub include_hidden: bool,
+
+    #[cfg_attr(feature = "cli", arg(long, env = "CHRONOMOVER_INCLUDE_MACOS_METADATA", default_value = "false", value_parser = BoolishValueParser::new(), help = "Also move the metadata macOS leaves behind (.DS_Store, ._* files, .Spotlight-V100 and .Trashes folders), which otherwise never keep a folder from being deleted as empty"))]
+    pub include_macos_metadata: bool,
+
+    #[cfg_attr(feature = "cli", arg(long, env = "CHRONOMOVER_SYNC_CONFLICTS", value_enum, default_value = "skip", value_name = "POLICY", help = "Whether to move the conflicted copies cloud-sync clients create (e.g., report (conflicted copy 2025-06-01).docx from Dropbox, report.sync-conflict-20250601-120000-ABCDEFG.docx from Syncthing), which are best resolved where they are"))]
+    pub sync_conflicts: SyncConflictPolicy,
+
+    #[cfg_attr(feature = "cli", arg(long, env = "CHRONOMOVER_DUPLICATES", value_enum, default_value = "move", value_name = "POLICY", conflicts_with_all = ["batch_size", "max_memory"], help = "What to do with the files whose content is already in the destination, under any name (compared by size, then by hash)"))]
+    pub duplicates: DuplicatePolicy,
+
+    #[cfg_attr(feature = "cli", arg(long, env = "CHRONOMOVER_CLOUD_PLACEHOLDERS", value_enum, default_value = "skip", value_name = "POLICY", help = "What to do with the online-only placeholders of cloud-sync clients (e.g., OneDrive Files On-Demand, iCloud Drive), whose content isn't stored locally"))]
+    pub cloud_placeholders: PlaceholderPolicy,
+
+    #[cfg_attr(feature = "cli", arg(long, env = "CHRONOMOVER_TOUCH_DESTINATION", value_enum, default_value = "preserve", value_name = "MODE", help = "Modification time of the moved files: left as is, set to the end of their period (so incremental backups see stable values) or set to the time of the move"))]
+    pub touch_destination: TouchDestination,
+
+    #[cfg_attr(feature = "cli", arg(long, env = "CHRONOMOVER_OBSIDIAN", default_value = "false", value_parser = BoolishValueParser::new(), help = "Treat the sources as Obsidian vaults (or folders of one): leave the .obsidian and .trash folders alone and warn when moved notes leave the vault"))]
+    pub obsidian: bool,
+
+    #[cfg_attr(feature = "cli", arg(long, env = "CHRONOMOVER_OBSIDIAN_REWRITE_LINKS", default_value = "false", value_parser = BoolishValueParser::new(), requires = "obsidian", help = "After moving, rewrite the wikilinks and markdown links of the notes left in the vault that point to the moved files"))]
+    pub obsidian_rewrite_links: bool,
+
+    #[cfg_attr(feature = "cli", arg(long, env = "CHRONOMOVER_REWRITE_LINKS", value_enum, value_delimiter = ',', value_name = "FORMATS", help = "After moving, rewrite the relative links to the moved files in the files of these formats left in the source directories (md, html)"))]
+    pub rewrite_links: Vec<LinkFormat>,
+
+    #[cfg_attr(feature = "cli", arg(long, env = "CHRONOMOVER_REWRITE_LINKS_IN_DESTINATION", default_value = "false", value_parser = BoolishValueParser::new(), requires = "rewrite_links", help = "Also rewrite the links of the files in the destination directory, including the relative links of the moved files themselves"))]
+    pub rewrite_links_in_destination: bool,
+
+    #[cfg_attr(feature = "cli", arg(long, env = "CHRONOMOVER_GIT_MV", default_value = "false", value_parser = BoolishValueParser::new(), help = "Stage the moves of the files tracked by the git repository of a source directory, like `git mv`, so the repository status stays clean"))]
+    pub git_mv: bool,
+
+    #[cfg_attr(feature = "cli", arg(long, env = "CHRONOMOVER_RECORD_HASHES", default_value = "false", value_parser = BoolishValueParser::new(), help = "Record a hash of every moved file in the run manifest, so `verify` can detect content changes"))]
+    pub record_hashes: bool,
+
+    #[cfg_attr(feature = "cli", arg(long, env = "CHRONOMOVER_MAX_ERRORS", value_name = "COUNT", help = "Abort the run after this many files failed to move"))]
+    pub max_errors: Option<usize>,
+
+    #[cfg_attr(feature = "cli", arg(long, env = "CHRONOMOVER_RETRIES", default_value = "3", value_name = "COUNT", help = "Retry a move failing with one of the --retry-errors this many times, waiting 1s, 2s, 4s... in between"))]
+    pub retries: u32,
+
+    #[cfg_attr(feature = "cli", arg(
+        long, env = "CHRONOMOVER_RETRY_ERRORS",
+        default_value = DEFAULT_RETRY_ERRORS,
+        value_delimiter = ',',
+        value_parser = parse_retryable_error,
+        value_name = "ERRORS",
+        help = "Transient errors of network shares worth retrying: stale (file handle), timed-out, network (lost connection), or raw OS error codes (e.g., 64 for ERROR_NETNAME_DELETED on Windows)"
+    ))]
+    pub retry_errors: Vec<RetryableError>,
+
+    #[cfg_attr(feature = "cli", arg(long, env = "CHRONOMOVER_DESTINATION_WAIT", default_value = "10m", value_parser = humantime::parse_duration, value_name = "DURATION", help = "Pause the run for up to this long when the destination folder, or a --route or --route-path one, disappears (e.g., a disconnected network share), resuming once it's back"))]
+    pub destination_wait: Duration,
+
+    #[cfg_attr(feature = "cli", arg(long, env = "CHRONOMOVER_INCREMENTAL", default_value = "false", value_parser = BoolishValueParser::new(), help = "Skip the files of folders unchanged since the last incremental run, until one of their files is old enough to move"))]
+    pub incremental: bool,
+
+    #[cfg_attr(feature = "cli", arg(long, env = "CHRONOMOVER_BATCH_SIZE", value_name = "COUNT", conflicts_with_all = ["keep_min_per_dir", "keep_newest_per_dir", "print0", "report_html"], help = "Plan and move files in batches of this many files instead of scanning the whole source first, to bound memory use on huge trees"))]
+    pub batch_size: Option<usize>,
+
+    #[cfg_attr(feature = "cli", arg(long, env = "CHRONOMOVER_MAX_MEMORY", value_name = "SIZE", value_parser = parse_size, conflicts_with_all = ["batch_size", "keep_min_per_dir", "keep_newest_per_dir", "print0", "report_html"], help = "Spill the planned files to a temporary file once they would take more memory than this (e.g., \"256MB\"), for low-RAM machines"))]
+    pub max_memory: Option<u64>,
+
+    #[cfg_attr(feature = "cli", arg(long, env = "CHRONOMOVER_ONLY_IF_COUNT_OVER", value_name = "COUNT", conflicts_with_all = ["batch_size", "max_memory"], help = "Leave the files in place until more than this many of them are to be moved, so scheduled runs don't create near-empty period folders"))]
+    pub only_if_count_over: Option<usize>,
+
+    #[cfg_attr(feature = "cli", arg(long, env = "CHRONOMOVER_ONLY_IF_SIZE_OVER", value_name = "SIZE", value_parser = parse_size, conflicts_with_all = ["batch_size", "max_memory"], help = "Leave the files in place until the files to move add up to more than this size (e.g., \"1G\")"))]
+    pub only_if_size_over: Option<u64>,
+
+    #[cfg_attr(feature = "cli", arg(long, env = "CHRONOMOVER_FREE_AT_LEAST", value_name = "SIZE", value_parser = parse_size, conflicts_with_all = ["batch_size", "max_memory", "sort"], help = "Only move the oldest files, until their sizes add up to this much space freed on the source volume (e.g., \"50G\")"))]
+    pub free_at_least: Option<u64>,
+
+    #[cfg_attr(feature = "cli", arg(long, env = "CHRONOMOVER_SORT", value_enum, value_name = "ORDER", conflicts_with_all = ["batch_size", "max_memory"], help = "Move the files in this order instead of the filesystem order, so runs and dry runs are reproducible"))]
+    pub sort: Option<SortOrder>,
+
+    #[cfg_attr(feature = "cli", arg(long, env = "CHRONOMOVER_ORDER", value_enum, value_name = "ORDER", help = "Start moving the smallest or the largest files first, after --sort: many small files clear out quickly, or large transfers start early and overlap with the small ones on the other --jobs"))]
+    pub order: Option<MoveOrder>,
+
+    #[cfg_attr(feature = "cli", arg(short, long, env = "CHRONOMOVER_JOBS", default_value = "1", value_name = "COUNT", help = "Number of threads walking the source folders, reading file dates and moving files, raise it for slow (e.g., network) filesystems"))]
+    pub jobs: usize,
+
+    #[cfg_attr(feature = "cli", arg(long, env = "CHRONOMOVER_PROTECTED_PATHS", value_name = "PATHS", value_delimiter = ',', help = "Comma-separated list of extra protected paths that can never be used as source"))]
+    pub protected_paths: Option<Vec<PathBuf>>,
+
+    #[cfg_attr(feature = "cli", arg(long, env = "CHRONOMOVER_I_KNOW_WHAT_IM_DOING", default_value = "false", value_parser = BoolishValueParser::new(), help = "Allow a protected path (filesystem root, home folder, system folders) to be used as source"))]
+    pub i_know_what_im_doing: bool,
+
+    #[cfg_attr(feature = "cli", arg(long, env = "CHRONOMOVER_DRY_RUN", default_value = "false", value_parser = BoolishValueParser::new(), help = "Preview what would be moved without actually moving files"))]
+    pub dry_run: bool,
+
+    #[cfg_attr(feature = "cli", arg(long, env = "CHRONOMOVER_PREVIEW", value_enum, default_value = "list", value_name = "STYLE", help = "How a dry run shows the files it would move"))]
+    pub preview: PreviewStyle,
+
+    #[cfg_attr(feature = "cli", arg(long, env = "CHRONOMOVER_PRINT0", default_value = "false", value_parser = BoolishValueParser::new(), requires = "dry_run", conflicts_with = "output", help = "Print the planned moves as NUL-delimited source/destination pairs, for piping into `xargs -0`"))]
+    pub print0: bool,
+
+    #[cfg_attr(feature = "cli", arg(long, env = "CHRONOMOVER_PLAN_FORMAT", value_enum, value_name = "FORMAT", requires_all = ["dry_run", "plan_output"], conflicts_with_all = ["batch_size", "max_memory"], help = "Also write the planned moves in this format to the --plan-output folder, so another tool can carry them out"))]
+    pub plan_format: Option<PlanFormat>,
+
+    #[cfg_attr(feature = "cli", arg(long, env = "CHRONOMOVER_PLAN_OUTPUT", value_name = "DIR", requires = "plan_format", help = "Folder the --plan-format files are written to, created if missing"))]
+    pub plan_output: Option<PathBuf>,
+
+    #[cfg_attr(feature = "cli", arg(long, env = "CHRONOMOVER_TUI", default_value = "false", value_parser = BoolishValueParser::new(), conflicts_with_all = ["batch_size", "max_memory", "print0"], help = "Review the planned files in an interactive terminal interface, excluding files or whole period folders, before moving them"))]
+    pub tui: bool,
+
+    #[cfg_attr(feature = "cli", arg(long, env = "CHRONOMOVER_REPORT_HTML", value_name = "PATH", help = "Write a self-contained HTML report of the run to this file"))]
+    pub report_html: Option<PathBuf>,
+
+    #[cfg_attr(feature = "cli", arg(long, env = "CHRONOMOVER_PRE_RUN", value_name = "COMMAND", help = "Shell command to run before scanning, aborting the run if it fails"))]
+    pub pre_run: Option<String>,
+
+    #[cfg_attr(feature = "cli", arg(long, env = "CHRONOMOVER_POST_RUN", value_name = "COMMAND", help = "Shell command to run once the run finishes, given its outcome and counts in CHRONOMOVER_HOOK_* environment variables"))]
+    pub post_run: Option<String>,
+
+    #[cfg_attr(feature = "cli", arg(long, env = "CHRONOMOVER_POST_FILE", value_name = "COMMAND", help = "Shell command to run after each file is moved or fails to, given its source, destination, period and status in CHRONOMOVER_HOOK_* environment variables"))]
+    pub post_file: Option<String>,
+
+    #[cfg_attr(feature = "cli", arg(long, env = "CHRONOMOVER_TIMINGS", default_value = "false", value_parser = BoolishValueParser::new(), help = "Print how long each phase of the run took"))]
+    pub timings: bool,
+
+    #[cfg_attr(feature = "cli", arg(long, env = "CHRONOMOVER_OUTPUT", value_enum, default_value = "text", value_name = "FORMAT", help = "Output format"))]
+    pub output: OutputFormat,
+
+    #[cfg_attr(feature = "cli", arg(short, long, env = "CHRONOMOVER_QUIET", default_value = "false", value_parser = BoolishValueParser::new(), conflicts_with = "verbose", help = "Only print errors and the final summary"))]
+    pub quiet: bool,
+
+    #[cfg_attr(feature = "cli", arg(short, long, env = "CHRONOMOVER_VERBOSE", action = ArgAction::Count, help = "Print per-file details (-v) and file metadata (-vv)"))]
+    pub verbose: u8,
+
+    /// Stops the run cleanly between files once cancelled, by Ctrl-C or the embedding program
+    #[cfg_attr(feature = "cli", arg(skip))]
+    #[serde(skip)]
+    pub cancellation: CancellationToken,
+}
+
+/// The defaults of the command line, with no sources nor destination, for the programs building a run themselves
+impl Default for Args {
+    fn default() -> Self {
+        Self {
+            sources: Vec::new(),
+            destination: PathBuf::new(),
+            routes: Vec::new(),
+            path_routes: Vec::new(),
+            mirrors: Vec::new(),
+            prefix_source_name: false,
+            normalize_unicode: UnicodeNormalization::None,
+            sanitize_names: false,
+            shorten_paths: ShortenPaths::Off,
+            case_collisions: CaseCollisionPolicy::Replace,
+            max_path_length: None,
+            files_from: None,
+            group_by: None,
+            grouper: None,
+            previous_period_only: false,
+            period_links: false,
+            period_index: Vec::new(),
+            catalog: None,
+            older_than: None,
+            weekend_days: vec![Weekday::Sat, Weekday::Sun],
+            holidays: None,
+            timestamp_tolerance: None,
+            now: None,
+            file_date_types: vec![FileDateType::Created, FileDateType::Modified],
+            ignored_paths: None,
+            filter_cmd: None,
+            min_depth: None,
+            max_depth: None,
+            keep_min_per_dir: None,
+            keep_newest_per_dir: false,
+            keep_empty_folders: false,
+            clean_destination_empty_dirs: false,
+            follow_symbolic_links: false,
+            junctions: JunctionPolicy::Skip,
+            permanent: false,
+            include_hidden: false,
+            include_macos_metadata: false,
+            sync_conflicts: SyncConflictPolicy::Skip,
+            duplicates: DuplicatePolicy::Move,
+            cloud_placeholders: PlaceholderPolicy::Skip,
+            touch_destination: TouchDestination::Preserve,
+            obsidian: false,
+            obsidian_rewrite_links: false,
+            rewrite_links: Vec::new(),
+            rewrite_links_in_destination: false,
+            git_mv: false,
+            record_hashes: false,
+            max_errors: None,
+            retries: 3,
+            retry_errors: DEFAULT_RETRY_ERRORS.split(',').map(|value| parse_retryable_error(value).expect("valid default retry errors")).collect(),
+            destination_wait: Duration::from_secs(10 * 60),
+            incremental: false,
+            batch_size: None,
+            max_memory: None,
+            only_if_count_over: None,
+            only_if_size_over: None,
+            free_at_least: None,
+            sort: None,
+            order: None,
+            jobs: 1,
+            protected_paths: None,
+            i_know_what_im_doing: false,
+            dry_run: false,
+            preview: PreviewStyle::List,
+            print0: false,
+            plan_format: None,
+            plan_output: None,
+            tui: false,
+            report_html: None,
+            pre_run: None,
+            post_run: None,
+            post_file: None,
+            timings: false,
+            output: OutputFormat::Text,
+            quiet: false,
+            verbose: 0,
+            cancellation: CancellationToken::default(),
+        }
+    }
+}
+
+/// System folders that should never be archived wholesale, checked in addition to filesystem roots and the home folder
+#[cfg(windows)]
+const BUILT_IN_PROTECTED_PATHS: &[&str] = &[
+    r"C:\Windows",
+    r"C:\Program Files",
+    r"C:\Program Files (x86)",
+    r"C:\ProgramData",
+    r"C:\Users",
+];
+
+/// System folders that should never be archived wholesale, checked in addition to filesystem roots and the home folder
+#[cfg(not(windows))]
+const BUILT_IN_PROTECTED_PATHS: &[&str] = &[
+    "/bin", "/boot", "/dev", "/etc", "/home", "/lib", "/opt", "/proc", "/sbin", "/sys", "/usr", "/var",
+    "/Applications", "/Library", "/System", "/Users",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    /// Human-readable log lines
+    Text,
+    /// A single JSON document describing the whole run, printed at the end
+    Json,
+    /// One JSON event per line, printed as soon as each event happens
+    Ndjson,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[serde(rename_all = "lowercase")]
+pub enum JunctionPolicy {
+    /// Leave junctions alone, as they may loop back to a parent folder
+    Skip,
+    /// Walk into junctions like into regular folders
+    Follow,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[serde(rename_all = "lowercase")]
+pub enum SyncConflictPolicy {
+    /// Leave conflicted copies in place, next to the file they conflict with
+    Skip,
+    /// Move conflicted copies like any other file
+    Include,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[serde(rename_all = "lowercase")]
+pub enum DuplicatePolicy {
+    /// Move duplicates like any other file
+    Move,
+    /// Leave duplicates in place
+    Skip,
+    /// Delete duplicates, sent to the Recycle Bin first on Windows unless --permanent is set
+    Delete,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[serde(rename_all = "lowercase")]
+pub enum MergeConflictPolicy {
+    /// Move the file next to the other one, with a number added to its name (e.g., report (2).pdf)
+    Rename,
+    /// Leave the file in its archive
+    Skip,
+    /// Replace the other file, sent to the Recycle Bin first on Windows
+    Overwrite,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[serde(rename_all = "lowercase")]
+pub enum PlaceholderPolicy {
+    /// Leave placeholders in place
+    Skip,
+    /// Move placeholders as they are, which the sync client may not allow outside of its folder
+    Include,
+    /// Download the content of placeholders before moving them, so they are moved as regular files
+    Hydrate,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[serde(rename_all = "lowercase")]
+pub enum CaseCollisionPolicy {
+    /// Move both, the later file replacing the earlier one, with a warning
+    Replace,
+    /// Add a numeric suffix to the later file's name (e.g., "a (1).txt")
+    Rename,
+    /// Stop before moving anything, listing the collisions
+    Fail,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[serde(rename_all = "kebab-case")]
+pub enum ShortenPaths {
+    /// Keep the destinations as they are, the moves past the limits failing
+    Off,
+    /// Shorten the folders closest to the middle of the path first, then the file name
+    TruncateMiddle,
+    /// Shorten the longest names first, file name included
+    HashSuffix,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[serde(rename_all = "kebab-case")]
+pub enum TouchDestination {
+    /// Keep the modification time the files had at their source
+    Preserve,
+    /// Set it to the last second of the file's period, leaving the files that aren't grouped as they are
+    PeriodEnd,
+    /// Set it to the time of the move
+    Now,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[serde(rename_all = "lowercase")]
+pub enum UnicodeNormalization {
+    /// Composed characters, as used by Linux and Windows (e.g., "é" as a single code point)
+    Nfc,
+    /// Decomposed characters, as used by macOS (e.g., "é" as "e" followed by a combining accent)
+    Nfd,
+    /// Keep the names as they are in the source
+    None,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[serde(rename_all = "lowercase")]
+pub enum LinkFormat {
+    /// Markdown links of .md and .markdown files
+    Md,
+    /// href and src attributes of .html and .htm files
+    Html,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[serde(rename_all = "lowercase")]
+pub enum PeriodIndexFormat {
+    /// manifest.csv, for spreadsheets and scripts
+    Csv,
+    /// index.md, a markdown table for reading
+    Md,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    /// Oldest files first
+    Date,
+    /// Alphabetical order of the file names
+    Name,
+    /// Smallest files first
+    Size,
+    /// Alphabetical order of the source paths
+    Path,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[serde(rename_all = "kebab-case")]
+pub enum MoveOrder {
+    /// Smallest files first, for quick visible progress
+    SmallestFirst,
+    /// Largest files first, so the long transfers start early
+    LargestFirst,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[serde(rename_all = "lowercase")]
+pub enum PlanFormat {
+    /// rsync `--files-from` lists of the paths relative to their source folder, one per destination folder, along with
+    /// a mapping.tsv file naming the source and destination folders of each list
+    Rsync,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[serde(rename_all = "lowercase")]
+pub enum PreviewStyle {
+    /// One line per file, in the order they would be moved
+    List,
+    /// The would-be destination hierarchy, with file counts and sizes per folder
+    Tree,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[serde(rename_all = "lowercase")]
+pub enum GroupBy {
+    /// Group by ISO week (e.g., 2025-49)
+    Week,
+    /// Group by biweekly period (e.g., 2025-BW01 through 2025-BW26)
+    Biweekly,
+    /// Group by month (e.g., 2025-11)
+    Month,
+    /// Group by trimester/quarter (e.g., 2025-Q1 through 2025-Q4)
+    Trimester,
+    /// Group by quadrimester (e.g., 2025-QD1 through 2025-QD3)
+    Quadrimester,
+    /// Group by semester/half-year (e.g., 2025-H1, 2025-H2)
+    Semester,
+    /// Group by year (e.g., 2025)
+    Year,
+}
+
+impl GroupBy {
+    /// Every grouping strategy, from the shortest periods to the longest
+    pub const ALL: [GroupBy; 7] = [GroupBy::Week, GroupBy::Biweekly, GroupBy::Month, GroupBy::Trimester, GroupBy::Quadrimester, GroupBy::Semester, GroupBy::Year];
+
+    /// Name of the strategy in `--group-by` and config files (e.g., "month")
+    pub fn name(self) -> &'static str {
+        match self {
+            GroupBy::Week => "week",
+            GroupBy::Biweekly => "biweekly",
+            GroupBy::Month => "month",
+            GroupBy::Trimester => "trimester",
+            GroupBy::Quadrimester => "quadrimester",
+            GroupBy::Semester => "semester",
+            GroupBy::Year => "year",
+        }
+    }
+}
+
+impl FromStr for GroupBy {
+    type Err = eyre::Report;
+
+    /// Parse the name of a strategy, ignoring case
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        GroupBy::ALL.into_iter()
+            .find(|group_by| group_by.name().eq_ignore_ascii_case(value.trim()))
+            .ok_or_else(|| eyre::eyre!("Unknown grouping strategy: {value}"))
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FileDateType {
+    Created,
+    Modified,
+    Accessed,
+}
+
+/// Errors retried by default: also the unexpected network error and deleted network name errors of SMB shares on Windows
+#[cfg(windows)]
+const DEFAULT_RETRY_ERRORS: &str = "stale,timed-out,network,59,64";
+
+/// Errors retried by default
+#[cfg(not(windows))]
+const DEFAULT_RETRY_ERRORS: &str = "stale,timed-out,network";
+
+/// Move error of a network share that may go away by itself
+#[derive(Debug, PartialEq, Clone, Copy, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RetryableError {
+    /// Stale file handle of an NFS or SMB share (ESTALE)
+    Stale,
+    /// Timed out I/O (ETIMEDOUT, STATUS_IO_TIMEOUT)
+    TimedOut,
+    /// Lost connection to the server of the share
+    Network,
+    /// Raw OS error code (errno on Unix, Win32 error code on Windows)
+    #[serde(untagged)]
+    Code(i32),
+}
+
+fn parse_retryable_error(value: &str) -> color_eyre::Result<RetryableError, String> {
+    let trimmed_value = value.trim();
+    if let Ok(code) = trimmed_value.parse::<i32>() {
+        return Ok(RetryableError::Code(code));
+    }
+    match trimmed_value.to_ascii_lowercase().as_str() {
+        "stale" => Ok(RetryableError::Stale),
+        "timed-out" => Ok(RetryableError::TimedOut),
+        "network" => Ok(RetryableError::Network),
+        _ => Err(format!(
+            "Unsupported retryable error: {}. Please use one of the following, or a raw OS error code: stale, timed-out, network",
+            trimmed_value
+        )),
+    }
+}
+
+/// Parse file date type from string
+pub(crate) fn file_date_type_parser(value: &str) -> color_eyre::Result<FileDateType, String> {
+    let trimmed_value = value.trim();
+    match trimmed_value.to_ascii_lowercase().as_str() {
+        "c" | "created" => Ok(FileDateType::Created),
+        "m" | "modified" => Ok(FileDateType::Modified),
+        "a" | "accessed" => Ok(FileDateType::Accessed),
+        _ => Err(format!(
+            "Unsupported file date type: {}. Please use one of the following: {}",
+            trimmed_value,
+            ["created (c)", "modified (m)", "accessed (a)"].join(", ")
+        )),
+    }
+}
+
+/// Parse --older-than argument (duration or ISO date/datetime)
+pub fn parse_older_than(value: &str) -> color_eyre::Result<DateTime<Utc>> {
+    if let Some(dt) = parse_local_datetime(value) {
+        return Ok(dt);
+    }
+
+    if let Some(days) = value.strip_suffix("bd").and_then(|days| days.trim().parse::<u32>().ok()) {
+        return Ok(get_business_calendar().subtract_business_days(get_reference_time(), days));
+    }
+
+    // Try parsing as humantime duration
+    if let Ok(duration) = humantime::parse_duration(value) {
+        let now = get_reference_time();
+        let cutoff = now - duration;
+        return Ok(cutoff);
+    }
+
+    Err(eyre::eyre!("Invalid format. Use duration (e.g., '30d', '1y6M'), business days ('10bd'), ISO date ('2025-01-15'), or ISO datetime ('2025-01-15T10:30:00')"))
+}
+
+/// Parse the `--now` reference time, an ISO date or datetime in local time
+pub fn parse_reference_time(value: &str) -> color_eyre::Result<DateTime<Utc>> {
+    parse_local_datetime(value)
+        .ok_or_else(|| eyre::eyre!("Invalid format. Use ISO date ('2025-07-01') or ISO datetime ('2025-07-01T09:00:00')"))
+}
+
+/// Parse an ISO datetime or date (at midnight) in local time
+fn parse_local_datetime(value: &str) -> Option<DateTime<Utc>> {
+    // Try parsing as ISO datetime first
+    let iso_datetime_option =  NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S").ok()
+        .and_then(|dt| {
+            let local_offset =  Local::now().offset().fix();
+            dt.and_local_timezone(local_offset).single()
+        })
+        .map(|dt| dt.to_utc());
+
+    if iso_datetime_option.is_some() {
+        return iso_datetime_option;
+    }
+
+    // Try parsing as ISO date
+    NaiveDate::parse_from_str(value, "%Y-%m-%d").ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .and_then(|dt| {
+            let local_offset =  Local::now().offset().fix();
+            dt.and_local_timezone(local_offset).single()
+        })
+        .map(|dt| dt.to_utc())
+}
+
+/// Reference time set by `--now`, read before the other arguments are parsed so `--older-than` durations count from it
+static REFERENCE_TIME: OnceLock<DateTime<Utc>> = OnceLock::new();
+
+/// Time the periods and `--older-than` durations are calculated from: `--now` if given, the current time otherwise
+pub fn get_reference_time() -> DateTime<Utc> {
+    REFERENCE_TIME.get().copied().unwrap_or_else(Utc::now)
+}
+
+pub fn set_reference_time(now: DateTime<Utc>) {
+    // Safe to ignore: the reference time is only set once, before the arguments are parsed
+    let _ = REFERENCE_TIME.set(now);
+}
+
+static BUSINESS_CALENDAR: OnceLock<BusinessCalendar> = OnceLock::new();
+
+/// Weekend days and holidays of business-day durations: `--weekend-days` and `--holidays` if given, Saturday and
+/// Sunday otherwise
+pub fn get_business_calendar() -> &'static BusinessCalendar {
+    BUSINESS_CALENDAR.get_or_init(BusinessCalendar::default)
+}
+
+pub fn set_business_calendar(calendar: BusinessCalendar) {
+    // Safe to ignore: the calendar is only set once, before the arguments are parsed
+    let _ = BUSINESS_CALENDAR.set(calendar);
+}
+
+/// Parse a day of the week, by its English name or abbreviation (e.g., "sat", "Saturday")
+pub fn parse_weekday(value: &str) -> color_eyre::Result<Weekday, String> {
+    value.trim().parse::<Weekday>()
+        .map_err(|_| format!("Unsupported day of the week: {}. Use e.g. mon, tue, wed, thu, fri, sat or sun", value.trim()))
+}
+
+/// Files of some extensions moved into their own destination, for `--route`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Route {
+    /// Lowercase extensions, without their dot
+    pub extensions: Vec<String>,
+    pub destination: PathBuf,
+}
+
+/// Parse a `--route` rule, comma-separated extensions then `=>` and the destination (e.g., "jpg,.PNG=>/archive/photos")
+pub fn parse_route(value: &str) -> color_eyre::Result<Route, String> {
+    let Some((extensions, destination)) = value.split_once("=>") else {
+        return Err(format!("Invalid route '{value}', expected EXTENSIONS=>PATH (e.g., \"jpg,png=>/archive/photos\")"));
+    };
+    let extensions = extensions.split(',')
+        .map(|extension| extension.trim().trim_start_matches('.').to_lowercase())
+        .collect::<Vec<_>>();
+    if extensions.iter().any(String::is_empty) {
+        return Err(format!("Invalid route '{value}': empty extension"));
+    }
+    let destination = destination.trim();
+    if destination.is_empty() {
+        return Err(format!("Invalid route '{value}': missing destination"));
+    }
+    Ok(Route { extensions, destination: PathBuf::from(destination) })
+}
+
+/// Files under some source subpaths moved into their own destination, for `--route-path`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PathRoute {
+    /// Glob matched against the path of the files relative to their source, with `/` separators
+    pub pattern: String,
+    pub destination: PathBuf,
+}
+
+/// Parse a `--route-path` rule, a glob then `=>` and the destination (e.g., "Work/**=>/archive/work")
+pub fn parse_path_route(value: &str) -> color_eyre::Result<PathRoute, String> {
+    let Some((pattern, destination)) = value.split_once("=>") else {
+        return Err(format!("Invalid path route '{value}', expected GLOB=>PATH (e.g., \"Work/**=>/archive/work\")"));
+    };
+    let pattern = pattern.trim().trim_matches(['/', '\\']);
+    if pattern.is_empty() {
+        return Err(format!("Invalid path route '{value}': empty glob"));
+    }
+    let destination = destination.trim();
+    if destination.is_empty() {
+        return Err(format!("Invalid path route '{value}': missing destination"));
+    }
+    Ok(PathRoute { pattern: pattern.to_string(), destination: PathBuf::from(destination) })
+}
+
+/// Parse a human-readable size (e.g., "512", "10KB", "1.5G") into bytes, using binary multiples
+pub fn parse_size(value: &str) -> color_eyre::Result<u64, String> {
+    let trimmed_value = value.trim();
+    let unit_start = trimmed_value.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(trimmed_value.len());
+    let (number, unit) = trimmed_value.split_at(unit_start);
+
+    let multiplier: u64 = match unit.trim().to_ascii_lowercase().as_str() {
+        "" | "b" => 1,
+        "k" | "kb" | "kib" => 1 << 10,
+        "m" | "mb" | "mib" => 1 << 20,
+        "g" | "gb" | "gib" => 1 << 30,
+        "t" | "tb" | "tib" => 1 << 40,
+        _ => return Err(format!("Unsupported size unit: {}. Please use one of the following: B, KB, MB, GB, TB", unit.trim())),
+    };
+    let number = number.parse::<f64>()
+        .map_err(|_| format!("Invalid size: {trimmed_value}. Use a number optionally followed by a unit (e.g., \"10MB\")"))?;
+
+    Ok((number * multiplier as f64) as u64)
+}
+
+/// Resolve how much human-readable output to print from the output format and the quiet/verbose flags
+pub fn get_verbosity(args: &Args) -> Verbosity {
+    if args.output != OutputFormat::Text || args.print0 {
+        return Verbosity::Silent;
+    }
+    get_verbosity_from_flags(args.quiet, args.verbose)
+}
+
+pub fn get_verbosity_from_flags(quiet: bool, verbose: u8) -> Verbosity {
+    match (quiet, verbose) {
+        (true, _) => Verbosity::Quiet,
+        (false, 0) => Verbosity::Normal,
+        (false, 1) => Verbosity::Verbose,
+        (false, _) => Verbosity::Debug,
+    }
+}
+
+pub fn enrich_arguments(args: &Args) -> Args {
+    let mut ignored_paths = args.ignored_paths.clone().unwrap_or_default();
+
+    // Automatically add destinations and mirrors to ignored paths to prevent loops
+    for destination in get_destination_roots(args).into_iter().chain(args.mirrors.iter().map(PathBuf::as_path)) {
+        if !ignored_paths.iter().any(|ignored_path| ignored_path == destination) {
+            ignored_paths.push(destination.to_path_buf());
+        }
+    }
+
+    Args {
+        ignored_paths: Some(ignored_paths),
+        ..args.clone()
+    }
+}
+
+pub fn validate_arguments(args: &Args) -> color_eyre::Result<()> {
+    let mut canonical_sources: Vec<PathBuf> = Vec::new();
+    for source in &args.sources {
+        if !source.exists() {
+            bail!("Source directory does not exist: {}", source.display());
+        }
+        if !source.is_dir() {
+            bail!("Source path is not a directory: {}", source.display());
+        }
+        let canonical_source = fs::canonicalize(source)
+            .with_context(|| format!("Failed to resolve source directory: {}", source.display()))?;
+        if !args.i_know_what_im_doing && is_protected_path(&canonical_source, &get_protected_paths(args)) {
+            bail!("Refusing to use protected path as source: {}. Pass --i-know-what-im-doing if this is really intended", source.display());
+        }
+        // The files of a source inside another one would be walked twice
+        if let Some(other) = canonical_sources.iter().find(|other| canonical_source.starts_with(other) || other.starts_with(&canonical_source)) {
+            bail!("Source directories cannot contain each other: {} and {}", other.display(), canonical_source.display());
+        }
+        canonical_sources.push(canonical_source);
+    }
+
+    for destination in get_destination_roots(args) {
+        if !args.dry_run && !destination.exists() {
+            // Create destination directory if it doesn't exist
+            info!("Destination directory does not exist. Creating: {}", destination.display());
+
+            fs::create_dir_all(destination)
+                .with_context(|| format!("Failed to create destination directory: {}", destination.display()))?;
+        }
+        if !args.dry_run && !destination.is_dir() {
+            bail!("Destination path is not a directory: {}", destination.display());
+        }
+    }
+
+    for mirror in &args.mirrors {
+        // Mirrors are typically removable drives, which shouldn't be recreated on the system drive while unplugged
+        if !mirror.is_dir() {
+            bail!("Mirror directory does not exist: {}", mirror.display());
+        }
+        if get_destination_roots(args).contains(&mirror.as_path()) || args.sources.contains(mirror) {
+            bail!("Mirror cannot also be a source or destination directory: {}", mirror.display());
+        }
+    }
+
+    if let Some(files_from) = &args.files_from
+        && files_from != Path::new("-") && !files_from.is_file() {
+            bail!("File list does not exist: {}", files_from.display());
+        }
+
+    if get_destination_roots(args).iter().any(|root| args.sources.iter().any(|source| source == root)) {
+        bail!("Source and destination directories cannot be the same");
+    }
+
+    if args.file_date_types.contains(&FileDateType::Accessed) {
+        warn_about_unreliable_access_times(&args.sources);
+    }
+
+    if args.previous_period_only && args.group_by.is_none() && args.grouper.is_none() {
+        warn!("--previous-period-only is only meaningful with --group-by");
+    }
+
+    if args.max_path_length.is_some() && args.shorten_paths == ShortenPaths::Off {
+        warn!("--max-path-length is only meaningful with --shorten-paths");
+    }
+    if args.period_links && args.group_by.is_none() {
+        warn!("--period-links is only meaningful with --group-by");
+    }
+    if !args.period_index.is_empty() && args.group_by.is_none() && args.grouper.is_none() {
+        warn!("--period-index is only meaningful with --group-by");
+    }
+
+    if let Some(ignored_paths) = &args.ignored_paths {
+        for path in ignored_paths {
+            if !path.exists() {
+                warn!("Ignored path does not exist: {}", path.display());
+            }
+        }
+    }
+
+    if let (Some(min_depth), Some(max_depth)) = (args.min_depth, args.max_depth)
+        && min_depth > max_depth {
+            bail!("Minimum depth ({}) must be less than or equal to maximum depth ({})", min_depth, max_depth);
+        }
+
+    if args.preview == PreviewStyle::Tree && !args.dry_run {
+        warn!("--preview tree is only meaningful with --dry-run");
+    }
+
+    if args.max_errors == Some(0) {
+        bail!("Maximum errors must be greater than 0");
+    }
+
+    if args.jobs == 0 {
+        bail!("Number of jobs must be greater than 0");
+    }
+
+    if args.batch_size.is_some() {
+        validate_batched_run(args)?;
+    }
+
+    if args.max_memory.is_some() {
+        validate_memory_capped_run(args)?;
+    }
+
+    // The rsync lists are carried out by rsync, which can take the files off the volume
+    if args.free_at_least.is_some() && args.plan_format.is_none() {
+        warn_about_moves_within_volume(args);
+    }
+
+    if args.tui {
+        if args.output != OutputFormat::Text {
+            bail!("--tui cannot be used with --output json or ndjson");
+        }
+        if !io::stdin().is_terminal() || !io::stdout().is_terminal() {
+            bail!("--tui requires an interactive terminal");
+        }
+    }
+
+    Ok(())
+}
+
+/// Batched runs never hold the whole plan, so they can't support the options that need it
+fn validate_batched_run(args: &Args) -> color_eyre::Result<()> {
+    if args.batch_size == Some(0) {
+        bail!("Batch size must be greater than 0");
+    }
+    if args.output == OutputFormat::Json {
+        bail!("--batch-size cannot be used with --output json, use --output ndjson instead");
+    }
+    if args.preview == PreviewStyle::Tree {
+        bail!("--batch-size cannot be used with --preview tree");
+    }
+    // Files moved into a destination inside the source would be scanned again by the next batches
+    let destination = fs::canonicalize(&args.destination).unwrap_or(args.destination.clone());
+    let is_inside_source = args.sources.iter()
+        .any(|source| destination.starts_with(fs::canonicalize(source).unwrap_or(source.clone())));
+    if is_inside_source {
+        bail!("--batch-size requires a destination outside of the source directories");
+    }
+    Ok(())
+}
+
+/// Moves within a volume rename files without freeing any of its space
+fn warn_about_moves_within_volume(args: &Args) {
+    let Ok(destination_device) = get_device_id(&get_existing_ancestor(&args.destination)) else {
+        return;
+    };
+    for source in &args.sources {
+        if get_device_id(source).is_ok_and(|source_device| source_device == destination_device) {
+            warn!(
+                "{} is on the same volume as the destination, so moving its files frees no space there. Plan with --dry-run --plan-format rsync and transfer them with `rsync --remove-source-files` instead",
+                source.display()
+            );
+        }
+    }
+}
+
+/// Memory-capped runs may have spilled the plan to disk, so they can't support the options that need it in memory
+fn validate_memory_capped_run(args: &Args) -> color_eyre::Result<()> {
+    if args.max_memory == Some(0) {
+        bail!("Maximum memory must be greater than 0");
+    }
+    if args.output == OutputFormat::Json {
+        bail!("--max-memory cannot be used with --output json, use --output ndjson instead");
+    }
+    if args.preview == PreviewStyle::Tree {
+        bail!("--max-memory cannot be used with --preview tree");
+    }
+    Ok(())
+}
+
+/// Collect built-in, home and user-provided protected paths, resolved to their canonical form when possible
+fn get_protected_paths(args: &Args) -> Vec<PathBuf> {
+    BUILT_IN_PROTECTED_PATHS.iter()
+        .map(PathBuf::from)
+        .chain(dirs::home_dir())
+        .chain(args.protected_paths.clone().unwrap_or_default())
+        .map(|path| fs::canonicalize(&path).unwrap_or(path))
+        .collect()
+}
+
+/// Check if a path is a filesystem root (e.g. `/`, `C:\`) or one of the protected paths
+fn is_protected_path(path: &Path, protected_paths: &[PathBuf]) -> bool {
+    path.parent().is_none() || protected_paths.iter().any(|protected| protected == path)
+}
+
+pub fn print_arguments(args: &Args) {
+    info!("These are the arguments you provided:");
+    if let [source] = args.sources.as_slice() {
+        info!("Source directory: {}", source.display());
+    } else {
+        info!("Source directories: {:?}", args.sources.iter().map(|p| p.display()).collect::<Vec<_>>());
+    }
+    info!("Destination directory: {}", args.destination.display());
+    for route in &args.routes {
+        info!("Route: {} => {}", route.extensions.join(","), route.destination.display());
+    }
+    for route in &args.path_routes {
+        info!("Path route: {} => {}", route.pattern, route.destination.display());
+    }
+    for mirror in &args.mirrors {
+        info!("Mirror: {}", mirror.display());
+    }
+    info!("Finding files to move by their: {:?}", args.file_date_types);
+    match &args.grouper {
+        Some(grouper) => info!("Grouping By: {:?}", grouper),
+        None => info!("Grouping By: {}", args.group_by.map_or("None".to_string(), |e| format!("{e:?}"))),
+    }
+    if args.previous_period_only {
+        info!("Filter: Previous periods only (excluding current period)");
+    }
+    if args.period_links {
+        info!("Maintaining the latest and previous period links of the destination");
+    }
+    if !args.period_index.is_empty() {
+        info!("Period Indexes: {:?}", args.period_index);
+    }
+    if let Some(catalog) = &args.catalog {
+        info!("Catalog: {}", catalog.display());
+    }
+    if let Some(cutoff) = args.older_than {
+        info!("Filter: Only files older than {}", cutoff);
+    }
+    if let Some(tolerance) = args.timestamp_tolerance {
+        info!("Timestamp tolerance: {}", humantime::format_duration(tolerance));
+    }
+    if let Some(now) = args.now {
+        info!("Running as if it were: {}", now);
+    }
+    if let Some(ignored_paths) = &args.ignored_paths {
+        info!("Ignored paths: {:?}", ignored_paths.iter().map(|p| p.display()).collect::<Vec<_>>());
+    }
+    if let Some(min_depth) = args.min_depth {
+        info!("Min depth: {}", min_depth);
+    }
+    if let Some(max_depth) = args.max_depth {
+        info!("Max depth: {}", max_depth);
+    }
+    if let Some(sort) = args.sort {
+        info!("Sorting files by: {:?}", sort);
+    }
+    if let Some(order) = args.order {
+        info!("Moving order: {:?}", order);
+    }
+    if args.keep_newest_per_dir {
+        info!("Keeping the newest file of every folder");
+    }
+    if let Some(keep_min) = args.keep_min_per_dir {
+        info!("Keeping at least {} file(s) per folder", keep_min);
+    }
+    if let Some(free_at_least) = args.free_at_least {
+        info!("Freeing at least {} with the oldest files", format_size(free_at_least));
+    }
+    if let Some(count) = args.only_if_count_over {
+        info!("Only moving files once more than {} are to be moved", count);
+    }
+    if let Some(bytes) = args.only_if_size_over {
+        info!("Only moving files once they add up to more than {}", format_size(bytes));
+    }
+    if args.keep_empty_folders {
+        info!("Keeping empty folders after moving files");
+    }
+    if args.clean_destination_empty_dirs {
+        info!("Deleting the empty folders of the destination after moving files");
+    }
+    info!("Follow symbolic links: {}", args.follow_symbolic_links);
+    if args.include_macos_metadata {
+        info!("Including macOS metadata files");
+    }
+    if args.sync_conflicts == SyncConflictPolicy::Include {
+        info!("Including the conflicted copies of cloud-sync clients");
+    }
+    info!("Cloud placeholders: {:?}", args.cloud_placeholders);
+    if args.touch_destination != TouchDestination::Preserve {
+        info!("Modification time of the moved files: {:?}", args.touch_destination);
+    }
+    if args.duplicates != DuplicatePolicy::Move {
+        info!("Duplicates of archived files: {:?}", args.duplicates);
+    }
+    if !args.rewrite_links.is_empty() {
+        info!("Rewriting the links to the moved files of: {:?}", args.rewrite_links);
+        if args.rewrite_links_in_destination {
+            info!("Rewriting the links of the destination files too");
+        }
+    }
+    if args.git_mv {
+        info!("Staging the moves of tracked files in git");
+    }
+    if args.obsidian {
+        warn_about_vaults(args);
+        if args.obsidian_rewrite_links {
+            info!("Rewriting the links to the moved files in the Obsidian vault");
+        }
+    }
+    if cfg!(windows) {
+        info!("Junctions: {:?}", args.junctions);
+        if args.permanent {
+            info!("Overwriting existing destination files without sending them to the Recycle Bin");
+        }
+        if args.include_hidden {
+            info!("Including hidden and system files");
+        }
+    }
+    if let Some(files_from) = &args.files_from {
+        info!("Reading the files to consider from: {}", if files_from == Path::new("-") { "stdin".into() } else { files_from.display().to_string() });
+    }
+    if let Some(filter_cmd) = &args.filter_cmd {
+        info!("Filter command: {}", filter_cmd);
+    }
+    if args.prefix_source_name {
+        info!("Prefixing destinations with the source directory name");
+    }
+    if args.case_collisions != CaseCollisionPolicy::Replace {
+        info!("Destinations only differing in case: {:?}", args.case_collisions);
+    }
+    if args.sanitize_names {
+        info!("Sanitizing destination names for Windows filesystems");
+    }
+    if args.shorten_paths != ShortenPaths::Off {
+        info!("Shortening the destinations longer than {} characters: {:?}", args.max_path_length.unwrap_or(DEFAULT_MAX_PATH_LENGTH), args.shorten_paths);
+    }
+    if args.normalize_unicode != UnicodeNormalization::None {
+        info!("Normalizing destination names to: {:?}", args.normalize_unicode);
+    }
+    if args.record_hashes {
+        info!("Recording file hashes in the run manifest");
+    }
+    if args.retries > 0 {
+        info!("Retrying transient errors ({:?}) up to {} time(s)", args.retry_errors, args.retries);
+    }
+    if let Some(max_errors) = args.max_errors {
+        info!("Max errors: {}", max_errors);
+    }
+    if args.incremental {
+        info!("Incremental run: skipping folders unchanged since the last incremental run");
+    }
+    if let Some(batch_size) = args.batch_size {
+        info!("Batch size: {}", batch_size);
+    }
+    if let Some(max_memory) = args.max_memory {
+        info!("Max memory: {}", format_size(max_memory));
+    }
+    if args.jobs > 1 {
+        info!("Parallel jobs: {}", args.jobs);
+    }
+    if let Some(protected_paths) = &args.protected_paths {
+        info!("Protected paths: {:?}", protected_paths.iter().map(|p| p.display()).collect::<Vec<_>>());
+    }
+    if args.i_know_what_im_doing {
+        warn!("Protected path check is disabled");
+    }
+    if let Some(report_html) = &args.report_html {
+        info!("HTML report: {}", report_html.display());
+    }
+    if let Some(pre_run) = &args.pre_run {
+        info!("Pre-run hook: {}", pre_run);
+    }
+    if let Some(post_run) = &args.post_run {
+        info!("Post-run hook: {}", post_run);
+    }
+    if let Some(post_file) = &args.post_file {
+        info!("Post-file hook: {}", post_file);
+    }
+    info!("Dry run: {}", args.dry_run);
+    if args.dry_run && args.preview == PreviewStyle::Tree {
+        info!("Preview: destination tree");
+    }
+    if let (Some(plan_format), Some(plan_output)) = (args.plan_format, &args.plan_output) {
+        info!("Writing the plan as {:?} files to: {}", plan_format, plan_output.display());
+    }
+    if args.tui {
+        info!("Reviewing the plan interactively before moving");
+    }
+    info!("");
+}
+
+/// The run options of the `chronomover` command line, for the tests to build their `Args` from flags
+#[cfg(test)]
+#[derive(clap::Parser, Debug)]
+#[command(name = "chronomover", args_override_self = true)]
+pub(crate) struct Cli {
+    #[command(flatten)]
+    pub args: Option<Args>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    #[test]
+    fn test_default_args_match_the_command_line() {
+        let parsed = Cli::parse_from(["chronomover", "-s", "/photos", "-d", "/archive"]).args.unwrap();
+        let default = Args { sources: vec![PathBuf::from("/photos")], destination: PathBuf::from("/archive"), ..Args::default() };
+
+        assert_eq!(serde_json::to_value(&default).unwrap(), serde_json::to_value(&parsed).unwrap());
+    }
+
+    #[test]
+    fn test_group_by_names_match_the_command_line() {
+        use clap::ValueEnum;
+
+        assert_eq!(GroupBy::value_variants(), GroupBy::ALL);
+        for group_by in GroupBy::ALL {
+            assert_eq!(group_by.to_possible_value().unwrap().get_name(), group_by.name());
+            assert_eq!(group_by.name().to_uppercase().parse::<GroupBy>().unwrap(), group_by);
+        }
+        assert!("decade".parse::<GroupBy>().is_err());
+    }
+
+    #[test]
+    fn test_parse_route() {
+        let route = parse_route("jpg, .PNG=>/archive/photos").unwrap();
+
+        assert_eq!(route.extensions, ["jpg", "png"]);
+        assert_eq!(route.destination, PathBuf::from("/archive/photos"));
+        assert!(parse_route("jpg:/archive/photos").is_err());
+        assert!(parse_route("jpg,,png=>/archive/photos").is_err());
+        assert!(parse_route("pdf=> ").is_err());
+    }
+
+    #[test]
+    fn test_parse_path_route() {
+        let route = parse_path_route(" Camera Uploads/** => /archive/pictures").unwrap();
+
+        assert_eq!(route.pattern, "Camera Uploads/**");
+        assert_eq!(route.destination, PathBuf::from("/archive/pictures"));
+        assert_eq!(parse_path_route("/Work/=>/archive/work").unwrap().pattern, "Work");
+        assert!(parse_path_route("Work:/archive/work").is_err());
+        assert!(parse_path_route("/=>/archive/work").is_err());
+        assert!(parse_path_route("Work=>").is_err());
+    }
+
+    #[test]
+    fn test_parse_older_than_business_days() {
+        // Ten business days are two weeks, or a few days less when counted from a weekend day
+        let age = Utc::now() - parse_older_than("10bd").unwrap();
+        assert!((12..=14).contains(&age.num_days()), "{age}");
+        assert!(parse_older_than("tenbd").is_err());
+    }
+
+    // file_date_type_parser tests
+    #[test]
+    fn test_file_date_type_parser_valid_full_names() {
+        assert_eq!(file_date_type_parser("created").unwrap(), FileDateType::Created);
+        assert_eq!(file_date_type_parser("modified").unwrap(), FileDateType::Modified);
+        assert_eq!(file_date_type_parser("accessed").unwrap(), FileDateType::Accessed);
+    }
+
+    #[test]
+    fn test_file_date_type_parser_valid_short_forms() {
+        assert_eq!(file_date_type_parser("c").unwrap(), FileDateType::Created);
+        assert_eq!(file_date_type_parser("m").unwrap(), FileDateType::Modified);
+        assert_eq!(file_date_type_parser("a").unwrap(), FileDateType::Accessed);
+    }
+
+    #[test]
+    fn test_file_date_type_parser_case_insensitive() {
+        assert_eq!(file_date_type_parser("CREATED").unwrap(), FileDateType::Created);
+        assert_eq!(file_date_type_parser("Modified").unwrap(), FileDateType::Modified);
+        assert_eq!(file_date_type_parser("ACCessed").unwrap(), FileDateType::Accessed);
+        assert_eq!(file_date_type_parser("C").unwrap(), FileDateType::Created);
+        assert_eq!(file_date_type_parser("M").unwrap(), FileDateType::Modified);
+        assert_eq!(file_date_type_parser("A").unwrap(), FileDateType::Accessed);
+    }
+
+    #[test]
+    fn test_file_date_type_parser_whitespace_handling() {
+        assert_eq!(file_date_type_parser(" created ").unwrap(), FileDateType::Created);
+        assert_eq!(file_date_type_parser("  m  ").unwrap(), FileDateType::Modified);
+        assert_eq!(file_date_type_parser("\taccessed\t").unwrap(), FileDateType::Accessed);
+    }
+
+    #[test]
+    fn test_file_date_type_parser_invalid_inputs() {
+        assert!(file_date_type_parser("invalid").is_err());
+        assert!(file_date_type_parser("").is_err());
+        assert!(file_date_type_parser("cm").is_err());
+        assert!(file_date_type_parser("x").is_err());
+        assert!(file_date_type_parser("create").is_err()); // typo
+        assert!(file_date_type_parser("modify").is_err()); // wrong word
+    }
+
+    #[test]
+    fn test_file_date_type_parser_error_message() {
+        let result = file_date_type_parser("invalid");
+        assert!(result.is_err());
+        let error = result.unwrap_err();
+        assert!(error.contains("Unsupported file date type"));
+        assert!(error.contains("created (c)"));
+        assert!(error.contains("modified (m)"));
+        assert!(error.contains("accessed (a)"));
+    }
+
+    // get_verbosity tests
+    fn parse_args(extra_args: &[&str]) -> Args {
+        let base_args = ["chronomover", "-s", "/notes", "-d", "/archive"];
+        Cli::parse_from(base_args.iter().chain(extra_args)).args.unwrap()
+    }
+
+    #[test]
+    fn test_get_verbosity() {
+        assert_eq!(get_verbosity(&parse_args(&[])), Verbosity::Normal);
+        assert_eq!(get_verbosity(&parse_args(&["-q"])), Verbosity::Quiet);
+        assert_eq!(get_verbosity(&parse_args(&["-v"])), Verbosity::Verbose);
+        assert_eq!(get_verbosity(&parse_args(&["-vv"])), Verbosity::Debug);
+        assert_eq!(get_verbosity(&parse_args(&["-vvv"])), Verbosity::Debug);
+    }
+
+    #[test]
+    fn test_get_verbosity_machine_readable_output_is_silent() {
+        assert_eq!(get_verbosity(&parse_args(&["--output", "json", "-v"])), Verbosity::Silent);
+        assert_eq!(get_verbosity(&parse_args(&["--output", "ndjson"])), Verbosity::Silent);
+        assert_eq!(get_verbosity(&parse_args(&["--dry-run", "--print0"])), Verbosity::Silent);
+    }
+
+    #[test]
+    fn test_print0_requires_dry_run() {
+        let base_args = ["chronomover", "-s", "/notes", "-d", "/archive", "--print0"];
+        assert!(Cli::try_parse_from(base_args).is_err());
+    }
+
+    #[test]
+    fn test_plan_format_requires_dry_run_and_plan_output() {
+        let base_args = ["chronomover", "-s", "/notes", "-d", "/archive", "--plan-format", "rsync"];
+        assert!(Cli::try_parse_from(base_args.iter().chain(&["--dry-run"])).is_err());
+        assert!(Cli::try_parse_from(base_args.iter().chain(&["--plan-output", "/plan"])).is_err());
+        assert!(Cli::try_parse_from(base_args.iter().chain(&["--dry-run", "--plan-output", "/plan"])).is_ok());
+    }
+
+    #[test]
+    fn test_quiet_conflicts_with_verbose() {
+        let base_args = ["chronomover", "-s", "/notes", "-d", "/archive", "-q", "-v"];
+        assert!(Cli::try_parse_from(base_args).is_err());
+    }
+
+    // parse_size tests
+    #[test]
+    fn test_parse_reference_time() {
+        let now = parse_reference_time("2025-07-01").unwrap();
+        assert_eq!(now.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S").to_string(), "2025-07-01 00:00:00");
+        assert!(parse_reference_time("2025-07-01T09:00:00").is_ok());
+        assert!(parse_reference_time("30d").is_err());
+    }
+
+    #[test]
+    fn test_parse_size_units() {
+        assert_eq!(parse_size("512").unwrap(), 512);
+        assert_eq!(parse_size("512B").unwrap(), 512);
+        assert_eq!(parse_size("10KB").unwrap(), 10 * 1024);
+        assert_eq!(parse_size("10k").unwrap(), 10 * 1024);
+        assert_eq!(parse_size("5MiB").unwrap(), 5 * 1024 * 1024);
+        assert_eq!(parse_size("1.5G").unwrap(), 1536 * 1024 * 1024);
+        assert_eq!(parse_size("2 TB").unwrap(), 2 * 1024 * 1024 * 1024 * 1024);
+    }
+
+    #[test]
+    fn test_parse_size_invalid_inputs() {
+        assert!(parse_size("").is_err());
+        assert!(parse_size("MB").is_err());
+        assert!(parse_size("10XB").is_err());
+        assert!(parse_size("1.2.3MB").is_err());
+    }
+
+    // is_protected_path tests
+    #[test]
+    fn test_is_protected_path_filesystem_root() {
+        assert!(is_protected_path(Path::new("/"), &[]));
+    }
+
+    #[test]
+    fn test_is_protected_path_listed_paths() {
+        let protected_paths = vec![PathBuf::from("/home/user"), PathBuf::from("/etc")];
+
+        assert!(is_protected_path(Path::new("/home/user"), &protected_paths));
+        assert!(is_protected_path(Path::new("/etc"), &protected_paths));
+
+        // Subfolders of protected paths are allowed
+        assert!(!is_protected_path(Path::new("/home/user/Downloads"), &protected_paths));
+        assert!(!is_protected_path(Path::new("/etc/nginx"), &protected_paths));
+    }
+    #[test]
+    fn test_validate_batched_run() {
+        let parse = |extra: &[&str]| {
+            let command_line = ["chronomover", "-s", "/nonexistent/notes", "-d", "/nonexistent/archive", "--batch-size", "100"];
+            Cli::parse_from(command_line.iter().chain(extra)).args.unwrap()
+        };
+
+        assert!(validate_batched_run(&parse(&[])).is_ok());
+        assert!(validate_batched_run(&parse(&["--output", "ndjson"])).is_ok());
+        assert!(validate_batched_run(&parse(&["--output", "json"])).is_err());
+        assert!(validate_batched_run(&parse(&["--dry-run", "--preview", "tree"])).is_err());
+        assert!(validate_batched_run(&parse(&["-d", "/nonexistent/notes/archive"])).is_err());
+        assert!(validate_batched_run(&parse(&["--batch-size", "0"])).is_err());
+        assert!(Cli::try_parse_from(["chronomover", "-s", "/a", "-d", "/b", "--batch-size", "10", "--keep-min-per-dir", "2"]).is_err());
+    }
+
+    #[test]
+    fn test_validate_memory_capped_run() {
+        let parse = |extra: &[&str]| {
+            let command_line = ["chronomover", "-s", "/nonexistent/notes", "-d", "/nonexistent/archive", "--max-memory", "64MB"];
+            Cli::parse_from(command_line.iter().chain(extra)).args.unwrap()
+        };
+
+        assert_eq!(parse(&[]).max_memory, Some(64 * 1024 * 1024));
+        assert!(validate_memory_capped_run(&parse(&[])).is_ok());
+        assert!(validate_memory_capped_run(&parse(&["-d", "/nonexistent/notes/archive"])).is_ok());
+        assert!(validate_memory_capped_run(&parse(&["--output", "json"])).is_err());
+        assert!(validate_memory_capped_run(&parse(&["--dry-run", "--preview", "tree"])).is_err());
+        assert!(validate_memory_capped_run(&parse(&["--max-memory", "0"])).is_err());
+        assert!(Cli::try_parse_from(["chronomover", "-s", "/a", "-d", "/b", "--max-memory", "1MB", "--batch-size", "10"]).is_err());
+    }
+}
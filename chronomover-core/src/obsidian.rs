@@ -0,0 +1,76 @@
+use crate::links::{get_moves, normalize_path, rewrite_file_links, LinkContext};
+use crate::manifest::ManifestEntry;
+use crate::model::{Args, LinkFormat};
+use color_eyre::eyre::Result;
+use std::collections::HashSet;
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+use walkdir::WalkDir;
+
+/// Folder holding the settings of an Obsidian vault, which marks the root of the vault
+const CONFIG_DIR_NAME: &str = ".obsidian";
+
+/// Folder Obsidian moves deleted notes to, when set to use its own trash
+const TRASH_DIR_NAME: &str = ".trash";
+
+/// Whether a folder is the settings or the trash folder of a vault, never moved nor deleted with `--obsidian`
+pub fn is_obsidian_folder(name: &OsStr) -> bool {
+    name == CONFIG_DIR_NAME || name == TRASH_DIR_NAME
+}
+
+/// Root of the Obsidian vault a source folder is in: the closest folder holding a `.obsidian` folder, from the source up
+pub fn find_vault_root(source: &Path) -> Option<PathBuf> {
+    let source = normalize_path(&std::path::absolute(source).ok()?);
+    source.ancestors().find(|dir| dir.join(CONFIG_DIR_NAME).is_dir()).map(Path::to_path_buf)
+}
+
+/// Warn about the sources outside of any vault, and about a destination outside of their vaults, whose notes could
+/// then only be linked to by their absolute path
+pub fn warn_about_vaults(args: &Args) {
+    let destination = std::path::absolute(&args.destination).map(|destination| normalize_path(&destination)).ok();
+    for source in &args.sources {
+        match find_vault_root(source) {
+            Some(vault_root) => {
+                info!("Obsidian vault of {}: {}", source.display(), vault_root.display());
+                if destination.as_ref().is_some_and(|destination| !destination.starts_with(&vault_root)) {
+                    warn!("The destination is outside of the Obsidian vault {}, links to the moved notes will break", vault_root.display());
+                }
+            }
+            None => warn!("No Obsidian vault (a folder holding a {} folder) found at or above {}", CONFIG_DIR_NAME, source.display()),
+        }
+    }
+}
+
+/// Rewrite the links of the notes left in the vaults of the sources that point to the files moved by a run, so they
+/// keep pointing to them at the destination. Returns the rewritten notes.
+pub fn rewrite_vault_links(args: &Args, moved: &[ManifestEntry]) -> Result<Vec<PathBuf>> {
+    let moves = get_moves(moved)?;
+    let moved_notes = moves.values().collect::<HashSet<_>>();
+    let vault_roots = args.sources.iter().filter_map(|source| find_vault_root(source)).collect::<HashSet<_>>();
+
+    let mut rewritten_notes = Vec::new();
+    for vault_root in &vault_roots {
+        let notes = WalkDir::new(vault_root)
+            .into_iter()
+            .filter_entry(|entry| entry.depth() == 0 || !(entry.file_type().is_dir() && is_obsidian_folder(entry.file_name())))
+            .filter_map(Result::ok)
+            .filter(|entry| entry.file_type().is_file() && entry.path().extension().is_some_and(|extension| extension == "md"))
+            .map(walkdir::DirEntry::into_path)
+            .filter(|note| !moved_notes.contains(note));
+        for note in notes {
+            // Safe to unwrap: walked files have a parent
+            let note_dir = note.parent().unwrap();
+            let context = LinkContext { base_dir: note_dir, file_dir: note_dir, vault_root: Some(vault_root), moves: &moves };
+            match rewrite_file_links(&note, LinkFormat::Md, &context) {
+                Ok(true) => {
+                    info!(path = %note.display(), "Rewrote the links of: {}", note.display());
+                    rewritten_notes.push(note);
+                }
+                Ok(false) => {}
+                Err(e) => warn!(path = %note.display(), "Failed to rewrite the links of {}: {:#}", note.display(), e),
+            }
+        }
+    }
+    Ok(rewritten_notes)
+}
@@ -0,0 +1,294 @@
+use crate::file::{FileError, FileToMove, MoveReport, Plan};
+use crate::model::Args;
+use crate::report::MirrorStats;
+use crate::shorten::ShortenedPath;
+use color_eyre::eyre::{Context, Result};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::io;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+/// Sinks receiving the events of the current run, by registration ID
+static EVENT_SINKS: Mutex<Vec<(u64, Box<dyn EventSink>)>> = Mutex::new(Vec::new());
+
+static NEXT_SINK_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Machine-readable description of a whole run, printed by `--output json`
+#[derive(Debug, Serialize)]
+struct JsonReport<'a> {
+    arguments: &'a Args,
+    planned: &'a [FileToMove],
+    moved: &'a [FileToMove],
+    deleted_directories: &'a [PathBuf],
+    shortened: &'a [ShortenedPath],
+    /// Files copied into each `--mirror`
+    mirrors: &'a BTreeMap<PathBuf, MirrorStats>,
+    errors: Vec<&'a FileError>,
+    summary: RunSummary<'a>,
+}
+
+/// Event of a run, handed to the registered `EventSink`s as soon as it happens
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "kebab-case")]
+pub enum Event<'a> {
+    ScanProgress { scanned: usize },
+    FilePlanned(&'a FileToMove),
+    FileMoved(&'a FileToMove),
+    FileFailed(&'a FileError),
+    DirDeleted { path: &'a Path },
+    Summary(RunSummary<'a>),
+}
+
+impl Event<'_> {
+    /// Name of the event, as in its `event` field
+    pub fn name(&self) -> &'static str {
+        match self {
+            Event::ScanProgress { .. } => "scan-progress",
+            Event::FilePlanned(_) => "file-planned",
+            Event::FileMoved(_) => "file-moved",
+            Event::FileFailed(_) => "file-failed",
+            Event::DirDeleted { .. } => "dir-deleted",
+            Event::Summary(_) => "summary",
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct RunSummary<'a> {
+    dry_run: bool,
+    run_id: Option<&'a str>,
+    planned: usize,
+    moved: usize,
+    failed: usize,
+    deleted_directories: usize,
+    /// Whether the run was cancelled, leaving files it planned or didn't scan yet in place
+    cancelled: bool,
+    /// Why the run stopped early (`--max-errors`, or the destination staying unavailable), if it did
+    aborted: Option<&'a str>,
+}
+
+/// Receives the events of a run as they happen, e.g. to print them, publish them or drive a progress bar. Every method
+/// ignores its event by default, so a sink only implements the ones it needs. Sinks must not emit events themselves.
+pub trait EventSink: Send {
+    /// Every event, handed to the method of its kind by default
+    fn on_event(&mut self, event: &Event) {
+        match event {
+            Event::ScanProgress { scanned } => self.on_scan_progress(*scanned),
+            Event::FilePlanned(file) => self.on_file_planned(file),
+            Event::FileMoved(file) => self.on_file_moved(file),
+            Event::FileFailed(error) => self.on_error(error),
+            Event::DirDeleted { path } => self.on_dir_deleted(path),
+            Event::Summary(summary) => self.on_summary(summary),
+        }
+    }
+
+    /// Number of files walked so far in the sources, every `SCAN_PROGRESS_INTERVAL` files
+    fn on_scan_progress(&mut self, _scanned: usize) {}
+
+    fn on_file_planned(&mut self, _file: &FileToMove) {}
+
+    fn on_file_moved(&mut self, _file: &FileToMove) {}
+
+    /// A file that could not be evaluated while planning, or could not be moved
+    fn on_error(&mut self, _error: &FileError) {}
+
+    fn on_dir_deleted(&mut self, _path: &Path) {}
+
+    fn on_summary(&mut self, _summary: &RunSummary) {}
+}
+
+/// Keeps a sink receiving the events until dropped, which drops the sink
+pub struct EventSinkRegistration {
+    id: u64,
+}
+
+impl Drop for EventSinkRegistration {
+    fn drop(&mut self) {
+        lock_event_sinks().retain(|(id, _)| *id != self.id);
+    }
+}
+
+/// Prints every event but the scan progress as a JSON line (`--output ndjson`)
+pub struct NdjsonSink;
+
+impl EventSink for NdjsonSink {
+    fn on_event(&mut self, event: &Event) {
+        if matches!(event, Event::ScanProgress { .. }) {
+            return;
+        }
+        match serde_json::to_string(event) {
+            Ok(line) => println!("{line}"),
+            Err(e) => eprintln!("WARNING: Failed to serialize event {:?}: {}", event, e),
+        }
+    }
+}
+
+/// Register a sink receiving the events from now on, until the returned registration is dropped
+pub fn add_event_sink(sink: Box<dyn EventSink>) -> EventSinkRegistration {
+    let id = NEXT_SINK_ID.fetch_add(1, Ordering::Relaxed);
+    lock_event_sinks().push((id, sink));
+    EventSinkRegistration { id }
+}
+
+/// Hand an event to every registered sink, in registration order
+pub fn emit_event(event: Event) {
+    for (_, sink) in lock_event_sinks().iter_mut() {
+        sink.on_event(&event);
+    }
+}
+
+fn lock_event_sinks() -> MutexGuard<'static, Vec<(u64, Box<dyn EventSink>)>> {
+    EVENT_SINKS.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Print the planned moves as `source\0destination\0` pairs (`--print0`)
+pub fn print_nul_delimited_plan(files_to_move: &[FileToMove]) -> Result<()> {
+    let mut stdout = io::stdout().lock();
+    write_nul_delimited_plan(&mut stdout, files_to_move)
+        .and_then(|_| stdout.flush())
+        .context("Failed to print the NUL-delimited plan")
+}
+
+fn write_nul_delimited_plan(writer: &mut impl Write, files_to_move: &[FileToMove]) -> io::Result<()> {
+    for file in files_to_move {
+        // Paths are written as raw bytes, since they may not be valid UTF-8
+        writer.write_all(file.source.as_os_str().as_encoded_bytes())?;
+        writer.write_all(b"\0")?;
+        writer.write_all(file.destination.as_os_str().as_encoded_bytes())?;
+        writer.write_all(b"\0")?;
+    }
+    Ok(())
+}
+
+pub fn build_run_summary<'a>(args: &Args, plan: &Plan, move_report: &'a MoveReport, deleted_dirs: &[PathBuf]) -> RunSummary<'a> {
+    RunSummary {
+        dry_run: args.dry_run,
+        run_id: move_report.run_id.as_deref(),
+        planned: plan.planned,
+        moved: move_report.moved_count(),
+        failed: plan.errors.len() + move_report.errors.len(),
+        deleted_directories: deleted_dirs.len(),
+        cancelled: args.cancellation.is_cancelled(),
+        aborted: move_report.aborted.as_deref(),
+    }
+}
+
+pub fn print_json_report(args: &Args, plan: &Plan, move_report: &MoveReport, deleted_dirs: &[PathBuf]) -> Result<()> {
+    println!("{}", render_json_report(args, plan, move_report, deleted_dirs)?);
+    Ok(())
+}
+
+fn render_json_report(args: &Args, plan: &Plan, move_report: &MoveReport, deleted_dirs: &[PathBuf]) -> Result<String> {
+    let report = JsonReport {
+        arguments: args,
+        planned: &plan.files_to_move,
+        moved: &move_report.moved,
+        deleted_directories: deleted_dirs,
+        shortened: &plan.shortened,
+        mirrors: &move_report.mirrors,
+        errors: plan.errors.iter().chain(&move_report.errors).collect(),
+        summary: build_run_summary(args, plan, move_report, deleted_dirs),
+    };
+
+    serde_json::to_string_pretty(&report).context("Failed to serialize JSON report")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::Cli;
+    use chrono::{DateTime, Utc};
+    use crate::report::{PeriodStats, UNGROUPED_PERIOD};
+    use clap::Parser;
+    use std::collections::BTreeMap;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_render_json_report_summary() {
+        let args = Cli::parse_from(["chronomover", "-s", "/notes", "-d", "/archive", "--output", "json"]).args.unwrap();
+        let file = FileToMove {
+            source: PathBuf::from("/notes/a.md"),
+            destination: PathBuf::from("/archive/a.md"),
+            file_date: "2025-06-15T00:00:00Z".parse::<DateTime<Utc>>().unwrap(),
+            size: 0,
+            period: None,
+        };
+        let plan = Plan {
+            files_to_move: vec![file.clone()],
+            planned: 1,
+            errors: vec![FileError { path: PathBuf::from("/notes/b.md"), error: "Permission denied".to_string() }],
+            ..Plan::default()
+        };
+        let move_report = MoveReport {
+            moved: vec![file],
+            run_id: Some("20250615T000000Z".to_string()),
+            periods: BTreeMap::from([(UNGROUPED_PERIOD.to_string(), PeriodStats { files: 1, bytes: 0 })]),
+            ..MoveReport::default()
+        };
+
+        let json = render_json_report(&args, &plan, &move_report, &[PathBuf::from("/notes/empty")]).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(value["summary"]["planned"], 1);
+        assert_eq!(value["summary"]["moved"], 1);
+        assert_eq!(value["summary"]["failed"], 1);
+        assert_eq!(value["summary"]["deleted_directories"], 1);
+        assert_eq!(value["summary"]["run_id"], "20250615T000000Z");
+        assert_eq!(value["arguments"]["output"], "json");
+        assert_eq!(value["errors"][0]["path"], "/notes/b.md");
+    }
+
+    #[test]
+    fn test_event_serialization() {
+        let error = FileError { path: PathBuf::from("/notes/b.md"), error: "Permission denied".to_string() };
+
+        let line = serde_json::to_string(&Event::FileFailed(&error)).unwrap();
+        assert_eq!(line, r#"{"event":"file-failed","path":"/notes/b.md","error":"Permission denied"}"#);
+
+        let line = serde_json::to_string(&Event::DirDeleted { path: Path::new("/notes/empty") }).unwrap();
+        assert_eq!(line, r#"{"event":"dir-deleted","path":"/notes/empty"}"#);
+        assert_eq!(Event::DirDeleted { path: Path::new("/notes/empty") }.name(), "dir-deleted");
+    }
+
+    #[test]
+    fn test_event_sink_receives_events_until_unregistered() {
+        /// Records the errors of its own test file, as the other tests emit events to the registered sinks too
+        struct RecordingSink(Arc<Mutex<Vec<String>>>);
+
+        impl EventSink for RecordingSink {
+            fn on_error(&mut self, error: &FileError) {
+                if error.path == Path::new("/sink/a.md") {
+                    self.0.lock().unwrap().push(error.error.clone());
+                }
+            }
+        }
+
+        let recorded = Arc::new(Mutex::new(Vec::new()));
+        let error = FileError { path: PathBuf::from("/sink/a.md"), error: "Permission denied".to_string() };
+        let registration = add_event_sink(Box::new(RecordingSink(recorded.clone())));
+        emit_event(Event::FileFailed(&error));
+        emit_event(Event::DirDeleted { path: Path::new("/sink") });
+        drop(registration);
+        emit_event(Event::FileFailed(&error));
+
+        assert_eq!(*recorded.lock().unwrap(), ["Permission denied"]);
+    }
+
+    #[test]
+    fn test_write_nul_delimited_plan() {
+        let file = FileToMove {
+            source: PathBuf::from("/notes/my notes\nv2.md"),
+            destination: PathBuf::from("/archive/my notes\nv2.md"),
+            file_date: "2025-06-15T00:00:00Z".parse::<DateTime<Utc>>().unwrap(),
+            size: 0,
+            period: None,
+        };
+        let mut output = Vec::new();
+        write_nul_delimited_plan(&mut output, &[file]).unwrap();
+
+        assert_eq!(output, b"/notes/my notes\nv2.md\0/archive/my notes\nv2.md\0");
+    }
+}
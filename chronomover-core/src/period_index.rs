@@ -0,0 +1,163 @@
+use crate::file::{get_destination_root, FileToMove};
+use crate::model::{Args, PeriodIndexFormat};
+use crate::report::format_size;
+use chrono::{DateTime, Local, SecondsFormat, Utc};
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use tracing::{debug, warn};
+
+/// Name of the CSV index of a period folder
+pub const CSV_INDEX_FILE_NAME: &str = "manifest.csv";
+/// Name of the markdown index of a period folder
+pub const MARKDOWN_INDEX_FILE_NAME: &str = "index.md";
+
+const CSV_HEADER: &str = "file,original_path,date,size,moved_at\n";
+
+/// Append the moved files to the `--period-index` files of their period folder, creating them on the first run that
+/// moves a file into the folder, so the archive says where its files came from when browsed years later. Files that
+/// aren't grouped have no period folder to describe. Failing to write an index only warns, the files are moved anyway.
+pub fn append_to_period_indexes(args: &Args, moved: &[FileToMove], moved_at: DateTime<Utc>) {
+    let mut files_by_period_dir = BTreeMap::<PathBuf, Vec<&FileToMove>>::new();
+    for file in moved {
+        if let Some(period) = &file.period {
+            files_by_period_dir.entry(get_destination_root(args, &file.source).join(period)).or_default().push(file);
+        }
+    }
+
+    for (period_dir, files) in &files_by_period_dir {
+        for format in &args.period_index {
+            let (index_path, written) = match format {
+                PeriodIndexFormat::Csv => {
+                    let index_path = period_dir.join(CSV_INDEX_FILE_NAME);
+                    let written = append_to_index(&index_path, CSV_HEADER, &format_csv_rows(period_dir, files, moved_at));
+                    (index_path, written)
+                }
+                PeriodIndexFormat::Md => {
+                    let index_path = period_dir.join(MARKDOWN_INDEX_FILE_NAME);
+                    let header = format_markdown_header(period_dir);
+                    let written = append_to_index(&index_path, &header, &format_markdown_rows(period_dir, files, moved_at));
+                    (index_path, written)
+                }
+            };
+            match written {
+                Ok(()) => debug!(path = %index_path.display(), "Added {} file(s) to {}", files.len(), index_path.display()),
+                Err(e) => warn!(path = %index_path.display(), error = %e, "Failed to update the period index {}: {}", index_path.display(), e),
+            }
+        }
+    }
+}
+
+/// Append `rows` to the index, starting a new index with `header`
+fn append_to_index(index_path: &Path, header: &str, rows: &str) -> io::Result<()> {
+    let is_new = fs::symlink_metadata(index_path).is_err();
+    let mut index = OpenOptions::new().create(true).append(true).open(index_path)?;
+    let mut content = String::with_capacity(header.len() + rows.len());
+    if is_new {
+        content.push_str(header);
+    }
+    content.push_str(rows);
+    // Written at once, so an interrupted run leaves no half row behind
+    index.write_all(content.as_bytes())
+}
+
+/// Path of a moved file relative to its period folder, with `/` separators on every platform
+fn get_relative_path(period_dir: &Path, file: &FileToMove) -> String {
+    let relative_path = file.destination.strip_prefix(period_dir).unwrap_or(&file.destination);
+    relative_path.components().map(|component| component.as_os_str().to_string_lossy()).collect::<Vec<_>>().join("/")
+}
+
+fn format_csv_rows(period_dir: &Path, files: &[&FileToMove], moved_at: DateTime<Utc>) -> String {
+    let mut rows = String::new();
+    for file in files {
+        let fields = [
+            escape_csv(&get_relative_path(period_dir, file)),
+            escape_csv(&file.source.to_string_lossy()),
+            file.file_date.to_rfc3339_opts(SecondsFormat::Secs, false),
+            file.size.to_string(),
+            moved_at.to_rfc3339_opts(SecondsFormat::Secs, false),
+        ];
+        rows.push_str(&fields.join(","));
+        rows.push('\n');
+    }
+    rows
+}
+
+/// Quote a CSV field holding a separator, a quote or a line break, doubling its quotes (RFC 4180)
+fn escape_csv(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn format_markdown_header(period_dir: &Path) -> String {
+    let period = period_dir.file_name().map(|name| name.to_string_lossy()).unwrap_or_default();
+    format!("# {period}\n\nFiles archived by ChronoMover into this folder.\n\n| File | Original location | Date | Size | Moved |\n| --- | --- | --- | --- | --- |\n")
+}
+
+/// Table rows of the moved files; appended rows extend the table, as it ends the file
+fn format_markdown_rows(period_dir: &Path, files: &[&FileToMove], moved_at: DateTime<Utc>) -> String {
+    let format_date = |date: DateTime<Utc>| date.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string();
+    let mut rows = String::new();
+    for file in files {
+        rows.push_str(&format!(
+            "| {} | {} | {} | {} | {} |\n",
+            escape_markdown(&get_relative_path(period_dir, file)),
+            escape_markdown(&file.source.to_string_lossy()),
+            format_date(file.file_date),
+            format_size(file.size),
+            format_date(moved_at),
+        ));
+    }
+    rows
+}
+
+/// Keep a cell on its line and in its column
+fn escape_markdown(cell: &str) -> String {
+    cell.replace('\\', "\\\\").replace('|', "\\|").replace(['\n', '\r'], " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::Cli;
+    use clap::Parser;
+
+    #[test]
+    fn test_append_to_period_indexes() {
+        let destination = std::env::temp_dir().join(format!("chronomover-period-index-{}", std::process::id()));
+        let destination_arg = destination.to_string_lossy().to_string();
+        let args = Cli::parse_from(["chronomover", "-s", "/src", "-d", &destination_arg, "--period-index", "csv,md"]).args.unwrap();
+        let moved_at = "2025-07-01T12:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let file = |source: &str, destination: PathBuf, period: Option<&str>| FileToMove {
+            source: PathBuf::from(source),
+            destination,
+            file_date: "2025-06-10T08:30:00Z".parse().unwrap(),
+            size: 2048,
+            period: period.map(str::to_string),
+        };
+        fs::create_dir_all(destination.join("2025-06/docs")).unwrap();
+
+        append_to_period_indexes(&args, &[
+            file("/src/a, b.txt", destination.join("2025-06/a, b.txt"), Some("2025-06")),
+            file("/src/loose.txt", destination.join("loose.txt"), None),
+        ], moved_at);
+        append_to_period_indexes(&args, &[file("/src/docs/c|d.md", destination.join("2025-06/docs/c|d.md"), Some("2025-06"))], moved_at);
+
+        let csv = fs::read_to_string(destination.join("2025-06").join(CSV_INDEX_FILE_NAME)).unwrap();
+        assert_eq!(csv, format!(
+            "{CSV_HEADER}\"a, b.txt\",\"/src/a, b.txt\",2025-06-10T08:30:00+00:00,2048,2025-07-01T12:00:00+00:00\n\
+             docs/c|d.md,/src/docs/c|d.md,2025-06-10T08:30:00+00:00,2048,2025-07-01T12:00:00+00:00\n"
+        ));
+        let markdown = fs::read_to_string(destination.join("2025-06").join(MARKDOWN_INDEX_FILE_NAME)).unwrap();
+        assert!(markdown.starts_with("# 2025-06\n"));
+        assert_eq!(markdown.matches("| File |").count(), 1);
+        assert!(markdown.contains("\n| docs/c\\|d.md | /src/docs/c\\|d.md | "));
+        assert!(!destination.join(CSV_INDEX_FILE_NAME).exists());
+
+        fs::remove_dir_all(&destination).unwrap();
+    }
+}
@@ -0,0 +1,142 @@
+use crate::date::{get_period_identifier, get_period_start};
+use crate::model::GroupBy;
+use chrono::{DateTime, TimeDelta, Utc};
+use color_eyre::eyre::{Context, Result};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use tracing::{debug, info, warn};
+
+/// Point the `latest-<strategy>` link of the destination at its most recent period folder, and the
+/// `previous-<strategy>` link at the folder of the period before the current one (e.g., `latest-week -> 2025-W24` and
+/// `previous-month -> 2025-05`), so scripts have stable entry points into the archive. The links are relative, and a
+/// link whose period folder doesn't exist (yet) is removed. Returns the number of links created or updated.
+pub fn update_period_links(destination: &Path, group_by: GroupBy, now: DateTime<Utc>) -> Result<usize> {
+    let strategy = group_by.name();
+    let period_dirs = find_period_dir_names(destination, group_by)?;
+
+    let latest = period_dirs.iter().max_by_key(|(start, _)| *start).map(|(_, name)| name.clone());
+    let current_start = get_period_start(group_by, &get_period_identifier(group_by, now));
+    let previous = current_start
+        .map(|start| get_period_identifier(group_by, start - TimeDelta::seconds(1)))
+        .filter(|identifier| period_dirs.iter().any(|(_, name)| name == identifier));
+
+    let mut updated = 0;
+    for (link_name, target) in [(format!("latest-{strategy}"), latest), (format!("previous-{strategy}"), previous)] {
+        let link = destination.join(&link_name);
+        match update_link(&link, target.as_deref()) {
+            Ok(true) => {
+                updated += 1;
+                info!(path = %link.display(), "Pointed {} at {}", link.display(), target.as_deref().unwrap_or_default());
+            }
+            Ok(false) => {}
+            Err(e) => warn!(path = %link.display(), error = %e, "Failed to update the link {}: {}", link.display(), e),
+        }
+    }
+    Ok(updated)
+}
+
+/// Period folders directly inside the destination, along with when their period starts
+fn find_period_dir_names(destination: &Path, group_by: GroupBy) -> Result<Vec<(DateTime<Utc>, String)>> {
+    let mut period_dirs = Vec::new();
+    let entries = fs::read_dir(destination).with_context(|| format!("Failed to list the destination: {}", destination.display()))?;
+    for entry in entries.flatten() {
+        // Links are skipped, including the ones maintained here
+        if !entry.file_type().is_ok_and(|file_type| file_type.is_dir()) {
+            continue;
+        }
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if let Some(start) = get_period_start(group_by, &name) {
+            period_dirs.push((start, name));
+        }
+    }
+    Ok(period_dirs)
+}
+
+/// Make `link` point at `target` (relative to its folder), or remove it when there is no target. Anything other than
+/// a link at that path is left alone. Returns whether the link was created or changed.
+fn update_link(link: &Path, target: Option<&str>) -> io::Result<bool> {
+    let current_target = match fs::symlink_metadata(link) {
+        Ok(metadata) if metadata.file_type().is_symlink() => Some(fs::read_link(link)?),
+        Ok(_) => return Err(io::Error::new(io::ErrorKind::AlreadyExists, "a file or folder already has its name")),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+        Err(e) => return Err(e),
+    };
+    if current_target.as_deref() == target.map(Path::new) {
+        return Ok(false);
+    }
+    let Some(target) = target else {
+        debug!(path = %link.display(), "Removing {}: its period folder is gone", link.display());
+        remove_link(link)?;
+        return Ok(false);
+    };
+    // Created aside then renamed over the old link, so the link never goes missing for a reader
+    let temporary_link = PathBuf::from(format!("{}.{}.tmp", link.display(), std::process::id()));
+    create_dir_link(Path::new(target), &temporary_link)?;
+    if let Err(e) = replace_link(&temporary_link, link) {
+        let _ = remove_link(&temporary_link);
+        return Err(e);
+    }
+    Ok(true)
+}
+
+#[cfg(unix)]
+fn create_dir_link(target: &Path, link: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(target, link)
+}
+
+/// Creating symbolic links requires Developer Mode or an elevated prompt on Windows
+#[cfg(windows)]
+fn create_dir_link(target: &Path, link: &Path) -> io::Result<()> {
+    std::os::windows::fs::symlink_dir(target, link)
+}
+
+#[cfg(unix)]
+fn replace_link(from: &Path, to: &Path) -> io::Result<()> {
+    fs::rename(from, to)
+}
+
+/// A folder link can't be renamed over another one on Windows, so the old link is removed first
+#[cfg(windows)]
+fn replace_link(from: &Path, to: &Path) -> io::Result<()> {
+    if fs::symlink_metadata(to).is_ok() {
+        remove_link(to)?;
+    }
+    fs::rename(from, to)
+}
+
+/// Folder links are removed like files on Unix, and like folders on Windows
+fn remove_link(link: &Path) -> io::Result<()> {
+    if cfg!(windows) { fs::remove_dir(link) } else { fs::remove_file(link) }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_update_period_links() {
+        let destination = std::env::temp_dir().join(format!("chronomover-period-links-{}", std::process::id()));
+        for name in ["2025-04", "2025-05", "2025-06", "notes"] {
+            fs::create_dir_all(destination.join(name)).unwrap();
+        }
+        let now = "2025-06-15T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+
+        assert_eq!(update_period_links(&destination, GroupBy::Month, now).unwrap(), 2);
+        assert_eq!(fs::read_link(destination.join("latest-month")).unwrap(), Path::new("2025-06"));
+        assert_eq!(fs::read_link(destination.join("previous-month")).unwrap(), Path::new("2025-05"));
+        assert_eq!(update_period_links(&destination, GroupBy::Month, now).unwrap(), 0);
+
+        // A month later, May is gone and there is no folder for June's successor yet
+        fs::remove_dir(destination.join("2025-05")).unwrap();
+        let next_month = "2025-07-02T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        update_period_links(&destination, GroupBy::Month, next_month).unwrap();
+        assert_eq!(fs::read_link(destination.join("previous-month")).unwrap(), Path::new("2025-06"));
+        fs::remove_dir(destination.join("2025-06")).unwrap();
+        update_period_links(&destination, GroupBy::Month, next_month).unwrap();
+        assert_eq!(fs::read_link(destination.join("latest-month")).unwrap(), Path::new("2025-04"));
+        assert!(fs::symlink_metadata(destination.join("previous-month")).is_err());
+
+        fs::remove_dir_all(&destination).unwrap();
+    }
+}
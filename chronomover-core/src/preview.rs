@@ -0,0 +1,95 @@
+use crate::report::format_size;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+/// Folder of the would-be destination hierarchy, with its files and the aggregate size of everything below it
+#[derive(Debug, Default)]
+struct TreeNode {
+    folders: BTreeMap<String, TreeNode>,
+    files: BTreeMap<String, u64>,
+    file_count: usize,
+    bytes: u64,
+}
+
+/// Print the destination hierarchy that a dry run would produce, from the destination paths and sizes of the files
+pub fn print_destination_tree(destination_root: &Path, files: &[(PathBuf, u64)]) {
+    for line in render_destination_tree(destination_root, files) {
+        info!("{}", line);
+    }
+}
+
+fn render_destination_tree(destination_root: &Path, files: &[(PathBuf, u64)]) -> Vec<String> {
+    let mut root = TreeNode::default();
+    for (destination, size) in files {
+        let relative_path = destination.strip_prefix(destination_root).unwrap_or(destination);
+        let components = relative_path.iter().map(|component| component.to_string_lossy().into_owned()).collect::<Vec<_>>();
+        let Some((file_name, folders)) = components.split_last() else {
+            continue;
+        };
+
+        let mut node = &mut root;
+        node.file_count += 1;
+        node.bytes += size;
+        for folder in folders {
+            node = node.folders.entry(folder.clone()).or_default();
+            node.file_count += 1;
+            node.bytes += size;
+        }
+        node.files.insert(file_name.clone(), *size);
+    }
+
+    let mut lines = vec![format_folder_line(&destination_root.display().to_string(), &root)];
+    render_children(&root, "", &mut lines);
+    lines
+}
+
+fn render_children(node: &TreeNode, indent: &str, lines: &mut Vec<String>) {
+    let child_count = node.folders.len() + node.files.len();
+    let mut index = 0;
+
+    for (name, folder) in &node.folders {
+        index += 1;
+        let is_last = index == child_count;
+        lines.push(format!("{}{}{}", indent, if is_last { "└── " } else { "├── " }, format_folder_line(name, folder)));
+        render_children(folder, &format!("{}{}", indent, if is_last { "    " } else { "│   " }), lines);
+    }
+    for (name, size) in &node.files {
+        index += 1;
+        let is_last = index == child_count;
+        lines.push(format!("{}{}{} ({})", indent, if is_last { "└── " } else { "├── " }, name, format_size(*size)));
+    }
+}
+
+fn format_folder_line(name: &str, folder: &TreeNode) -> String {
+    format!("{}/ ({} file(s), {})", name.trim_end_matches(['/', '\\']), folder.file_count, format_size(folder.bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_destination_tree() {
+        let files = vec![
+            (PathBuf::from("/archive/2025-06/work/meeting.md"), 2048),
+            (PathBuf::from("/archive/2025-05/daily.md"), 100),
+            (PathBuf::from("/archive/2025-06/daily.md"), 10),
+        ];
+
+        assert_eq!(render_destination_tree(Path::new("/archive"), &files), vec![
+            "/archive/ (3 file(s), 2.1 KiB)",
+            "├── 2025-05/ (1 file(s), 100 B)",
+            "│   └── daily.md (100 B)",
+            "└── 2025-06/ (2 file(s), 2.0 KiB)",
+            "    ├── work/ (1 file(s), 2.0 KiB)",
+            "    │   └── meeting.md (2.0 KiB)",
+            "    └── daily.md (10 B)",
+        ]);
+    }
+
+    #[test]
+    fn test_render_destination_tree_empty() {
+        assert_eq!(render_destination_tree(Path::new("/archive"), &[]), vec!["/archive/ (0 file(s), 0 B)"]);
+    }
+}
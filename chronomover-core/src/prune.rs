@@ -0,0 +1,163 @@
+use crate::date::{get_next_period_start, get_period_start};
+use crate::logging::SUMMARY_TARGET;
+use crate::model::PruneArgs;
+use crate::report::format_size;
+use crate::storage::{StorageBackend, WalkOptions};
+use chrono::{DateTime, Utc};
+use color_eyre::eyre::{bail, Result};
+use std::cmp::Reverse;
+use std::path::{Path, PathBuf};
+use tracing::{error, info};
+
+/// Period folder of the archive, along with when its period ends
+#[derive(Debug)]
+struct PeriodDir {
+    path: PathBuf,
+    end: DateTime<Utc>,
+}
+
+/// Delete the period folders no retention rule keeps: the `--keep` most recent ones are kept, and so are the ones whose
+/// period ended after `--older-than`. Folders are sent to the Recycle Bin unless `--permanent` is passed.
+pub fn prune_archive(args: &PruneArgs, storage: &dyn StorageBackend) -> Result<()> {
+    if !cfg!(windows) && !args.permanent && !args.dry_run {
+        bail!("There is no Recycle Bin support on this platform, pass --permanent to delete the pruned folders for good");
+    }
+    if !storage.is_dir(&args.destination) {
+        bail!("Destination does not exist: {}", args.destination.display());
+    }
+
+    let period_dirs = find_period_dirs(args, storage)?;
+    let pruned_dirs = select_pruned_dirs(&period_dirs, args.keep, args.older_than);
+    info!("Pruning {} of the {} {:?} folder(s) of {}", pruned_dirs.len(), period_dirs.len(), args.group_by, args.destination.display());
+
+    let status = if args.dry_run { "dry-run" } else { "pruned" };
+    let mut pruned = 0;
+    let mut pruned_bytes = 0;
+    let mut failed = 0;
+    for period_dir in pruned_dirs {
+        let (file_count, bytes) = measure_dir(storage, &period_dir.path);
+        let deleted = match (args.dry_run, args.permanent) {
+            (true, _) => Ok(()),
+            (false, true) => storage.remove_dir_all(&period_dir.path),
+            (false, false) => storage.recycle_dir(&period_dir.path),
+        };
+        match deleted {
+            Ok(()) => {
+                pruned += 1;
+                pruned_bytes += bytes;
+                info!(
+                    path = %period_dir.path.display(), status,
+                    "{}. {} ({} file(s), {})", pruned, period_dir.path.display(), file_count, format_size(bytes)
+                );
+            }
+            Err(e) => {
+                error!(path = %period_dir.path.display(), error = %e, "Pruning folder {}: {}", period_dir.path.display(), e);
+                failed += 1;
+            }
+        }
+    }
+
+    let verb = if args.dry_run { "would have been pruned" } else { "pruned" };
+    if failed > 0 {
+        bail!("{} period folder(s) {} ({}), {} failed", pruned, verb, format_size(pruned_bytes), failed);
+    }
+    info!(target: SUMMARY_TARGET, "{} period folder(s) {} ({})", pruned, verb, format_size(pruned_bytes));
+    Ok(())
+}
+
+/// Folders directly inside the archive named after a period of `--group-by`, the most recent first
+fn find_period_dirs(args: &PruneArgs, storage: &dyn StorageBackend) -> Result<Vec<PeriodDir>> {
+    let mut period_dirs = Vec::new();
+    for entry in storage.list_dir(&args.destination)? {
+        let entry = entry?;
+        let start = entry.path.file_name()
+            .and_then(|name| name.to_str())
+            .filter(|_| entry.is_dir)
+            .and_then(|name| get_period_start(args.group_by, name));
+        if let Some(start) = start {
+            period_dirs.push(PeriodDir { end: get_next_period_start(args.group_by, start), path: entry.path });
+        }
+    }
+    period_dirs.sort_by_key(|period_dir| Reverse(period_dir.end));
+    Ok(period_dirs)
+}
+
+/// Period folders, the most recent first, that neither the count nor the age rule keeps
+fn select_pruned_dirs(period_dirs: &[PeriodDir], keep: Option<usize>, older_than: Option<DateTime<Utc>>) -> Vec<&PeriodDir> {
+    period_dirs.iter()
+        .enumerate()
+        .filter(|(index, _)| keep.is_none_or(|keep| *index >= keep))
+        .filter(|(_, period_dir)| older_than.is_none_or(|cutoff| period_dir.end <= cutoff))
+        .map(|(_, period_dir)| period_dir)
+        .collect()
+}
+
+/// Number of files and bytes in a folder, for the log
+fn measure_dir(storage: &dyn StorageBackend, dir: &Path) -> (usize, u64) {
+    storage.walk(dir, WalkOptions::default(), Box::new(|_| true))
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.is_file)
+        .fold((0, 0), |(file_count, bytes), entry| {
+            let size = entry.stat.or_else(|| storage.stat(&entry.path).ok()).map_or(0, |stat| stat.size);
+            (file_count + 1, bytes + size)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::GroupBy;
+    use crate::storage::MemoryStorage;
+
+    fn create_args(keep: Option<usize>, older_than: Option<&str>) -> PruneArgs {
+        PruneArgs {
+            destination: PathBuf::from("/archive"),
+            group_by: GroupBy::Month,
+            keep,
+            older_than: older_than.map(|older_than| older_than.parse().unwrap()),
+            permanent: true,
+            dry_run: false,
+        }
+    }
+
+    fn create_storage() -> MemoryStorage {
+        let date = "2025-01-10T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let storage = MemoryStorage::default();
+        for month in ["2024-11", "2024-12", "2025-01", "2025-02"] {
+            storage.add_file(format!("/archive/{month}/a.jpg"), date);
+        }
+        storage.add_file("/archive/2025-Q1/b.jpg", date);
+        storage
+    }
+
+    #[test]
+    fn test_prune_archive_keeps_most_recent_folders() {
+        let storage = create_storage();
+
+        prune_archive(&create_args(Some(2), None), &storage).unwrap();
+
+        assert!(!storage.is_dir(Path::new("/archive/2024-11")));
+        assert!(!storage.is_dir(Path::new("/archive/2024-12")));
+        assert!(storage.is_file("/archive/2025-01/a.jpg"));
+        assert!(storage.is_file("/archive/2025-02/a.jpg"));
+        assert!(storage.is_file("/archive/2025-Q1/b.jpg"));
+    }
+
+    #[test]
+    fn test_select_pruned_dirs() {
+        let storage = create_storage();
+        let period_dirs = find_period_dirs(&create_args(None, None), &storage).unwrap();
+        let select = |keep, older_than: Option<&str>| {
+            select_pruned_dirs(&period_dirs, keep, older_than.map(|older_than| older_than.parse().unwrap()))
+                .iter()
+                .map(|period_dir| period_dir.path.to_str().unwrap().to_string())
+                .collect::<Vec<_>>()
+        };
+
+        // Periods that ended by the cutoff are pruned, the ongoing one is not
+        assert_eq!(select(None, Some("2025-01-15T00:00:00Z")), ["/archive/2024-12", "/archive/2024-11"]);
+        // A folder either rule keeps is kept
+        assert_eq!(select(Some(3), Some("2025-01-15T00:00:00Z")), ["/archive/2024-11"]);
+        assert_eq!(select(Some(1), Some("2024-12-15T00:00:00Z")), ["/archive/2024-11"]);
+    }
+}
@@ -0,0 +1,111 @@
+use crate::date::{get_next_period_start, get_period_identifier, get_period_start};
+use crate::logging::SUMMARY_TARGET;
+use crate::model::{GroupBy, RegroupArgs};
+use crate::restore::{move_folder_files, MoveCounts};
+use crate::storage::StorageBackend;
+use chrono::TimeDelta;
+use color_eyre::eyre::{bail, Result};
+use std::collections::HashSet;
+use std::path::PathBuf;
+use tracing::{info, warn};
+
+/// Move the files of the `--from` period folders of an archive into the `--to` period folders their periods belong to,
+/// keeping their path below the period folder. Periods are told from the folder names, the file dates are never read,
+/// so a folder whose period spans several `--to` periods (e.g., a week across two months) is left in place.
+pub fn regroup_archive(args: &RegroupArgs, storage: &dyn StorageBackend) -> Result<()> {
+    if args.from == args.to {
+        bail!("--from and --to must be different grouping strategies");
+    }
+    if !storage.is_dir(&args.dir) {
+        bail!("Archive does not exist: {}", args.dir.display());
+    }
+
+    let mut period_dirs = Vec::new();
+    let mut split_periods = 0;
+    for entry in storage.list_dir(&args.dir)? {
+        let entry = entry?;
+        let Some(name) = entry.path.file_name().and_then(|name| name.to_str()).filter(|_| entry.is_dir) else {
+            continue;
+        };
+        if get_period_start(args.from, name).is_none() {
+            info!(path = %entry.path.display(), "Leaving {} alone: not a {:?} folder", entry.path.display(), args.from);
+            continue;
+        }
+        match get_regrouped_identifier(args.from, args.to, name) {
+            Some(identifier) => period_dirs.push((entry.path, identifier)),
+            None => {
+                warn!(path = %entry.path.display(), "Leaving {} in place: it spans several {:?} periods, only the file dates can tell them apart", entry.path.display(), args.to);
+                split_periods += 1;
+            }
+        }
+    }
+    if period_dirs.is_empty() && split_periods == 0 {
+        bail!("No {:?} folders found in {}", args.from, args.dir.display());
+    }
+    period_dirs.sort();
+    info!("Regrouping {} {:?} folder(s) of {} by {:?}", period_dirs.len(), args.from, args.dir.display(), args.to);
+
+    let mut counts = MoveCounts::default();
+    let mut destinations = HashSet::<PathBuf>::new();
+    for (period_dir, identifier) in &period_dirs {
+        move_folder_files(storage, period_dir, &args.dir.join(identifier), args.dry_run, &mut destinations, &mut counts);
+    }
+
+    let verb = if args.dry_run { "would have been regrouped" } else { "regrouped" };
+    if counts.kept + counts.failed + split_periods > 0 {
+        bail!(
+            "{} file(s) {}, {} kept in place to not replace another file, {} failed, {} folder(s) spanning several periods left in place",
+            counts.moved, verb, counts.kept, counts.failed, split_periods
+        );
+    }
+    info!(target: SUMMARY_TARGET, "{} file(s) {} by {:?} in {}", counts.moved, verb, args.to, args.dir.display());
+    Ok(())
+}
+
+/// Identifier of the `to` period holding the whole `from` period an identifier names, if a single one does
+fn get_regrouped_identifier(from: GroupBy, to: GroupBy, identifier: &str) -> Option<String> {
+    let start = get_period_start(from, identifier)?;
+    let last_day = get_next_period_start(from, start) - TimeDelta::days(1);
+    let regrouped_identifier = get_period_identifier(to, start);
+    (get_period_identifier(to, last_day) == regrouped_identifier).then_some(regrouped_identifier)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::MemoryStorage;
+    use chrono::{DateTime, Utc};
+    use std::path::Path;
+
+    #[test]
+    fn test_get_regrouped_identifier() {
+        assert_eq!(get_regrouped_identifier(GroupBy::Month, GroupBy::Trimester, "2025-05").as_deref(), Some("2025-Q2"));
+        assert_eq!(get_regrouped_identifier(GroupBy::Trimester, GroupBy::Year, "2025-Q4").as_deref(), Some("2025"));
+        assert_eq!(get_regrouped_identifier(GroupBy::Week, GroupBy::Biweekly, "2025-W04").as_deref(), Some("2025-BW02"));
+        assert_eq!(get_regrouped_identifier(GroupBy::Week, GroupBy::Month, "2025-W02").as_deref(), Some("2025-01"));
+        // Weeks across two months, and months split into weeks, only split by file date
+        assert_eq!(get_regrouped_identifier(GroupBy::Week, GroupBy::Month, "2025-W05"), None);
+        assert_eq!(get_regrouped_identifier(GroupBy::Month, GroupBy::Week, "2025-06"), None);
+        assert_eq!(get_regrouped_identifier(GroupBy::Trimester, GroupBy::Quadrimester, "2025-Q2"), None);
+    }
+
+    #[test]
+    fn test_regroup_archive() {
+        let date = "2025-01-10T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let storage = MemoryStorage::default();
+        storage.add_file("/archive/2025-01/trip/a.jpg", date);
+        storage.add_file("/archive/2025-03/b.jpg", date);
+        storage.add_file("/archive/2025-04/c.jpg", date);
+        storage.add_file("/archive/unsorted/d.jpg", date);
+        let args = RegroupArgs { dir: PathBuf::from("/archive"), from: GroupBy::Month, to: GroupBy::Trimester, dry_run: false };
+
+        regroup_archive(&args, &storage).unwrap();
+
+        assert!(storage.is_file("/archive/2025-Q1/trip/a.jpg"));
+        assert!(storage.is_file("/archive/2025-Q1/b.jpg"));
+        assert!(storage.is_file("/archive/2025-Q2/c.jpg"));
+        assert!(storage.is_file("/archive/unsorted/d.jpg"));
+        assert!(!storage.is_dir(Path::new("/archive/2025-01")));
+        assert!(!storage.is_dir(Path::new("/archive/2025-04")));
+    }
+}
@@ -0,0 +1,374 @@
+use crate::logging::SUMMARY_TARGET;
+use crate::shorten::ShortenedPath;
+use crate::storage::available_space;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::Duration;
+use tracing::{info, warn};
+
+/// Bucket used for the files of a run without `--group-by`
+pub const UNGROUPED_PERIOD: &str = "(ungrouped)";
+
+/// Files and bytes moved into a single destination period folder
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct PeriodStats {
+    pub files: usize,
+    pub bytes: u64,
+}
+
+/// Files and bytes copied into a `--mirror`, and the files whose copy failed
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct MirrorStats {
+    pub files: usize,
+    pub bytes: u64,
+    pub failed: usize,
+}
+
+/// Statistics printed at the end of a run
+#[derive(Debug)]
+pub struct RunStatistics<'a> {
+    pub periods: &'a BTreeMap<String, PeriodStats>,
+    pub skipped: usize,
+    pub failed: usize,
+    pub elapsed: Duration,
+    /// Time spent moving the files, for the throughput of real runs (none for dry runs). It includes copying them into
+    /// the mirrors first.
+    pub moving: Option<Duration>,
+    pub shortened: &'a [ShortenedPath],
+    pub mirrors: &'a BTreeMap<PathBuf, MirrorStats>,
+}
+
+/// Time spent in each phase of a run (`--timings`)
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct PhaseTimings {
+    /// Walking the source folder and skipping ignored paths
+    pub scan: Duration,
+    /// Reading file timestamps (on the `--jobs` threads) and filtering on them
+    pub date_extraction: Duration,
+    pub plan: Duration,
+    pub move_files: Duration,
+    pub cleanup: Duration,
+}
+
+/// How a run that did not hit a fatal error ended, reported to the caller through the exit code
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RunOutcome {
+    /// Every matching file was moved
+    Success,
+    /// The run completed, but some files could not be evaluated or moved
+    CompletedWithFailures,
+    /// No file matched the filters
+    NothingMatched,
+    /// The run was cancelled (e.g., by Ctrl-C) and stopped between files
+    Cancelled,
+}
+
+impl RunOutcome {
+    /// Exit code of the outcome (1 is reserved for fatal errors, which are returned as `Err` from `main`)
+    pub fn exit_code(self) -> ExitCode {
+        match self {
+            RunOutcome::Success => ExitCode::SUCCESS,
+            RunOutcome::CompletedWithFailures => ExitCode::from(2),
+            RunOutcome::NothingMatched => ExitCode::from(3),
+            // As shells report a process stopped by Ctrl-C (SIGINT)
+            RunOutcome::Cancelled => ExitCode::from(130),
+        }
+    }
+}
+
+/// Overall outcome of several runs: cancellation wins over failures, failures over success, and nothing matched only if
+/// no run matched anything
+pub fn combine_run_outcomes(outcomes: &[RunOutcome]) -> RunOutcome {
+    if outcomes.contains(&RunOutcome::Cancelled) {
+        RunOutcome::Cancelled
+    } else if outcomes.contains(&RunOutcome::CompletedWithFailures) {
+        RunOutcome::CompletedWithFailures
+    } else if outcomes.iter().all(|outcome| *outcome == RunOutcome::NothingMatched) {
+        RunOutcome::NothingMatched
+    } else {
+        RunOutcome::Success
+    }
+}
+
+pub fn get_run_outcome(planned: usize, failed: usize) -> RunOutcome {
+    if failed > 0 {
+        RunOutcome::CompletedWithFailures
+    } else if planned == 0 {
+        RunOutcome::NothingMatched
+    } else {
+        RunOutcome::Success
+    }
+}
+
+pub fn print_run_statistics(statistics: &RunStatistics) {
+    for line in render_run_statistics(statistics) {
+        info!(target: SUMMARY_TARGET, "{}", line);
+    }
+}
+
+fn render_run_statistics(statistics: &RunStatistics) -> Vec<String> {
+    let mut lines = vec![String::new(), "Summary:".to_string()];
+
+    if !statistics.periods.is_empty() {
+        let period_width = statistics.periods.keys()
+            .map(|period| period.chars().count())
+            .chain(["Period".len(), "Total".len()])
+            .max()
+            .unwrap_or_default();
+        let total_files = statistics.periods.values().map(|stats| stats.files).sum::<usize>();
+        let total_bytes = statistics.periods.values().map(|stats| stats.bytes).sum::<u64>();
+
+        lines.push(format!("{:<period_width$}  {:>7}  {:>10}", "Period", "Files", "Size"));
+        for (period, stats) in statistics.periods {
+            lines.push(format!("{:<period_width$}  {:>7}  {:>10}", period, stats.files, format_size(stats.bytes)));
+        }
+        lines.push(format!("{:<period_width$}  {:>7}  {:>10}", "Total", total_files, format_size(total_bytes)));
+        if let Some(moving) = statistics.moving {
+            lines.push(format!(
+                "Moved {} in {} ({}{})",
+                format_size(total_bytes), format_seconds(moving), format_throughput(total_bytes, moving),
+                if statistics.mirrors.is_empty() { "" } else { ", including the copies into the mirrors" }
+            ));
+        }
+    }
+
+    for (mirror, stats) in statistics.mirrors {
+        let failed = if stats.failed > 0 { format!(", {} failed", stats.failed) } else { String::new() };
+        lines.push(format!("Mirror {}: {} file(s) ({}){}", mirror.display(), stats.files, format_size(stats.bytes), failed));
+    }
+
+    // Sub-millisecond precision is just noise for a run summary
+    let elapsed = Duration::from_millis(statistics.elapsed.as_millis() as u64);
+    lines.push(format!(
+        "Skipped: {}, failed: {}, elapsed: {}",
+        statistics.skipped,
+        statistics.failed,
+        humantime::format_duration(elapsed)
+    ));
+    if !statistics.shortened.is_empty() {
+        lines.push(format!("Shortened {} destination(s) past the path limits:", statistics.shortened.len()));
+        for shortened in statistics.shortened {
+            lines.push(format!("  {}\n    ↳ {}", shortened.original.display(), shortened.shortened.display()));
+        }
+    }
+    lines
+}
+
+/// Print the bytes a dry run would move into each period folder of each destination, along with the free space of the
+/// destination's volume, warning about the destinations that would run out of room
+pub fn print_disk_usage(destination_periods: &BTreeMap<PathBuf, BTreeMap<String, PeriodStats>>) {
+    let available = destination_periods.keys()
+        .map(|destination| {
+            let space = available_space(destination)
+                .inspect_err(|e| warn!(path = %destination.display(), error = %e, "Failed to read the free space of {}: {}", destination.display(), e))
+                .ok();
+            (destination.clone(), space)
+        })
+        .collect::<BTreeMap<_, _>>();
+    for line in render_disk_usage(destination_periods, &available) {
+        info!(target: SUMMARY_TARGET, "{}", line);
+    }
+    for (destination, periods) in destination_periods {
+        let planned = periods.values().map(|stats| stats.bytes).sum::<u64>();
+        if let Some(Some(space)) = available.get(destination)
+            && planned > *space {
+                warn!(
+                    path = %destination.display(),
+                    "{} would be moved into {}, which only has {} free", format_size(planned), destination.display(), format_size(*space)
+                );
+            }
+    }
+}
+
+fn render_disk_usage(destination_periods: &BTreeMap<PathBuf, BTreeMap<String, PeriodStats>>, available: &BTreeMap<PathBuf, Option<u64>>) -> Vec<String> {
+    // The files of a run without `--group-by` land in the destination itself
+    let get_folder = |destination: &Path, period: &str| {
+        if period == UNGROUPED_PERIOD { destination.to_path_buf() } else { destination.join(period) }
+    };
+    let folder_width = destination_periods.iter()
+        .flat_map(|(destination, periods)| periods.keys().map(|period| get_folder(destination, period).display().to_string().chars().count()))
+        .chain(["Folder".len()])
+        .max()
+        .unwrap_or_default();
+
+    let mut lines = vec![String::new(), "Estimated disk usage:".to_string()];
+    lines.push(format!("{:<folder_width$}  {:>7}  {:>10}", "Folder", "Files", "Size"));
+    for (destination, periods) in destination_periods {
+        for (period, stats) in periods {
+            lines.push(format!("{:<folder_width$}  {:>7}  {:>10}", get_folder(destination, period).display(), stats.files, format_size(stats.bytes)));
+        }
+    }
+    for (destination, periods) in destination_periods {
+        let planned = periods.values().map(|stats| stats.bytes).sum::<u64>();
+        let free = match available.get(destination).copied().flatten() {
+            Some(space) if planned > space => format!("{} free, {} short", format_size(space), format_size(planned - space)),
+            Some(space) => format!("{} free", format_size(space)),
+            None => "free space unknown".to_string(),
+        };
+        lines.push(format!("{}: {} planned, {}", destination.display(), format_size(planned), free));
+    }
+    lines
+}
+
+pub fn print_phase_timings(timings: &PhaseTimings) {
+    for line in render_phase_timings(timings) {
+        info!(target: SUMMARY_TARGET, "{}", line);
+    }
+}
+
+fn render_phase_timings(timings: &PhaseTimings) -> Vec<String> {
+    let phases = [
+        ("Scanning", timings.scan),
+        ("Date extraction", timings.date_extraction),
+        ("Planning", timings.plan),
+        ("Moving", timings.move_files),
+        ("Cleanup", timings.cleanup),
+    ];
+    let total = phases.iter().map(|(_, duration)| *duration).sum::<Duration>();
+
+    let mut lines = vec![String::new(), "Timings:".to_string()];
+    for (phase, duration) in phases.into_iter().chain([("Total", total)]) {
+        let percentage = if total.is_zero() { 0.0 } else { duration.as_secs_f64() / total.as_secs_f64() * 100.0 };
+        lines.push(format!("{:<15}  {:>10.3}s  {:>5.1}%", phase, duration.as_secs_f64(), percentage));
+    }
+    lines
+}
+
+/// Format a duration to the millisecond (e.g., "1.234s")
+fn format_seconds(duration: Duration) -> String {
+    format!("{:.3}s", duration.as_secs_f64())
+}
+
+/// Format the average rate of moving `bytes` in `duration` (e.g., "12.5 MiB/s"). Moves within the same filesystem are
+/// renames, so their rate mostly tells how fast the file system updates its folders.
+fn format_throughput(bytes: u64, duration: Duration) -> String {
+    // Below a millisecond the rate is noise, a floor keeps it finite
+    let seconds = duration.as_secs_f64().max(0.001);
+    format!("{}/s", format_size((bytes as f64 / seconds) as u64))
+}
+
+/// Format a byte count using binary units (e.g., "1.5 MiB")
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+
+    let mut size = bytes as f64;
+    let mut unit_index = 0;
+    while size >= 1024.0 && unit_index < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit_index += 1;
+    }
+
+    if unit_index == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit_index])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_get_run_outcome() {
+        assert_eq!(get_run_outcome(5, 0), RunOutcome::Success);
+        assert_eq!(get_run_outcome(5, 1), RunOutcome::CompletedWithFailures);
+        assert_eq!(get_run_outcome(0, 2), RunOutcome::CompletedWithFailures);
+        assert_eq!(get_run_outcome(0, 0), RunOutcome::NothingMatched);
+    }
+
+    #[test]
+    fn test_combine_run_outcomes() {
+        use RunOutcome::*;
+        assert_eq!(combine_run_outcomes(&[Success, NothingMatched]), Success);
+        assert_eq!(combine_run_outcomes(&[Success, CompletedWithFailures, NothingMatched]), CompletedWithFailures);
+        assert_eq!(combine_run_outcomes(&[NothingMatched, NothingMatched]), NothingMatched);
+        assert_eq!(combine_run_outcomes(&[CompletedWithFailures, Cancelled, Success]), Cancelled);
+    }
+
+    #[test]
+    fn test_format_size() {
+        assert_eq!(format_size(0), "0 B");
+        assert_eq!(format_size(1023), "1023 B");
+        assert_eq!(format_size(1536), "1.5 KiB");
+        assert_eq!(format_size(5 * 1024 * 1024), "5.0 MiB");
+        assert_eq!(format_size(3 * 1024 * 1024 * 1024 * 1024 * 1024), "3072.0 TiB");
+    }
+
+    #[test]
+    fn test_render_phase_timings() {
+        let timings = PhaseTimings {
+            scan: Duration::from_millis(250),
+            date_extraction: Duration::from_millis(500),
+            plan: Duration::ZERO,
+            move_files: Duration::from_millis(1250),
+            cleanup: Duration::ZERO,
+        };
+
+        assert_eq!(render_phase_timings(&timings), vec![
+            "".to_string(),
+            "Timings:".to_string(),
+            "Scanning              0.250s   12.5%".to_string(),
+            "Date extraction       0.500s   25.0%".to_string(),
+            "Planning              0.000s    0.0%".to_string(),
+            "Moving                1.250s   62.5%".to_string(),
+            "Cleanup               0.000s    0.0%".to_string(),
+            "Total                 2.000s  100.0%".to_string(),
+        ]);
+    }
+
+    #[test]
+    fn test_render_run_statistics() {
+        let periods = BTreeMap::from([
+            ("2025-05".to_string(), PeriodStats { files: 2, bytes: 2048 }),
+            ("2025-06".to_string(), PeriodStats { files: 1, bytes: 100 }),
+        ]);
+        let shortened = [ShortenedPath {
+            source: PathBuf::from("/notes/long name.md"),
+            original: PathBuf::from("/archive/2025-05/long name.md"),
+            shortened: PathBuf::from("/archive/2025-05/long~0123abcd.md"),
+        }];
+        let mirrors = BTreeMap::from([(PathBuf::from("/media/backup"), MirrorStats { files: 2, bytes: 2048, failed: 1 })]);
+        let statistics = RunStatistics { periods: &periods, skipped: 4, failed: 1, elapsed: Duration::from_micros(1_234_567), moving: Some(Duration::from_millis(500)), shortened: &shortened, mirrors: &mirrors };
+
+        assert_eq!(render_run_statistics(&statistics), vec![
+            "".to_string(),
+            "Summary:".to_string(),
+            "Period     Files        Size".to_string(),
+            "2025-05        2     2.0 KiB".to_string(),
+            "2025-06        1       100 B".to_string(),
+            "Total          3     2.1 KiB".to_string(),
+            "Moved 2.1 KiB in 0.500s (4.2 KiB/s, including the copies into the mirrors)".to_string(),
+            "Mirror /media/backup: 2 file(s) (2.0 KiB), 1 failed".to_string(),
+            "Skipped: 4, failed: 1, elapsed: 1s 234ms".to_string(),
+            "Shortened 1 destination(s) past the path limits:".to_string(),
+            "  /archive/2025-05/long name.md\n    ↳ /archive/2025-05/long~0123abcd.md".to_string(),
+        ]);
+    }
+
+    #[test]
+    fn test_render_disk_usage() {
+        let destination_periods = BTreeMap::from([
+            (PathBuf::from("/archive"), BTreeMap::from([
+                ("2023-Q3".to_string(), PeriodStats { files: 2, bytes: 3 << 30 }),
+                ("2023-Q4".to_string(), PeriodStats { files: 12, bytes: 180 << 30 }),
+            ])),
+            (PathBuf::from("/photos"), BTreeMap::from([(UNGROUPED_PERIOD.to_string(), PeriodStats { files: 1, bytes: 100 })])),
+        ]);
+        let available = BTreeMap::from([(PathBuf::from("/archive"), Some(120 << 30)), (PathBuf::from("/photos"), None)]);
+
+        assert_eq!(render_disk_usage(&destination_periods, &available), vec![
+            "".to_string(),
+            "Estimated disk usage:".to_string(),
+            "Folder              Files        Size".to_string(),
+            "/archive/2023-Q3        2     3.0 GiB".to_string(),
+            "/archive/2023-Q4       12   180.0 GiB".to_string(),
+            "/photos                 1       100 B".to_string(),
+            "/archive: 183.0 GiB planned, 120.0 GiB free, 63.0 GiB short".to_string(),
+            "/photos: 100 B planned, free space unknown".to_string(),
+        ]);
+    }
+}
@@ -0,0 +1,173 @@
+use crate::date::is_period_identifier;
+use crate::logging::SUMMARY_TARGET;
+use crate::model::RestoreArgs;
+use crate::storage::{StorageBackend, WalkOptions};
+use color_eyre::eyre::{bail, Result};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use tracing::{debug, error, info, warn};
+
+/// Move the files of the period folders of an archive back into a folder, at their path below their period folder:
+/// the inverse of a grouped run. A file that would replace another, including one restored from another period, stays
+/// in the archive.
+pub fn restore_archive(args: &RestoreArgs, storage: &dyn StorageBackend) -> Result<()> {
+    if !storage.is_dir(&args.from) {
+        bail!("Archive does not exist: {}", args.from.display());
+    }
+    let period_dirs = find_period_dirs(storage, &args.from)?;
+    if period_dirs.is_empty() {
+        bail!("No period folders (e.g., 2025-06, 2025-Q2) found in {}", args.from.display());
+    }
+    info!("Restoring {} period folder(s) from {} into {}", period_dirs.len(), args.from.display(), args.to.display());
+
+    let mut counts = MoveCounts::default();
+    let mut destinations = HashSet::new();
+    for period_dir in &period_dirs {
+        move_folder_files(storage, period_dir, &args.to, args.dry_run, &mut destinations, &mut counts);
+    }
+
+    let verb = if args.dry_run { "would have been restored" } else { "restored" };
+    if counts.kept + counts.failed > 0 {
+        bail!("{} file(s) {}, {} kept in the archive to not replace another file, {} failed", counts.moved, verb, counts.kept, counts.failed);
+    }
+    info!(target: SUMMARY_TARGET, "{} file(s) {} into {}", counts.moved, verb, args.to.display());
+    Ok(())
+}
+
+/// Files moved, kept in place and failed to move by `move_folder_files()`
+#[derive(Debug, Default)]
+pub struct MoveCounts {
+    pub moved: usize,
+    pub kept: usize,
+    pub failed: usize,
+}
+
+/// Move the files of a folder to the same path relative to `destination_dir`, then delete the folders left empty. A
+/// file whose destination exists, or is in `destinations` as another file was moved there, is kept in place.
+pub fn move_folder_files(
+    storage: &dyn StorageBackend,
+    folder: &Path,
+    destination_dir: &Path,
+    dry_run: bool,
+    destinations: &mut HashSet<PathBuf>,
+    counts: &mut MoveCounts,
+) {
+    let status = if dry_run { "dry-run" } else { "moved" };
+    let walk = storage.walk(folder, WalkOptions { sorted: true, ..WalkOptions::default() }, Box::new(|_| true));
+    for entry in walk {
+        let entry = match entry {
+            Ok(entry) if entry.is_dir => continue,
+            Ok(entry) => entry,
+            Err(e) => {
+                error!(error = %e, "Failed to walk {}: {}", folder.display(), e);
+                counts.failed += 1;
+                continue;
+            }
+        };
+        // Safe to unwrap: walked entries are inside the walked folder
+        let destination = destination_dir.join(entry.path.strip_prefix(folder).unwrap());
+        if is_taken(storage, &destination) || !destinations.insert(destination.clone()) {
+            warn!(path = %entry.path.display(), "Keeping {} in place: {} already exists", entry.path.display(), destination.display());
+            counts.kept += 1;
+            continue;
+        }
+        if !dry_run
+            && let Err(e) = move_file(storage, &entry.path, &destination) {
+                error!(path = %entry.path.display(), error = %e, "Moving file {}: {}", entry.path.display(), e);
+                counts.failed += 1;
+                continue;
+            }
+        counts.moved += 1;
+        info!(
+            path = %entry.path.display(), destination = %destination.display(), status,
+            "{}. {}\n       ↳ {}", counts.moved, entry.path.display(), destination.parent().unwrap_or(&destination).display()
+        );
+    }
+    if !dry_run {
+        delete_empty_dirs(storage, folder);
+    }
+}
+
+/// Folders directly inside the archive named after a period, in name order. The other entries are left alone.
+fn find_period_dirs(storage: &dyn StorageBackend, archive: &Path) -> Result<Vec<PathBuf>> {
+    let mut period_dirs = Vec::new();
+    for entry in storage.list_dir(archive)? {
+        let entry = entry?;
+        let is_period_dir = entry.is_dir && entry.path.file_name().and_then(|name| name.to_str()).is_some_and(is_period_identifier);
+        if is_period_dir {
+            period_dirs.push(entry.path);
+        } else {
+            info!(path = %entry.path.display(), "Leaving {} alone: not a period folder", entry.path.display());
+        }
+    }
+    period_dirs.sort();
+    Ok(period_dirs)
+}
+
+fn is_taken(storage: &dyn StorageBackend, path: &Path) -> bool {
+    storage.is_dir(path) || storage.stat(path).is_ok()
+}
+
+fn move_file(storage: &dyn StorageBackend, source: &Path, destination: &Path) -> std::io::Result<()> {
+    if let Some(parent) = destination.parent() {
+        storage.create_dir_all(parent)?;
+    }
+    storage.rename(source, destination)
+}
+
+/// Delete a folder and its subfolders that are empty, deepest first
+pub fn delete_empty_dirs(storage: &dyn StorageBackend, root: &Path) {
+    let dirs = storage.walk(root, WalkOptions { sorted: true, ..WalkOptions::default() }, Box::new(|entry| entry.is_dir))
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path)
+        .collect::<Vec<_>>();
+    for dir in dirs.iter().rev() {
+        // Folders still holding files kept in the archive fail to be deleted, and stay
+        if storage.remove_dir(dir).is_ok() {
+            debug!(path = %dir.display(), "Deleted empty folder: {}", dir.display());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::MemoryStorage;
+    use chrono::{DateTime, Utc};
+
+    #[test]
+    fn test_restore_archive() {
+        let date = "2025-01-10T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let storage = MemoryStorage::default();
+        storage.add_file("/archive/2025-01/notes/a.md", date);
+        storage.add_file("/archive/2025-02/notes/b.md", date);
+        storage.add_file("/archive/2025-02/c.md", date);
+        storage.add_file("/archive/photos/d.jpg", date);
+        storage.add_dir("/restored");
+        let args = RestoreArgs { from: PathBuf::from("/archive"), to: PathBuf::from("/restored"), dry_run: false };
+
+        restore_archive(&args, &storage).unwrap();
+
+        assert!(storage.is_file("/restored/notes/a.md"));
+        assert!(storage.is_file("/restored/notes/b.md"));
+        assert!(storage.is_file("/restored/c.md"));
+        assert!(storage.is_file("/archive/photos/d.jpg"));
+        assert!(!storage.is_dir(Path::new("/archive/2025-01")));
+        assert!(!storage.is_dir(Path::new("/archive/2025-02")));
+    }
+
+    #[test]
+    fn test_restore_archive_keeps_conflicting_files() {
+        let date = "2025-01-10T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let storage = MemoryStorage::default();
+        storage.add_file("/archive/2025-01/report.pdf", date);
+        storage.add_file("/archive/2025-02/report.pdf", date);
+        let args = RestoreArgs { from: PathBuf::from("/archive"), to: PathBuf::from("/restored"), dry_run: false };
+
+        assert!(restore_archive(&args, &storage).is_err());
+
+        assert!(storage.is_file("/restored/report.pdf"));
+        assert!(!storage.is_file("/archive/2025-01/report.pdf"));
+        assert!(storage.is_file("/archive/2025-02/report.pdf"));
+    }
+}
@@ -0,0 +1,121 @@
+use crate::file::{get_source_root, FileToMove};
+use crate::model::Args;
+use color_eyre::eyre::{Context, Result};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+/// File naming the source and destination folders of each list, one `list<TAB>source<TAB>destination` line per list
+const MAPPING_FILE_NAME: &str = "mapping.tsv";
+
+/// Write the plan as rsync `--files-from` lists into `dir`: one list per source folder and destination folder, holding
+/// the paths of its files relative to the source folder, plus the mapping file. Each list can then be copied with
+/// `rsync -a --files-from=<list> <source>/ <host>:<destination>/`. Returns the number of lists.
+pub fn write_rsync_plan(dir: &Path, args: &Args, files_to_move: &[FileToMove]) -> Result<usize> {
+    let lists = group_by_folders(args, files_to_move);
+    fs::create_dir_all(dir).with_context(|| format!("Failed to create plan directory: {}", dir.display()))?;
+
+    let mut mapping = Vec::new();
+    for (index, ((source, destination), relative_paths)) in lists.iter().enumerate() {
+        let list_name = format!("files-{:03}.txt", index + 1);
+        let mut list = Vec::new();
+        for relative_path in relative_paths {
+            list.extend(to_rsync_path(relative_path));
+            list.push(b'\n');
+        }
+        let list_path = dir.join(&list_name);
+        fs::write(&list_path, list).with_context(|| format!("Failed to write rsync file list: {}", list_path.display()))?;
+
+        // Raw bytes, as in `write_nul_delimited_plan()`
+        mapping.extend(list_name.as_bytes());
+        mapping.push(b'\t');
+        mapping.extend(source.as_os_str().as_encoded_bytes());
+        mapping.push(b'\t');
+        mapping.extend(destination.as_os_str().as_encoded_bytes());
+        mapping.push(b'\n');
+    }
+    let mapping_path = dir.join(MAPPING_FILE_NAME);
+    fs::write(&mapping_path, mapping).with_context(|| format!("Failed to write rsync mapping: {}", mapping_path.display()))?;
+
+    info!("Wrote {} rsync file list(s) and their mapping to {}", lists.len(), dir.display());
+    Ok(lists.len())
+}
+
+/// Paths of the planned files relative to their source folder, by source folder and destination folder. Files rsync
+/// can't copy to their planned destination, renamed by `--normalize-unicode`, `--sanitize-names` or `--shorten-paths`
+/// or with a newline in their path, are left out with a warning.
+fn group_by_folders<'a>(args: &'a Args, files_to_move: &'a [FileToMove]) -> BTreeMap<(&'a Path, PathBuf), Vec<&'a Path>> {
+    let mut lists = BTreeMap::<_, Vec<_>>::new();
+    for file in files_to_move {
+        let source_root = get_source_root(args, &file.source);
+        // Safe to unwrap: planned files are inside the source they were found in
+        let relative_path = file.source.strip_prefix(source_root).unwrap();
+        let destination_folder = get_destination_folder(&file.destination, relative_path)
+            .filter(|_| !file.source.as_os_str().as_encoded_bytes().contains(&b'\n'));
+        match destination_folder {
+            Some(destination_folder) => lists.entry((source_root, destination_folder)).or_default().push(relative_path),
+            None => warn!(path = %file.source.display(), "Leaving {} out of the rsync plan: rsync can't copy it to {}", file.source.display(), file.destination.display()),
+        }
+    }
+    lists
+}
+
+/// Folder a file must be copied to with its relative path to land at `destination`, if its relative path was kept
+fn get_destination_folder(destination: &Path, relative_path: &Path) -> Option<PathBuf> {
+    if !destination.ends_with(relative_path) {
+        return None;
+    }
+    let mut destination_folder = destination.to_path_buf();
+    for _ in relative_path.components() {
+        destination_folder.pop();
+    }
+    Some(destination_folder)
+}
+
+/// Relative path with the `/` separators rsync expects
+fn to_rsync_path(relative_path: &Path) -> Vec<u8> {
+    let components = relative_path.components().map(|component| component.as_os_str().as_encoded_bytes()).collect::<Vec<_>>();
+    components.join(&b'/')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::Cli;
+    use chrono::{DateTime, Utc};
+    use clap::Parser;
+
+    fn create_file_to_move(source: &str, destination: &str) -> FileToMove {
+        FileToMove {
+            source: PathBuf::from(source),
+            destination: PathBuf::from(destination),
+            file_date: DateTime::<Utc>::UNIX_EPOCH,
+            size: 0,
+            period: None,
+        }
+    }
+
+    #[test]
+    fn test_group_by_folders() {
+        let args = Cli::parse_from(["chronomover", "-s", "/photos", "-s", "/notes", "-d", "/archive"]).args.unwrap();
+        let files = vec![
+            create_file_to_move("/photos/trip/a.jpg", "/archive/2025-01/trip/a.jpg"),
+            create_file_to_move("/notes/b.md", "/archive/2025-01/b.md"),
+            create_file_to_move("/photos/c.jpg", "/archive/2025-01/c.jpg"),
+            create_file_to_move("/photos/d.jpg", "/archive/2025-02/d.jpg"),
+            create_file_to_move("/photos/Cafe\u{301}.jpg", "/archive/2025-02/Caf\u{e9}.jpg"),
+        ];
+
+        let lists = group_by_folders(&args, &files);
+
+        let lists = lists.iter()
+            .map(|((source, destination), paths)| (source.to_str().unwrap(), destination.to_str().unwrap(), paths.iter().map(|path| path.to_str().unwrap()).collect::<Vec<_>>()))
+            .collect::<Vec<_>>();
+        assert_eq!(lists, vec![
+            ("/notes", "/archive/2025-01", vec!["b.md"]),
+            ("/photos", "/archive/2025-01", vec!["trip/a.jpg", "c.jpg"]),
+            ("/photos", "/archive/2025-02", vec!["d.jpg"]),
+        ]);
+    }
+}
@@ -0,0 +1,213 @@
+use crate::config::DirSettings;
+use crate::date::{get_next_period_start, get_period_identifier};
+use crate::model::GroupBy;
+use chrono::{DateTime, TimeDelta, Utc};
+use color_eyre::eyre::{Context, ContextCompat, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+/// What an incremental run remembers about a source folder
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct DirState {
+    /// Modification time of the folder, which changes whenever a file is added, removed or renamed in it
+    modified: DateTime<Utc>,
+    /// Grouping and filters the folder was evaluated with
+    settings: String,
+    /// When the first file left in the folder becomes old enough to move, `None` if none was left
+    next_due: Option<DateTime<Utc>>,
+}
+
+/// Source folders seen by the last incremental run (`--incremental`). The files of a folder that didn't change since
+/// then, and whose files left behind aren't old enough to move yet, are skipped without reading their metadata.
+/// Editing a file only makes it newer, so it can't become due earlier than recorded.
+#[derive(Debug, Default)]
+pub struct ScanState {
+    path: PathBuf,
+    previous: HashMap<PathBuf, DirState>,
+    /// Whether each folder checked by this run was unchanged, in which case its previous state is kept
+    checked: HashMap<PathBuf, bool>,
+    /// Settings and next due time of the folders evaluated by this run
+    evaluated: HashMap<PathBuf, (String, Option<DateTime<Utc>>)>,
+    /// Folders with files that failed or were kept in place, evaluated again by the next run
+    unsettled: HashSet<PathBuf>,
+    next_period_starts: HashMap<(GroupBy, String), DateTime<Utc>>,
+}
+
+impl ScanState {
+    /// Load the state of the last incremental run with the same sources and destination, if any
+    pub fn load(sources: &[PathBuf], destination: &Path) -> Result<Self> {
+        let path = get_scan_state_path(sources, destination)?;
+        let previous = match fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                warn!("Ignoring invalid scan state {}: {}", path.display(), e);
+                HashMap::new()
+            }),
+            Err(_) => HashMap::new(),
+        };
+        Ok(Self { path, previous, ..Self::default() })
+    }
+
+    /// Check whether the files of a folder can be skipped, because nothing changed since the last run
+    pub fn is_unchanged(&mut self, dir: &Path, settings: &DirSettings, now: DateTime<Utc>) -> bool {
+        if let Some(&unchanged) = self.checked.get(dir) {
+            return unchanged;
+        }
+        let unchanged = self.previous.get(dir).is_some_and(|state| {
+            state.settings == get_settings_fingerprint(settings, now)
+                && state.next_due.is_none_or(|next_due| now < next_due)
+                && read_modified(dir).is_some_and(|modified| modified == state.modified)
+        });
+        self.checked.insert(dir.to_path_buf(), unchanged);
+        unchanged
+    }
+
+    /// Record a file of the folder that is planned to move
+    pub fn record_planned(&mut self, dir: &Path, settings: &DirSettings, now: DateTime<Utc>) {
+        self.evaluated.entry(dir.to_path_buf()).or_insert_with(|| (get_settings_fingerprint(settings, now), None));
+    }
+
+    /// Record a file of the folder excluded by the filters, which makes the folder due once the file is old enough
+    pub fn record_skipped(&mut self, dir: &Path, settings: &DirSettings, file_date: DateTime<Utc>, now: DateTime<Utc>) {
+        let eligible_at = self.get_eligible_at(settings, file_date, now);
+        let (_, next_due) = self.evaluated.entry(dir.to_path_buf())
+            .or_insert_with(|| (get_settings_fingerprint(settings, now), None));
+        *next_due = Some(next_due.map_or(eligible_at, |next_due| next_due.min(eligible_at)));
+    }
+
+    /// Make the next run evaluate the folder of a file again (e.g., because the file failed)
+    pub fn mark_unsettled(&mut self, file: &Path) {
+        if let Some(dir) = file.parent() {
+            self.unsettled.insert(dir.to_path_buf());
+        }
+    }
+
+    /// Save the folders of this run, reading their modification time after the moves and the cleanup changed them
+    pub fn save(&self) -> Result<()> {
+        let unchanged_dirs = self.checked.iter()
+            .filter(|(_, unchanged)| **unchanged)
+            .filter_map(|(dir, _)| self.previous.get(dir).map(|state| (dir.clone(), state.clone())));
+        let evaluated_dirs = self.evaluated.iter()
+            .filter(|(dir, _)| !self.unsettled.contains(*dir))
+            .filter_map(|(dir, (settings, next_due))| {
+                let modified = read_modified(dir)?;
+                Some((dir.clone(), DirState { modified, settings: settings.clone(), next_due: *next_due }))
+            });
+        // JSON keys must be strings, so folders with non UTF-8 names are simply evaluated every time
+        let state = unchanged_dirs.chain(evaluated_dirs)
+            .filter(|(dir, _)| dir.to_str().is_some())
+            .collect::<HashMap<_, _>>();
+
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create scan state directory: {}", parent.display()))?;
+        }
+        let content = serde_json::to_string(&state).context("Failed to serialize scan state")?;
+        fs::write(&self.path, content).with_context(|| format!("Failed to write scan state: {}", self.path.display()))
+    }
+
+    /// Earliest time at which a file excluded by the filters could pass them. An absolute `--older-than` date is
+    /// treated like a relative one, which only makes its folder be evaluated again too early.
+    fn get_eligible_at(&mut self, settings: &DirSettings, file_date: DateTime<Utc>, now: DateTime<Utc>) -> DateTime<Utc> {
+        let mut eligible_at = file_date;
+        if let Some(cutoff) = settings.older_than {
+            eligible_at = eligible_at.max(file_date + (now - cutoff) + TimeDelta::seconds(1));
+        }
+        if settings.previous_period_only && let Some(group_by) = settings.group_by {
+            let next_period_start = *self.next_period_starts
+                .entry((group_by, get_period_identifier(group_by, file_date)))
+                .or_insert_with(|| get_next_period_start(group_by, file_date));
+            eligible_at = eligible_at.max(next_period_start);
+        }
+        eligible_at
+    }
+}
+
+/// A relative `--older-than` moves its cutoff with every run, so only its distance from now is compared
+fn get_settings_fingerprint(settings: &DirSettings, now: DateTime<Utc>) -> String {
+    let older_than_hours = settings.older_than.map(|cutoff| (now - cutoff).num_hours());
+    format!("{:?} {} {:?} {:?}", settings.group_by, settings.previous_period_only, settings.file_date_types, older_than_hours)
+}
+
+fn read_modified(dir: &Path) -> Option<DateTime<Utc>> {
+    fs::metadata(dir).and_then(|metadata| metadata.modified()).ok().map(Into::into)
+}
+
+/// Scan state file of a set of sources and a destination, inside the user's local data folder
+fn get_scan_state_path(sources: &[PathBuf], destination: &Path) -> Result<PathBuf> {
+    let data_dir = dirs::data_local_dir().context("Failed to find the local data directory of the current user")?;
+    let mut hasher = blake3::Hasher::new();
+    for source in sources {
+        let source = fs::canonicalize(source).unwrap_or(source.clone());
+        hasher.update(source.as_os_str().as_encoded_bytes());
+        hasher.update(b"\0");
+    }
+    hasher.update(destination.as_os_str().as_encoded_bytes());
+    let key = hasher.finalize().to_hex();
+    Ok(data_dir.join("chronomover").join("scan-state").join(format!("{}.json", &key[..16])))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_settings(group_by: Option<GroupBy>, previous_period_only: bool, older_than: Option<DateTime<Utc>>) -> DirSettings {
+        DirSettings { group_by, previous_period_only, older_than, file_date_types: Vec::new() }
+    }
+
+    #[test]
+    fn test_get_eligible_at_older_than() {
+        let now = "2025-06-15T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let cutoff = "2025-05-16T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let file_date = "2025-06-01T12:00:00Z".parse::<DateTime<Utc>>().unwrap();
+
+        let eligible_at = ScanState::default().get_eligible_at(&create_settings(None, false, Some(cutoff)), file_date, now);
+        assert_eq!(eligible_at, "2025-07-01T12:00:01Z".parse::<DateTime<Utc>>().unwrap());
+    }
+
+    #[test]
+    fn test_get_eligible_at_previous_period_only() {
+        let now = "2025-06-15T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let file_date = "2025-06-10T12:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let mut scan_state = ScanState::default();
+
+        let eligible_at = scan_state.get_eligible_at(&create_settings(Some(GroupBy::Month), true, None), file_date, now);
+        assert_eq!(eligible_at, "2025-07-01T00:00:00Z".parse::<DateTime<Utc>>().unwrap());
+
+        // The latest of both filters wins
+        let cutoff = "2025-05-22T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let eligible_at = scan_state.get_eligible_at(&create_settings(Some(GroupBy::Month), true, Some(cutoff)), file_date, now);
+        assert_eq!(eligible_at, "2025-07-04T12:00:01Z".parse::<DateTime<Utc>>().unwrap());
+    }
+
+    #[test]
+    fn test_record_skipped_keeps_earliest_due() {
+        let now = "2025-06-15T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let settings = create_settings(Some(GroupBy::Week), true, None);
+        let dir = Path::new("/photos/camera");
+        let mut scan_state = ScanState::default();
+
+        scan_state.record_planned(dir, &settings, now);
+        assert_eq!(scan_state.evaluated[dir].1, None);
+        scan_state.record_skipped(dir, &settings, "2025-06-14T00:00:00Z".parse::<DateTime<Utc>>().unwrap(), now);
+        scan_state.record_skipped(dir, &settings, "2025-06-10T00:00:00Z".parse::<DateTime<Utc>>().unwrap(), now);
+        assert_eq!(scan_state.evaluated[dir].1, Some("2025-06-16T00:00:00Z".parse::<DateTime<Utc>>().unwrap()));
+    }
+
+    #[test]
+    fn test_get_settings_fingerprint_ignores_relative_cutoff_drift() {
+        let settings = create_settings(None, false, Some("2025-05-16T00:00:00Z".parse::<DateTime<Utc>>().unwrap()));
+        let next_day_settings = create_settings(None, false, Some("2025-05-17T00:00:00Z".parse::<DateTime<Utc>>().unwrap()));
+
+        assert_eq!(
+            get_settings_fingerprint(&settings, "2025-06-15T00:00:00Z".parse::<DateTime<Utc>>().unwrap()),
+            get_settings_fingerprint(&next_day_settings, "2025-06-16T00:00:00Z".parse::<DateTime<Utc>>().unwrap()),
+        );
+        assert_ne!(
+            get_settings_fingerprint(&settings, "2025-06-15T00:00:00Z".parse::<DateTime<Utc>>().unwrap()),
+            get_settings_fingerprint(&settings, "2025-06-16T00:00:00Z".parse::<DateTime<Utc>>().unwrap()),
+        );
+    }
+}
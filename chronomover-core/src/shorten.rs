@@ -0,0 +1,142 @@
+use crate::model::ShortenPaths;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+/// Longest file or folder name of most filesystems (NTFS, ext4, APFS)
+const MAX_NAME_LENGTH: usize = 255;
+
+/// Longest destination path by default, without the terminating NUL character: MAX_PATH on Windows, past which most
+/// programs can't open a file
+#[cfg(windows)]
+pub const DEFAULT_MAX_PATH_LENGTH: usize = 259;
+
+/// Longest destination path by default, without the terminating NUL character: PATH_MAX elsewhere
+#[cfg(not(windows))]
+pub const DEFAULT_MAX_PATH_LENGTH: usize = 4095;
+
+/// Shortest length a name is shortened to, keeping a few of its characters before the hash
+const MIN_SHORTENED_NAME_LENGTH: usize = 16;
+
+/// Hex digits of the hash ending a shortened name
+const HASH_LENGTH: usize = 8;
+
+/// Longest extension (with its dot) a shortened file name keeps
+const MAX_EXTENSION_LENGTH: usize = 16;
+
+/// Destination shortened by `--shorten-paths`, reported in the summary
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ShortenedPath {
+    pub source: PathBuf,
+    /// Destination before shortening
+    pub original: PathBuf,
+    pub shortened: PathBuf,
+}
+
+/// Shorten a destination whose names or whole path are longer than the platform allows, only renaming the folders and
+/// file below `base` (the destination root and period folder). A shortened name keeps its start, then `~` and a hash
+/// of the whole name, so different names stay different and every run shortens a name the same way. Returns none when
+/// the destination already fits, or when its names aren't valid Unicode.
+pub fn shorten_dest_path(strategy: ShortenPaths, max_length: usize, base: &Path, dest_path: &Path) -> Option<PathBuf> {
+    if strategy == ShortenPaths::Off {
+        return None;
+    }
+    let relative_path = dest_path.strip_prefix(base).ok()?;
+    let mut names = relative_path.components()
+        .map(|component| component.as_os_str().to_str().map(str::to_string))
+        .collect::<Option<Vec<_>>>()?;
+    let file_index = names.len().checked_sub(1)?;
+    let original_names = names.clone();
+    for (index, name) in names.iter_mut().enumerate() {
+        if measure(name) > MAX_NAME_LENGTH {
+            *name = shorten_name(name, MAX_NAME_LENGTH, index == file_index);
+        }
+    }
+
+    let absolute_base = std::path::absolute(base).unwrap_or_else(|_| base.to_path_buf());
+    let base_length = measure(&absolute_base.to_string_lossy());
+    let path_length = |names: &[String]| base_length + names.iter().map(|name| measure(name) + 1).sum::<usize>();
+    let mut indexes = (0..names.len()).collect::<Vec<_>>();
+    if strategy == ShortenPaths::TruncateMiddle {
+        // The folders closest to the middle of the path first, the file name last
+        let middle = file_index.saturating_sub(1) / 2;
+        indexes.sort_by_key(|&index| (index == file_index, index.abs_diff(middle)));
+    } else {
+        indexes.sort_by_key(|&index| std::cmp::Reverse(measure(&names[index])));
+    }
+    for index in indexes {
+        let length = path_length(&names);
+        if length <= max_length {
+            break;
+        }
+        let name_length = measure(&names[index]);
+        let target_length = name_length.saturating_sub(length - max_length).max(MIN_SHORTENED_NAME_LENGTH);
+        if target_length < name_length {
+            names[index] = shorten_name(&names[index], target_length, index == file_index);
+        }
+    }
+    (names != original_names).then(|| base.join(names.iter().collect::<PathBuf>()))
+}
+
+/// Shorten a name to `max_length`: its start, `~`, a hash of the whole name, then the extension of a file name
+fn shorten_name(name: &str, max_length: usize, is_file: bool) -> String {
+    let (stem, extension) = match name.rsplit_once('.') {
+        Some((stem, extension)) if is_file && !stem.is_empty() && measure(extension) < MAX_EXTENSION_LENGTH => (stem, format!(".{extension}")),
+        _ => (name, String::new()),
+    };
+    let hash = blake3::hash(name.as_bytes()).to_hex();
+    let suffix = format!("~{}{}", &hash[..HASH_LENGTH], extension);
+    let budget = max_length.saturating_sub(measure(&suffix));
+    let mut kept_length = 0;
+    let kept = stem.chars()
+        .take_while(|character| {
+            kept_length += measure(character.encode_utf8(&mut [0; 4]));
+            kept_length <= budget
+        })
+        .collect::<String>();
+    // Windows drops the spaces and dots ending a name
+    format!("{}{}", kept.trim_end_matches([' ', '.']), suffix)
+}
+
+/// Length of a name or path as the platform limits count it: UTF-16 code units on Windows, bytes elsewhere
+fn measure(text: &str) -> usize {
+    if cfg!(windows) { text.encode_utf16().count() } else { text.len() }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shorten_dest_path_shortens_long_names() {
+        let base = Path::new("/dest/2025-06");
+        let long_name = format!("{}.pdf", "report ".repeat(50));
+        let dest_path = base.join("docs").join(&long_name);
+
+        let shortened = shorten_dest_path(ShortenPaths::HashSuffix, DEFAULT_MAX_PATH_LENGTH, base, &dest_path).unwrap();
+
+        let name = shortened.file_name().unwrap().to_str().unwrap();
+        assert_eq!(shortened.parent(), Some(base.join("docs").as_path()));
+        assert_eq!(name.len(), MAX_NAME_LENGTH);
+        assert!(name.starts_with("report report") && name.ends_with(".pdf") && name.contains('~'));
+        assert_eq!(shorten_dest_path(ShortenPaths::HashSuffix, DEFAULT_MAX_PATH_LENGTH, base, &dest_path), Some(shortened));
+        assert_eq!(shorten_dest_path(ShortenPaths::Off, DEFAULT_MAX_PATH_LENGTH, base, &dest_path), None);
+        assert_eq!(shorten_dest_path(ShortenPaths::HashSuffix, DEFAULT_MAX_PATH_LENGTH, base, &base.join("a.pdf")), None);
+    }
+
+    #[test]
+    fn test_shorten_dest_path_fits_the_path_limit() {
+        let base = Path::new("/dest/2025-06");
+        let folder = |name: &str| format!("{name} {}", "x".repeat(40));
+        let dest_path = base.join(folder("first")).join(folder("middle")).join(folder("last")).join("notes.txt");
+
+        let truncated = shorten_dest_path(ShortenPaths::TruncateMiddle, 140, base, &dest_path).unwrap();
+        let names = truncated.strip_prefix(base).unwrap().iter().map(|name| name.to_str().unwrap()).collect::<Vec<_>>();
+        assert!(truncated.as_os_str().len() <= 140, "{}", truncated.display());
+        assert_eq!((names[0], names[2], names[3]), (folder("first").as_str(), folder("last").as_str(), "notes.txt"));
+        assert!(names[1].starts_with("middle") && names[1].len() < folder("middle").len());
+
+        let hashed = shorten_dest_path(ShortenPaths::HashSuffix, 140, base, &dest_path).unwrap();
+        assert!(hashed.as_os_str().len() <= 140, "{}", hashed.display());
+        assert_eq!(hashed.file_name().unwrap(), "notes.txt");
+    }
+}
@@ -0,0 +1,123 @@
+use crate::file::FileToMove;
+use color_eyre::eyre::{Context, Result};
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::mem::size_of;
+use std::path::PathBuf;
+
+/// Planned files written to a temporary file once the plan outgrows `--max-memory`, to be read back in chunks
+pub struct PlanSpill {
+    pub path: PathBuf,
+    /// Taken when reading back, so the file is closed before being deleted
+    writer: Option<BufWriter<File>>,
+}
+
+impl PlanSpill {
+    /// Create the spill file in the temporary folder of the system
+    pub fn create() -> Result<Self> {
+        let path = std::env::temp_dir().join(format!("chronomover-plan-{}.ndjson", std::process::id()));
+        let file = File::create(&path)
+            .with_context(|| format!("Failed to create plan spill file: {}", path.display()))?;
+        Ok(Self { path, writer: Some(BufWriter::new(file)) })
+    }
+
+    pub fn append(&mut self, files: &[FileToMove]) -> Result<()> {
+        // Safe to unwrap: the writer is only taken by `read_chunks`, which consumes the spill
+        let writer = self.writer.as_mut().unwrap();
+        for file in files {
+            serde_json::to_writer(&mut *writer, file).context("Failed to serialize planned file")?;
+            writeln!(writer).with_context(|| format!("Failed to write to plan spill file: {}", self.path.display()))?;
+        }
+        Ok(())
+    }
+
+    /// Read the planned files back in order, handing them to `handle_chunk` in chunks estimated to fit in
+    /// `max_memory` bytes. The spill file is deleted afterwards.
+    pub fn read_chunks(mut self, max_memory: u64, handle_chunk: impl FnMut(Vec<FileToMove>) -> Result<()>) -> Result<()> {
+        if let Some(mut writer) = self.writer.take() {
+            writer.flush().with_context(|| format!("Failed to write to plan spill file: {}", self.path.display()))?;
+        }
+        let file = File::open(&self.path)
+            .with_context(|| format!("Failed to open plan spill file: {}", self.path.display()))?;
+        read_chunks_from(BufReader::new(file), max_memory, handle_chunk)
+            .with_context(|| format!("Failed to read plan spill file: {}", self.path.display()))
+    }
+}
+
+impl Drop for PlanSpill {
+    fn drop(&mut self) {
+        drop(self.writer.take());
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+fn read_chunks_from(
+    reader: impl BufRead,
+    max_memory: u64,
+    mut handle_chunk: impl FnMut(Vec<FileToMove>) -> Result<()>,
+) -> Result<()> {
+    let mut chunk = Vec::new();
+    let mut chunk_memory = 0;
+    for line in reader.lines() {
+        let file: FileToMove = serde_json::from_str(&line?).context("Failed to deserialize planned file")?;
+        chunk_memory += estimate_memory(&file);
+        chunk.push(file);
+        if chunk_memory >= max_memory {
+            handle_chunk(std::mem::take(&mut chunk))?;
+            chunk_memory = 0;
+        }
+    }
+    if !chunk.is_empty() {
+        handle_chunk(chunk)?;
+    }
+    Ok(())
+}
+
+/// Rough number of bytes a planned file takes in memory
+pub fn estimate_memory(file: &FileToMove) -> u64 {
+    let heap_size = file.source.as_os_str().len()
+        + file.destination.as_os_str().len()
+        + file.period.as_ref().map_or(0, String::len);
+    (size_of::<FileToMove>() + heap_size) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{DateTime, Utc};
+    use std::io::Cursor;
+
+    fn create_file(name: &str) -> FileToMove {
+        FileToMove {
+            source: PathBuf::from(format!("/notes/{name}")),
+            destination: PathBuf::from(format!("/archive/2025-06/{name}")),
+            file_date: "2025-06-15T08:30:00Z".parse::<DateTime<Utc>>().unwrap(),
+            size: 42,
+            period: Some("2025-06".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_estimate_memory() {
+        let file = create_file("a.md");
+        let expected = size_of::<FileToMove>() + "/notes/a.md".len() + "/archive/2025-06/a.md".len() + "2025-06".len();
+        assert_eq!(estimate_memory(&file), expected as u64);
+    }
+
+    #[test]
+    fn test_read_chunks_from_splits_by_memory_in_order() {
+        let files = ["a.md", "b.md", "c.md", "d.md", "e.md"].map(create_file);
+        let content = files.iter().map(|file| serde_json::to_string(file).unwrap() + "\n").collect::<String>();
+        let max_memory = estimate_memory(&files[0]) * 2;
+
+        let mut chunks = Vec::new();
+        read_chunks_from(Cursor::new(content), max_memory, |chunk| {
+            chunks.push(chunk.into_iter().map(|file| file.source).collect::<Vec<_>>());
+            Ok(())
+        }).unwrap();
+
+        let expected = [vec!["a.md", "b.md"], vec!["c.md", "d.md"], vec!["e.md"]]
+            .map(|names| names.into_iter().map(|name| PathBuf::from(format!("/notes/{name}"))).collect::<Vec<_>>());
+        assert_eq!(chunks, expected);
+    }
+}
@@ -0,0 +1,118 @@
+use crate::file::get_files_to_move;
+use crate::logging::SUMMARY_TARGET;
+use crate::model::{get_reference_time, Args, StatsArgs};
+use crate::report::format_size;
+use crate::storage::LocalStorage;
+use chrono::{DateTime, Utc};
+use color_eyre::eyre::{bail, Result};
+use std::collections::BTreeMap;
+use tracing::info;
+
+/// Files of a period folder found by `stats`
+#[derive(Debug, Clone, Copy)]
+struct PeriodSummary {
+    files: usize,
+    bytes: u64,
+    newest_file_date: DateTime<Utc>,
+}
+
+/// Scan the source folders without moving anything, then print how many files and bytes fall into each period and
+/// the `--older-than` duration that would move each period along with the older ones
+pub fn print_stats(stats_args: &StatsArgs) -> Result<()> {
+    for source in &stats_args.sources {
+        if !source.is_dir() {
+            bail!("Source directory does not exist: {}", source.display());
+        }
+    }
+    if stats_args.jobs == 0 {
+        bail!("Number of jobs must be greater than 0");
+    }
+
+    // The scan is a dry run with no filters, so every file is planned into its period
+    let args = Args {
+        sources: stats_args.sources.clone(),
+        destination: ".".into(),
+        dry_run: true,
+        group_by: Some(stats_args.group_by),
+        file_date_types: stats_args.file_date_types.clone(),
+        jobs: stats_args.jobs,
+        ..Args::default()
+    };
+
+    info!(target: SUMMARY_TARGET, "Scanning {}...", stats_args.sources.iter().map(|source| source.display().to_string()).collect::<Vec<_>>().join(", "));
+    let now = get_reference_time();
+    let plan = get_files_to_move(&args, &LocalStorage, now, None)?;
+
+    let mut periods: BTreeMap<String, PeriodSummary> = BTreeMap::new();
+    for file in &plan.files_to_move {
+        // Safe to unwrap: the scan groups every file
+        let period = file.period.clone().unwrap();
+        let summary = periods.entry(period).or_insert(PeriodSummary { files: 0, bytes: 0, newest_file_date: file.file_date });
+        summary.files += 1;
+        summary.bytes += file.size;
+        summary.newest_file_date = summary.newest_file_date.max(file.file_date);
+    }
+
+    for line in render_stats(&periods, now) {
+        info!(target: SUMMARY_TARGET, "{}", line);
+    }
+    if !plan.errors.is_empty() {
+        info!(target: SUMMARY_TARGET, "Failed to read the date of {} file(s)", plan.errors.len());
+    }
+    Ok(())
+}
+
+fn render_stats(periods: &BTreeMap<String, PeriodSummary>, now: DateTime<Utc>) -> Vec<String> {
+    if periods.is_empty() {
+        return vec!["No files found".to_string()];
+    }
+    let period_width = periods.keys()
+        .map(|period| period.chars().count())
+        .chain(["Period".len(), "Total".len()])
+        .max()
+        .unwrap_or_default();
+    let total_files = periods.values().map(|summary| summary.files).sum::<usize>();
+    let total_bytes = periods.values().map(|summary| summary.bytes).sum::<u64>();
+
+    let mut lines = vec![format!("{:<period_width$}  {:>7}  {:>10}  {:>12}", "Period", "Files", "Size", "--older-than")];
+    for (period, summary) in periods {
+        // Whole days, so the newest file of the period is strictly older than the duration
+        let older_than = match (now - summary.newest_file_date).num_days() {
+            days if days > 0 => format!("{days}d"),
+            _ => "-".to_string(),
+        };
+        lines.push(format!("{:<period_width$}  {:>7}  {:>10}  {:>12}", period, summary.files, format_size(summary.bytes), older_than));
+    }
+    lines.push(format!("{:<period_width$}  {:>7}  {:>10}", "Total", total_files, format_size(total_bytes)));
+    lines.push(String::new());
+    lines.push("The --older-than column moves a period along with the ones before it".to_string());
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_stats() {
+        let now = "2025-06-15T12:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let periods = BTreeMap::from([
+            ("2025-05".to_string(), PeriodSummary { files: 3, bytes: 3072, newest_file_date: "2025-05-30T00:00:00Z".parse().unwrap() }),
+            ("2025-06".to_string(), PeriodSummary { files: 1, bytes: 10, newest_file_date: "2025-06-15T08:00:00Z".parse().unwrap() }),
+        ]);
+
+        let lines = render_stats(&periods, now);
+
+        assert_eq!(lines[0], "Period     Files        Size  --older-than");
+        assert_eq!(lines[1], "2025-05        3     3.0 KiB           16d");
+        assert_eq!(lines[2], "2025-06        1        10 B             -");
+        assert_eq!(lines[3], "Total          4     3.0 KiB");
+    }
+
+    #[test]
+    fn test_render_stats_no_files() {
+        let now = "2025-06-15T12:00:00Z".parse::<DateTime<Utc>>().unwrap();
+
+        assert_eq!(render_stats(&BTreeMap::new(), now), vec!["No files found"]);
+    }
+}
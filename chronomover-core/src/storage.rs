@@ -0,0 +1,828 @@
+use std::borrow::Cow;
+use std::fs::{self, File};
+use std::io;
+use std::iter;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use tracing::{debug, warn};
+use walkdir::{DirEntry, WalkDir};
+
+/// Size and timestamps of a file. Timestamps the filesystem or platform doesn't record are `None`.
+#[derive(Debug, Clone, Copy)]
+pub struct FileStat {
+    pub size: u64,
+    pub created: Option<SystemTime>,
+    pub modified: Option<SystemTime>,
+    pub accessed: Option<SystemTime>,
+    /// Whether the file is an online-only placeholder of a cloud-sync client, whose content isn't stored locally
+    pub is_placeholder: bool,
+}
+
+impl From<&fs::Metadata> for FileStat {
+    fn from(metadata: &fs::Metadata) -> Self {
+        Self {
+            size: metadata.len(),
+            created: metadata.created().ok(),
+            modified: metadata.modified().ok(),
+            accessed: metadata.accessed().ok(),
+            is_placeholder: is_cloud_placeholder(metadata),
+        }
+    }
+}
+
+/// Kind of link a walked entry is
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LinkKind {
+    Symbolic,
+    /// A Windows junction (a mount point reparse point)
+    Junction,
+}
+
+/// A file or folder found while walking or listing a folder
+#[derive(Debug, Clone)]
+pub struct StorageEntry {
+    pub path: PathBuf,
+    /// Depth below the walked folder, which is at depth 0
+    pub depth: usize,
+    /// Whether the entry is a folder, or a link to one when links are followed
+    pub is_dir: bool,
+    /// Whether the entry is a regular file, or a link to one when links are followed
+    pub is_file: bool,
+    /// Kind of link the entry itself is, whatever it points to
+    pub link: Option<LinkKind>,
+    /// Whether the entry is marked as hidden or system (Windows only)
+    pub is_hidden: bool,
+    /// Size and timestamps, when they came with the entry at no extra cost (on Windows)
+    pub stat: Option<FileStat>,
+}
+
+/// How a folder is walked
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WalkOptions {
+    pub max_depth: Option<usize>,
+    pub follow_links: bool,
+    /// Walk each folder in file name order, so the walk never depends on the filesystem
+    pub sorted: bool,
+}
+
+/// Where the files are enumerated, read, moved and deleted. Runs use the local filesystem, and tests an in-memory one.
+pub trait StorageBackend: Sync {
+    /// Walk a folder depth-first, starting with the folder itself. Entries `keep` rejects are left out, along with
+    /// everything under them.
+    fn walk<'a>(
+        &'a self,
+        root: &Path,
+        options: WalkOptions,
+        keep: Box<dyn FnMut(&StorageEntry) -> bool + 'a>,
+    ) -> Box<dyn Iterator<Item = io::Result<StorageEntry>> + 'a>;
+
+    /// Entries directly inside a folder, without following links
+    fn list_dir<'a>(&'a self, dir: &Path) -> io::Result<Box<dyn Iterator<Item = io::Result<StorageEntry>> + 'a>>;
+
+    /// Size and timestamps of a file, following links
+    fn stat(&self, path: &Path) -> io::Result<FileStat>;
+
+    fn is_dir(&self, path: &Path) -> bool;
+
+    fn create_dir_all(&self, dir: &Path) -> io::Result<()>;
+
+    /// Move a file into an existing folder, replacing any file already there
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()>;
+
+    /// Copy a file into an existing folder along with its modification time, replacing any file already there
+    fn copy_file(&self, from: &Path, to: &Path) -> io::Result<()>;
+
+    /// Set the modification time of a file or folder
+    fn set_modified(&self, path: &Path, modified: SystemTime) -> io::Result<()>;
+
+    /// Download the content of a file if it is a cloud placeholder, so it is stored locally. Returns whether it was one.
+    fn hydrate(&self, path: &Path) -> io::Result<bool>;
+
+    /// Set a file about to be replaced aside so it can still be restored, where the storage can (the Recycle Bin on
+    /// Windows)
+    fn recycle_file(&self, path: &Path) -> io::Result<()>;
+
+    fn remove_file(&self, path: &Path) -> io::Result<()>;
+
+    /// Delete an empty folder
+    fn remove_dir(&self, path: &Path) -> io::Result<()>;
+
+    /// Whether names only differing in case name the same file at `destination`
+    fn is_case_insensitive(&self, destination: &Path) -> bool;
+
+    /// Delete a folder and everything in it for good
+    fn remove_dir_all(&self, path: &Path) -> io::Result<()>;
+
+    /// Send a folder and everything in it to the Recycle Bin (Windows only, fails as unsupported elsewhere)
+    fn recycle_dir(&self, path: &Path) -> io::Result<()>;
+
+    /// BLAKE3 hash of a file's content, as a hex string
+    fn hash_file(&self, path: &Path) -> io::Result<String>;
+}
+
+/// The local filesystem
+pub struct LocalStorage;
+
+impl StorageBackend for LocalStorage {
+    fn walk<'a>(
+        &'a self,
+        root: &Path,
+        options: WalkOptions,
+        mut keep: Box<dyn FnMut(&StorageEntry) -> bool + 'a>,
+    ) -> Box<dyn Iterator<Item = io::Result<StorageEntry>> + 'a> {
+        let mut walk = WalkDir::new(root).follow_links(options.follow_links);
+        if let Some(max_depth) = options.max_depth {
+            walk = walk.max_depth(max_depth);
+        }
+        if options.sorted {
+            walk = walk.sort_by_file_name();
+        }
+        // Like `FilterEntry`, but converting each entry only once
+        let mut walk = walk.into_iter();
+        Box::new(iter::from_fn(move || loop {
+            let entry = match walk.next()? {
+                Ok(entry) => to_storage_entry(&entry),
+                Err(e) => return Some(Err(e.into())),
+            };
+            if keep(&entry) {
+                return Some(Ok(entry));
+            }
+            if entry.is_dir {
+                walk.skip_current_dir();
+            }
+        }))
+    }
+
+    fn list_dir<'a>(&'a self, dir: &Path) -> io::Result<Box<dyn Iterator<Item = io::Result<StorageEntry>> + 'a>> {
+        let entries = fs::read_dir(dir)?.map(|entry| {
+            let entry = entry?;
+            let file_type = entry.file_type()?;
+            let path = entry.path();
+            Ok(StorageEntry {
+                link: file_type.is_symlink().then(|| get_link_kind(&path)),
+                is_hidden: cfg!(windows) && entry.metadata().is_ok_and(|metadata| is_hidden_or_system(&metadata)),
+                path,
+                depth: 1,
+                is_dir: file_type.is_dir(),
+                is_file: file_type.is_file(),
+                stat: None,
+            })
+        });
+        Ok(Box::new(entries))
+    }
+
+    fn stat(&self, path: &Path) -> io::Result<FileStat> {
+        fs::metadata(path).map(|metadata| FileStat::from(&metadata))
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        path.is_dir()
+    }
+
+    fn create_dir_all(&self, dir: &Path) -> io::Result<()> {
+        fs::create_dir_all(to_extended_length_path(dir))
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        fs::rename(to_extended_length_path(from), to_extended_length_path(to))
+    }
+
+    /// Copied aside then renamed over the target, so an interrupted copy never leaves a partial file at its path
+    fn copy_file(&self, from: &Path, to: &Path) -> io::Result<()> {
+        let (from, to) = (to_extended_length_path(from), to_extended_length_path(to));
+        let mut temporary_path = to.as_os_str().to_owned();
+        temporary_path.push(format!(".{}.tmp", std::process::id()));
+        let temporary_path = PathBuf::from(temporary_path);
+        let copied = fs::copy(&from, &temporary_path)
+            .and_then(|_| File::options().write(true).open(&temporary_path)?.set_modified(fs::metadata(&from)?.modified()?))
+            .and_then(|()| fs::rename(&temporary_path, &to));
+        if copied.is_err() {
+            let _ = fs::remove_file(&temporary_path);
+        }
+        copied
+    }
+
+    fn set_modified(&self, path: &Path, modified: SystemTime) -> io::Result<()> {
+        open_to_set_times(&to_extended_length_path(path))?.set_modified(modified)
+    }
+
+    /// Reading the whole content of a placeholder makes the sync client download it
+    fn hydrate(&self, path: &Path) -> io::Result<bool> {
+        let path = to_extended_length_path(path);
+        if !is_cloud_placeholder(&fs::metadata(&path)?) {
+            return Ok(false);
+        }
+        io::copy(&mut File::open(&path)?, &mut io::sink())?;
+        Ok(true)
+    }
+
+    fn recycle_file(&self, path: &Path) -> io::Result<()> {
+        recycle_overwritten_file(path)
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        fs::remove_file(to_extended_length_path(path))
+    }
+
+    fn remove_dir(&self, path: &Path) -> io::Result<()> {
+        fs::remove_dir(to_extended_length_path(path))
+    }
+
+    fn remove_dir_all(&self, path: &Path) -> io::Result<()> {
+        fs::remove_dir_all(to_extended_length_path(path))
+    }
+
+    fn recycle_dir(&self, path: &Path) -> io::Result<()> {
+        recycle_dir(path)
+    }
+
+    fn hash_file(&self, path: &Path) -> io::Result<String> {
+        crate::manifest::hash_file(path).map_err(io::Error::other)
+    }
+
+    /// Checked with a probe file created in the closest existing folder of `destination`. When the probe can't be
+    /// created, assumes the default of the platform (case-insensitive on Windows and macOS).
+    fn is_case_insensitive(&self, destination: &Path) -> bool {
+        let folder = destination.ancestors().find(|ancestor| ancestor.is_dir()).unwrap_or(Path::new("."));
+        let probe_name = format!(".chronomover-case-probe-{}", std::process::id());
+        let probe = folder.join(&probe_name);
+        match File::create_new(&probe) {
+            Ok(_) => {
+                let is_case_insensitive = folder.join(probe_name.to_uppercase()).exists();
+                if let Err(e) = fs::remove_file(&probe) {
+                    warn!(path = %probe.display(), error = %e, "Failed to delete the probe file {}: {}", probe.display(), e);
+                }
+                is_case_insensitive
+            }
+            Err(e) => {
+                debug!(path = %folder.display(), error = %e, "Failed to probe the case sensitivity of {}: {}", folder.display(), e);
+                cfg!(any(windows, target_os = "macos"))
+            }
+        }
+    }
+}
+
+fn to_storage_entry(entry: &DirEntry) -> StorageEntry {
+    // The metadata is only read on Windows, where it comes with the walked entry at no extra cost
+    let metadata = if cfg!(windows) { entry.metadata().ok() } else { None };
+    StorageEntry {
+        path: entry.path().to_path_buf(),
+        depth: entry.depth(),
+        is_dir: entry.file_type().is_dir(),
+        is_file: entry.file_type().is_file(),
+        link: entry.path_is_symlink().then(|| get_link_kind(entry.path())),
+        is_hidden: metadata.as_ref().is_some_and(is_hidden_or_system),
+        stat: metadata.as_ref().filter(|metadata| metadata.is_file()).map(FileStat::from),
+    }
+}
+
+fn get_link_kind(path: &Path) -> LinkKind {
+    if is_junction(path) { LinkKind::Junction } else { LinkKind::Symbolic }
+}
+
+/// Whether Windows marks a file or folder as hidden or system (e.g., `desktop.ini`, `Thumbs.db`)
+#[cfg(windows)]
+pub fn is_hidden_or_system(metadata: &fs::Metadata) -> bool {
+    use std::os::windows::fs::MetadataExt;
+    use windows_sys::Win32::Storage::FileSystem::{FILE_ATTRIBUTE_HIDDEN, FILE_ATTRIBUTE_SYSTEM};
+
+    metadata.file_attributes() & (FILE_ATTRIBUTE_HIDDEN | FILE_ATTRIBUTE_SYSTEM) != 0
+}
+
+/// Hidden and system attributes only exist on Windows
+#[cfg(not(windows))]
+pub fn is_hidden_or_system(_metadata: &fs::Metadata) -> bool {
+    false
+}
+
+/// Whether a file is an online-only placeholder (e.g., OneDrive Files On-Demand), whose content is recalled from the
+/// cloud when read
+#[cfg(windows)]
+pub fn is_cloud_placeholder(metadata: &fs::Metadata) -> bool {
+    use std::os::windows::fs::MetadataExt;
+    use windows_sys::Win32::Storage::FileSystem::{
+        FILE_ATTRIBUTE_OFFLINE, FILE_ATTRIBUTE_RECALL_ON_DATA_ACCESS, FILE_ATTRIBUTE_RECALL_ON_OPEN,
+    };
+
+    metadata.file_attributes() & (FILE_ATTRIBUTE_OFFLINE | FILE_ATTRIBUTE_RECALL_ON_DATA_ACCESS | FILE_ATTRIBUTE_RECALL_ON_OPEN) != 0
+}
+
+/// Whether a file is a dataless file of a File Provider (e.g., iCloud Drive, Dropbox), whose content is downloaded
+/// when read
+#[cfg(target_os = "macos")]
+pub fn is_cloud_placeholder(metadata: &fs::Metadata) -> bool {
+    use std::os::macos::fs::MetadataExt;
+
+    /// `SF_DATALESS` from `sys/stat.h`
+    const SF_DATALESS: u32 = 0x40000000;
+    metadata.st_flags() & SF_DATALESS != 0
+}
+
+/// Placeholders can only be told apart on Windows and macOS
+#[cfg(not(any(windows, target_os = "macos")))]
+pub fn is_cloud_placeholder(_metadata: &fs::Metadata) -> bool {
+    false
+}
+
+/// Whether a path is a junction (a mount point reparse point), which the standard library can't tell apart from a
+/// symbolic link to a folder
+#[cfg(windows)]
+fn is_junction(path: &Path) -> bool {
+    use std::os::windows::ffi::OsStrExt;
+    use windows_sys::Win32::Foundation::INVALID_HANDLE_VALUE;
+    use windows_sys::Win32::Storage::FileSystem::{FindClose, FindFirstFileW, FILE_ATTRIBUTE_REPARSE_POINT, WIN32_FIND_DATAW};
+    use windows_sys::Win32::System::SystemServices::IO_REPARSE_TAG_MOUNT_POINT;
+
+    let wide_path = to_extended_length_path(path).as_os_str().encode_wide().chain([0]).collect::<Vec<_>>();
+    // Safe: the path is NUL-terminated, and the find data is a plain struct filled in by the call
+    let find_data = unsafe {
+        let mut find_data = std::mem::zeroed::<WIN32_FIND_DATAW>();
+        let handle = FindFirstFileW(wide_path.as_ptr(), &mut find_data);
+        if handle == INVALID_HANDLE_VALUE {
+            return false;
+        }
+        FindClose(handle);
+        find_data
+    };
+    // The reparse tag of a found entry is reported in `dwReserved0`
+    find_data.dwFileAttributes & FILE_ATTRIBUTE_REPARSE_POINT != 0 && find_data.dwReserved0 == IO_REPARSE_TAG_MOUNT_POINT
+}
+
+/// Junctions only exist on Windows
+#[cfg(not(windows))]
+fn is_junction(_path: &Path) -> bool {
+    false
+}
+
+/// Send the file a move is about to overwrite to the Recycle Bin, so it can still be restored. The shell takes the
+/// path as is, only the check needs its extended-length form.
+#[cfg(windows)]
+fn recycle_overwritten_file(destination: &Path) -> io::Result<()> {
+    if !to_extended_length_path(destination).is_file() {
+        return Ok(());
+    }
+    trash::delete(destination).map_err(|e| {
+        io::Error::other(format!("Failed to send the file it would overwrite to the Recycle Bin: {e}"))
+    })
+}
+
+/// Overwritten files are only recycled on Windows
+#[cfg(not(windows))]
+fn recycle_overwritten_file(_destination: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+/// Timestamps can be set through any handle of the owner's on Unix, folders included
+#[cfg(not(windows))]
+fn open_to_set_times(path: &Path) -> io::Result<File> {
+    File::open(path)
+}
+
+/// Setting timestamps takes a handle with the right to write attributes on Windows, which folders only give with backup
+/// semantics
+#[cfg(windows)]
+fn open_to_set_times(path: &Path) -> io::Result<File> {
+    use std::os::windows::fs::OpenOptionsExt;
+    use windows_sys::Win32::Storage::FileSystem::{FILE_FLAG_BACKUP_SEMANTICS, FILE_WRITE_ATTRIBUTES};
+    File::options().access_mode(FILE_WRITE_ATTRIBUTES).custom_flags(FILE_FLAG_BACKUP_SEMANTICS).open(path)
+}
+
+/// Bytes the current user can still write on the volume holding `path`, read from the nearest existing folder above it
+/// when it doesn't exist yet (e.g., the period folders of a dry run)
+pub fn available_space(path: &Path) -> io::Result<u64> {
+    let absolute_path = std::path::absolute(path)?;
+    let existing = absolute_path.ancestors()
+        .find(|ancestor| ancestor.is_dir())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no folder of the path exists"))?;
+    get_available_space(existing)
+}
+
+#[cfg(unix)]
+fn get_available_space(dir: &Path) -> io::Result<u64> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_path = CString::new(dir.as_os_str().as_bytes()).map_err(io::Error::other)?;
+    // Safe: the path is NUL-terminated, and the stats are a plain struct filled in by the call
+    let stats = unsafe {
+        let mut stats = std::mem::zeroed::<libc::statvfs>();
+        if libc::statvfs(c_path.as_ptr(), &mut stats) != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        stats
+    };
+    // Blocks available to unprivileged users, leaving out the ones reserved for root
+    #[allow(clippy::unnecessary_cast)]
+    Ok(stats.f_bavail as u64 * stats.f_frsize as u64)
+}
+
+#[cfg(windows)]
+fn get_available_space(dir: &Path) -> io::Result<u64> {
+    use std::os::windows::ffi::OsStrExt;
+    use windows_sys::Win32::Storage::FileSystem::GetDiskFreeSpaceExW;
+
+    let wide_path = dir.as_os_str().encode_wide().chain([0]).collect::<Vec<_>>();
+    let mut available = 0u64;
+    // Safe: the path is NUL-terminated, and the totals not asked for are null
+    let is_read = unsafe { GetDiskFreeSpaceExW(wide_path.as_ptr(), &mut available, std::ptr::null_mut(), std::ptr::null_mut()) };
+    if is_read == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // Quotas included, unlike the total free bytes
+    Ok(available)
+}
+
+/// Send a pruned folder to the Recycle Bin, so it can still be restored
+#[cfg(windows)]
+fn recycle_dir(path: &Path) -> io::Result<()> {
+    trash::delete(path).map_err(|e| io::Error::other(format!("Failed to send the folder to the Recycle Bin: {e}")))
+}
+
+/// The Recycle Bin is only supported on Windows
+#[cfg(not(windows))]
+fn recycle_dir(_path: &Path) -> io::Result<()> {
+    Err(io::Error::from(io::ErrorKind::Unsupported))
+}
+
+/// Extended-length form (`\\?\C:\...`, `\\?\UNC\server\share\...`) of a path, which the Windows file APIs accept past
+/// MAX_PATH. Paths that can't be made absolute, and paths that already are verbatim or device paths, are kept as is.
+#[cfg(windows)]
+fn to_extended_length_path(path: &Path) -> Cow<'_, Path> {
+    use std::ffi::OsString;
+    use std::path::{Component, Prefix};
+
+    // Verbatim paths are not normalized by Windows, so `absolute()` must resolve `.` and `..` first
+    let Ok(absolute_path) = std::path::absolute(path) else {
+        return Cow::Borrowed(path);
+    };
+    let mut components = absolute_path.components();
+    let mut extended_path = match components.next() {
+        Some(Component::Prefix(prefix)) => match prefix.kind() {
+            Prefix::Disk(_) => {
+                let mut extended_path = OsString::from(r"\\?\");
+                extended_path.push(prefix.as_os_str());
+                extended_path
+            }
+            Prefix::UNC(server, share) => {
+                let mut extended_path = OsString::from(r"\\?\UNC\");
+                extended_path.push(server);
+                extended_path.push(r"\");
+                extended_path.push(share);
+                extended_path
+            }
+            _ => return Cow::Borrowed(path),
+        },
+        _ => return Cow::Borrowed(path),
+    };
+    for component in components {
+        match component {
+            Component::RootDir => {}
+            Component::Normal(name) => {
+                extended_path.push(r"\");
+                extended_path.push(name);
+            }
+            _ => return Cow::Borrowed(path),
+        }
+    }
+    Cow::Owned(PathBuf::from(extended_path))
+}
+
+/// Paths have no MAX_PATH limit outside of Windows
+#[cfg(not(windows))]
+fn to_extended_length_path(path: &Path) -> Cow<'_, Path> {
+    Cow::Borrowed(path)
+}
+
+#[cfg(test)]
+pub use memory::MemoryStorage;
+
+#[cfg(test)]
+mod memory {
+    use super::{FileStat, StorageBackend, StorageEntry, WalkOptions};
+    use chrono::{DateTime, Utc};
+    use std::collections::BTreeMap;
+    use std::io;
+    use std::iter;
+    use std::path::{Path, PathBuf};
+    use std::sync::{Mutex, MutexGuard, PoisonError};
+    use std::time::SystemTime;
+
+    /// Folders (without a stat) and files by path
+    type Entries = BTreeMap<PathBuf, Option<FileStat>>;
+
+    /// In-memory storage holding folders and zero-filled files with a single date, for tests that never touch the disk.
+    /// Names are case-sensitive and there are no links.
+    #[derive(Default)]
+    pub struct MemoryStorage {
+        entries: Mutex<Entries>,
+        reports_case_insensitive: bool,
+    }
+
+    impl MemoryStorage {
+        /// Report the destination as ignoring case, to test the handling of names only differing in case (the names
+        /// held in memory stay case-sensitive)
+        pub fn reporting_case_insensitive(mut self) -> Self {
+            self.reports_case_insensitive = true;
+            self
+        }
+
+        /// Storage holding a tree of files and folders, each file dated by an RFC 3339 date or a plain `YYYY-MM-DD`
+        /// one (at midnight UTC). Paths ending with `/` are empty folders and take no date.
+        pub fn with_tree<'a>(tree: impl IntoIterator<Item = (&'a str, &'a str)>) -> Self {
+            let storage = Self::default();
+            for (path, date) in tree {
+                if path.ends_with('/') {
+                    storage.add_dir(path);
+                } else {
+                    storage.add_file(path, parse_fixture_date(date));
+                }
+            }
+            storage
+        }
+
+        /// Add a file dated `date`, along with its missing parent folders
+        pub fn add_file(&self, path: impl AsRef<Path>, date: DateTime<Utc>) {
+            let path = path.as_ref();
+            if let Some(parent) = path.parent() {
+                self.add_dir(parent);
+            }
+            let date = Some(SystemTime::from(date));
+            let stat = FileStat { size: 0, created: date, modified: date, accessed: date, is_placeholder: false };
+            self.lock().insert(path.to_path_buf(), Some(stat));
+        }
+
+        /// Add an online-only placeholder file dated `date`, along with its missing parent folders
+        pub fn add_placeholder(&self, path: impl AsRef<Path>, date: DateTime<Utc>) {
+            self.add_file(&path, date);
+            if let Some(Some(stat)) = self.lock().get_mut(path.as_ref()) {
+                stat.is_placeholder = true;
+            }
+        }
+
+        /// Set the size of a file, which holds that many zero bytes
+        pub fn set_size(&self, path: impl AsRef<Path>, size: u64) {
+            if let Some(Some(stat)) = self.lock().get_mut(path.as_ref()) {
+                stat.size = size;
+            }
+        }
+
+        /// Set each timestamp of a file separately, `None` being a timestamp the filesystem doesn't record
+        pub fn set_dates(
+            &self,
+            path: impl AsRef<Path>,
+            created: Option<DateTime<Utc>>,
+            modified: Option<DateTime<Utc>>,
+            accessed: Option<DateTime<Utc>>,
+        ) {
+            if let Some(Some(stat)) = self.lock().get_mut(path.as_ref()) {
+                stat.created = created.map(SystemTime::from);
+                stat.modified = modified.map(SystemTime::from);
+                stat.accessed = accessed.map(SystemTime::from);
+            }
+        }
+
+        /// Add a folder, along with its missing parent folders
+        pub fn add_dir(&self, path: impl AsRef<Path>) {
+            let mut entries = self.lock();
+            for ancestor in path.as_ref().ancestors().filter(|ancestor| !ancestor.as_os_str().is_empty()) {
+                entries.entry(ancestor.to_path_buf()).or_insert(None);
+            }
+        }
+
+        pub fn is_file(&self, path: impl AsRef<Path>) -> bool {
+            self.lock().get(path.as_ref()).is_some_and(Option::is_some)
+        }
+
+        /// Every file stored, sorted by path, to compare the whole tree after a run
+        pub fn file_paths(&self) -> Vec<PathBuf> {
+            self.lock().iter().filter(|(_, stat)| stat.is_some()).map(|(path, _)| path.clone()).collect()
+        }
+
+        /// Every folder stored, sorted by path
+        pub fn dir_paths(&self) -> Vec<PathBuf> {
+            self.lock().iter().filter(|(_, stat)| stat.is_none()).map(|(path, _)| path.clone()).collect()
+        }
+
+        fn lock(&self) -> MutexGuard<'_, Entries> {
+            self.entries.lock().unwrap_or_else(PoisonError::into_inner)
+        }
+    }
+
+    fn parse_fixture_date(date: &str) -> DateTime<Utc> {
+        date.parse::<DateTime<Utc>>()
+            .or_else(|_| format!("{date}T00:00:00Z").parse::<DateTime<Utc>>())
+            .unwrap_or_else(|e| panic!("Invalid fixture date {date:?}: {e}"))
+    }
+
+    fn to_entry(path: &Path, depth: usize, stat: Option<FileStat>) -> StorageEntry {
+        StorageEntry { path: path.to_path_buf(), depth, is_dir: stat.is_none(), is_file: stat.is_some(), link: None, is_hidden: false, stat }
+    }
+
+    fn get_children(entries: &Entries, dir: &Path) -> Vec<(PathBuf, Option<FileStat>)> {
+        entries.iter()
+            .filter(|(path, _)| path.parent() == Some(dir))
+            .map(|(path, stat)| (path.clone(), *stat))
+            .collect()
+    }
+
+    fn walk_into(
+        entries: &Entries,
+        (path, stat): (PathBuf, Option<FileStat>),
+        depth: usize,
+        max_depth: Option<usize>,
+        keep: &mut dyn FnMut(&StorageEntry) -> bool,
+        walked: &mut Vec<io::Result<StorageEntry>>,
+    ) {
+        let entry = to_entry(&path, depth, stat);
+        if !keep(&entry) {
+            return;
+        }
+        walked.push(Ok(entry));
+        if stat.is_none() && max_depth.is_none_or(|max_depth| depth < max_depth) {
+            for child in get_children(entries, &path) {
+                walk_into(entries, child, depth + 1, max_depth, keep, walked);
+            }
+        }
+    }
+
+    impl StorageBackend for MemoryStorage {
+        fn walk<'a>(
+            &'a self,
+            root: &Path,
+            options: WalkOptions,
+            mut keep: Box<dyn FnMut(&StorageEntry) -> bool + 'a>,
+        ) -> Box<dyn Iterator<Item = io::Result<StorageEntry>> + 'a> {
+            let entries = self.lock();
+            let Some(stat) = entries.get(root).copied() else {
+                return Box::new(iter::once(Err(io::Error::from(io::ErrorKind::NotFound))));
+            };
+            let mut walked = Vec::new();
+            walk_into(&entries, (root.to_path_buf(), stat), 0, options.max_depth, &mut keep, &mut walked);
+            Box::new(walked.into_iter())
+        }
+
+        fn list_dir<'a>(&'a self, dir: &Path) -> io::Result<Box<dyn Iterator<Item = io::Result<StorageEntry>> + 'a>> {
+            if !self.is_dir(dir) {
+                return Err(io::Error::from(io::ErrorKind::NotFound));
+            }
+            let children = get_children(&self.lock(), dir);
+            Ok(Box::new(children.into_iter().map(|(path, stat)| Ok(to_entry(&path, 1, stat)))))
+        }
+
+        fn stat(&self, path: &Path) -> io::Result<FileStat> {
+            match self.lock().get(path) {
+                Some(Some(stat)) => Ok(*stat),
+                Some(None) => Err(io::Error::from(io::ErrorKind::IsADirectory)),
+                None => Err(io::Error::from(io::ErrorKind::NotFound)),
+            }
+        }
+
+        fn is_dir(&self, path: &Path) -> bool {
+            self.lock().get(path).is_some_and(Option::is_none)
+        }
+
+        fn create_dir_all(&self, dir: &Path) -> io::Result<()> {
+            if self.is_file(dir) {
+                return Err(io::Error::from(io::ErrorKind::AlreadyExists));
+            }
+            self.add_dir(dir);
+            Ok(())
+        }
+
+        fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+            let mut entries = self.lock();
+            if !to.parent().is_some_and(|parent| entries.get(parent).is_some_and(Option::is_none)) {
+                return Err(io::Error::from(io::ErrorKind::NotFound));
+            }
+            if entries.get(to).is_some_and(Option::is_none) {
+                return Err(io::Error::from(io::ErrorKind::IsADirectory));
+            }
+            let stat = entries.remove(from).ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))?;
+            entries.insert(to.to_path_buf(), stat);
+            Ok(())
+        }
+
+        fn copy_file(&self, from: &Path, to: &Path) -> io::Result<()> {
+            let mut entries = self.lock();
+            if !to.parent().is_some_and(|parent| entries.get(parent).is_some_and(Option::is_none)) {
+                return Err(io::Error::from(io::ErrorKind::NotFound));
+            }
+            if entries.get(to).is_some_and(Option::is_none) {
+                return Err(io::Error::from(io::ErrorKind::IsADirectory));
+            }
+            let stat = entries.get(from).copied().flatten().ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))?;
+            entries.insert(to.to_path_buf(), Some(stat));
+            Ok(())
+        }
+
+        fn set_modified(&self, path: &Path, modified: SystemTime) -> io::Result<()> {
+            match self.lock().get_mut(path) {
+                Some(Some(stat)) => {
+                    stat.modified = Some(modified);
+                    Ok(())
+                }
+                // Folders have no timestamps in memory
+                Some(None) => Ok(()),
+                None => Err(io::Error::from(io::ErrorKind::NotFound)),
+            }
+        }
+
+        fn hydrate(&self, path: &Path) -> io::Result<bool> {
+            match self.lock().get_mut(path) {
+                Some(Some(stat)) => Ok(std::mem::replace(&mut stat.is_placeholder, false)),
+                Some(None) => Err(io::Error::from(io::ErrorKind::IsADirectory)),
+                None => Err(io::Error::from(io::ErrorKind::NotFound)),
+            }
+        }
+
+        /// There is no Recycle Bin in memory, recycled files just leave their path as they do on Windows
+        fn recycle_file(&self, path: &Path) -> io::Result<()> {
+            self.lock().remove(path);
+            Ok(())
+        }
+
+        fn remove_file(&self, path: &Path) -> io::Result<()> {
+            match self.lock().remove(path) {
+                Some(Some(_)) => Ok(()),
+                _ => Err(io::Error::from(io::ErrorKind::NotFound)),
+            }
+        }
+
+        fn remove_dir(&self, path: &Path) -> io::Result<()> {
+            let mut entries = self.lock();
+            if entries.keys().any(|entry| entry.parent() == Some(path)) {
+                return Err(io::Error::from(io::ErrorKind::DirectoryNotEmpty));
+            }
+            match entries.remove(path) {
+                Some(None) => Ok(()),
+                _ => Err(io::Error::from(io::ErrorKind::NotFound)),
+            }
+        }
+
+        fn is_case_insensitive(&self, _destination: &Path) -> bool {
+            self.reports_case_insensitive
+        }
+
+        fn remove_dir_all(&self, path: &Path) -> io::Result<()> {
+            let mut entries = self.lock();
+            if !entries.get(path).is_some_and(Option::is_none) {
+                return Err(io::Error::from(io::ErrorKind::NotFound));
+            }
+            entries.retain(|entry, _| !entry.starts_with(path));
+            Ok(())
+        }
+
+        fn recycle_dir(&self, path: &Path) -> io::Result<()> {
+            self.remove_dir_all(path)
+        }
+
+        /// Files hold as many zero bytes as their size
+        fn hash_file(&self, path: &Path) -> io::Result<String> {
+            let size = self.stat(path)?.size;
+            Ok(blake3::hash(&vec![0; size as usize]).to_hex().to_string())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{DateTime, Utc};
+
+    #[cfg(windows)]
+    #[test]
+    fn test_to_extended_length_path() {
+        assert_eq!(to_extended_length_path(Path::new(r"C:\Archive\2025-06\..\2025-07\a.txt")), Path::new(r"\\?\C:\Archive\2025-07\a.txt"));
+        assert_eq!(to_extended_length_path(Path::new(r"\\nas\photos\a.jpg")), Path::new(r"\\?\UNC\nas\photos\a.jpg"));
+        assert_eq!(to_extended_length_path(Path::new(r"\\?\C:\a.txt")), Path::new(r"\\?\C:\a.txt"));
+    }
+
+    #[test]
+    fn test_memory_storage_walk_prunes_rejected_folders() {
+        let storage = MemoryStorage::default();
+        let date = "2025-01-01T00:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        storage.add_file("/src/a.txt", date);
+        storage.add_file("/src/skipped/b.txt", date);
+        storage.add_file("/src/kept/c.txt", date);
+
+        let keep = Box::new(|entry: &StorageEntry| !entry.path.ends_with("skipped"));
+        let walked = storage.walk(Path::new("/src"), WalkOptions::default(), keep)
+            .map(|entry| entry.unwrap().path)
+            .collect::<Vec<_>>();
+
+        assert_eq!(walked, ["/src", "/src/a.txt", "/src/kept", "/src/kept/c.txt"].map(PathBuf::from));
+    }
+
+    #[test]
+    fn test_memory_storage_with_tree() {
+        let storage = MemoryStorage::with_tree([("/src/a.txt", "2025-01-01"), ("/src/b.txt", "2025-02-01T12:30:00Z"), ("/src/empty/", "")]);
+        storage.set_dates("/src/a.txt", None, Some("2025-03-01T00:00:00Z".parse::<DateTime<Utc>>().unwrap()), None);
+
+        assert_eq!(storage.file_paths(), ["/src/a.txt", "/src/b.txt"].map(PathBuf::from));
+        assert_eq!(storage.dir_paths(), ["/", "/src", "/src/empty"].map(PathBuf::from));
+        let a = storage.stat(Path::new("/src/a.txt")).unwrap();
+        assert_eq!((a.created, a.modified.map(DateTime::<Utc>::from)), (None, Some("2025-03-01T00:00:00Z".parse().unwrap())));
+        let b = storage.stat(Path::new("/src/b.txt")).unwrap();
+        assert_eq!(b.modified.map(DateTime::<Utc>::from), Some("2025-02-01T12:30:00Z".parse().unwrap()));
+    }
+}
@@ -0,0 +1,85 @@
+use crate::file::run_in_order;
+use crate::logging::SUMMARY_TARGET;
+use crate::manifest::{hash_file, load_manifest, reroot_destination, ManifestEntry};
+use crate::model::VerifyArgs;
+use color_eyre::eyre::{bail, Result};
+use std::fs;
+use std::path::Path;
+use tracing::{error, info, warn};
+
+#[derive(Debug)]
+enum Drift {
+    Missing,
+    SizeChanged { expected: u64, actual: u64 },
+    HashChanged,
+    Unreadable(String),
+}
+
+/// Check that every file recorded in a run manifest is still present and unchanged in the archive
+pub fn verify_run(args: &VerifyArgs) -> Result<()> {
+    if args.jobs == 0 {
+        bail!("Number of jobs must be greater than 0");
+    }
+    let (run_id, entries) = load_manifest(args.run.as_deref())?;
+
+    info!("Verifying {} file(s) moved by run '{}'...", entries.len(), run_id);
+
+    let get_destination = |entry: &ManifestEntry| match &args.destination {
+        Some(new_archive_root) => reroot_destination(entry, new_archive_root),
+        None => entry.destination.clone(),
+    };
+
+    // Files are hashed on the worker threads, while drift is reported in manifest order
+    let mut drift_count = 0;
+    let check = |entry: &ManifestEntry| check_entry(entry, &get_destination(entry));
+    run_in_order(&entries, args.jobs, check, |index, drift| {
+        let destination = get_destination(&entries[index]);
+        if let Some(drift) = drift {
+            drift_count += 1;
+            match drift {
+                Drift::Missing => {
+                    warn!(path = %destination.display(), "Missing: {}", destination.display());
+                }
+                Drift::SizeChanged { expected, actual } => {
+                    warn!(
+                        path = %destination.display(), expected, actual,
+                        "Size changed: {} (expected {} bytes, found {} bytes)", destination.display(), expected, actual
+                    );
+                }
+                Drift::HashChanged => {
+                    warn!(path = %destination.display(), "Content changed: {}", destination.display());
+                }
+                Drift::Unreadable(e) => {
+                    error!(path = %destination.display(), error = %e, "Checking {}: {}", destination.display(), e);
+                }
+            }
+        }
+        Ok(())
+    })?;
+
+    if drift_count > 0 {
+        bail!("{} of {} file(s) from run '{}' no longer match the manifest", drift_count, entries.len(), run_id);
+    }
+
+    info!(target: SUMMARY_TARGET, "All {} file(s) from run '{}' match the manifest", entries.len(), run_id);
+    Ok(())
+}
+
+fn check_entry(entry: &ManifestEntry, destination: &Path) -> Option<Drift> {
+    let metadata = match fs::metadata(destination) {
+        Ok(metadata) => metadata,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Some(Drift::Missing),
+        Err(e) => return Some(Drift::Unreadable(e.to_string())),
+    };
+
+    if metadata.len() != entry.size {
+        return Some(Drift::SizeChanged { expected: entry.size, actual: metadata.len() });
+    }
+
+    let expected_hash = entry.hash.as_ref()?;
+    match hash_file(destination) {
+        Ok(actual_hash) if &actual_hash == expected_hash => None,
+        Ok(_) => Some(Drift::HashChanged),
+        Err(e) => Some(Drift::Unreadable(e.to_string())),
+    }
+}
@@ -0,0 +1,119 @@
+/* Generated by cbindgen from chronomover-ffi/src/lib.rs, do not edit */
+
+#ifndef CHRONOMOVER_H
+#define CHRONOMOVER_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// Outcome of a call, details being written to its `out_error` string on failure
+typedef enum ChronomoverStatus {
+  CHRONOMOVER_STATUS_OK = 0,
+  // A pointer was NULL, a string wasn't valid UTF-8, or an option was unknown
+  CHRONOMOVER_STATUS_INVALID_ARGUMENT = 1,
+  // Planning or executing failed, e.g. because a source doesn't exist
+  CHRONOMOVER_STATUS_FAILED = 2,
+} ChronomoverStatus;
+
+// Files planned to move, opaque to C
+typedef struct ChronomoverPlan ChronomoverPlan;
+
+// Options of a plan, the command line defaults applying to everything else
+typedef struct ChronomoverOptions {
+  // Grouping strategy, as `--group-by` takes it (e.g., "month"), or NULL not to group the files
+  const char *group_by;
+  // Only plan the files of the periods before the current one, as `--previous-period-only`
+  bool previous_period_only;
+  // Only plan the files older than this Unix timestamp in seconds, or 0 for no cutoff
+  int64_t older_than;
+} ChronomoverOptions;
+
+// Counts of an executed plan
+typedef struct ChronomoverReport {
+  size_t moved;
+  size_t failed;
+} ChronomoverReport;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Plan the moves of the files of `sources` into `destination`, nothing being moved yet. On success, `*out_plan` is
+// set to a plan to free with `chronomover_plan_free()`.
+//
+// # Safety
+//
+// `sources` must point to `source_count` valid strings, `destination` must be a valid string, `options` must be NULL
+// or point to valid options, and `out_plan` must be a valid pointer. `out_error` may be NULL.
+enum ChronomoverStatus chronomover_plan(const char *const *sources,
+                                        size_t source_count,
+                                        const char *destination,
+                                        const struct ChronomoverOptions *options,
+                                        struct ChronomoverPlan **out_plan,
+                                        char **out_error);
+
+// Number of files planned to move, 0 for a NULL plan
+//
+// # Safety
+//
+// `plan` must be NULL or a plan returned by `chronomover_plan()`, not freed yet.
+size_t chronomover_plan_len(const struct ChronomoverPlan *plan);
+
+// Source of the planned file at `index`, or NULL past the end. The string belongs to the plan.
+//
+// # Safety
+//
+// `plan` must be NULL or a plan returned by `chronomover_plan()`, not freed yet.
+const char *chronomover_plan_source(const struct ChronomoverPlan *plan, size_t index);
+
+// Destination of the planned file at `index`, or NULL past the end. The string belongs to the plan.
+//
+// # Safety
+//
+// `plan` must be NULL or a plan returned by `chronomover_plan()`, not freed yet.
+const char *chronomover_plan_destination(const struct ChronomoverPlan *plan, size_t index);
+
+// Move the planned files, recording the run manifest, then delete the source folders left empty. With `dry_run`,
+// nothing is moved and `out_report` counts the files that would have been. Files failing to move are counted in
+// `out_report` instead of failing the call.
+//
+// # Safety
+//
+// `plan` must be a plan returned by `chronomover_plan()`, not freed yet, and `out_report` must be NULL or a valid
+// pointer. `out_error` may be NULL.
+enum ChronomoverStatus chronomover_execute(const struct ChronomoverPlan *plan,
+                                           bool dry_run,
+                                           struct ChronomoverReport *out_report,
+                                           char **out_error);
+
+// Name of the period folder a Unix timestamp in seconds is grouped into (e.g., "2025-06" with "month"), or NULL for
+// an unknown grouping strategy. Free the string with `chronomover_string_free()`.
+//
+// # Safety
+//
+// `group_by` must be a valid string.
+char *chronomover_period_identifier(const char *group_by,
+                                    int64_t timestamp);
+
+// Free a plan returned by `chronomover_plan()`, along with the paths it handed out. NULL is ignored.
+//
+// # Safety
+//
+// `plan` must be NULL or a plan returned by `chronomover_plan()`, not freed yet.
+void chronomover_plan_free(struct ChronomoverPlan *plan);
+
+// Free a string returned by the library. NULL is ignored.
+//
+// # Safety
+//
+// `string` must be NULL or a string returned by the library, not freed yet.
+void chronomover_string_free(char *string);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* CHRONOMOVER_H */
@@ -0,0 +1,424 @@
+//! C API of the planning core, so file manager extensions (e.g., Dolphin or Nautilus plugins) and other programs not
+//! written in Rust can plan and execute moves with the same grouping logic as the command line. The header is
+//! generated by cbindgen into `include/chronomover.h`.
+//!
+//! Every string is NUL-terminated UTF-8. Strings and plans returned by the library are freed with
+//! `chronomover_string_free()` and `chronomover_plan_free()`, never with `free()`. A panic never unwinds into the
+//! caller: calls returning a status fail with `CHRONOMOVER_STATUS_FAILED`, the others return NULL or 0.
+
+use chronomover_core::date::get_period_identifier;
+use chronomover_core::model::GroupBy;
+use chronomover_core::{Mover, Plan, Planner};
+use chrono::DateTime;
+use std::any::Any;
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+
+/// Outcome of a call, details being written to its `out_error` string on failure
+#[repr(C)]
+#[derive(Debug, PartialEq)]
+pub enum ChronomoverStatus {
+    Ok = 0,
+    /// A pointer was NULL, a string wasn't valid UTF-8, or an option was unknown
+    InvalidArgument = 1,
+    /// Planning or executing failed, e.g. because a source doesn't exist
+    Failed = 2,
+}
+
+/// Options of a plan, the command line defaults applying to everything else
+#[repr(C)]
+pub struct ChronomoverOptions {
+    /// Grouping strategy, as `--group-by` takes it (e.g., "month"), or NULL not to group the files
+    pub group_by: *const c_char,
+    /// Only plan the files of the periods before the current one, as `--previous-period-only`
+    pub previous_period_only: bool,
+    /// Only plan the files older than this Unix timestamp in seconds, or 0 for no cutoff
+    pub older_than: i64,
+}
+
+/// Counts of an executed plan
+#[repr(C)]
+#[derive(Debug, Default)]
+pub struct ChronomoverReport {
+    pub moved: usize,
+    pub failed: usize,
+}
+
+/// Files planned to move, opaque to C
+pub struct ChronomoverPlan {
+    planner: Planner,
+    plan: Plan,
+    /// Sources and destinations of the planned files, kept alive as long as the plan for the pointers handed out
+    paths: Vec<(CString, CString)>,
+}
+
+/// Plan the moves of the files of `sources` into `destination`, nothing being moved yet. On success, `*out_plan` is
+/// set to a plan to free with `chronomover_plan_free()`.
+///
+/// # Safety
+///
+/// `sources` must point to `source_count` valid strings, `destination` must be a valid string, `options` must be NULL
+/// or point to valid options, and `out_plan` must be a valid pointer. `out_error` may be NULL.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn chronomover_plan(
+    sources: *const *const c_char,
+    source_count: usize,
+    destination: *const c_char,
+    options: *const ChronomoverOptions,
+    out_plan: *mut *mut ChronomoverPlan,
+    out_error: *mut *mut c_char,
+) -> ChronomoverStatus {
+    // SAFETY: the caller guarantees the pointers are valid, as `plan()` requires
+    unsafe { catch_panic_status(out_error, || plan(sources, source_count, destination, options, out_plan, out_error)) }
+}
+
+/// # Safety
+///
+/// As `chronomover_plan()`.
+unsafe fn plan(
+    sources: *const *const c_char,
+    source_count: usize,
+    destination: *const c_char,
+    options: *const ChronomoverOptions,
+    out_plan: *mut *mut ChronomoverPlan,
+    out_error: *mut *mut c_char,
+) -> ChronomoverStatus {
+    if sources.is_null() || out_plan.is_null() {
+        return unsafe { fail(out_error, ChronomoverStatus::InvalidArgument, "sources and out_plan can't be NULL") };
+    }
+    // SAFETY: the caller guarantees `sources` points to `source_count` valid strings
+    let source_paths = unsafe { std::slice::from_raw_parts(sources, source_count) }
+        .iter()
+        .map(|&source| unsafe { read_str(source, "source") })
+        .collect::<Result<Vec<_>, _>>();
+    let planner = source_paths
+        .and_then(|source_paths| Ok((source_paths, unsafe { read_str(destination, "destination") }?)))
+        .and_then(|(source_paths, destination)| {
+            Planner::new(source_paths, destination).map_err(|e| format!("{e:#}"))
+        })
+        .and_then(|planner| unsafe { apply_options(planner, options) });
+    let planner = match planner {
+        Ok(planner) => planner,
+        Err(e) => return unsafe { fail(out_error, ChronomoverStatus::InvalidArgument, &e) },
+    };
+
+    let plan = match planner.plan() {
+        Ok(plan) => plan,
+        Err(e) => return unsafe { fail(out_error, ChronomoverStatus::Failed, &format!("{e:#}")) },
+    };
+    let paths = plan.files_to_move.iter()
+        .map(|file| (to_c_string(&file.source.to_string_lossy()), to_c_string(&file.destination.to_string_lossy())))
+        .collect();
+    // SAFETY: checked to be non-NULL above, the caller guarantees it is valid
+    unsafe { *out_plan = Box::into_raw(Box::new(ChronomoverPlan { planner, plan, paths })) };
+    ChronomoverStatus::Ok
+}
+
+/// Number of files planned to move, 0 for a NULL plan
+///
+/// # Safety
+///
+/// `plan` must be NULL or a plan returned by `chronomover_plan()`, not freed yet.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn chronomover_plan_len(plan: *const ChronomoverPlan) -> usize {
+    // SAFETY: the caller guarantees the plan is NULL or valid
+    catch_panic(0, || unsafe { plan.as_ref() }.map_or(0, |plan| plan.paths.len()))
+}
+
+/// Source of the planned file at `index`, or NULL past the end. The string belongs to the plan.
+///
+/// # Safety
+///
+/// `plan` must be NULL or a plan returned by `chronomover_plan()`, not freed yet.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn chronomover_plan_source(plan: *const ChronomoverPlan, index: usize) -> *const c_char {
+    catch_panic(ptr::null(), || {
+        // SAFETY: the caller guarantees the plan is NULL or valid
+        unsafe { plan.as_ref() }
+            .and_then(|plan| plan.paths.get(index))
+            .map_or(ptr::null(), |(source, _)| source.as_ptr())
+    })
+}
+
+/// Destination of the planned file at `index`, or NULL past the end. The string belongs to the plan.
+///
+/// # Safety
+///
+/// `plan` must be NULL or a plan returned by `chronomover_plan()`, not freed yet.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn chronomover_plan_destination(plan: *const ChronomoverPlan, index: usize) -> *const c_char {
+    catch_panic(ptr::null(), || {
+        // SAFETY: the caller guarantees the plan is NULL or valid
+        unsafe { plan.as_ref() }
+            .and_then(|plan| plan.paths.get(index))
+            .map_or(ptr::null(), |(_, destination)| destination.as_ptr())
+    })
+}
+
+/// Move the planned files, recording the run manifest, then delete the source folders left empty. With `dry_run`,
+/// nothing is moved and `out_report` counts the files that would have been. Files failing to move are counted in
+/// `out_report` instead of failing the call.
+///
+/// # Safety
+///
+/// `plan` must be a plan returned by `chronomover_plan()`, not freed yet, and `out_report` must be NULL or a valid
+/// pointer. `out_error` may be NULL.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn chronomover_execute(
+    plan: *const ChronomoverPlan,
+    dry_run: bool,
+    out_report: *mut ChronomoverReport,
+    out_error: *mut *mut c_char,
+) -> ChronomoverStatus {
+    // SAFETY: the caller guarantees the pointers are valid, as `execute()` requires
+    unsafe { catch_panic_status(out_error, || execute(plan, dry_run, out_report, out_error)) }
+}
+
+/// # Safety
+///
+/// As `chronomover_execute()`.
+unsafe fn execute(
+    plan: *const ChronomoverPlan,
+    dry_run: bool,
+    out_report: *mut ChronomoverReport,
+    out_error: *mut *mut c_char,
+) -> ChronomoverStatus {
+    // SAFETY: the caller guarantees the plan is NULL or valid
+    let Some(plan) = (unsafe { plan.as_ref() }) else {
+        return unsafe { fail(out_error, ChronomoverStatus::InvalidArgument, "plan can't be NULL") };
+    };
+    match Mover::new(&plan.planner).dry_run(dry_run).execute(&plan.plan) {
+        Ok(move_report) => {
+            // SAFETY: the caller guarantees the report is NULL or valid
+            if let Some(out_report) = unsafe { out_report.as_mut() } {
+                *out_report = ChronomoverReport { moved: move_report.moved_count(), failed: move_report.errors.len() };
+            }
+            ChronomoverStatus::Ok
+        }
+        Err(e) => unsafe { fail(out_error, ChronomoverStatus::Failed, &format!("{e:#}")) },
+    }
+}
+
+/// Name of the period folder a Unix timestamp in seconds is grouped into (e.g., "2025-06" with "month"), or NULL for
+/// an unknown grouping strategy. Free the string with `chronomover_string_free()`.
+///
+/// # Safety
+///
+/// `group_by` must be a valid string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn chronomover_period_identifier(group_by: *const c_char, timestamp: i64) -> *mut c_char {
+    catch_panic(ptr::null_mut(), || {
+        let group_by = unsafe { read_str(group_by, "group_by") }.and_then(parse_group_by);
+        match (group_by, DateTime::from_timestamp(timestamp, 0)) {
+            (Ok(group_by), Some(date)) => to_c_string(&get_period_identifier(group_by, date)).into_raw(),
+            _ => ptr::null_mut(),
+        }
+    })
+}
+
+/// Free a plan returned by `chronomover_plan()`, along with the paths it handed out. NULL is ignored.
+///
+/// # Safety
+///
+/// `plan` must be NULL or a plan returned by `chronomover_plan()`, not freed yet.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn chronomover_plan_free(plan: *mut ChronomoverPlan) {
+    catch_panic((), || {
+        if !plan.is_null() {
+            // SAFETY: the caller guarantees the plan came from `Box::into_raw()` in `chronomover_plan()`
+            drop(unsafe { Box::from_raw(plan) });
+        }
+    })
+}
+
+/// Free a string returned by the library. NULL is ignored.
+///
+/// # Safety
+///
+/// `string` must be NULL or a string returned by the library, not freed yet.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn chronomover_string_free(string: *mut c_char) {
+    catch_panic((), || {
+        if !string.is_null() {
+            // SAFETY: the caller guarantees the string came from `CString::into_raw()`
+            drop(unsafe { CString::from_raw(string) });
+        }
+    })
+}
+
+/// # Safety
+///
+/// `options` must be NULL or point to valid options.
+unsafe fn apply_options(mut planner: Planner, options: *const ChronomoverOptions) -> Result<Planner, String> {
+    // SAFETY: the caller guarantees the options are NULL or valid
+    let Some(options) = (unsafe { options.as_ref() }) else {
+        return Ok(planner);
+    };
+    if !options.group_by.is_null() {
+        planner = planner.group_by(parse_group_by(unsafe { read_str(options.group_by, "group_by") }?)?);
+    }
+    planner = planner.previous_period_only(options.previous_period_only);
+    if options.older_than != 0 {
+        let cutoff = DateTime::from_timestamp(options.older_than, 0)
+            .ok_or_else(|| format!("Invalid older_than timestamp: {}", options.older_than))?;
+        planner = planner.older_than(cutoff);
+    }
+    Ok(planner)
+}
+
+fn parse_group_by(group_by: &str) -> Result<GroupBy, String> {
+    group_by.parse::<GroupBy>().map_err(|_| format!("Unknown grouping strategy: {group_by}"))
+}
+
+/// # Safety
+///
+/// `string` must be NULL or a valid string, outliving the returned slice.
+unsafe fn read_str<'a>(string: *const c_char, name: &str) -> Result<&'a str, String> {
+    if string.is_null() {
+        return Err(format!("{name} can't be NULL"));
+    }
+    // SAFETY: the caller guarantees the string is valid
+    unsafe { CStr::from_ptr(string) }.to_str().map_err(|_| format!("{name} isn't valid UTF-8"))
+}
+
+/// C copy of a string, without the NUL characters a path can't hold anyway
+fn to_c_string(string: &str) -> CString {
+    // Safe to unwrap: the NUL characters were removed
+    CString::new(string.replace('\0', "")).unwrap()
+}
+
+/// Write the error message to `out_error`, if the caller wants it, and return the status
+///
+/// # Safety
+///
+/// `out_error` must be NULL or a valid pointer.
+unsafe fn fail(out_error: *mut *mut c_char, status: ChronomoverStatus, message: &str) -> ChronomoverStatus {
+    if !out_error.is_null() {
+        // SAFETY: the caller guarantees a non-NULL `out_error` is valid
+        unsafe { *out_error = to_c_string(message).into_raw() };
+    }
+    status
+}
+
+/// Run the body of an entry point, returning `on_panic` if it panics, as a panic must not unwind into C
+fn catch_panic<T>(on_panic: T, body: impl FnOnce() -> T) -> T {
+    panic::catch_unwind(AssertUnwindSafe(body)).unwrap_or(on_panic)
+}
+
+/// `catch_panic()` for the entry points returning a status: a panic fails the call with its message
+///
+/// # Safety
+///
+/// `out_error` must be NULL or a valid pointer.
+unsafe fn catch_panic_status(out_error: *mut *mut c_char, body: impl FnOnce() -> ChronomoverStatus) -> ChronomoverStatus {
+    match panic::catch_unwind(AssertUnwindSafe(body)) {
+        Ok(status) => status,
+        // SAFETY: the caller guarantees `out_error` is NULL or valid
+        Err(payload) => unsafe { fail(out_error, ChronomoverStatus::Failed, &format!("Panicked: {}", get_panic_message(payload.as_ref()))) },
+    }
+}
+
+fn get_panic_message(payload: &(dyn Any + Send)) -> &str {
+    payload.downcast_ref::<&str>().copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("unknown panic")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::Path;
+
+    fn read_c_str<'a>(string: *const c_char) -> &'a str {
+        unsafe { CStr::from_ptr(string) }.to_str().unwrap()
+    }
+
+    #[test]
+    fn test_plan_and_execute() {
+        let root = std::env::temp_dir().join(format!("chronomover-ffi-{}", std::process::id()));
+        let source = root.join("source");
+        fs::create_dir_all(&source).unwrap();
+        fs::write(source.join("notes.txt"), "notes").unwrap();
+        let source_path = CString::new(source.to_str().unwrap()).unwrap();
+        let destination_path = CString::new(root.join("archive").to_str().unwrap()).unwrap();
+        let options = ChronomoverOptions { group_by: c"year".as_ptr(), previous_period_only: false, older_than: 0 };
+        let mut plan = ptr::null_mut();
+
+        let status = unsafe {
+            chronomover_plan(&source_path.as_ptr(), 1, destination_path.as_ptr(), &options, &mut plan, ptr::null_mut())
+        };
+        assert_eq!(status, ChronomoverStatus::Ok);
+        assert_eq!(unsafe { chronomover_plan_len(plan) }, 1);
+        assert!(read_c_str(unsafe { chronomover_plan_source(plan, 0) }).ends_with("notes.txt"));
+        assert!(read_c_str(unsafe { chronomover_plan_destination(plan, 0) }).ends_with("notes.txt"));
+        assert!(unsafe { chronomover_plan_source(plan, 1) }.is_null());
+
+        let mut report = ChronomoverReport::default();
+        let status = unsafe { chronomover_execute(plan, true, &mut report, ptr::null_mut()) };
+        assert_eq!(status, ChronomoverStatus::Ok);
+        assert_eq!((report.moved, report.failed), (1, 0));
+        assert!(source.join("notes.txt").exists());
+
+        unsafe { chronomover_plan_free(plan) };
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_plan_reports_invalid_arguments() {
+        let options = ChronomoverOptions { group_by: c"decade".as_ptr(), previous_period_only: false, older_than: 0 };
+        let mut plan = ptr::null_mut();
+        let mut error = ptr::null_mut();
+
+        let status = unsafe {
+            chronomover_plan(&c"/source".as_ptr(), 1, c"/archive".as_ptr(), &options, &mut plan, &mut error)
+        };
+
+        assert_eq!(status, ChronomoverStatus::InvalidArgument);
+        assert!(plan.is_null());
+        assert_eq!(read_c_str(error), "Unknown grouping strategy: decade");
+        unsafe { chronomover_string_free(error) };
+    }
+
+    #[test]
+    fn test_period_identifier() {
+        // 2025-06-15T00:00:00Z
+        let identifier = unsafe { chronomover_period_identifier(c"trimester".as_ptr(), 1_749_945_600) };
+        assert_eq!(read_c_str(identifier), "2025-Q2");
+        unsafe { chronomover_string_free(identifier) };
+
+        assert!(unsafe { chronomover_period_identifier(c"decade".as_ptr(), 0) }.is_null());
+    }
+
+    #[test]
+    fn test_panic_fails_the_call() {
+        let mut error = ptr::null_mut();
+
+        let status = unsafe { catch_panic_status(&mut error, || panic!("the planner broke")) };
+
+        assert_eq!(status, ChronomoverStatus::Failed);
+        assert_eq!(read_c_str(error), "Panicked: the planner broke");
+        unsafe { chronomover_string_free(error) };
+        assert_eq!(catch_panic(0, || panic!("the plan broke")), 0);
+    }
+
+    #[test]
+    fn test_header_is_up_to_date() {
+        let crate_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let config = cbindgen::Config::from_file(crate_dir.join("cbindgen.toml")).unwrap();
+        let mut generated = Vec::new();
+        cbindgen::Builder::new()
+            .with_config(config)
+            .with_src(crate_dir.join("src/lib.rs"))
+            .generate()
+            .unwrap()
+            .write(&mut generated);
+
+        let header = fs::read_to_string(crate_dir.join("include/chronomover.h")).unwrap();
+        assert!(
+            String::from_utf8(generated).unwrap() == header,
+            "include/chronomover.h is out of date, regenerate it with `cbindgen --config cbindgen.toml --crate chronomover-ffi --output include/chronomover.h` from chronomover-ffi"
+        );
+    }
+}
@@ -0,0 +1,223 @@
+//! `chronomover` Python module, wrapping the planner and mover of the core so scripts can organize files by period
+//! programmatically. Built into a wheel with maturin (`maturin build --release` in this folder).
+//!
+//! ```python
+//! from datetime import datetime, timezone
+//! import chronomover
+//!
+//! planner = chronomover.Planner(["/data/drops"], "/data/archive", group_by="month",
+//!                               older_than=datetime(2025, 1, 1, tzinfo=timezone.utc))
+//! plan = planner.plan()
+//! for file in plan.files:
+//!     print(file.source, "->", file.destination)
+//! report = planner.execute(plan, dry_run=True)
+//! ```
+
+use chrono::{DateTime, Utc};
+use chronomover_core::date::get_period_identifier;
+use chronomover_core::file::{FileError, FileToMove};
+use chronomover_core::model::GroupBy;
+use chronomover_core::{Mover, Plan, Planner};
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+use std::path::PathBuf;
+
+/// Plans which files of the source folders move where, with the defaults of the command line for the other options
+#[pyclass(name = "Planner", module = "chronomover", frozen)]
+struct PyPlanner {
+    planner: Planner,
+}
+
+#[pymethods]
+impl PyPlanner {
+    #[new]
+    #[pyo3(signature = (sources, destination, *, group_by = None, older_than = None, previous_period_only = false))]
+    fn new(
+        sources: Vec<PathBuf>,
+        destination: PathBuf,
+        group_by: Option<&str>,
+        older_than: Option<DateTime<Utc>>,
+        previous_period_only: bool,
+    ) -> PyResult<Self> {
+        let mut planner = Planner::new(sources, destination).map_err(|e| PyValueError::new_err(format!("{e:#}")))?;
+        if let Some(group_by) = group_by {
+            planner = planner.group_by(parse_group_by(group_by)?);
+        }
+        if let Some(older_than) = older_than {
+            planner = planner.older_than(older_than);
+        }
+        Ok(Self { planner: planner.previous_period_only(previous_period_only) })
+    }
+
+    /// Scan the sources for the files to move, nothing being moved yet
+    fn plan(&self, py: Python<'_>) -> PyResult<PyPlan> {
+        // Scanning may take long on large folders, so other Python threads keep running meanwhile
+        let plan = py.detach(|| self.planner.plan()).map_err(|e| PyRuntimeError::new_err(format!("{e:#}")))?;
+        Ok(PyPlan::from(plan))
+    }
+
+    /// Move the files of a plan, then delete the source folders left empty. With `dry_run`, nothing is moved and the
+    /// report lists the files that would have been.
+    #[pyo3(signature = (plan, *, dry_run = false))]
+    fn execute(&self, py: Python<'_>, plan: &PyPlan, dry_run: bool) -> PyResult<PyReport> {
+        let mover = Mover::new(&self.planner).dry_run(dry_run);
+        let move_report = py.detach(|| mover.execute(&plan.plan)).map_err(|e| PyRuntimeError::new_err(format!("{e:#}")))?;
+        Ok(PyReport {
+            moved: move_report.moved.iter().map(PlannedFile::from).collect(),
+            errors: move_report.errors.iter().map(PlanError::from).collect(),
+            run_id: move_report.run_id,
+        })
+    }
+}
+
+/// Files planned to move, plus the files that could not be evaluated
+#[pyclass(name = "Plan", module = "chronomover", frozen)]
+struct PyPlan {
+    plan: Plan,
+    #[pyo3(get)]
+    files: Vec<PlannedFile>,
+    #[pyo3(get)]
+    errors: Vec<PlanError>,
+    /// Files left in place by the filters
+    #[pyo3(get)]
+    skipped: usize,
+}
+
+impl From<Plan> for PyPlan {
+    fn from(plan: Plan) -> Self {
+        Self {
+            files: plan.files_to_move.iter().map(PlannedFile::from).collect(),
+            errors: plan.errors.iter().map(PlanError::from).collect(),
+            skipped: plan.skipped,
+            plan,
+        }
+    }
+}
+
+#[pymethods]
+impl PyPlan {
+    fn __len__(&self) -> usize {
+        self.files.len()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Plan(files={}, errors={}, skipped={})", self.files.len(), self.errors.len(), self.skipped)
+    }
+}
+
+/// A file planned to move, or moved
+#[pyclass(module = "chronomover", frozen, get_all, skip_from_py_object)]
+#[derive(Clone)]
+struct PlannedFile {
+    source: PathBuf,
+    destination: PathBuf,
+    date: DateTime<Utc>,
+    size: u64,
+    /// Period folder the file is grouped into, if grouped
+    period: Option<String>,
+}
+
+impl From<&FileToMove> for PlannedFile {
+    fn from(file: &FileToMove) -> Self {
+        Self {
+            source: file.source.clone(),
+            destination: file.destination.clone(),
+            date: file.file_date,
+            size: file.size,
+            period: file.period.clone(),
+        }
+    }
+}
+
+#[pymethods]
+impl PlannedFile {
+    fn __repr__(&self) -> String {
+        format!("PlannedFile(source={:?}, destination={:?})", self.source, self.destination)
+    }
+}
+
+/// Python view of [`FileError`]
+#[pyclass(module = "chronomover", frozen, get_all, skip_from_py_object)]
+#[derive(Clone)]
+struct PlanError {
+    path: PathBuf,
+    error: String,
+}
+
+impl From<&FileError> for PlanError {
+    fn from(error: &FileError) -> Self {
+        Self { path: error.path.clone(), error: error.error.clone() }
+    }
+}
+
+/// Python view of [`MoveReport`](chronomover_core::MoveReport)
+#[pyclass(name = "Report", module = "chronomover", frozen, get_all)]
+struct PyReport {
+    moved: Vec<PlannedFile>,
+    errors: Vec<PlanError>,
+    /// Identifier of the run manifest, for `chronomover verify` (None in dry-run mode)
+    run_id: Option<String>,
+}
+
+/// Name of the period folder a date is grouped into (e.g., "2025-06" with "month")
+#[pyfunction]
+fn period_identifier(group_by: &str, date: DateTime<Utc>) -> PyResult<String> {
+    Ok(get_period_identifier(parse_group_by(group_by)?, date))
+}
+
+fn parse_group_by(group_by: &str) -> PyResult<GroupBy> {
+    group_by.parse::<GroupBy>().map_err(|_| PyValueError::new_err(format!("Unknown grouping strategy: {group_by}")))
+}
+
+#[pymodule(name = "chronomover")]
+fn chronomover_module(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyPlanner>()?;
+    module.add_class::<PyPlan>()?;
+    module.add_class::<PlannedFile>()?;
+    module.add_class::<PlanError>()?;
+    module.add_class::<PyReport>()?;
+    module.add_function(wrap_pyfunction!(period_identifier, module)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pyo3::types::PyDict;
+    use std::ffi::CString;
+    use std::fs;
+
+    #[test]
+    fn test_dry_run_plan_from_python() {
+        let root = std::env::temp_dir().join(format!("chronomover-py-{}", std::process::id()));
+        let source = root.join("drops");
+        fs::create_dir_all(&source).unwrap();
+        fs::write(source.join("dataset.csv"), "a,b").unwrap();
+
+        pyo3::append_to_inittab!(chronomover_module);
+        Python::initialize();
+        Python::attach(|py| {
+            let locals = PyDict::new(py);
+            locals.set_item("source", &source).unwrap();
+            locals.set_item("destination", root.join("archive")).unwrap();
+            let script = CString::new(r#"
+import chronomover
+planner = chronomover.Planner([source], destination, group_by="year")
+plan = planner.plan()
+report = planner.execute(plan, dry_run=True)
+result = (len(plan), plan.files[0].destination.parent.name, len(report.moved), report.run_id)
+"#).unwrap();
+            py.run(&script, None, Some(&locals)).unwrap();
+
+            let (planned, period, moved, run_id): (usize, String, usize, Option<String>) =
+                locals.get_item("result").unwrap().unwrap().extract().unwrap();
+            assert_eq!((planned, moved, run_id), (1, 1, None));
+            assert_eq!(period, get_period_identifier(GroupBy::Year, Utc::now()));
+
+            let script = c"import chronomover, datetime; chronomover.period_identifier('decade', datetime.datetime.now(datetime.timezone.utc))";
+            assert!(py.run(script, None, None).unwrap_err().is_instance_of::<PyValueError>(py));
+        });
+        assert!(source.join("dataset.csv").exists());
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,192 @@
+use chronomover_core::file::{delete_empty_directories, get_files_to_move, move_files};
+use chronomover_core::logging::SUMMARY_TARGET;
+use chronomover_core::manifest::{get_manifest_dir, get_manifest_path};
+use crate::cli::BenchArgs;
+use chronomover_core::model::{enrich_arguments, Args, FileDateType};
+use chronomover_core::report::PhaseTimings;
+use chronomover_core::storage::LocalStorage;
+use chrono::Utc;
+use color_eyre::eyre::{bail, Context, Result};
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+use tracing::info;
+
+/// Number of days the modification dates of the generated files are spread over, so they land in many period folders
+const DATE_SPREAD_DAYS: usize = 730;
+
+/// Temporary folder holding the generated tree and its archive, deleted on drop
+struct BenchDir {
+    path: PathBuf,
+}
+
+impl BenchDir {
+    fn create() -> Result<Self> {
+        let path = std::env::temp_dir().join(format!("chronomover-bench-{}", std::process::id()));
+        // A previous benchmark that was killed may have left its folder behind
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).with_context(|| format!("Failed to create directory: {}", path.display()))?;
+        Ok(Self { path })
+    }
+}
+
+impl Drop for BenchDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
+
+/// Generate a temporary tree and move it into a temporary archive once per job count, then print the duration of
+/// each phase and the overall throughput
+pub fn run_bench(args: &BenchArgs) -> Result<()> {
+    if args.files == 0 {
+        bail!("Number of files must be greater than 0");
+    }
+    if args.width == 0 {
+        bail!("Number of subfolders must be greater than 0");
+    }
+    if args.width.checked_pow(args.depth).is_none_or(|leaf_dirs| leaf_dirs > args.files) {
+        bail!("The generated tree can't have more deepest folders ({}^{}) than files ({})", args.width, args.depth, args.files);
+    }
+    if args.jobs.contains(&0) {
+        bail!("Number of jobs must be greater than 0");
+    }
+
+    info!(
+        target: SUMMARY_TARGET,
+        "Benchmarking {} file(s) in {} folder level(s) of {} subfolder(s)...", args.files, args.depth, args.width
+    );
+    let mut results = Vec::new();
+    for &jobs in &args.jobs {
+        info!(target: SUMMARY_TARGET, "Running with {} job(s)...", jobs);
+        let bench_dir = BenchDir::create()?;
+        let source = bench_dir.path.join("source");
+        generate_tree(&source, args)?;
+        results.push((jobs, measure_run(&source, &bench_dir.path.join("archive"), args, jobs)?));
+    }
+
+    for line in render_bench_results(args.files, &results) {
+        info!(target: SUMMARY_TARGET, "{}", line);
+    }
+    Ok(())
+}
+
+/// Create empty files spread evenly over the deepest folders of the tree, with modification dates spread over the
+/// last `DATE_SPREAD_DAYS` days
+fn generate_tree(source: &Path, args: &BenchArgs) -> Result<()> {
+    let leaf_dirs = get_leaf_dirs(source, args.depth, args.width);
+    let now = SystemTime::now();
+    for index in 0..args.files {
+        let dir = &leaf_dirs[index % leaf_dirs.len()];
+        if index < leaf_dirs.len() {
+            fs::create_dir_all(dir).with_context(|| format!("Failed to create directory: {}", dir.display()))?;
+        }
+
+        let path = dir.join(format!("file-{index}.txt"));
+        let file = File::create(&path).with_context(|| format!("Failed to create file: {}", path.display()))?;
+        let age = Duration::from_secs((index % DATE_SPREAD_DAYS) as u64 * 24 * 60 * 60);
+        file.set_modified(now - age)
+            .with_context(|| format!("Failed to set the modification date of: {}", path.display()))?;
+    }
+    Ok(())
+}
+
+fn get_leaf_dirs(root: &Path, depth: u32, width: usize) -> Vec<PathBuf> {
+    let mut dirs = vec![root.to_path_buf()];
+    for level in 0..depth {
+        dirs = dirs.iter()
+            .flat_map(|dir| (0..width).map(move |index| dir.join(format!("dir-{level}-{index}"))))
+            .collect();
+    }
+    dirs
+}
+
+/// Move the generated tree like a regular run would, without leaving a run manifest behind
+fn measure_run(source: &Path, archive: &Path, bench_args: &BenchArgs, jobs: usize) -> Result<PhaseTimings> {
+    // Built from the defaults rather than parsed, so no CHRONOMOVER_* variable (catalog, hooks, filters) leaks in
+    let args = enrich_arguments(&Args {
+        sources: vec![source.into()],
+        destination: archive.into(),
+        group_by: Some(bench_args.group_by),
+        file_date_types: vec![FileDateType::Modified],
+        jobs,
+        ..Args::default()
+    });
+
+    let plan = get_files_to_move(&args, &LocalStorage, Utc::now(), None)?;
+    let move_started_at = Instant::now();
+    let report = move_files(&args, &LocalStorage, &plan.files_to_move, false)?;
+    let move_duration = move_started_at.elapsed();
+    let cleanup_started_at = Instant::now();
+    delete_empty_directories(&args, &LocalStorage, &report.moved)?;
+    let cleanup_duration = cleanup_started_at.elapsed();
+
+    if let Some(run_id) = &report.run_id {
+        let manifest_path = get_manifest_path(&get_manifest_dir()?, run_id);
+        fs::remove_file(&manifest_path)
+            .with_context(|| format!("Failed to delete run manifest: {}", manifest_path.display()))?;
+    }
+    if report.moved_count() != bench_args.files {
+        bail!("Only {} of {} file(s) were moved", report.moved_count(), bench_args.files);
+    }
+    Ok(PhaseTimings { move_files: move_duration, cleanup: cleanup_duration, ..plan.timings })
+}
+
+fn render_bench_results(files: usize, results: &[(usize, PhaseTimings)]) -> Vec<String> {
+    let mut lines = vec![
+        String::new(),
+        format!(
+            "{:<6}  {:>10}  {:>15}  {:>10}  {:>10}  {:>10}  {:>10}",
+            "Jobs", "Scanning", "Date extraction", "Planning", "Moving", "Cleanup", "Files/s"
+        ),
+    ];
+    for (jobs, timings) in results {
+        let total = timings.scan + timings.date_extraction + timings.plan + timings.move_files + timings.cleanup;
+        let files_per_second = if total.is_zero() { 0.0 } else { files as f64 / total.as_secs_f64() };
+        lines.push(format!(
+            "{:<6}  {:>9.3}s  {:>14.3}s  {:>9.3}s  {:>9.3}s  {:>9.3}s  {:>10.0}",
+            jobs,
+            timings.scan.as_secs_f64(),
+            timings.date_extraction.as_secs_f64(),
+            timings.plan.as_secs_f64(),
+            timings.move_files.as_secs_f64(),
+            timings.cleanup.as_secs_f64(),
+            files_per_second
+        ));
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_leaf_dirs() {
+        let leaf_dirs = get_leaf_dirs(Path::new("/bench"), 2, 2);
+        let expected = ["dir-0-0/dir-1-0", "dir-0-0/dir-1-1", "dir-0-1/dir-1-0", "dir-0-1/dir-1-1"]
+            .map(|dir| Path::new("/bench").join(dir));
+        assert_eq!(leaf_dirs, expected);
+    }
+
+    #[test]
+    fn test_get_leaf_dirs_without_depth() {
+        assert_eq!(get_leaf_dirs(Path::new("/bench"), 0, 4), [PathBuf::from("/bench")]);
+    }
+
+    #[test]
+    fn test_render_bench_results() {
+        let timings = PhaseTimings {
+            scan: Duration::from_millis(250),
+            date_extraction: Duration::from_millis(500),
+            plan: Duration::from_millis(50),
+            move_files: Duration::from_millis(1000),
+            cleanup: Duration::from_millis(200),
+        };
+        let lines = render_bench_results(1000, &[(1, timings), (8, PhaseTimings::default())]);
+
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[2], "1           0.250s           0.500s      0.050s      1.000s      0.200s         500");
+        assert!(lines[3].starts_with("8 ") && lines[3].ends_with(" 0"));
+    }
+}
@@ -0,0 +1,64 @@
+use crate::cli::{Cli, CompletionsArgs};
+use crate::config::{get_default_config_path, load_config};
+use clap::builder::PossibleValuesParser;
+use clap::CommandFactory;
+use clap_complete::Shell;
+use color_eyre::eyre::{Context, Result};
+use std::io::{self, Write};
+
+/// Print the completion script of a shell. Grouping strategies and other fixed values are always completed, while
+/// the profile names are read from the config file when the script is generated.
+pub fn print_completions(args: &CompletionsArgs) -> Result<()> {
+    let config_path = match &args.config {
+        Some(config_path) => config_path.clone(),
+        None => get_default_config_path()?,
+    };
+    // Without a config file there are simply no profiles to complete, unless one was asked for explicitly
+    let profile_names = if args.config.is_some() || config_path.exists() {
+        load_config(&config_path)?.profiles.into_keys().collect()
+    } else {
+        Vec::new()
+    };
+
+    // `clap_complete` panics on write errors, so the script is written to stdout separately
+    let mut script = Vec::new();
+    generate_completions(args.shell, profile_names, &mut script);
+    io::stdout().write_all(&script).context("Failed to write the completion script")
+}
+
+fn generate_completions(shell: Shell, profile_names: Vec<String>, buffer: &mut impl io::Write) {
+    let mut command = Cli::command();
+    if !profile_names.is_empty() {
+        command = command.mut_arg("profile", |arg| arg.value_parser(PossibleValuesParser::new(profile_names)));
+    }
+    let bin_name = command.get_name().to_string();
+    clap_complete::generate(shell, &mut command, bin_name, buffer);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn generate_to_string(shell: Shell, profile_names: &[&str]) -> String {
+        let mut buffer = Vec::new();
+        generate_completions(shell, profile_names.iter().map(|name| name.to_string()).collect(), &mut buffer);
+        String::from_utf8(buffer).unwrap()
+    }
+
+    #[test]
+    fn test_generate_completions_includes_group_by_values() {
+        let script = generate_to_string(Shell::Bash, &[]);
+        assert!(script.contains("--group-by"));
+        assert!(script.contains("quadrimester"));
+    }
+
+    #[test]
+    fn test_generate_completions_includes_profile_names() {
+        // The PowerShell script only completes flags, not their values
+        for shell in [Shell::Bash, Shell::Zsh, Shell::Fish] {
+            let script = generate_to_string(shell, &["work", "photos"]);
+            assert!(script.contains("work") && script.contains("photos"), "{shell} script lacks the profile names");
+        }
+        assert!(!generate_to_string(Shell::Fish, &[]).contains("photos"));
+    }
+}
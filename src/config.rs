@@ -0,0 +1,577 @@
+use crate::cli::{Cli, ConfigInitArgs, ConfigValidateArgs, NotifyArgs, RunArgs};
+use crate::notify::validate_notify_args;
+use chrono::{DateTime, Utc};
+use chronomover_core::date::{parse_holidays, BusinessCalendar};
+use chronomover_core::logging::SUMMARY_TARGET;
+use chronomover_core::model::{parse_reference_time, parse_weekday, validate_arguments, Args};
+use clap::parser::ValueSource;
+use clap::{ArgAction, ArgMatches, Args as _, Parser};
+use color_eyre::eyre::{bail, eyre, Context, ContextCompat, Result};
+use serde::Deserialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::ffi::OsString;
+use std::fs;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tracing::{error, info};
+
+/// Options left out of generated configs, since they only make sense on the command line (like `--now`, the business-day
+/// calendar is read before the options of rules are parsed)
+const COMMAND_LINE_ONLY_OPTIONS: &[&str] = &["verbose", "profile", "config", "now", "weekend_days", "holidays"];
+
+/// Rules and profiles defined in a config file. Rules are independent runs executed by `run`, while profiles are
+/// sets of options picked with `--profile`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    #[serde(default, rename = "rule")]
+    pub rules: Vec<RuleConfig>,
+    #[serde(default, rename = "profile")]
+    pub profiles: BTreeMap<String, toml::Table>,
+}
+
+/// A named rule. Its options use the same names as the long command line flags (e.g., `group_by = "month"`).
+#[derive(Debug, Clone, Deserialize)]
+pub struct RuleConfig {
+    pub name: String,
+    #[serde(flatten)]
+    pub options: toml::Table,
+}
+
+/// Default config file location, inside the user's config folder
+pub fn get_default_config_path() -> Result<PathBuf> {
+    let config_dir = dirs::config_dir().context("Failed to find the config directory of the current user")?;
+    Ok(config_dir.join("chronomover").join("config.toml"))
+}
+
+/// Absolute path of the config file of `run_args`, checked to define the selected rules, for the commands scheduling
+/// `chronomover run` to start later from another working directory or as another user
+pub fn resolve_scheduled_config(run_args: &RunArgs) -> Result<PathBuf> {
+    let config_path = match &run_args.config {
+        Some(config_path) => config_path.clone(),
+        None => get_default_config_path()?,
+    };
+    let config_path = std::path::absolute(&config_path)
+        .with_context(|| format!("Failed to resolve config file: {}", config_path.display()))?;
+    // Unknown rules fail now rather than on every scheduled run
+    let config = load_config(&config_path)?;
+    select_rules(&config, run_args.all, &run_args.rules)?;
+    Ok(config_path)
+}
+
+pub fn load_config(path: &Path) -> Result<Config> {
+    let content = fs::read_to_string(path).with_context(|| format!("Failed to read config file: {}", path.display()))?;
+    let config = parse_config(&content).with_context(|| format!("Invalid config file: {}", path.display()))?;
+    Ok(config)
+}
+
+fn parse_config(content: &str) -> Result<Config> {
+    let config: Config = toml::from_str(content)?;
+
+    let mut names = Vec::new();
+    for rule in &config.rules {
+        if names.contains(&&rule.name) {
+            bail!("Rule '{}' is defined more than once", rule.name);
+        }
+        names.push(&rule.name);
+    }
+    Ok(config)
+}
+
+/// Pick the rules to run, in config order: all of them, or the ones with the given names
+pub fn select_rules<'a>(config: &'a Config, all: bool, names: &[String]) -> Result<Vec<&'a RuleConfig>> {
+    if let Some(unknown_name) = names.iter().find(|name| !config.rules.iter().any(|rule| &rule.name == *name)) {
+        bail!("Rule '{}' is not defined in the config file", unknown_name);
+    }
+    let rules = config.rules.iter()
+        .filter(|rule| all || names.contains(&rule.name))
+        .collect::<Vec<_>>();
+    if rules.is_empty() {
+        bail!("The config file does not define any rule");
+    }
+    Ok(rules)
+}
+
+/// Build the arguments and notifications of a rule by parsing its options as command line flags, so rules accept (and
+/// validate) exactly the same options as the command line
+pub fn get_rule_args(rule: &RuleConfig) -> Result<(Args, NotifyArgs)> {
+    parse_options(&rule.options, &format!("rule '{}'", rule.name))
+}
+
+/// Parse the options of a profile, which may leave out the source and destination
+fn get_profile_args(name: &str, options: &toml::Table) -> Result<Args> {
+    parse_options_without_paths(options, &format!("profile '{name}'")).map(|(args, _)| args)
+}
+
+/// Parse options that may leave out the source and destination, which are filled with placeholders
+fn parse_options_without_paths(options: &toml::Table, owner: &str) -> Result<(Args, NotifyArgs)> {
+    let mut options = options.clone();
+    for required_option in ["source", "destination"] {
+        options.entry(required_option).or_insert_with(|| toml::Value::String("placeholder".to_string()));
+    }
+    parse_options(&options, owner)
+}
+
+fn parse_options(options: &toml::Table, owner: &str) -> Result<(Args, NotifyArgs)> {
+    let mut command_line = vec!["chronomover".to_string()];
+    for (key, value) in options {
+        // `--older-than` durations of the config would not count from it, as it's read before parsing the command line
+        if key == "now" {
+            bail!("Invalid option 'now' in {}: it can only be given on the command line", owner);
+        }
+        command_line.extend(option_to_flags(key, value).with_context(|| format!("Invalid option '{}' in {}", key, owner))?);
+    }
+
+    let cli = Cli::try_parse_from(&command_line).map_err(|e| {
+        // Only keep the first line of clap's message, the usage and help hints refer to the command line
+        let message = e.to_string();
+        let message = message.lines().next().unwrap_or_default().trim_start_matches("error: ").to_string();
+        eyre!("Invalid {}: {}", owner, message)
+    })?;
+    let args = cli.args.with_context(|| format!("Invalid {owner}: missing source and destination"))?;
+    Ok((args, cli.notify_args))
+}
+
+/// Print (or write to a new file) a starter config with a single rule made of the flags given to `config init`
+pub fn init_config(init_args: &ConfigInitArgs, matches: &ArgMatches) -> Result<()> {
+    let content = render_starter_config(&init_args.name, matches);
+    let Some(path) = &init_args.file else {
+        print!("{content}");
+        return Ok(());
+    };
+
+    write_new_config(path, &content)?;
+    info!("Config written to {}", path.display());
+    Ok(())
+}
+
+/// Write a config file, refusing to overwrite an existing one
+pub fn write_new_config(path: &Path, content: &str) -> Result<()> {
+    let mut file = OpenOptions::new().write(true).create_new(true).open(path)
+        .with_context(|| format!("Failed to create config file (it must not exist yet): {}", path.display()))?;
+    file.write_all(content.as_bytes()).with_context(|| format!("Failed to write config file: {}", path.display()))
+}
+
+pub fn render_starter_config(rule_name: &str, matches: &ArgMatches) -> String {
+    let mut lines = vec![
+        "# ChronoMover config, generated by `chronomover init` or `chronomover config init`".to_string(),
+        "# Run its rules with `chronomover run --all`. Options use the same names as the long command line flags,".to_string(),
+        "# uncomment the ones you need.".to_string(),
+        String::new(),
+        "[[rule]]".to_string(),
+        format!("name = {}", toml::Value::String(rule_name.to_string())),
+        String::new(),
+    ];
+
+    let command = NotifyArgs::augment_args(Args::augment_args(clap::Command::new("chronomover")));
+    for argument in command.get_arguments() {
+        let id = argument.get_id().as_str();
+        if COMMAND_LINE_ONLY_OPTIONS.contains(&id) || argument.get_long().is_none() {
+            continue;
+        }
+
+        if let Some(help) = argument.get_help() {
+            lines.push(format!("# {help}"));
+        }
+        let is_given = matches!(matches.value_source(id), Some(ValueSource::CommandLine | ValueSource::EnvVariable));
+        match (is_given, get_option_value(argument, matches)) {
+            (true, Some(value)) => lines.push(format!("{id} = {value}")),
+            (false, Some(default_value)) => lines.push(format!("# {id} = {default_value}")),
+            (_, None) => {
+                let value_name = argument.get_value_names().and_then(|names| names.first()).map_or("VALUE".to_string(), |name| name.to_string());
+                lines.push(format!("# {id} = \"<{value_name}>\""));
+            }
+        }
+        lines.push(String::new());
+    }
+
+    lines.join("\n")
+}
+
+/// Config value of an option, as given on the command line (or its default value)
+fn get_option_value(argument: &clap::Arg, matches: &ArgMatches) -> Option<toml::Value> {
+    let id = argument.get_id().as_str();
+    if matches!(argument.get_action(), ArgAction::SetTrue) {
+        return Some(toml::Value::Boolean(matches.get_flag(id)));
+    }
+
+    let raw_values = matches.get_raw(id)?
+        .map(|value| value.to_string_lossy().into_owned())
+        .collect::<Vec<_>>();
+    // Repeatable options given once (e.g., a single source) stay plain values
+    let is_repeated = matches!(argument.get_action(), ArgAction::Append) && raw_values.len() > 1;
+    if argument.get_value_delimiter().is_some() || is_repeated {
+        return Some(toml::Value::Array(raw_values.into_iter().map(toml::Value::String).collect()));
+    }
+
+    let raw_value = raw_values.into_iter().next()?;
+    Some(match raw_value.parse::<i64>() {
+        Ok(number) => toml::Value::Integer(number),
+        Err(_) => toml::Value::String(raw_value),
+    })
+}
+
+/// Check every rule and profile of a config file without running anything
+pub fn validate_config(validate_args: &ConfigValidateArgs) -> Result<()> {
+    let path = match &validate_args.config {
+        Some(path) => path.clone(),
+        None => get_default_config_path()?,
+    };
+    let config = load_config(&path)?;
+
+    let mut problem_count = 0;
+    for rule in &config.rules {
+        info!("Checking rule '{}'...", rule.name);
+        // As a dry run, validation neither creates the destination nor requires it to exist yet
+        let result = get_rule_args(rule).and_then(|(args, notify_args)| {
+            let args = Args { dry_run: true, ..args };
+            validate_arguments(&args).and_then(|_| validate_notify_args(&notify_args)).with_context(|| format!("Invalid rule '{}'", rule.name))
+        });
+        if let Err(e) = result {
+            error!(rule = %rule.name, "{:#}", e);
+            problem_count += 1;
+        }
+    }
+    for (name, options) in &config.profiles {
+        info!("Checking profile '{}'...", name);
+        if let Err(e) = get_profile_args(name, options) {
+            error!(profile = %name, "{:#}", e);
+            problem_count += 1;
+        }
+    }
+
+    if problem_count > 0 {
+        bail!("Found {} problem(s) in {}", problem_count, path.display());
+    }
+    info!(target: SUMMARY_TARGET, "{} is valid: {} rule(s), {} profile(s)", path.display(), config.rules.len(), config.profiles.len());
+    Ok(())
+}
+
+/// Expand `--profile <NAME>` by inserting the options of the profile right after the program name (and subcommand,
+/// if any), so the flags given on the command line come later and override them. Options also set through their environment variable are
+/// left out, so the precedence is command line, then environment, then profile.
+pub fn expand_profile_args(command_line: Vec<OsString>) -> Result<Vec<OsString>> {
+    let Some(profile) = find_flag_value(&command_line, "--profile").or_else(|| get_env_value("profile")) else {
+        return Ok(command_line);
+    };
+    let config_path = match find_flag_value(&command_line, "--config").or_else(|| get_env_value("config")) {
+        Some(config_path) => PathBuf::from(config_path),
+        None => get_default_config_path()?,
+    };
+    let config = load_config(&config_path)?;
+    let options = config.profiles.get(&profile)
+        .with_context(|| format!("Profile '{}' is not defined in {}", profile, config_path.display()))?;
+
+    let mut profile_flags = Vec::new();
+    for (key, value) in options.iter().filter(|(key, _)| get_env_value(key).is_none()) {
+        profile_flags.extend(option_to_flags(key, value).with_context(|| format!("Invalid option '{}' in profile '{}'", key, profile))?);
+    }
+
+    let insert_index = 1 + command_line.iter().skip(1).take_while(|argument| !argument.to_string_lossy().starts_with('-')).count();
+    let mut expanded_command_line = command_line;
+    expanded_command_line.splice(insert_index..insert_index, profile_flags.into_iter().map(OsString::from));
+    Ok(expanded_command_line)
+}
+
+/// The `--now` reference time of the command line (or its environment variable), which must be known before parsing
+/// the other arguments
+pub fn find_reference_time(command_line: &[OsString]) -> Result<Option<DateTime<Utc>>> {
+    find_flag_value(command_line, "--now").or_else(|| get_env_value("now"))
+        .map(|now| parse_reference_time(&now).context("Invalid --now"))
+        .transpose()
+}
+
+/// The `--weekend-days` and `--holidays` of the command line (or their environment variables), which business-day
+/// `--older-than` durations need before the other arguments are parsed. `None` when neither is given.
+pub fn find_business_calendar(command_line: &[OsString]) -> Result<Option<BusinessCalendar>> {
+    let weekend_days = find_flag_value(command_line, "--weekend-days").or_else(|| get_env_value("weekend-days"));
+    let holidays = find_flag_value(command_line, "--holidays").or_else(|| get_env_value("holidays"));
+    if weekend_days.is_none() && holidays.is_none() {
+        return Ok(None);
+    }
+    let weekend_days = match weekend_days {
+        Some(weekend_days) => weekend_days.split(',')
+            .map(|day| parse_weekday(day).map_err(|e| eyre!(e)))
+            .collect::<Result<Vec<_>>>()
+            .context("Invalid --weekend-days")?,
+        None => BusinessCalendar::default().weekend_days,
+    };
+    let holidays = match holidays {
+        Some(path) => {
+            let content = fs::read_to_string(&path).with_context(|| format!("Failed to read the holidays file: {}", path))?;
+            parse_holidays(&content).with_context(|| format!("Invalid holidays file: {}", path))?
+        }
+        None => BTreeSet::new(),
+    };
+    BusinessCalendar::new(weekend_days, holidays).map(Some).context("Invalid --weekend-days")
+}
+
+/// Value of the `CHRONOMOVER_<OPTION>` environment variable of an option
+fn get_env_value(option: &str) -> Option<String> {
+    std::env::var(get_env_var_name(option)).ok()
+}
+
+fn get_env_var_name(option: &str) -> String {
+    format!("CHRONOMOVER_{}", option.replace('-', "_").to_ascii_uppercase())
+}
+
+/// Value of a long flag given as `--flag value` or `--flag=value`, ignoring everything after `--`
+fn find_flag_value(command_line: &[OsString], flag: &str) -> Option<String> {
+    let mut arguments = command_line.iter().skip(1).map(|argument| argument.to_string_lossy());
+    while let Some(argument) = arguments.next() {
+        if argument == "--" {
+            return None;
+        }
+        if argument == flag {
+            return arguments.next().map(|value| value.into_owned());
+        }
+        if let Some(value) = argument.strip_prefix(flag).and_then(|rest| rest.strip_prefix('=')) {
+            return Some(value.to_string());
+        }
+    }
+    None
+}
+
+fn option_to_flags(key: &str, value: &toml::Value) -> Result<Vec<String>> {
+    let flag = format!("--{}", key.replace('_', "-"));
+    let flags = match value {
+        toml::Value::Boolean(true) => vec![flag],
+        toml::Value::Boolean(false) => Vec::new(),
+        toml::Value::String(value) => vec![flag, value.clone()],
+        toml::Value::Integer(value) => vec![flag, value.to_string()],
+        toml::Value::Float(value) => vec![flag, value.to_string()],
+        toml::Value::Datetime(value) => vec![flag, value.to_string()],
+        toml::Value::Array(values) => values.iter()
+            .map(|value| match value {
+                toml::Value::String(value) => Ok(vec![flag.clone(), value.clone()]),
+                _ => Err(eyre!("Lists may only contain strings")),
+            })
+            .collect::<Result<Vec<_>>>()?
+            .concat(),
+        toml::Value::Table(_) => bail!("Nested tables are not supported"),
+    };
+    Ok(flags)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chronomover_core::model::GroupBy;
+    use chrono::Weekday;
+
+    const CONFIG: &str = r#"
+        [[rule]]
+        name = "downloads"
+        source = "/home/me/Downloads"
+        destination = "/home/me/Archive"
+        group_by = "month"
+        older_than = "30d"
+        ignored_paths = ["/home/me/Downloads/keep", "/home/me/Downloads/tmp"]
+        keep_empty_folders = true
+        route_path = ["Work/**=>/home/me/Work/Archive", "Camera Uploads=>/home/me/Pictures"]
+
+        [[rule]]
+        name = "screenshots"
+        source = "/home/me/Screenshots"
+        destination = "/home/me/Pictures"
+        group-by = "week"
+        dry_run = false
+        mqtt = "mqtt://nas.local"
+    "#;
+
+    #[test]
+    fn test_get_rule_args() {
+        let config = parse_config(CONFIG).unwrap();
+        assert_eq!(config.rules.len(), 2);
+
+        let (downloads, downloads_notify_args) = get_rule_args(&config.rules[0]).unwrap();
+        assert_eq!(downloads.sources, [PathBuf::from("/home/me/Downloads")]);
+        assert_eq!(downloads.group_by, Some(GroupBy::Month));
+        assert!(downloads.older_than.is_some());
+        assert_eq!(downloads.ignored_paths.unwrap().len(), 2);
+        assert!(downloads.keep_empty_folders);
+        assert_eq!(downloads.path_routes.iter().map(|route| route.pattern.as_str()).collect::<Vec<_>>(), ["Work/**", "Camera Uploads"]);
+        assert_eq!(downloads_notify_args.mqtt, None);
+
+        let (screenshots, screenshots_notify_args) = get_rule_args(&config.rules[1]).unwrap();
+        assert_eq!(screenshots.group_by, Some(GroupBy::Week));
+        assert!(!screenshots.dry_run);
+        assert_eq!(screenshots_notify_args.mqtt.as_deref(), Some("mqtt://nas.local"));
+    }
+
+    #[test]
+    fn test_get_rule_args_invalid_options() {
+        let config = parse_config(r#"
+            [[rule]]
+            name = "broken"
+            source = "/notes"
+            destination = "/archive"
+            group_by = "decade"
+
+            [[rule]]
+            name = "incomplete"
+            source = "/notes"
+        "#).unwrap();
+
+        let error = get_rule_args(&config.rules[0]).unwrap_err().to_string();
+        assert!(error.starts_with("Invalid rule 'broken': invalid value 'decade'"), "{error}");
+        let error = get_rule_args(&config.rules[1]).unwrap_err().to_string();
+        assert!(error.starts_with("Invalid rule 'incomplete':"), "{error}");
+    }
+
+    #[test]
+    fn test_get_rule_args_rejects_now() {
+        let config = parse_config("[[rule]]\nname = \"a\"\nsource = \"/notes\"\ndestination = \"/archive\"\nnow = \"2025-07-01\"\n").unwrap();
+
+        let error = get_rule_args(&config.rules[0]).unwrap_err().to_string();
+
+        assert_eq!(error, "Invalid option 'now' in rule 'a': it can only be given on the command line");
+    }
+
+    #[test]
+    fn test_find_reference_time() {
+        let command_line = ["chronomover", "-s", "/src", "--now=2025-07-01", "-d", "/dst"].map(OsString::from);
+
+        let now = find_reference_time(&command_line).unwrap().unwrap();
+
+        assert_eq!(now, parse_reference_time("2025-07-01").unwrap());
+        assert!(find_reference_time(&["chronomover", "--now", "30d"].map(OsString::from)).is_err());
+    }
+
+    #[test]
+    fn test_find_business_calendar() {
+        let command_line = ["chronomover", "-s", "/src", "--weekend-days", "fri,sat", "--older-than", "10bd"].map(OsString::from);
+        let calendar = find_business_calendar(&command_line).unwrap().unwrap();
+
+        assert_eq!(calendar.weekend_days, [Weekday::Fri, Weekday::Sat]);
+        assert!(calendar.holidays.is_empty());
+        assert!(find_business_calendar(&["chronomover", "--weekend-days", "funday"].map(OsString::from)).is_err());
+        assert!(find_business_calendar(&["chronomover", "--holidays", "/missing/holidays.txt"].map(OsString::from)).is_err());
+    }
+
+    #[test]
+    fn test_parse_config_duplicate_rule_names() {
+        let error = parse_config("[[rule]]\nname = \"a\"\n[[rule]]\nname = \"a\"\n").unwrap_err();
+        assert_eq!(error.to_string(), "Rule 'a' is defined more than once");
+    }
+
+    #[test]
+    fn test_get_env_var_name() {
+        assert_eq!(get_env_var_name("dry_run"), "CHRONOMOVER_DRY_RUN");
+        assert_eq!(get_env_var_name("log-file-max-size"), "CHRONOMOVER_LOG_FILE_MAX_SIZE");
+    }
+
+    #[test]
+    fn test_every_option_has_its_env_var() {
+        use clap::CommandFactory;
+
+        for argument in Cli::command().get_arguments().filter(|argument| argument.get_long().is_some_and(|long| long != "help" && long != "version")) {
+            let env = argument.get_env().map(|env| env.to_string_lossy().into_owned());
+            assert_eq!(env, Some(get_env_var_name(argument.get_long().unwrap())), "--{}", argument.get_long().unwrap());
+        }
+    }
+
+    #[test]
+    fn test_find_flag_value() {
+        let command_line = ["chronomover", "--profile", "work", "--config=/etc/chronomover.toml", "--", "--profile"]
+            .map(OsString::from);
+
+        assert_eq!(find_flag_value(&command_line, "--profile"), Some("work".to_string()));
+        assert_eq!(find_flag_value(&command_line, "--config"), Some("/etc/chronomover.toml".to_string()));
+        assert_eq!(find_flag_value(&command_line, "--dry-run"), None);
+    }
+
+    #[test]
+    fn test_profile_options_are_overridden_by_command_line() {
+        let config = parse_config(r#"
+            [profile.work]
+            source = "/work/notes"
+            destination = "/work/archive"
+            group_by = "month"
+        "#).unwrap();
+
+        let mut command_line = vec!["chronomover".to_string()];
+        for (key, value) in &config.profiles["work"] {
+            command_line.extend(option_to_flags(key, value).unwrap());
+        }
+        command_line.extend(["--group-by", "week", "--profile", "work"].map(String::from));
+        let args = Cli::parse_from(command_line).args.unwrap();
+
+        assert_eq!(args.sources, [PathBuf::from("/work/notes")]);
+        assert_eq!(args.group_by, Some(GroupBy::Week));
+    }
+
+    #[test]
+    fn test_get_profile_args_allows_missing_paths() {
+        let config = parse_config(r#"
+            [profile.monthly]
+            group_by = "month"
+
+            [profile.broken]
+            unknown_option = true
+        "#).unwrap();
+
+        assert_eq!(get_profile_args("monthly", &config.profiles["monthly"]).unwrap().group_by, Some(GroupBy::Month));
+        let error = get_profile_args("broken", &config.profiles["broken"]).unwrap_err().to_string();
+        assert!(error.starts_with("Invalid profile 'broken': unexpected argument '--unknown-option'"), "{error}");
+    }
+
+    #[test]
+    fn test_rule_with_several_sources() {
+        use clap::CommandFactory;
+
+        let config = parse_config(r#"
+            [[rule]]
+            name = "clutter"
+            source = ["/home/me/Downloads", "/home/me/Desktop"]
+            destination = "/home/me/Archive"
+        "#).unwrap();
+        let (args, _) = get_rule_args(&config.rules[0]).unwrap();
+        assert_eq!(args.sources, [PathBuf::from("/home/me/Downloads"), PathBuf::from("/home/me/Desktop")]);
+
+        let matches = Cli::command().get_matches_from(["chronomover", "config", "init", "-s", "/a", "-s", "/b", "-d", "/archive"]);
+        let init_matches = matches.subcommand_matches("config").and_then(|m| m.subcommand_matches("init")).unwrap();
+        assert!(render_starter_config("clutter", init_matches).contains("\nsource = [\"/a\", \"/b\"]\n"));
+    }
+
+    #[test]
+    fn test_render_starter_config() {
+        use clap::CommandFactory;
+
+        let matches = Cli::command().get_matches_from([
+            "chronomover", "config", "init", "--name", "notes", "-s", "/notes", "-d", "/archive", "-g", "month",
+            "--older-than", "30d", "--ignored-paths", "/notes/a,/notes/b", "--dry-run", "--max-depth", "3",
+        ]);
+        let init_matches = matches.subcommand_matches("config").and_then(|m| m.subcommand_matches("init")).unwrap();
+        let content = render_starter_config("notes", init_matches);
+
+        assert!(content.contains("[[rule]]\nname = \"notes\""));
+        assert!(content.contains("\nsource = \"/notes\"\n"));
+        assert!(content.contains("\ngroup_by = \"month\"\n"));
+        assert!(content.contains("\nolder_than = \"30d\"\n"));
+        assert!(content.contains("\nignored_paths = [\"/notes/a\", \"/notes/b\"]\n"));
+        assert!(content.contains("\ndry_run = true\n"));
+        assert!(content.contains("\nmax_depth = 3\n"));
+        assert!(content.contains("\n# keep_empty_folders = false\n"));
+        assert!(content.contains("\n# file_date_types = [\"created\", \"modified\"]\n"));
+        assert!(content.contains("\n# min_depth = \"<DEPTH>\"\n"));
+        assert!(!content.contains("verbose"));
+
+        // The generated rule must be accepted by `run`
+        let config = parse_config(&content).unwrap();
+        let (args, _) = get_rule_args(&config.rules[0]).unwrap();
+        assert_eq!(args.max_depth, Some(3));
+        assert!(args.dry_run);
+    }
+
+    #[test]
+    fn test_select_rules() {
+        let config = parse_config(CONFIG).unwrap();
+
+        assert_eq!(select_rules(&config, true, &[]).unwrap().len(), 2);
+        let selected = select_rules(&config, false, &["screenshots".to_string()]).unwrap();
+        assert_eq!(selected.iter().map(|rule| rule.name.as_str()).collect::<Vec<_>>(), vec!["screenshots"]);
+        assert!(select_rules(&config, false, &["missing".to_string()]).is_err());
+    }
+}
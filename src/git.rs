@@ -0,0 +1,116 @@
+use chronomover_core::links::to_absolute;
+use chronomover_core::manifest::ManifestEntry;
+use chronomover_core::model::Args;
+use color_eyre::eyre::{Context, ContextCompat, Result};
+use git2::{Index, Repository};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+/// Stage the moves of the tracked files of a run in the index of the git repositories of their source folders, like
+/// `git mv` would: the index entry of a file is renamed, keeping its staged content, or removed when the file moved
+/// out of the repository. Untracked files are left alone. Returns the number of staged moves.
+pub fn stage_git_moves(args: &Args, moved: &[ManifestEntry]) -> Result<usize> {
+    let mut staged = 0;
+    for source in &args.sources {
+        let repository = match Repository::discover(source) {
+            Ok(repository) if !repository.is_bare() => repository,
+            _ => {
+                warn!("Not staging the moves of {}: not inside a git worktree", source.display());
+                continue;
+            }
+        };
+        // Safe to unwrap: a repository that isn't bare has a worktree
+        let worktree = fs::canonicalize(repository.workdir().unwrap())
+            .context("Failed to resolve the git worktree")?;
+        let absolute_source = to_absolute(source)?;
+        let source_in_worktree = get_worktree_path(&worktree, source)
+            .with_context(|| format!("Source directory is outside of its git worktree: {}", source.display()))?;
+
+        let mut index = repository.index().context("Failed to read the git index")?;
+        let mut staged_in_repository = 0;
+        for entry in moved {
+            let Ok(relative_source) = to_absolute(&entry.source)?.strip_prefix(&absolute_source).map(Path::to_path_buf) else {
+                continue;
+            };
+            let destination = entry.destination.parent()
+                .and_then(|parent| get_worktree_path(&worktree, parent))
+                .zip(entry.destination.file_name())
+                .map(|(parent, file_name)| parent.join(file_name));
+            if stage_move(&mut index, &source_in_worktree.join(relative_source), destination.as_deref())? {
+                staged_in_repository += 1;
+            }
+        }
+        if staged_in_repository > 0 {
+            index.write().context("Failed to write the git index")?;
+            info!("Staged {} move(s) in the git repository {}", staged_in_repository, worktree.display());
+        }
+        staged += staged_in_repository;
+    }
+    Ok(staged)
+}
+
+/// Rename the index entry of a file, or remove it if the file left the worktree, returning whether it was tracked
+fn stage_move(index: &mut Index, source: &Path, destination: Option<&Path>) -> Result<bool> {
+    let source = to_git_path(source)?;
+    let Some(mut index_entry) = index.get_path(&source, 0) else {
+        return Ok(false);
+    };
+    index.remove_path(&source).with_context(|| format!("Failed to unstage: {}", source.display()))?;
+    if let Some(destination) = destination {
+        index_entry.path = to_git_path(destination)?.into_os_string().into_encoded_bytes();
+        index.add(&index_entry).with_context(|| format!("Failed to stage: {}", destination.display()))?;
+    }
+    Ok(true)
+}
+
+/// Path of an existing file or folder relative to the worktree, if inside it
+fn get_worktree_path(worktree: &Path, path: &Path) -> Option<PathBuf> {
+    fs::canonicalize(path).ok()?.strip_prefix(worktree).ok().map(Path::to_path_buf)
+}
+
+/// Worktree path with the `/` separators of index entries
+fn to_git_path(path: &Path) -> Result<PathBuf> {
+    let components = path.components()
+        .map(|component| component.as_os_str().to_str())
+        .collect::<Option<Vec<_>>>()
+        .with_context(|| format!("File name is not valid UTF-8: {}", path.display()))?;
+    Ok(PathBuf::from(components.join("/")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use git2::{IndexEntry, IndexTime, Oid};
+
+    fn create_index_entry(path: &str) -> IndexEntry {
+        IndexEntry {
+            ctime: IndexTime::new(0, 0),
+            mtime: IndexTime::new(0, 0),
+            dev: 0,
+            ino: 0,
+            mode: 0o100644,
+            uid: 0,
+            gid: 0,
+            file_size: 0,
+            id: Oid::zero(),
+            flags: 0,
+            flags_extended: 0,
+            path: path.as_bytes().to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_stage_move() {
+        let mut index = Index::new().unwrap();
+        index.add(&create_index_entry("docs/old.md")).unwrap();
+        index.add(&create_index_entry("docs/gone.md")).unwrap();
+
+        assert!(stage_move(&mut index, Path::new("docs/old.md"), Some(Path::new("docs/2024/old.md"))).unwrap());
+        assert!(stage_move(&mut index, Path::new("docs/gone.md"), None).unwrap());
+        assert!(!stage_move(&mut index, Path::new("docs/untracked.md"), Some(Path::new("docs/2024/untracked.md"))).unwrap());
+
+        let paths = index.iter().map(|entry| String::from_utf8(entry.path).unwrap()).collect::<Vec<_>>();
+        assert_eq!(paths, ["docs/2024/old.md"]);
+    }
+}
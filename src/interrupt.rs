@@ -0,0 +1,41 @@
+use chronomover_core::cancel::CancellationToken;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{LazyLock, Once};
+use tracing::warn;
+
+/// Cancelled by the first Ctrl-C during a run, which then stops cleanly between files
+static CTRL_C_TOKEN: LazyLock<CancellationToken> = LazyLock::new(CancellationToken::new);
+
+static RUN_IN_PROGRESS: AtomicBool = AtomicBool::new(false);
+
+static INSTALL_HANDLER: Once = Once::new();
+
+/// Keeps Ctrl-C cancelling the current run until dropped, instead of quitting right away
+pub struct InterruptibleRun;
+
+impl Drop for InterruptibleRun {
+    fn drop(&mut self) {
+        RUN_IN_PROGRESS.store(false, Ordering::Relaxed);
+    }
+}
+
+/// Token cancelled by Ctrl-C, for a run starting now. The first Ctrl-C during the run cancels it, so the files being
+/// moved complete and the summary reports the partial run; a second one, or one between runs, quits right away.
+pub fn start_interruptible_run() -> (CancellationToken, InterruptibleRun) {
+    INSTALL_HANDLER.call_once(|| {
+        let result = ctrlc::set_handler(|| {
+            if RUN_IN_PROGRESS.load(Ordering::Relaxed) && !CTRL_C_TOKEN.is_cancelled() {
+                CTRL_C_TOKEN.cancel();
+                warn!("Stopping once the files being moved are done, press Ctrl-C again to quit right away");
+            } else {
+                // The exit code of `RunOutcome::Cancelled`
+                std::process::exit(130);
+            }
+        });
+        if let Err(e) = result {
+            warn!("Failed to handle Ctrl-C, it will stop the run right away: {}", e);
+        }
+    });
+    RUN_IN_PROGRESS.store(true, Ordering::Relaxed);
+    (CTRL_C_TOKEN.clone(), InterruptibleRun)
+}
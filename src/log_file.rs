@@ -0,0 +1,70 @@
+use std::fs;
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Number of rotated log files kept next to the log file (`<name>.1` being the most recent)
+const MAX_ROTATED_LOG_FILES: usize = 5;
+
+/// Log file of `--log-file`, appended to and rotated once it grows past `max_size` bytes
+pub struct LogFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+    max_size: Option<u64>,
+}
+
+impl Write for LogFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        append_to_log_file(self, buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+pub fn open_log_file(path: &Path, max_size: Option<u64>) -> io::Result<LogFile> {
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    let size = file.metadata()?.len();
+    Ok(LogFile { path: path.to_path_buf(), file, size, max_size })
+}
+
+fn append_to_log_file(log_file: &mut LogFile, line: &[u8]) -> io::Result<usize> {
+    if log_file.max_size.is_some_and(|max_size| log_file.size > 0 && log_file.size + line.len() as u64 > max_size) {
+        rotate_log_files(&log_file.path)?;
+        *log_file = open_log_file(&log_file.path, log_file.max_size)?;
+    }
+    let written = log_file.file.write(line)?;
+    log_file.size += written as u64;
+    Ok(written)
+}
+
+/// Shift `<name>.1..<name>.N-1` one position up (dropping the oldest) and move the current file to `<name>.1`
+fn rotate_log_files(path: &Path) -> io::Result<()> {
+    for index in (1..MAX_ROTATED_LOG_FILES).rev() {
+        let rotated_path = get_rotated_log_path(path, index);
+        if rotated_path.exists() {
+            fs::rename(&rotated_path, get_rotated_log_path(path, index + 1))?;
+        }
+    }
+    fs::rename(path, get_rotated_log_path(path, 1))
+}
+
+fn get_rotated_log_path(path: &Path, index: usize) -> PathBuf {
+    let mut rotated_path = path.as_os_str().to_owned();
+    rotated_path.push(format!(".{index}"));
+    PathBuf::from(rotated_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_rotated_log_path() {
+        assert_eq!(get_rotated_log_path(Path::new("/logs/chronomover.log"), 1), PathBuf::from("/logs/chronomover.log.1"));
+        assert_eq!(get_rotated_log_path(Path::new("chronomover.log"), 5), PathBuf::from("chronomover.log.5"));
+    }
+}
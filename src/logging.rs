@@ -0,0 +1,369 @@
+use crate::log_file::open_log_file;
+use chrono::Local;
+use chronomover_core::logging::{Verbosity, SUMMARY_TARGET};
+use color_eyre::eyre::{Context, Result};
+use std::fmt;
+use std::io;
+use std::io::IsTerminal;
+use std::path::Path;
+use std::sync::Mutex;
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::fmt::format::Writer;
+use tracing_subscriber::fmt::{FmtContext, FormatEvent, FormatFields, MakeWriter};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{EnvFilter, Layer, Registry};
+
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum LogFormat {
+    /// Plain log lines
+    Text,
+    /// One JSON object per log event, including its fields and span
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum ColorChoice {
+    /// Colorize when printing to a terminal and `NO_COLOR` is not set
+    Auto,
+    Always,
+    Never,
+}
+
+/// Outcome attached to a log line through its `status` field, which decides its color in the terminal
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Status {
+    Moved,
+    Skipped,
+    DryRun,
+}
+
+impl Status {
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "moved" => Some(Status::Moved),
+            "skipped" => Some(Status::Skipped),
+            "dry-run" => Some(Status::DryRun),
+            _ => None,
+        }
+    }
+}
+
+/// Resolve `--color` against the terminal, following the `NO_COLOR` convention (https://no-color.org) for `auto`
+pub fn should_colorize(color: ColorChoice) -> bool {
+    match color {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none_or(|value| value.is_empty()),
+    }
+}
+
+/// Install the global `tracing` subscriber. `RUST_LOG` takes precedence over the verbosity when set.
+pub fn init_logging(
+    verbosity: Verbosity,
+    log_format: LogFormat,
+    colorize: bool,
+    log_file: Option<(&Path, Option<u64>)>,
+) -> Result<()> {
+    // Machine-readable output owns stdout, so logs explicitly enabled through RUST_LOG go to stderr instead
+    let mut layers = vec![if verbosity == Verbosity::Silent {
+        create_layer(log_format, false, false, io::stderr)
+    } else {
+        create_layer(log_format, colorize, false, io::stdout)
+    }];
+    if let Some((path, max_size)) = log_file {
+        let log_file = open_log_file(path, max_size)
+            .with_context(|| format!("Failed to open log file: {}", path.display()))?;
+        layers.push(create_layer(log_format, false, true, Mutex::new(log_file)));
+    }
+
+    tracing_subscriber::registry()
+        .with(layers)
+        .with(create_filter(verbosity))
+        .try_init()
+        .context("Failed to initialize logging")
+}
+
+/// Install the global `tracing` subscriber of runs started by systemd: plain lines on stdout prefixed with their syslog
+/// priority (e.g., `<4>` for warnings), which journald records as the priority of the entry and timestamps itself
+pub fn init_journald_logging(verbosity: Verbosity) -> Result<()> {
+    let layer = tracing_subscriber::fmt::layer()
+        .with_ansi(false)
+        .event_format(CliFormat { with_timestamps: false, with_syslog_priority: true })
+        .with_writer(io::stdout)
+        .boxed();
+
+    tracing_subscriber::registry()
+        .with(vec![layer])
+        .with(create_filter(verbosity))
+        .try_init()
+        .context("Failed to initialize logging")
+}
+
+/// Install the global `tracing` subscriber of the Windows service, reporting each log line as an event of the
+/// Application event log under `source_name`
+#[cfg(windows)]
+pub fn init_event_log_logging(verbosity: Verbosity, source_name: &str) -> Result<()> {
+    let event_log = EventLog::register(source_name)
+        .with_context(|| format!("Failed to register the event log source: {}", source_name))?;
+
+    tracing_subscriber::registry()
+        .with(vec![create_layer(LogFormat::Text, false, false, event_log)])
+        .with(create_filter(verbosity))
+        .try_init()
+        .context("Failed to initialize logging")
+}
+
+fn create_filter(verbosity: Verbosity) -> EnvFilter {
+    EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(get_default_filter_directive(verbosity)))
+}
+
+fn get_default_filter_directive(verbosity: Verbosity) -> String {
+    match verbosity {
+        Verbosity::Silent => "off".to_string(),
+        Verbosity::Quiet => format!("warn,{SUMMARY_TARGET}=info"),
+        Verbosity::Normal => "info".to_string(),
+        Verbosity::Verbose => "debug".to_string(),
+        Verbosity::Debug => "trace".to_string(),
+    }
+}
+
+fn create_layer<W>(
+    log_format: LogFormat,
+    colorize: bool,
+    with_timestamps: bool,
+    writer: W,
+) -> Box<dyn Layer<Registry> + Send + Sync>
+where
+    W: for<'writer> MakeWriter<'writer> + Send + Sync + 'static,
+{
+    match log_format {
+        LogFormat::Text => tracing_subscriber::fmt::layer()
+            .with_ansi(colorize)
+            .event_format(CliFormat { with_timestamps, with_syslog_priority: false })
+            .with_writer(writer)
+            .boxed(),
+        LogFormat::Json => tracing_subscriber::fmt::layer()
+            .json()
+            .with_ansi(false)
+            .with_current_span(true)
+            .with_span_list(false)
+            .with_writer(writer)
+            .boxed(),
+    }
+}
+
+/// Formats events as the plain log lines ChronoMover always printed: only the message, prefixed by the severity
+/// for warnings and errors. Structured fields are left to the JSON format, except `status`, which picks the line color.
+struct CliFormat {
+    with_timestamps: bool,
+    /// Start the lines with the `<N>` syslog priority of their level, for journald
+    with_syslog_priority: bool,
+}
+
+impl<S, N> FormatEvent<S, N> for CliFormat
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(&self, _ctx: &FmtContext<'_, S, N>, mut writer: Writer<'_>, event: &Event<'_>) -> fmt::Result {
+        if self.with_timestamps {
+            write!(writer, "[{}] ", Local::now().format("%Y-%m-%d %H:%M:%S"))?;
+        }
+        let mut visitor = MessageVisitor::default();
+        event.record(&mut visitor);
+
+        let prefix = match *event.metadata().level() {
+            Level::ERROR => "ERROR: ",
+            Level::WARN => "WARNING: ",
+            _ => "",
+        };
+        // journald records each line as its own entry, so every line carries the priority and blank lines are dropped
+        if self.with_syslog_priority {
+            let priority = get_syslog_priority(*event.metadata().level());
+            for line in format!("{prefix}{}", visitor.message).lines().filter(|line| !line.trim().is_empty()) {
+                writeln!(writer, "<{priority}>{line}")?;
+            }
+            return Ok(());
+        }
+        let color = if writer.has_ansi_escapes() { get_ansi_color(*event.metadata().level(), visitor.status) } else { None };
+        match color {
+            Some(color) => writeln!(writer, "{color}{prefix}{}{ANSI_RESET}", visitor.message),
+            None => writeln!(writer, "{prefix}{}", visitor.message),
+        }
+    }
+}
+
+/// Syslog priority of a level, as understood by journald: error, warning, info and debug
+fn get_syslog_priority(level: Level) -> u8 {
+    match level {
+        Level::ERROR => 3,
+        Level::WARN => 4,
+        Level::INFO => 6,
+        Level::DEBUG | Level::TRACE => 7,
+    }
+}
+
+const ANSI_RED: &str = "\x1b[31m";
+const ANSI_GREEN: &str = "\x1b[32m";
+const ANSI_YELLOW: &str = "\x1b[33m";
+const ANSI_DIM: &str = "\x1b[2m";
+const ANSI_RESET: &str = "\x1b[0m";
+
+/// Red for errors, yellow for warnings and skipped files, green for moved files and dimmed for dry-run previews
+fn get_ansi_color(level: Level, status: Option<Status>) -> Option<&'static str> {
+    match (level, status) {
+        (Level::ERROR, _) => Some(ANSI_RED),
+        (Level::WARN, _) | (_, Some(Status::Skipped)) => Some(ANSI_YELLOW),
+        (_, Some(Status::Moved)) => Some(ANSI_GREEN),
+        (_, Some(Status::DryRun)) => Some(ANSI_DIM),
+        _ => None,
+    }
+}
+
+#[derive(Default)]
+struct MessageVisitor {
+    message: String,
+    status: Option<Status>,
+}
+
+impl Visit for MessageVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "status" {
+            self.status = Status::parse(value);
+        } else {
+            self.record_debug(field, &value);
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            self.message = format!("{value:?}");
+        }
+    }
+}
+
+/// Event source of the Windows event log, handing out one writer per log line with the event type of its level
+#[cfg(windows)]
+struct EventLog {
+    handle: windows_sys::Win32::Foundation::HANDLE,
+}
+
+// Safe: event source handles can be used from any thread
+#[cfg(windows)]
+unsafe impl Send for EventLog {}
+#[cfg(windows)]
+unsafe impl Sync for EventLog {}
+
+#[cfg(windows)]
+impl EventLog {
+    fn register(source_name: &str) -> io::Result<Self> {
+        use windows_sys::Win32::System::EventLog::RegisterEventSourceW;
+
+        let wide_source_name = source_name.encode_utf16().chain([0]).collect::<Vec<_>>();
+        // Safe: the source name is NUL-terminated
+        let handle = unsafe { RegisterEventSourceW(std::ptr::null(), wide_source_name.as_ptr()) };
+        if handle.is_null() {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Self { handle })
+    }
+}
+
+#[cfg(windows)]
+impl<'a> MakeWriter<'a> for EventLog {
+    type Writer = EventLogEntry<'a>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        EventLogEntry { event_log: self, level: Level::INFO, message: Vec::new() }
+    }
+
+    fn make_writer_for(&'a self, meta: &tracing::Metadata<'_>) -> Self::Writer {
+        EventLogEntry { event_log: self, level: *meta.level(), message: Vec::new() }
+    }
+}
+
+/// Log line buffered until it is complete, then reported as a single event
+#[cfg(windows)]
+struct EventLogEntry<'a> {
+    event_log: &'a EventLog,
+    level: Level,
+    message: Vec<u8>,
+}
+
+#[cfg(windows)]
+impl io::Write for EventLogEntry<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.message.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(windows)]
+impl Drop for EventLogEntry<'_> {
+    fn drop(&mut self) {
+        use windows_sys::Win32::System::EventLog::{ReportEventW, EVENTLOG_ERROR_TYPE, EVENTLOG_INFORMATION_TYPE, EVENTLOG_WARNING_TYPE};
+
+        let message = String::from_utf8_lossy(&self.message);
+        let message = message.trim_end();
+        if message.is_empty() {
+            return;
+        }
+        let event_type = match self.level {
+            Level::ERROR => EVENTLOG_ERROR_TYPE,
+            Level::WARN => EVENTLOG_WARNING_TYPE,
+            _ => EVENTLOG_INFORMATION_TYPE,
+        };
+        let wide_message = message.encode_utf16().chain([0]).collect::<Vec<_>>();
+        let strings = [wide_message.as_ptr()];
+        // Safe: the single message string is NUL-terminated and outlives the call. A line that fails to be reported
+        // has nowhere else to go, so the result is ignored.
+        unsafe {
+            ReportEventW(self.event_log.handle, event_type, 0, 0, std::ptr::null_mut(), 1, 0, strings.as_ptr(), std::ptr::null());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_default_filter_directive() {
+        assert_eq!(get_default_filter_directive(Verbosity::Silent), "off");
+        assert_eq!(get_default_filter_directive(Verbosity::Quiet), "warn,chronomover::summary=info");
+        assert_eq!(get_default_filter_directive(Verbosity::Normal), "info");
+        assert_eq!(get_default_filter_directive(Verbosity::Verbose), "debug");
+        assert_eq!(get_default_filter_directive(Verbosity::Debug), "trace");
+    }
+
+    #[test]
+    fn test_get_ansi_color() {
+        assert_eq!(get_ansi_color(Level::ERROR, None), Some(ANSI_RED));
+        assert_eq!(get_ansi_color(Level::WARN, None), Some(ANSI_YELLOW));
+        assert_eq!(get_ansi_color(Level::DEBUG, Some(Status::Skipped)), Some(ANSI_YELLOW));
+        assert_eq!(get_ansi_color(Level::INFO, Some(Status::Moved)), Some(ANSI_GREEN));
+        assert_eq!(get_ansi_color(Level::INFO, Some(Status::DryRun)), Some(ANSI_DIM));
+        assert_eq!(get_ansi_color(Level::INFO, None), None);
+    }
+
+    #[test]
+    fn test_get_syslog_priority() {
+        assert_eq!(get_syslog_priority(Level::ERROR), 3);
+        assert_eq!(get_syslog_priority(Level::WARN), 4);
+        assert_eq!(get_syslog_priority(Level::INFO), 6);
+        assert_eq!(get_syslog_priority(Level::TRACE), 7);
+    }
+
+    #[test]
+    fn test_should_colorize_explicit_choices() {
+        assert!(should_colorize(ColorChoice::Always));
+        assert!(!should_colorize(ColorChoice::Never));
+    }
+}
@@ -1,27 +1,414 @@
-use crate::file::get_files_to_move;
-use crate::model::{enrich_arguments, print_arguments, validate_arguments, Args};
-use chrono::Utc;
-use clap::Parser;
-use color_eyre::eyre::Result;
-use file::{delete_empty_directories, move_files};
-
-mod date;
-mod file;
-mod log_macro;
-mod model;
-
-fn main() -> Result<()> {
+use crate::bench::run_bench;
+use crate::completions::print_completions;
+use crate::interrupt::start_interruptible_run;
+use chronomover_core::audit::audit_archive;
+use crate::config::{expand_profile_args, find_business_calendar, find_reference_time, get_default_config_path, get_rule_args, init_config, load_config, select_rules, validate_config};
+use chronomover_core::doctor::run_doctor;
+use chronomover_core::dupes::{delete_duplicates, report_duplicates};
+use chronomover_core::explain::explain_file;
+use chronomover_core::hooks::{run_post_run_hook, run_pre_run_hook};
+use crate::git::stage_git_moves;
+use chronomover_core::file::{get_files_to_move, plan_and_move_in_batches, plan_and_move_with_memory_cap};
+use chronomover_core::html_report::write_html_report;
+use chronomover_core::logging::{SUMMARY_TARGET, Verbosity};
+use crate::logging::{init_journald_logging, init_logging, should_colorize, ColorChoice, LogFormat};
+use chronomover_core::model::{enrich_arguments, get_verbosity, get_reference_time, get_verbosity_from_flags, print_arguments, set_business_calendar, set_reference_time, validate_arguments, Args, OutputFormat, PlanFormat};
+use crate::cli::{Cli, Command, ConfigCommand, EmailTrigger, LogArgs, NotifyArgs, RunArgs, ScheduledRunArgs, ServiceCommand};
+use chronomover_core::manifest::load_manifest;
+use crate::mqtt::connect_mqtt;
+use crate::notify::{notify_email, notify_email_failure, notify_webhook, print_notify_args, validate_notify_args};
+use chronomover_core::links::rewrite_moved_links;
+use chronomover_core::locate::locate_moved_files;
+use chronomover_core::obsidian::rewrite_vault_links;
+use chronomover_core::period_links::update_period_links;
+use chronomover_core::merge::merge_archives;
+use chronomover_core::output::{add_event_sink, build_run_summary, emit_event, print_json_report, print_nul_delimited_plan, Event, NdjsonSink};
+use chronomover_core::prune::prune_archive;
+use chronomover_core::rsync::write_rsync_plan;
+use chronomover_core::scan_state::ScanState;
+use crate::schedule::run_on_schedule;
+use crate::service::{install_service, run_service, uninstall_service};
+use chronomover_core::regroup::regroup_archive;
+use chronomover_core::report::{combine_run_outcomes, get_run_outcome, print_disk_usage, print_phase_timings, print_run_statistics, PhaseTimings, RunOutcome, RunStatistics};
+use chronomover_core::restore::restore_archive;
+use chronomover_core::stats::print_stats;
+use chronomover_core::storage::LocalStorage;
+use crate::systemd::{notify_systemd, print_systemd_units, Watchdog};
+use crate::tui::review_plan;
+use chronomover_core::verify::verify_run;
+use crate::wizard::run_setup_wizard;
+use clap::{CommandFactory, FromArgMatches};
+use color_eyre::eyre::{bail, Result};
+use chronomover_core::file::{delete_empty_directories, get_destination_roots, move_files};
+use std::process::ExitCode;
+use std::time::Instant;
+use tracing::{error, info, info_span, warn};
+
+mod bench;
+mod cli;
+mod completions;
+mod config;
+mod git;
+mod interrupt;
+mod log_file;
+mod logging;
+mod mqtt;
+mod notify;
+mod schedule;
+mod service;
+mod systemd;
+mod tui;
+mod wizard;
+
+fn main() -> Result<ExitCode> {
     color_eyre::install()?;
-    let args = Args::parse();
+    let command_line = expand_profile_args(std::env::args_os().collect())?;
+    if let Some(now) = find_reference_time(&command_line)? {
+        set_reference_time(now);
+    }
+    if let Some(calendar) = find_business_calendar(&command_line)? {
+        set_business_calendar(calendar);
+    }
+    let matches = Cli::command().get_matches_from(command_line);
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+
+    match cli.command {
+        Some(Command::Verify(verify_args)) => {
+            init_logging(Verbosity::Normal, LogFormat::Text, should_colorize(ColorChoice::Auto), None)?;
+            verify_run(&verify_args)?;
+            Ok(ExitCode::SUCCESS)
+        }
+        Some(Command::Run(scheduled_args)) => run_rules(&scheduled_args),
+        Some(Command::Dupes(dupes_args)) => {
+            init_logging(Verbosity::Normal, LogFormat::Text, should_colorize(ColorChoice::Auto), None)?;
+            report_duplicates(&dupes_args, &LocalStorage)?;
+            Ok(ExitCode::SUCCESS)
+        }
+        Some(Command::Audit(audit_args)) => {
+            // Only warnings and errors are logged, the audit is printed as the summary
+            init_logging(Verbosity::Quiet, LogFormat::Text, should_colorize(ColorChoice::Auto), None)?;
+            audit_archive(&audit_args, &LocalStorage)?;
+            Ok(ExitCode::SUCCESS)
+        }
+        Some(Command::Where(where_args)) => {
+            init_logging(Verbosity::Normal, LogFormat::Text, should_colorize(ColorChoice::Auto), None)?;
+            locate_moved_files(&where_args)?;
+            Ok(ExitCode::SUCCESS)
+        }
+        Some(Command::Prune(prune_args)) => {
+            init_logging(Verbosity::Normal, LogFormat::Text, should_colorize(ColorChoice::Auto), None)?;
+            prune_archive(&prune_args, &LocalStorage)?;
+            Ok(ExitCode::SUCCESS)
+        }
+        Some(Command::Merge(merge_args)) => {
+            init_logging(Verbosity::Normal, LogFormat::Text, should_colorize(ColorChoice::Auto), None)?;
+            merge_archives(&merge_args, &LocalStorage)?;
+            Ok(ExitCode::SUCCESS)
+        }
+        Some(Command::Regroup(regroup_args)) => {
+            init_logging(Verbosity::Normal, LogFormat::Text, should_colorize(ColorChoice::Auto), None)?;
+            regroup_archive(&regroup_args, &LocalStorage)?;
+            Ok(ExitCode::SUCCESS)
+        }
+        Some(Command::Restore(restore_args)) => {
+            init_logging(Verbosity::Normal, LogFormat::Text, should_colorize(ColorChoice::Auto), None)?;
+            restore_archive(&restore_args, &LocalStorage)?;
+            Ok(ExitCode::SUCCESS)
+        }
+        Some(Command::Bench(bench_args)) => {
+            init_logging(Verbosity::Quiet, LogFormat::Text, should_colorize(ColorChoice::Auto), None)?;
+            run_bench(&bench_args)?;
+            Ok(ExitCode::SUCCESS)
+        }
+        Some(Command::Init(init_args)) => {
+            // Only warnings and errors are logged, the wizard prints its own questions
+            init_logging(Verbosity::Quiet, LogFormat::Text, should_colorize(ColorChoice::Auto), None)?;
+            run_setup_wizard(&init_args)?;
+            Ok(ExitCode::SUCCESS)
+        }
+        Some(Command::Doctor(doctor_args)) => {
+            init_logging(Verbosity::Normal, LogFormat::Text, should_colorize(ColorChoice::Auto), None)?;
+            run_doctor(&doctor_args)?;
+            Ok(ExitCode::SUCCESS)
+        }
+        Some(Command::Explain(explain_args)) => {
+            init_logging(Verbosity::Normal, LogFormat::Text, should_colorize(ColorChoice::Auto), None)?;
+            explain_file(&explain_args)?;
+            Ok(ExitCode::SUCCESS)
+        }
+        Some(Command::Stats(stats_args)) => {
+            // Only warnings and errors are logged, the statistics are printed as the summary
+            init_logging(Verbosity::Quiet, LogFormat::Text, should_colorize(ColorChoice::Auto), None)?;
+            print_stats(&stats_args)?;
+            Ok(ExitCode::SUCCESS)
+        }
+        Some(Command::Completions(completions_args)) => {
+            print_completions(&completions_args)?;
+            Ok(ExitCode::SUCCESS)
+        }
+        Some(Command::Config(config_command)) => {
+            init_logging(Verbosity::Normal, LogFormat::Text, should_colorize(ColorChoice::Auto), None)?;
+            match config_command {
+                ConfigCommand::Init(init_args) => {
+                    // Safe to unwrap: clap just matched these subcommands
+                    let init_matches = matches.subcommand_matches("config").and_then(|m| m.subcommand_matches("init")).unwrap();
+                    init_config(&init_args, init_matches)?;
+                }
+                ConfigCommand::Validate(validate_args) => validate_config(&validate_args)?,
+            }
+            Ok(ExitCode::SUCCESS)
+        }
+        Some(Command::Service(service_command)) => {
+            match service_command {
+                ServiceCommand::Install(service_args) => {
+                    init_logging(Verbosity::Normal, LogFormat::Text, should_colorize(ColorChoice::Auto), None)?;
+                    install_service(&service_args)?;
+                }
+                ServiceCommand::Uninstall => {
+                    init_logging(Verbosity::Normal, LogFormat::Text, should_colorize(ColorChoice::Auto), None)?;
+                    uninstall_service()?;
+                }
+                // Logs to the event log, as a service has no console
+                ServiceCommand::Run(service_args) => run_service(service_args, execute_rules)?,
+            }
+            Ok(ExitCode::SUCCESS)
+        }
+        Some(Command::SystemdUnit(unit_args)) => {
+            init_logging(Verbosity::Quiet, LogFormat::Text, should_colorize(ColorChoice::Auto), None)?;
+            print_systemd_units(&unit_args)?;
+            Ok(ExitCode::SUCCESS)
+        }
+        // Safe to unwrap: clap requires --source and --destination whenever no subcommand is given
+        None => run(&cli.args.unwrap(), &cli.notify_args, &cli.log_args, cli.profile.as_deref()),
+    }
+}
+
+fn run(args: &Args, notify_args: &NotifyArgs, log_args: &LogArgs, profile: Option<&str>) -> Result<ExitCode> {
+    let log_file = log_args.log_file.as_deref().map(|log_file| (log_file, log_args.log_file_max_size));
+    init_logging(get_verbosity(args), log_args.log_format, should_colorize(log_args.color), log_file)?;
+    if let Some(profile) = profile {
+        info!("Profile: {}", profile);
+    }
+    if let Some(log_file) = &log_args.log_file {
+        info!("Log file: {}", log_file.display());
+    }
+    if log_args.log_format != LogFormat::Text {
+        info!("Log format: {:?}", log_args.log_format);
+    }
+
+    Ok(execute(args, notify_args)?.exit_code())
+}
+
+fn run_rules(scheduled_args: &ScheduledRunArgs) -> Result<ExitCode> {
+    let run_args = &scheduled_args.run_args;
+    let verbosity = get_verbosity_from_flags(run_args.quiet, run_args.verbose);
+    if !run_args.systemd {
+        init_logging(verbosity, LogFormat::Text, should_colorize(ColorChoice::Auto), None)?;
+        return execute_scheduled_rules(scheduled_args);
+    }
+
+    init_journald_logging(verbosity)?;
+    let watchdog = Watchdog::start();
+    notify_systemd("READY=1");
+    let result = execute_scheduled_rules(scheduled_args);
+    notify_systemd("STOPPING=1");
+    if let Some(watchdog) = watchdog {
+        watchdog.stop();
+    }
+    result
+}
+
+/// Run the selected rules once, or on the `--schedule` until the process is stopped
+fn execute_scheduled_rules(scheduled_args: &ScheduledRunArgs) -> Result<ExitCode> {
+    match &scheduled_args.schedule {
+        Some(schedule) => run_on_schedule(scheduled_args, schedule, execute_rules),
+        None => execute_rules(&scheduled_args.run_args),
+    }
+}
+
+/// Run the selected rules of the config file one after the other. A failing rule doesn't stop the next ones.
+fn execute_rules(run_args: &RunArgs) -> Result<ExitCode> {
+    let config_path = match &run_args.config {
+        Some(config_path) => config_path.clone(),
+        None => get_default_config_path()?,
+    };
+    let config = load_config(&config_path)?;
+    let rules = select_rules(&config, run_args.all, &run_args.rules)?;
+
+    let mut outcomes = Vec::new();
+    let mut failed_rules = 0;
+    for rule in &rules {
+        let _rule_span = info_span!("rule", name = %rule.name).entered();
+        info!(target: SUMMARY_TARGET, "\n=== Rule '{}' ===", rule.name);
+
+        let result = get_rule_args(rule).and_then(|(mut args, notify_args)| {
+            args.dry_run |= run_args.dry_run;
+            execute(&args, &notify_args)
+        });
+        match result {
+            Ok(RunOutcome::Cancelled) => {
+                outcomes.push(RunOutcome::Cancelled);
+                break;
+            }
+            Ok(outcome) => outcomes.push(outcome),
+            Err(e) => {
+                error!(rule = %rule.name, "Rule '{}' failed: {:#}", rule.name, e);
+                failed_rules += 1;
+            }
+        }
+    }
+
+    if failed_rules > 0 {
+        bail!("{} of {} rule(s) failed", failed_rules, rules.len());
+    }
+    Ok(combine_run_outcomes(&outcomes).exit_code())
+}
+
+/// Execute a single run, emailing the error it failed with to the `--notify-email` recipients
+fn execute(args: &Args, notify_args: &NotifyArgs) -> Result<RunOutcome> {
+    execute_run(args, notify_args).inspect_err(|e| {
+        if !notify_args.notify_email.is_empty()
+            && let Err(email_error) = notify_email_failure(args, notify_args, e) {
+                warn!("Failed to email the failed run: {:#}", email_error);
+            }
+    })
+}
+
+/// Execute a single run, from validating the arguments to the final report
+fn execute_run(args: &Args, notify_args: &NotifyArgs) -> Result<RunOutcome> {
+    let started_at = Instant::now();
+    validate_arguments(args)?;
+    validate_notify_args(notify_args)?;
+    print_arguments(args);
+    print_notify_args(notify_args);
+    run_pre_run_hook(args)?;
+
+    let now = args.now.unwrap_or_else(get_reference_time);
+    let mut args = enrich_arguments(args);
+    let (cancellation, _interruptible_run) = start_interruptible_run();
+    args.cancellation = cancellation;
+    let _ndjson_sink = (args.output == OutputFormat::Ndjson).then(|| add_event_sink(Box::new(NdjsonSink)));
+    let _mqtt_sink = notify_args.mqtt.as_deref().and_then(|url| connect_mqtt(url, &notify_args.mqtt_topic));
+    let mut scan_state = if args.incremental { Some(ScanState::load(&args.sources, &args.destination)?) } else { None };
+    let (plan, move_report) = match (args.batch_size, args.max_memory) {
+        (Some(batch_size), _) => plan_and_move_in_batches(&args, &LocalStorage, now, batch_size, scan_state.as_mut())?,
+        (None, Some(max_memory)) => plan_and_move_with_memory_cap(&args, &LocalStorage, now, max_memory, scan_state.as_mut())?,
+        (None, None) => {
+            let mut plan = get_files_to_move(&args, &LocalStorage, now, scan_state.as_mut())?;
+            if args.tui {
+                review_plan(&mut plan, scan_state.as_mut())?;
+            }
+            if args.print0 {
+                print_nul_delimited_plan(&plan.files_to_move)?;
+            }
+            if args.plan_format == Some(PlanFormat::Rsync)
+                && let Some(plan_output) = &args.plan_output {
+                    write_rsync_plan(plan_output, &args, &plan.files_to_move)?;
+                }
+            let move_started_at = Instant::now();
+            let mut move_report = move_files(&args, &LocalStorage, &plan.files_to_move, args.dry_run)?;
+            if move_report.aborted.is_none() {
+                delete_duplicates(&args, &LocalStorage, &plan.duplicates, args.dry_run, &mut move_report);
+            }
+            plan.timings.move_files = move_started_at.elapsed();
+            (plan, move_report)
+        }
+    };
+    // Only real runs have a manifest of the moved files
+    if args.git_mv
+        && let Some(run_id) = &move_report.run_id
+        && let Err(e) = load_manifest(Some(run_id)).and_then(|(_, moved)| stage_git_moves(&args, &moved)) {
+            warn!("Failed to stage the moves in git: {:#}", e);
+        }
+    if (args.obsidian_rewrite_links || !args.rewrite_links.is_empty())
+        && let Some(run_id) = &move_report.run_id
+        && let Err(e) = rewrite_links_to_moved_files(&args, run_id) {
+            warn!("Failed to rewrite the links to the moved files: {:#}", e);
+        }
+    if args.period_links
+        && let Some(group_by) = args.group_by
+        && !args.dry_run
+        && !args.cancellation.is_cancelled() {
+            for destination in get_destination_roots(&args).into_iter().filter(|destination| destination.is_dir()) {
+                if let Err(e) = update_period_links(destination, group_by, now) {
+                    warn!("Failed to update the period links of {}: {:#}", destination.display(), e);
+                }
+            }
+        }
+    let cleanup_started_at = Instant::now();
+    let deleted_dirs = delete_empty_directories(&args, &LocalStorage, &move_report.moved)?;
+    let timings = PhaseTimings { cleanup: cleanup_started_at.elapsed(), ..plan.timings };
+    // Files planned by a dry run or left by a cancelled run are still in place, so only complete runs update the scan
+    // state
+    if let Some(scan_state) = &mut scan_state
+        && !args.dry_run
+        && !args.cancellation.is_cancelled() {
+            for error in &move_report.errors {
+                scan_state.mark_unsettled(&error.path);
+            }
+            for path in &move_report.left_in_place {
+                scan_state.mark_unsettled(path);
+            }
+            if let Err(e) = scan_state.save() {
+                warn!("Failed to save the scan state: {:#}", e);
+            }
+        }
+    let failed = plan.errors.len() + move_report.errors.len();
+    if let Some(report_html) = &args.report_html {
+        write_html_report(report_html, &args, &plan, &move_report)?;
+    }
+    if let Some(url) = &notify_args.notify_webhook
+        && let Err(e) = notify_webhook(url, args.dry_run, &plan, &move_report, started_at.elapsed()) {
+            warn!("Failed to notify the webhook: {:#}", e);
+        }
+    // An aborted run fails below, which emails the failure instead
+    if !notify_args.notify_email.is_empty()
+        && move_report.aborted.is_none()
+        && (notify_args.notify_email_on == EmailTrigger::Always || failed > 0)
+        && let Err(e) = notify_email(&args, notify_args, &plan, &move_report, started_at.elapsed()) {
+            warn!("Failed to email the run: {:#}", e);
+        }
 
-    validate_arguments(&args)?;
-    print_arguments(&args);
+    match args.output {
+        OutputFormat::Text => {
+            print_run_statistics(&RunStatistics {
+                periods: &move_report.periods,
+                skipped: plan.skipped,
+                failed,
+                elapsed: started_at.elapsed(),
+                moving: (!args.dry_run).then_some(timings.move_files),
+                shortened: &plan.shortened,
+                mirrors: &move_report.mirrors,
+            });
+            if args.dry_run && !move_report.destination_periods.is_empty() {
+                print_disk_usage(&move_report.destination_periods);
+            }
+            if args.timings {
+                print_phase_timings(&timings);
+            }
+        }
+        OutputFormat::Json => print_json_report(&args, &plan, &move_report, &deleted_dirs)?,
+        OutputFormat::Ndjson => {}
+    }
+    emit_event(Event::Summary(build_run_summary(&args, &plan, &move_report, &deleted_dirs)));
 
-    let now = Utc::now();
-    let args = enrich_arguments(&args);
-    let files_to_move = get_files_to_move(&args, now);
-    move_files(&args, &files_to_move, args.dry_run)?;
-    delete_empty_directories(&args, &args.source)?;
+    let outcome = if args.cancellation.is_cancelled() { RunOutcome::Cancelled } else { get_run_outcome(plan.planned, failed) };
+    run_post_run_hook(&args, &plan, &move_report, outcome);
+    // The reports above describe the files handled before the abort
+    if let Some(abort_reason) = move_report.aborted {
+        bail!(abort_reason);
+    }
+    Ok(outcome)
+}
 
+/// Rewrite the links to the files moved by a run, read back from its manifest
+fn rewrite_links_to_moved_files(args: &Args, run_id: &str) -> Result<()> {
+    let (_, moved) = load_manifest(Some(run_id))?;
+    if args.obsidian_rewrite_links {
+        rewrite_vault_links(args, &moved)?;
+    }
+    if !args.rewrite_links.is_empty() {
+        rewrite_moved_links(args, &moved)?;
+    }
     Ok(())
 }
\ No newline at end of file
@@ -0,0 +1,250 @@
+use crate::cli::{EmailTrigger, NotifyArgs};
+use crate::mqtt::validate_mqtt_url;
+use chronomover_core::file::{FileError, MoveReport, Plan};
+use chronomover_core::model::Args;
+use chronomover_core::report::format_size;
+use color_eyre::eyre::{bail, Context, ContextCompat, Report, Result};
+use lettre::message::Mailbox;
+use lettre::{Message, SmtpTransport, Transport};
+use serde::Serialize;
+use std::fmt::Write;
+use std::time::Duration;
+use tracing::info;
+
+/// Upper bound for the whole request, so an unreachable endpoint can't hang a scheduled run
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Upper bound for every exchange with the SMTP server, for the same reason
+const SMTP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Errors listed in an email, the others only being counted, so a run failing on a whole drive sends a readable email
+const MAX_EMAILED_ERRORS: usize = 100;
+
+/// JSON body posted to `--notify-webhook` once the run finishes
+#[derive(Debug, Serialize)]
+struct WebhookPayload<'a> {
+    dry_run: bool,
+    run_id: Option<&'a str>,
+    planned: usize,
+    moved: usize,
+    skipped: usize,
+    failed: usize,
+    bytes: u64,
+    duration_ms: u128,
+    errors: Vec<&'a FileError>,
+}
+
+/// POST a JSON summary of the run to the webhook URL
+pub fn notify_webhook(url: &str, dry_run: bool, plan: &Plan, move_report: &MoveReport, duration: Duration) -> Result<()> {
+    let payload = build_webhook_payload(dry_run, plan, move_report, duration);
+    let body = serde_json::to_string(&payload).context("Failed to serialize webhook payload")?;
+
+    let agent: ureq::Agent = ureq::Agent::config_builder()
+        .timeout_global(Some(WEBHOOK_TIMEOUT))
+        .build()
+        .into();
+    agent.post(url)
+        .header("Content-Type", "application/json")
+        .send(body)
+        .with_context(|| format!("Failed to notify webhook: {url}"))?;
+
+    info!("Notified webhook: {}", url);
+    Ok(())
+}
+
+fn build_webhook_payload<'a>(dry_run: bool, plan: &'a Plan, move_report: &'a MoveReport, duration: Duration) -> WebhookPayload<'a> {
+    let errors = plan.errors.iter().chain(&move_report.errors).collect::<Vec<_>>();
+    WebhookPayload {
+        dry_run,
+        run_id: move_report.run_id.as_deref(),
+        planned: plan.planned,
+        moved: move_report.moved_count(),
+        skipped: plan.skipped,
+        failed: errors.len(),
+        bytes: move_report.periods.values().map(|stats| stats.bytes).sum(),
+        duration_ms: duration.as_millis(),
+        errors,
+    }
+}
+
+/// Email the summary and errors of a finished run to the `--notify-email` recipients
+pub fn notify_email(args: &Args, notify_args: &NotifyArgs, plan: &Plan, move_report: &MoveReport, duration: Duration) -> Result<()> {
+    let (subject, body) = build_run_email(args, plan, move_report, duration);
+    send_email(notify_args, &subject, body)
+}
+
+/// Email the error a run failed with to the `--notify-email` recipients
+pub fn notify_email_failure(args: &Args, notify_args: &NotifyArgs, error: &Report) -> Result<()> {
+    let body = format!("The run failed before finishing:\n\n{error:#}\n\n{}", describe_folders(args));
+    send_email(notify_args, "ChronoMover: run failed", body)
+}
+
+/// Check the webhook URL, the MQTT broker URL, and the addresses and SMTP server URL of a run emailing its outcome,
+/// before anything runs
+pub fn validate_notify_args(notify_args: &NotifyArgs) -> Result<()> {
+    if let Some(url) = &notify_args.notify_webhook
+        && !url.starts_with("http://") && !url.starts_with("https://") {
+            bail!("Webhook URL must start with http:// or https://: {}", url);
+        }
+    if let Some(url) = &notify_args.mqtt {
+        validate_mqtt_url(url)?;
+    }
+    if notify_args.notify_email.is_empty() {
+        return Ok(());
+    }
+    get_sender(notify_args)?;
+    get_recipients(notify_args)?;
+    if let Some(smtp_url) = &notify_args.smtp_url {
+        SmtpTransport::from_url(smtp_url).with_context(|| format!("Invalid SMTP server URL: {smtp_url}"))?;
+    }
+    Ok(())
+}
+
+/// Log the notifications of a run, along with its other arguments
+pub fn print_notify_args(notify_args: &NotifyArgs) {
+    if let Some(notify_webhook) = &notify_args.notify_webhook {
+        info!("Notify webhook: {}", notify_webhook);
+    }
+    if notify_args.mqtt.is_some() {
+        info!("MQTT topic prefix: {}", notify_args.mqtt_topic);
+    }
+    if !notify_args.notify_email.is_empty() {
+        let run = if notify_args.notify_email_on == EmailTrigger::Failure { "failed" } else { "finished" };
+        info!("Emailing the {} run to: {}", run, notify_args.notify_email.join(", "));
+    }
+}
+
+fn send_email(notify_args: &NotifyArgs, subject: &str, body: String) -> Result<()> {
+    let smtp_url = notify_args.smtp_url.as_deref().context("--notify-email requires --smtp-url")?;
+    let mut message = Message::builder().from(get_sender(notify_args)?).subject(subject);
+    for recipient in get_recipients(notify_args)? {
+        message = message.to(recipient);
+    }
+    let message = message.body(body).context("Failed to build the email")?;
+
+    let transport = SmtpTransport::from_url(smtp_url)
+        .with_context(|| format!("Invalid SMTP server URL: {smtp_url}"))?
+        .timeout(Some(SMTP_TIMEOUT))
+        .build();
+    transport.send(&message).context("Failed to send the email")?;
+
+    info!("Emailed the run to: {}", notify_args.notify_email.join(", "));
+    Ok(())
+}
+
+/// The `--email-from` address, or the first recipient
+fn get_sender(notify_args: &NotifyArgs) -> Result<Mailbox> {
+    let sender = notify_args.email_from.as_ref().or(notify_args.notify_email.first()).context("--notify-email requires an address")?;
+    sender.parse().with_context(|| format!("Invalid email address: {sender}"))
+}
+
+fn get_recipients(notify_args: &NotifyArgs) -> Result<Vec<Mailbox>> {
+    notify_args.notify_email.iter()
+        .map(|recipient| recipient.parse().with_context(|| format!("Invalid email address: {recipient}")))
+        .collect()
+}
+
+/// Subject and plain text body of the email of a finished run
+fn build_run_email(args: &Args, plan: &Plan, move_report: &MoveReport, duration: Duration) -> (String, String) {
+    let moved = move_report.moved_count();
+    let errors = plan.errors.iter().chain(&move_report.errors).collect::<Vec<_>>();
+    let subject = match (args.dry_run, errors.len()) {
+        (true, 0) => format!("ChronoMover: {moved} file(s) would be moved (dry run)"),
+        (true, failed) => format!("ChronoMover: {moved} file(s) would be moved, {failed} failed (dry run)"),
+        (false, 0) => format!("ChronoMover: {moved} file(s) moved"),
+        (false, failed) => format!("ChronoMover: {moved} file(s) moved, {failed} failed"),
+    };
+
+    // Writing to a String never fails
+    let mut body = String::new();
+    let duration = humantime::format_duration(Duration::from_millis(duration.as_millis() as u64));
+    match &move_report.run_id {
+        Some(run_id) => writeln!(body, "Run {} finished in {}.", run_id, duration),
+        None => writeln!(body, "{} finished in {}.", if args.dry_run { "Dry run" } else { "Run" }, duration),
+    }.unwrap();
+    writeln!(body, "{}\n", describe_folders(args)).unwrap();
+    let bytes = move_report.periods.values().map(|stats| stats.bytes).sum();
+    writeln!(body, "Planned: {}\nMoved: {} ({})\nSkipped: {}\nFailed: {}", plan.planned, moved, format_size(bytes), plan.skipped, errors.len()).unwrap();
+    if !move_report.periods.is_empty() {
+        writeln!(body, "\nPeriods:").unwrap();
+        for (period, stats) in &move_report.periods {
+            writeln!(body, "  {}: {} file(s), {}", period, stats.files, format_size(stats.bytes)).unwrap();
+        }
+    }
+    if !errors.is_empty() {
+        writeln!(body, "\nErrors:").unwrap();
+        for error in errors.iter().take(MAX_EMAILED_ERRORS) {
+            writeln!(body, "  {}: {}", error.path.display(), error.error).unwrap();
+        }
+        if errors.len() > MAX_EMAILED_ERRORS {
+            writeln!(body, "  ... and {} more", errors.len() - MAX_EMAILED_ERRORS).unwrap();
+        }
+    }
+    (subject, body)
+}
+
+fn describe_folders(args: &Args) -> String {
+    let sources = args.sources.iter().map(|source| source.display().to_string()).collect::<Vec<_>>();
+    format!("Sources: {}\nDestination: {}", sources.join(", "), args.destination.display())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::Cli;
+    use chronomover_core::report::PeriodStats;
+    use clap::Parser;
+    use std::collections::BTreeMap;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_build_webhook_payload() {
+        let plan = Plan {
+            errors: vec![FileError { path: PathBuf::from("/notes/b.md"), error: "Permission denied".to_string() }],
+            skipped: 3,
+            ..Plan::default()
+        };
+        let move_report = MoveReport {
+            run_id: Some("20250615T000000Z".to_string()),
+            periods: BTreeMap::from([
+                ("2025-05".to_string(), PeriodStats { files: 2, bytes: 2048 }),
+                ("2025-06".to_string(), PeriodStats { files: 1, bytes: 100 }),
+            ]),
+            ..MoveReport::default()
+        };
+
+        let payload = build_webhook_payload(false, &plan, &move_report, Duration::from_millis(1500));
+        let value = serde_json::to_value(&payload).unwrap();
+
+        assert_eq!(value["run_id"], "20250615T000000Z");
+        assert_eq!(value["skipped"], 3);
+        assert_eq!(value["failed"], 1);
+        assert_eq!(value["bytes"], 2148);
+        assert_eq!(value["duration_ms"], 1500);
+        assert_eq!(value["errors"][0]["error"], "Permission denied");
+    }
+
+    #[test]
+    fn test_build_run_email() {
+        let args = Cli::parse_from(["chronomover", "-s", "/notes", "-d", "/archive"]).args.unwrap();
+        let plan = Plan {
+            errors: vec![FileError { path: PathBuf::from("/notes/b.md"), error: "Permission denied".to_string() }],
+            planned: 2,
+            skipped: 3,
+            ..Plan::default()
+        };
+        let move_report = MoveReport {
+            run_id: Some("20250615T000000Z".to_string()),
+            periods: BTreeMap::from([("2025-05".to_string(), PeriodStats { files: 2, bytes: 2048 })]),
+            ..MoveReport::default()
+        };
+
+        let (subject, body) = build_run_email(&args, &plan, &move_report, Duration::from_micros(3_000_500));
+
+        assert_eq!(subject, "ChronoMover: 2 file(s) moved, 1 failed");
+        assert!(body.starts_with("Run 20250615T000000Z finished in 3s.\nSources: /notes\nDestination: /archive\n"));
+        assert!(body.contains("\nMoved: 2 (2.0 KiB)\nSkipped: 3\nFailed: 1\n"));
+        assert!(body.contains("\nPeriods:\n  2025-05: 2 file(s), 2.0 KiB\n"));
+        assert!(body.ends_with("\nErrors:\n  /notes/b.md: Permission denied\n"));
+    }
+}
@@ -0,0 +1,244 @@
+use crate::cli::{RunArgs, ScheduledRunArgs};
+use crate::config::resolve_scheduled_config;
+use chronomover_core::report::RunOutcome;
+use crate::service::RulesRunner;
+use chrono::{DateTime, Local, TimeDelta};
+use color_eyre::eyre::{Context, ContextCompat, Result};
+use croner::Cron;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::hash::{BuildHasher, RandomState};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::thread;
+use std::time::Duration;
+use tracing::{error, info, warn};
+
+/// Longest sleep between two looks at the clock. Sleeps don't count the time the computer was suspended, so waking up
+/// regularly keeps a run from starting hours late.
+const MAX_SLEEP: Duration = Duration::from_secs(60);
+
+/// State of a scheduled process, written to the status file whenever it changes, for monitoring. The last run is read
+/// back when the process starts, to catch up on a run missed while it was stopped.
+#[derive(Debug, Serialize, Deserialize)]
+struct ScheduleStatus {
+    schedule: String,
+    pid: u32,
+    state: ScheduleState,
+    next_run: Option<DateTime<Local>>,
+    last_run: Option<LastRun>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum ScheduleState {
+    Waiting,
+    Running,
+    /// Stopped by Ctrl-C during a run
+    Stopped,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct LastRun {
+    /// Scheduled time of the run, before the jitter
+    scheduled_for: DateTime<Local>,
+    started_at: DateTime<Local>,
+    finished_at: Option<DateTime<Local>>,
+    /// `success`, `completed-with-failures`, `nothing-matched`, `cancelled` or `failed`, `None` while running
+    status: Option<String>,
+    error: Option<String>,
+}
+
+/// Run the rules on the `--schedule` until the process is stopped. A run still going at the next scheduled time
+/// delays it; runs missed meanwhile, or while the process was stopped, are caught up with a single run with
+/// `--catch-up` and skipped otherwise.
+pub fn run_on_schedule(scheduled_args: &ScheduledRunArgs, schedule: &Cron, run_rules: RulesRunner) -> Result<ExitCode> {
+    let status_path = match &scheduled_args.status_file {
+        Some(status_file) => status_file.clone(),
+        None => get_default_status_path(&scheduled_args.run_args)?,
+    };
+    let previous_status = load_status(&status_path);
+    // A run of another schedule tells nothing about the runs missed by this one
+    let mut last_scheduled_for = previous_status
+        .filter(|status| status.schedule == schedule.pattern.to_string())
+        .and_then(|status| status.last_run)
+        .map(|last_run| last_run.scheduled_for);
+    let mut status = ScheduleStatus {
+        schedule: schedule.pattern.to_string(),
+        pid: std::process::id(),
+        state: ScheduleState::Waiting,
+        next_run: None,
+        last_run: None,
+    };
+    info!("Running the rules on the schedule '{}', writing its status to {}", schedule.pattern, status_path.display());
+
+    loop {
+        let scheduled_for = get_next_run(schedule, last_scheduled_for, Local::now(), scheduled_args.catch_up)?;
+        let start_at = scheduled_for + get_jitter_delay(scheduled_args.jitter);
+        status.state = ScheduleState::Waiting;
+        status.next_run = Some(start_at);
+        save_status(&status_path, &status);
+        info!("Next run: {}", start_at.format("%Y-%m-%d %H:%M:%S"));
+        sleep_until(start_at);
+
+        status.state = ScheduleState::Running;
+        status.next_run = None;
+        status.last_run = Some(LastRun { scheduled_for, started_at: Local::now(), finished_at: None, status: None, error: None });
+        save_status(&status_path, &status);
+
+        let result = run_rules(&scheduled_args.run_args);
+        if let Err(e) = &result {
+            error!("Scheduled run failed: {:#}", e);
+        }
+        if let Some(last_run) = &mut status.last_run {
+            last_run.finished_at = Some(Local::now());
+            last_run.status = Some(get_result_status(&result).to_string());
+            last_run.error = result.as_ref().err().map(|e| format!("{e:#}"));
+        }
+        // Ctrl-C stops the schedule along with the run
+        if matches!(result, Ok(exit_code) if exit_code == RunOutcome::Cancelled.exit_code()) {
+            status.state = ScheduleState::Stopped;
+            save_status(&status_path, &status);
+            return result;
+        }
+        last_scheduled_for = Some(scheduled_for);
+    }
+}
+
+/// Scheduled time of the next run. With `catch_up`, the occurrences missed since the last run are made up for by
+/// running right away, once for all of them.
+fn get_next_run(schedule: &Cron, last_scheduled_for: Option<DateTime<Local>>, now: DateTime<Local>, catch_up: bool) -> Result<DateTime<Local>> {
+    let next_after_now = schedule.find_next_occurrence(&now, false).context("Failed to find the next run of the schedule")?;
+    let Some(last_scheduled_for) = last_scheduled_for.filter(|_| catch_up) else {
+        return Ok(next_after_now);
+    };
+    let mut missed = None;
+    let mut occurrence = last_scheduled_for;
+    loop {
+        occurrence = schedule.find_next_occurrence(&occurrence, false).context("Failed to find the next run of the schedule")?;
+        if occurrence > now {
+            break;
+        }
+        missed = Some(occurrence);
+    }
+    if let Some(missed) = missed {
+        info!("Catching up on the run scheduled at {}", missed.format("%Y-%m-%d %H:%M:%S"));
+    }
+    Ok(missed.unwrap_or(next_after_now))
+}
+
+/// Random delay up to `jitter`
+fn get_jitter_delay(jitter: Option<Duration>) -> TimeDelta {
+    let Some(jitter) = jitter.filter(|jitter| !jitter.is_zero()) else {
+        return TimeDelta::zero();
+    };
+    // The hasher of a new `RandomState` is randomly seeded, which is random enough to spread runs
+    let random = RandomState::new().hash_one(std::process::id());
+    let delay = Duration::from_millis(random % jitter.as_millis().max(1) as u64);
+    TimeDelta::from_std(delay).unwrap_or_default()
+}
+
+fn sleep_until(time: DateTime<Local>) {
+    while let Ok(remaining) = (time - Local::now()).to_std() {
+        if remaining.is_zero() {
+            break;
+        }
+        thread::sleep(remaining.min(MAX_SLEEP));
+    }
+}
+
+fn get_result_status(result: &Result<ExitCode>) -> &'static str {
+    match result {
+        Ok(exit_code) if *exit_code == ExitCode::SUCCESS => "success",
+        Ok(exit_code) if *exit_code == RunOutcome::NothingMatched.exit_code() => "nothing-matched",
+        Ok(exit_code) if *exit_code == RunOutcome::Cancelled.exit_code() => "cancelled",
+        Ok(_) => "completed-with-failures",
+        Err(_) => "failed",
+    }
+}
+
+fn load_status(path: &Path) -> Option<ScheduleStatus> {
+    let content = fs::read_to_string(path).ok()?;
+    serde_json::from_str(&content)
+        .inspect_err(|e| warn!("Ignoring invalid schedule status {}: {}", path.display(), e))
+        .ok()
+}
+
+/// Write the status file, only warning on failure, as a missing status shouldn't stop the runs
+fn save_status(path: &Path, status: &ScheduleStatus) {
+    let result = (|| -> Result<()> {
+        if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+            fs::create_dir_all(parent).with_context(|| format!("Failed to create schedule status directory: {}", parent.display()))?;
+        }
+        let content = serde_json::to_string_pretty(status).context("Failed to serialize schedule status")?;
+        fs::write(path, content).with_context(|| format!("Failed to write schedule status: {}", path.display()))
+    })();
+    if let Err(e) = result {
+        warn!("Failed to save the schedule status: {:#}", e);
+    }
+}
+
+/// Status file of the rules of a config file, inside the user's local data folder
+fn get_default_status_path(run_args: &RunArgs) -> Result<PathBuf> {
+    let config_path = resolve_scheduled_config(run_args)?;
+    let data_dir = dirs::data_local_dir().context("Failed to find the local data directory of the current user")?;
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(config_path.as_os_str().as_encoded_bytes());
+    hasher.update(if run_args.all { b"\0all" } else { b"\0" });
+    for rule in &run_args.rules {
+        hasher.update(b"\0");
+        hasher.update(rule.as_bytes());
+    }
+    let key = hasher.finalize().to_hex();
+    Ok(data_dir.join("chronomover").join("schedule").join(format!("{}.json", &key[..16])))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::parse_schedule;
+    use chrono::TimeZone;
+
+    fn local(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> DateTime<Local> {
+        Local.with_ymd_and_hms(year, month, day, hour, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn test_get_next_run() {
+        let schedule = parse_schedule("0 3 * * *").unwrap();
+        let now = local(2025, 6, 10, 12, 0);
+
+        assert_eq!(get_next_run(&schedule, None, now, true).unwrap(), local(2025, 6, 11, 3, 0));
+        // Missed runs are skipped without --catch-up
+        assert_eq!(get_next_run(&schedule, Some(local(2025, 6, 7, 3, 0)), now, false).unwrap(), local(2025, 6, 11, 3, 0));
+        // Nothing was missed since the last run
+        assert_eq!(get_next_run(&schedule, Some(local(2025, 6, 10, 3, 0)), now, true).unwrap(), local(2025, 6, 11, 3, 0));
+    }
+
+    #[test]
+    fn test_get_next_run_catches_up_once() {
+        let schedule = parse_schedule("0 3 * * *").unwrap();
+        let now = local(2025, 6, 10, 12, 0);
+
+        let next_run = get_next_run(&schedule, Some(local(2025, 6, 7, 3, 0)), now, true).unwrap();
+
+        assert_eq!(next_run, local(2025, 6, 10, 3, 0));
+        assert_eq!(get_next_run(&schedule, Some(next_run), now, true).unwrap(), local(2025, 6, 11, 3, 0));
+    }
+
+    #[test]
+    fn test_get_jitter_delay() {
+        assert_eq!(get_jitter_delay(None), TimeDelta::zero());
+        let delay = get_jitter_delay(Some(Duration::from_secs(60)));
+        assert!(delay >= TimeDelta::zero() && delay < TimeDelta::seconds(60));
+    }
+
+    #[test]
+    fn test_get_result_status() {
+        assert_eq!(get_result_status(&Ok(ExitCode::SUCCESS)), "success");
+        assert_eq!(get_result_status(&Ok(RunOutcome::CompletedWithFailures.exit_code())), "completed-with-failures");
+        assert_eq!(get_result_status(&Ok(RunOutcome::NothingMatched.exit_code())), "nothing-matched");
+        assert_eq!(get_result_status(&Ok(RunOutcome::Cancelled.exit_code())), "cancelled");
+        assert_eq!(get_result_status(&Err(color_eyre::eyre::eyre!("failed"))), "failed");
+    }
+}
@@ -0,0 +1,252 @@
+use crate::cli::{RunArgs, ServiceRunArgs};
+use crate::config::resolve_scheduled_config;
+use color_eyre::eyre::{Context, Result};
+use std::ffi::OsString;
+use std::path::Path;
+use std::process::ExitCode;
+use tracing::info;
+
+/// Name of the Windows service and of its event log source
+pub const SERVICE_NAME: &str = "ChronoMover";
+
+/// Runs the selected rules of a config file once, like `chronomover run`
+pub type RulesRunner = fn(&RunArgs) -> Result<ExitCode>;
+
+/// Register the Windows service, started with Windows, running the rules every `--interval`. The config file is
+/// resolved now, as the service runs as LocalSystem, whose config directory is not the one of the installing user.
+pub fn install_service(service_args: &ServiceRunArgs) -> Result<()> {
+    let config_path = resolve_scheduled_config(&service_args.run_args)?;
+
+    let executable = std::env::current_exe().context("Failed to locate the chronomover executable")?;
+    platform::create_service(&executable, get_service_arguments(service_args, &config_path))?;
+    info!(
+        "Installed the '{}' service, running the rules of {} every {}. It starts with Windows, or now with `sc start {}`",
+        SERVICE_NAME, config_path.display(), humantime::format_duration(service_args.interval), SERVICE_NAME
+    );
+    Ok(())
+}
+
+/// Stop and remove the Windows service
+pub fn uninstall_service() -> Result<()> {
+    platform::delete_service()?;
+    info!("Removed the '{}' service", SERVICE_NAME);
+    Ok(())
+}
+
+/// Run the rules every `--interval` until Windows stops the service, logging to the Application event log
+pub fn run_service(service_args: ServiceRunArgs, run_rules: RulesRunner) -> Result<()> {
+    platform::run_service(service_args, run_rules)
+}
+
+/// Arguments the service control manager starts the executable with, the `service run` equivalent of the options
+/// given to `service install`
+fn get_service_arguments(service_args: &ServiceRunArgs, config_path: &Path) -> Vec<OsString> {
+    let run_args = &service_args.run_args;
+    let mut arguments = vec![
+        OsString::from("service"),
+        OsString::from("run"),
+        OsString::from("--interval"),
+        OsString::from(humantime::format_duration(service_args.interval).to_string()),
+        OsString::from("--config"),
+        config_path.as_os_str().to_os_string(),
+    ];
+    if run_args.all {
+        arguments.push(OsString::from("--all"));
+    }
+    if run_args.dry_run {
+        arguments.push(OsString::from("--dry-run"));
+    }
+    if run_args.quiet {
+        arguments.push(OsString::from("--quiet"));
+    }
+    arguments.extend((0..run_args.verbose).map(|_| OsString::from("--verbose")));
+    arguments.extend(run_args.rules.iter().map(OsString::from));
+    arguments
+}
+
+#[cfg(windows)]
+mod platform {
+    use super::{RulesRunner, SERVICE_NAME};
+    use crate::logging::init_event_log_logging;
+    use crate::cli::ServiceRunArgs;
+    use chronomover_core::model::get_verbosity_from_flags;
+    use color_eyre::eyre::{bail, Context, Result};
+    use std::ffi::OsString;
+    use std::path::Path;
+    use std::process;
+    use std::sync::mpsc::{self, RecvTimeoutError};
+    use std::sync::OnceLock;
+    use std::time::Duration;
+    use tracing::{error, info};
+    use windows_service::service::{
+        ServiceAccess, ServiceControl, ServiceControlAccept, ServiceErrorControl, ServiceExitCode, ServiceInfo,
+        ServiceStartType, ServiceState, ServiceStatus, ServiceType,
+    };
+    use windows_service::service_control_handler::{self, ServiceControlHandlerResult, ServiceStatusHandle};
+    use windows_service::service_manager::{ServiceManager, ServiceManagerAccess};
+    use windows_service::{define_windows_service, service_dispatcher};
+
+    /// Registry key of the event log source, so Event Viewer can display the messages of the service
+    const EVENT_SOURCE_KEY: &str = r"HKLM\SYSTEM\CurrentControlSet\Services\EventLog\Application\ChronoMover";
+    /// Message file whose event 0 is the bare message string, installed with the .NET Framework on every Windows
+    const EVENT_MESSAGE_FILE: &str = r"%SystemRoot%\Microsoft.NET\Framework64\v4.0.30319\EventLogMessages.dll";
+
+    /// Options of the running service, handed to the service main function started by the dispatcher
+    static SERVICE: OnceLock<(ServiceRunArgs, RulesRunner)> = OnceLock::new();
+
+    pub fn create_service(executable: &Path, arguments: Vec<OsString>) -> Result<()> {
+        let manager = ServiceManager::local_computer(None::<&str>, ServiceManagerAccess::CONNECT | ServiceManagerAccess::CREATE_SERVICE)
+            .context("Failed to connect to the service control manager, installing a service requires an administrator prompt")?;
+        let service_info = ServiceInfo {
+            name: OsString::from(SERVICE_NAME),
+            display_name: OsString::from(SERVICE_NAME),
+            service_type: ServiceType::OWN_PROCESS,
+            start_type: ServiceStartType::AutoStart,
+            error_control: ServiceErrorControl::Normal,
+            executable_path: executable.to_path_buf(),
+            launch_arguments: arguments,
+            dependencies: Vec::new(),
+            account_name: None,
+            account_password: None,
+        };
+        let service = manager.create_service(&service_info, ServiceAccess::CHANGE_CONFIG)
+            .with_context(|| format!("Failed to create the '{}' service", SERVICE_NAME))?;
+        service.set_description("Archives files into date-based folders on a schedule")
+            .context("Failed to set the description of the service")?;
+
+        let status = process::Command::new("reg")
+            .args(["add", EVENT_SOURCE_KEY, "/v", "EventMessageFile", "/t", "REG_EXPAND_SZ", "/d", EVENT_MESSAGE_FILE, "/f"])
+            .stdout(process::Stdio::null())
+            .status()
+            .context("Failed to run reg")?;
+        if !status.success() {
+            bail!("reg exited with {} while registering the event log source", status);
+        }
+        Ok(())
+    }
+
+    pub fn delete_service() -> Result<()> {
+        let manager = ServiceManager::local_computer(None::<&str>, ServiceManagerAccess::CONNECT)
+            .context("Failed to connect to the service control manager, removing a service requires an administrator prompt")?;
+        let service = manager.open_service(SERVICE_NAME, ServiceAccess::QUERY_STATUS | ServiceAccess::STOP | ServiceAccess::DELETE)
+            .with_context(|| format!("Failed to open the '{}' service", SERVICE_NAME))?;
+        if service.query_status().context("Failed to query the service")?.current_state != ServiceState::Stopped {
+            service.stop().context("Failed to stop the service")?;
+        }
+        service.delete().context("Failed to delete the service")?;
+
+        // The event log source only lets Event Viewer display past events, so failing to remove it is not an error
+        let _ = process::Command::new("reg")
+            .args(["delete", EVENT_SOURCE_KEY, "/f"])
+            .stdout(process::Stdio::null())
+            .stderr(process::Stdio::null())
+            .status();
+        Ok(())
+    }
+
+    define_windows_service!(ffi_service_main, service_main);
+
+    pub fn run_service(service_args: ServiceRunArgs, run_rules: RulesRunner) -> Result<()> {
+        init_event_log_logging(get_verbosity_from_flags(service_args.run_args.quiet, service_args.run_args.verbose), SERVICE_NAME)?;
+        // Safe to ignore: the dispatcher only starts once per process
+        let _ = SERVICE.set((service_args, run_rules));
+        service_dispatcher::start(SERVICE_NAME, ffi_service_main)
+            .context("Failed to connect to the service control manager, `service run` is only started by Windows (see `service install`)")
+    }
+
+    fn service_main(_arguments: Vec<OsString>) {
+        if let Err(e) = run_until_stopped() {
+            error!("The service failed: {:#}", e);
+        }
+    }
+
+    fn run_until_stopped() -> Result<()> {
+        // Safe to unwrap: set before the dispatcher starts the service
+        let (service_args, run_rules) = SERVICE.get().unwrap();
+        let (stop_sender, stop_receiver) = mpsc::channel();
+        let status_handle = service_control_handler::register(SERVICE_NAME, move |control| match control {
+            ServiceControl::Stop | ServiceControl::Shutdown => {
+                let _ = stop_sender.send(());
+                ServiceControlHandlerResult::NoError
+            }
+            ServiceControl::Interrogate => ServiceControlHandlerResult::NoError,
+            _ => ServiceControlHandlerResult::NotImplemented,
+        })?;
+        set_service_state(&status_handle, ServiceState::Running)?;
+        info!("The service started, running the rules every {}", humantime::format_duration(service_args.interval));
+
+        // A stop request during a run is handled once the run is over, so no move is interrupted halfway
+        loop {
+            if let Err(e) = run_rules(&service_args.run_args) {
+                error!("Scheduled run failed: {:#}", e);
+            }
+            match stop_receiver.recv_timeout(service_args.interval) {
+                Err(RecvTimeoutError::Timeout) => continue,
+                Ok(()) | Err(RecvTimeoutError::Disconnected) => break,
+            }
+        }
+
+        info!("The service stopped");
+        set_service_state(&status_handle, ServiceState::Stopped)?;
+        Ok(())
+    }
+
+    fn set_service_state(status_handle: &ServiceStatusHandle, state: ServiceState) -> windows_service::Result<()> {
+        status_handle.set_service_status(ServiceStatus {
+            service_type: ServiceType::OWN_PROCESS,
+            current_state: state,
+            controls_accepted: if state == ServiceState::Running {
+                ServiceControlAccept::STOP | ServiceControlAccept::SHUTDOWN
+            } else {
+                ServiceControlAccept::empty()
+            },
+            exit_code: ServiceExitCode::Win32(0),
+            checkpoint: 0,
+            wait_hint: Duration::default(),
+            process_id: None,
+        })
+    }
+}
+
+/// Windows services only exist on Windows, other platforms schedule `chronomover run` instead
+#[cfg(not(windows))]
+mod platform {
+    use super::RulesRunner;
+    use crate::cli::ServiceRunArgs;
+    use color_eyre::eyre::{bail, Result};
+    use std::ffi::OsString;
+    use std::path::Path;
+
+    const UNSUPPORTED: &str = "Windows services are only available on Windows, schedule `chronomover run` with cron or a systemd timer instead";
+
+    pub fn create_service(_executable: &Path, _arguments: Vec<OsString>) -> Result<()> {
+        bail!(UNSUPPORTED)
+    }
+
+    pub fn delete_service() -> Result<()> {
+        bail!(UNSUPPORTED)
+    }
+
+    pub fn run_service(_service_args: ServiceRunArgs, _run_rules: RulesRunner) -> Result<()> {
+        bail!(UNSUPPORTED)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::{Cli, Command, ServiceCommand};
+    use clap::Parser;
+
+    #[test]
+    fn test_get_service_arguments() {
+        let cli = Cli::parse_from(["chronomover", "service", "install", "--interval", "12h", "-q", "downloads", "desktop"]);
+        let Some(Command::Service(ServiceCommand::Install(service_args))) = cli.command else {
+            panic!("Expected the service install command");
+        };
+
+        let arguments = get_service_arguments(&service_args, Path::new("/config/config.toml"));
+
+        assert_eq!(arguments, ["service", "run", "--interval", "12h", "--config", "/config/config.toml", "--quiet", "downloads", "desktop"]);
+    }
+}
@@ -0,0 +1,208 @@
+use crate::cli::{RunArgs, SystemdUnitArgs};
+use crate::config::resolve_scheduled_config;
+use color_eyre::eyre::{Context, Result};
+use std::env;
+use std::ffi::OsStr;
+use std::io;
+use std::path::Path;
+use std::process;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+use tracing::debug;
+
+/// Name of the generated service and timer units
+const UNIT_NAME: &str = "chronomover";
+
+/// Watchdog timeout of the generated service, pinged at half of it while the rules run
+const WATCHDOG_SEC: &str = "1min";
+
+/// Send a state change (e.g., `READY=1`) to systemd through `$NOTIFY_SOCKET`. Outside of systemd there is no socket
+/// and nothing is sent, and failures are only logged, as notifications never decide the outcome of a run.
+pub fn notify_systemd(state: &str) {
+    let Some(socket_path) = env::var_os("NOTIFY_SOCKET") else {
+        return;
+    };
+    if let Err(e) = send_notification(&socket_path, state) {
+        debug!(error = %e, "Failed to notify systemd of '{}': {}", state, e);
+    }
+}
+
+#[cfg(unix)]
+fn send_notification(socket_path: &OsStr, state: &str) -> io::Result<()> {
+    use std::os::unix::ffi::OsStrExt;
+    use std::os::unix::net::UnixDatagram;
+
+    let socket = UnixDatagram::unbound()?;
+    // A leading '@' names a socket of the abstract namespace, which only exists on Linux
+    #[cfg(target_os = "linux")]
+    if let Some(name) = socket_path.as_bytes().strip_prefix(b"@") {
+        use std::os::linux::net::SocketAddrExt;
+        let address = std::os::unix::net::SocketAddr::from_abstract_name(name)?;
+        socket.send_to_addr(state.as_bytes(), &address)?;
+        return Ok(());
+    }
+    socket.send_to(state.as_bytes(), socket_path)?;
+    Ok(())
+}
+
+/// systemd only runs on Linux
+#[cfg(not(unix))]
+fn send_notification(_socket_path: &OsStr, _state: &str) -> io::Result<()> {
+    Ok(())
+}
+
+/// Pings the systemd watchdog at half of the `WatchdogSec` timeout until stopped
+pub struct Watchdog {
+    stop_sender: mpsc::Sender<()>,
+    thread: JoinHandle<()>,
+}
+
+impl Watchdog {
+    /// Start pinging, when systemd expects this process to
+    pub fn start() -> Option<Self> {
+        let interval = get_watchdog_interval(
+            env::var("WATCHDOG_USEC").ok().as_deref(),
+            env::var("WATCHDOG_PID").ok().as_deref(),
+            process::id(),
+        )?;
+        let (stop_sender, stop_receiver) = mpsc::channel();
+        let thread = thread::spawn(move || {
+            while let Err(RecvTimeoutError::Timeout) = stop_receiver.recv_timeout(interval) {
+                notify_systemd("WATCHDOG=1");
+            }
+        });
+        Some(Self { stop_sender, thread })
+    }
+
+    pub fn stop(self) {
+        // Safe to ignore: sending only fails when the thread already stopped
+        let _ = self.stop_sender.send(());
+        let _ = self.thread.join();
+    }
+}
+
+/// Ping interval of the watchdog, half of its `WATCHDOG_USEC` timeout, if enabled for this process
+fn get_watchdog_interval(watchdog_usec: Option<&str>, watchdog_pid: Option<&str>, own_pid: u32) -> Option<Duration> {
+    // WATCHDOG_PID, when set, names the process expected to ping
+    if watchdog_pid.is_some_and(|pid| pid.parse::<u32>().ok() != Some(own_pid)) {
+        return None;
+    }
+    let timeout = Duration::from_micros(watchdog_usec?.parse::<u64>().ok()?);
+    (!timeout.is_zero()).then_some(timeout / 2)
+}
+
+/// Print a service and timer pair running the selected rules on `--on-calendar`, ready to be saved as units
+pub fn print_systemd_units(unit_args: &SystemdUnitArgs) -> Result<()> {
+    let config_path = resolve_scheduled_config(&unit_args.run_args)?;
+    let executable = env::current_exe().context("Failed to locate the chronomover executable")?;
+    print!("{}", render_systemd_units(unit_args, &executable, &config_path));
+    Ok(())
+}
+
+fn render_systemd_units(unit_args: &SystemdUnitArgs, executable: &Path, config_path: &Path) -> String {
+    let (unit_dir, systemctl) = if unit_args.system {
+        ("/etc/systemd/system", "systemctl")
+    } else {
+        ("~/.config/systemd/user", "systemctl --user")
+    };
+    let exec_start = get_run_arguments(&unit_args.run_args, config_path)
+        .iter()
+        .map(|argument| quote_exec_argument(argument))
+        .fold(quote_exec_argument(&executable.to_string_lossy()), |command, argument| command + " " + &argument);
+
+    format!(
+        "\
+# Save as {unit_dir}/{UNIT_NAME}.service
+[Unit]
+Description=Archive files into date-based folders with ChronoMover
+
+[Service]
+Type=notify
+ExecStart={exec_start}
+WatchdogSec={WATCHDOG_SEC}
+
+# Save as {unit_dir}/{UNIT_NAME}.timer, then enable it with `{systemctl} daemon-reload && {systemctl} enable --now {UNIT_NAME}.timer`
+[Unit]
+Description=Run ChronoMover on a schedule
+
+[Timer]
+OnCalendar={on_calendar}
+Persistent=true
+
+[Install]
+WantedBy=timers.target
+",
+        on_calendar = unit_args.on_calendar,
+    )
+}
+
+/// Arguments of the `chronomover run --systemd` command started by the service
+fn get_run_arguments(run_args: &RunArgs, config_path: &Path) -> Vec<String> {
+    let mut arguments = vec!["run".to_string(), "--systemd".to_string(), "--config".to_string(), config_path.to_string_lossy().into_owned()];
+    if run_args.all {
+        arguments.push("--all".to_string());
+    }
+    if run_args.dry_run {
+        arguments.push("--dry-run".to_string());
+    }
+    if run_args.quiet {
+        arguments.push("--quiet".to_string());
+    }
+    arguments.extend((0..run_args.verbose).map(|_| "--verbose".to_string()));
+    arguments.extend(run_args.rules.iter().cloned());
+    arguments
+}
+
+/// Quote an argument of `ExecStart=` when needed, escaping the `%` specifiers and `$` variables systemd expands
+fn quote_exec_argument(argument: &str) -> String {
+    let escaped = argument.replace('%', "%%").replace('$', "$$");
+    if !escaped.is_empty() && !escaped.contains(|c: char| c.is_whitespace() || matches!(c, '"' | '\'' | '\\' | ';')) {
+        return escaped;
+    }
+    format!("\"{}\"", escaped.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::{Cli, Command};
+    use clap::Parser;
+
+    fn parse_unit_args(arguments: &[&str]) -> SystemdUnitArgs {
+        let cli = Cli::parse_from(["chronomover", "systemd-unit"].iter().chain(arguments));
+        let Some(Command::SystemdUnit(unit_args)) = cli.command else {
+            panic!("Expected the systemd-unit command");
+        };
+        unit_args
+    }
+
+    #[test]
+    fn test_get_watchdog_interval() {
+        assert_eq!(get_watchdog_interval(Some("60000000"), None, 42), Some(Duration::from_secs(30)));
+        assert_eq!(get_watchdog_interval(Some("60000000"), Some("42"), 42), Some(Duration::from_secs(30)));
+        assert_eq!(get_watchdog_interval(Some("60000000"), Some("7"), 42), None);
+        assert_eq!(get_watchdog_interval(Some("0"), None, 42), None);
+        assert_eq!(get_watchdog_interval(None, None, 42), None);
+    }
+
+    #[test]
+    fn test_quote_exec_argument() {
+        assert_eq!(quote_exec_argument("/usr/bin/chronomover"), "/usr/bin/chronomover");
+        assert_eq!(quote_exec_argument("/home/me/My Config/config.toml"), "\"/home/me/My Config/config.toml\"");
+        assert_eq!(quote_exec_argument("100%"), "100%%");
+        assert_eq!(quote_exec_argument("say \"hi\""), "\"say \\\"hi\\\"\"");
+    }
+
+    #[test]
+    fn test_render_systemd_units() {
+        let unit_args = parse_unit_args(&["--on-calendar", "weekly", "--quiet", "downloads"]);
+
+        let units = render_systemd_units(&unit_args, Path::new("/usr/bin/chronomover"), Path::new("/home/me/.config/chronomover/config.toml"));
+
+        assert!(units.contains("# Save as ~/.config/systemd/user/chronomover.service\n"));
+        assert!(units.contains("\nExecStart=/usr/bin/chronomover run --systemd --config /home/me/.config/chronomover/config.toml --quiet downloads\n"));
+        assert!(units.contains("\nOnCalendar=weekly\n"));
+        assert!(units.contains("`systemctl --user daemon-reload && systemctl --user enable --now chronomover.timer`"));
+    }
+}
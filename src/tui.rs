@@ -0,0 +1,298 @@
+use chronomover_core::file::{FileToMove, Plan};
+use chronomover_core::report::{format_size, UNGROUPED_PERIOD};
+use chronomover_core::scan_state::ScanState;
+use color_eyre::eyre::{Context, Result};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Modifier, Style};
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+use std::collections::BTreeMap;
+use tracing::{debug, info};
+
+/// Number of rows skipped by Page Up and Page Down
+const PAGE_SIZE: usize = 10;
+
+const KEY_HINTS: &str = "↑/↓ move  Space include/exclude  Enter expand/collapse  a all  n none  x execute  q quit";
+
+/// Planned files of a destination period folder
+#[derive(Debug)]
+struct Bucket {
+    name: String,
+    /// Indexes of the files of the bucket in the plan
+    files: Vec<usize>,
+    expanded: bool,
+}
+
+/// A line of the review list
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Row {
+    Bucket(usize),
+    /// A file of an expanded bucket, by bucket index and plan index
+    File(usize, usize),
+}
+
+/// Files of the plan grouped by period folder, with the files the user kept selected
+#[derive(Debug)]
+struct ReviewState<'a> {
+    files: &'a [FileToMove],
+    included: Vec<bool>,
+    buckets: Vec<Bucket>,
+    cursor: usize,
+}
+
+impl<'a> ReviewState<'a> {
+    fn new(files: &'a [FileToMove]) -> Self {
+        let mut files_by_bucket: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+        for (index, file) in files.iter().enumerate() {
+            files_by_bucket.entry(file.period.as_deref().unwrap_or(UNGROUPED_PERIOD)).or_default().push(index);
+        }
+        let buckets = files_by_bucket.into_iter()
+            .map(|(name, files)| Bucket { name: name.to_string(), files, expanded: false })
+            .collect();
+        Self { files, included: vec![true; files.len()], buckets, cursor: 0 }
+    }
+
+    fn rows(&self) -> Vec<Row> {
+        self.buckets.iter().enumerate()
+            .flat_map(|(bucket_index, bucket)| {
+                let file_rows = bucket.files.iter()
+                    .filter(|_| bucket.expanded)
+                    .map(move |&file_index| Row::File(bucket_index, file_index));
+                std::iter::once(Row::Bucket(bucket_index)).chain(file_rows)
+            })
+            .collect()
+    }
+
+    fn move_cursor(&mut self, delta: isize) {
+        let last_row = self.rows().len().saturating_sub(1);
+        self.cursor = self.cursor.saturating_add_signed(delta).min(last_row);
+    }
+
+    /// Include or exclude the file under the cursor, or every file of the bucket under the cursor (excluding them
+    /// unless some are already excluded)
+    fn toggle_included(&mut self) {
+        match self.rows()[self.cursor] {
+            Row::Bucket(bucket_index) => {
+                let files = &self.buckets[bucket_index].files;
+                let include = !files.iter().all(|&index| self.included[index]);
+                for &index in files {
+                    self.included[index] = include;
+                }
+            }
+            Row::File(_, file_index) => self.included[file_index] = !self.included[file_index],
+        }
+    }
+
+    /// Expand or collapse the bucket under the cursor, or the bucket of the file under the cursor
+    fn toggle_expanded(&mut self) {
+        let (Row::Bucket(bucket_index) | Row::File(bucket_index, _)) = self.rows()[self.cursor];
+        let bucket = &mut self.buckets[bucket_index];
+        bucket.expanded = !bucket.expanded;
+        // Safe to unwrap: the bucket row is always shown
+        self.cursor = self.rows().iter().position(|row| *row == Row::Bucket(bucket_index)).unwrap();
+    }
+
+    fn set_all_included(&mut self, included: bool) {
+        self.included.fill(included);
+    }
+
+    /// Number and total size of the files in `indexes` that are included
+    fn count_included(&self, indexes: impl IntoIterator<Item = usize>) -> (usize, u64) {
+        indexes.into_iter()
+            .filter(|&index| self.included[index])
+            .fold((0, 0), |(count, bytes), index| (count + 1, bytes + self.files[index].size))
+    }
+
+    fn render_row(&self, row: Row) -> String {
+        match row {
+            Row::Bucket(bucket_index) => {
+                let bucket = &self.buckets[bucket_index];
+                let (count, bytes) = self.count_included(bucket.files.iter().copied());
+                let checkbox = match count {
+                    0 => "[ ]",
+                    count if count == bucket.files.len() => "[x]",
+                    _ => "[-]",
+                };
+                let arrow = if bucket.expanded { "▾" } else { "▸" };
+                format!("{} {} {}  ({}/{} file(s), {})", checkbox, arrow, bucket.name, count, bucket.files.len(), format_size(bytes))
+            }
+            Row::File(_, file_index) => {
+                let file = &self.files[file_index];
+                let checkbox = if self.included[file_index] { "[x]" } else { "[ ]" };
+                format!("    {} {}  ({})", checkbox, file.source.display(), format_size(file.size))
+            }
+        }
+    }
+}
+
+/// Let the user review the plan in an interactive terminal interface before it is executed. Excluded files are left
+/// in place and counted as skipped, and quitting without executing leaves every file in place.
+pub fn review_plan(plan: &mut Plan, mut scan_state: Option<&mut ScanState>) -> Result<()> {
+    if plan.files_to_move.is_empty() {
+        return Ok(());
+    }
+
+    let mut state = ReviewState::new(&plan.files_to_move);
+    let mut terminal = ratatui::try_init().context("Failed to set up the terminal for the interactive review")?;
+    let result = run_review(&mut terminal, &mut state);
+    ratatui::restore();
+    let execute = result.context("Failed to run the interactive review")?;
+    let selection = if execute { state.included } else { vec![false; plan.files_to_move.len()] };
+
+    let (to_move, excluded): (Vec<_>, Vec<_>) = std::mem::take(&mut plan.files_to_move).into_iter()
+        .zip(selection)
+        .partition(|(_, included)| *included);
+    for (file, _) in &excluded {
+        debug!(path = %file.source.display(), status = "skipped", "Skipping {}: excluded during the review", file.source.display());
+        // Excluded files are still due, so incremental runs must look at their folder again
+        if let Some(scan_state) = scan_state.as_deref_mut() {
+            scan_state.mark_unsettled(&file.source);
+        }
+    }
+    plan.files_to_move = to_move.into_iter().map(|(file, _)| file).collect();
+    plan.planned = plan.files_to_move.len();
+    plan.skipped += excluded.len();
+
+    if execute {
+        info!(count = plan.planned, "Review done, {} file(s) to move, {} excluded", plan.planned, excluded.len());
+    } else {
+        info!("Review cancelled, no file will be moved");
+    }
+    Ok(())
+}
+
+/// Handle key presses until the user executes the plan (returning `true`) or quits (returning `false`)
+fn run_review(terminal: &mut DefaultTerminal, state: &mut ReviewState) -> Result<bool> {
+    let mut list_state = ListState::default();
+    loop {
+        list_state.select(Some(state.cursor));
+        terminal.draw(|frame| draw(frame, state, &mut list_state))?;
+
+        let Event::Key(key) = event::read()? else { continue };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        match key.code {
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return Ok(false),
+            KeyCode::Up | KeyCode::Char('k') => state.move_cursor(-1),
+            KeyCode::Down | KeyCode::Char('j') => state.move_cursor(1),
+            KeyCode::PageUp => state.move_cursor(-(PAGE_SIZE as isize)),
+            KeyCode::PageDown => state.move_cursor(PAGE_SIZE as isize),
+            KeyCode::Home => state.cursor = 0,
+            KeyCode::End => state.move_cursor(isize::MAX),
+            KeyCode::Char(' ') => state.toggle_included(),
+            KeyCode::Enter | KeyCode::Left | KeyCode::Right => state.toggle_expanded(),
+            KeyCode::Char('a') => state.set_all_included(true),
+            KeyCode::Char('n') => state.set_all_included(false),
+            KeyCode::Char('x') => return Ok(true),
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(false),
+            _ => {}
+        }
+    }
+}
+
+fn draw(frame: &mut Frame, state: &ReviewState, list_state: &mut ListState) {
+    let [header_area, list_area, footer_area] = Layout::vertical([
+        Constraint::Length(1),
+        Constraint::Min(1),
+        Constraint::Length(1),
+    ]).areas(frame.area());
+
+    let (count, bytes) = state.count_included(0..state.files.len());
+    let header = format!("Review the plan: {}/{} file(s) selected, {}", count, state.files.len(), format_size(bytes));
+    frame.render_widget(Paragraph::new(header), header_area);
+
+    let items = state.rows().into_iter().map(|row| ListItem::new(state.render_row(row))).collect::<Vec<_>>();
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::TOP | Borders::BOTTOM))
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    frame.render_stateful_widget(list, list_area, list_state);
+
+    frame.render_widget(Paragraph::new(KEY_HINTS), footer_area);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{DateTime, Utc};
+    use std::path::PathBuf;
+
+    fn create_file(name: &str, period: Option<&str>) -> FileToMove {
+        FileToMove {
+            source: PathBuf::from(format!("/notes/{name}")),
+            destination: PathBuf::from(format!("/archive/{}/{name}", period.unwrap_or_default())),
+            file_date: "2025-06-15T08:30:00Z".parse::<DateTime<Utc>>().unwrap(),
+            size: 10,
+            period: period.map(str::to_string),
+        }
+    }
+
+    fn create_files() -> Vec<FileToMove> {
+        vec![
+            create_file("a.md", Some("2025-06")),
+            create_file("b.md", Some("2025-05")),
+            create_file("c.md", Some("2025-06")),
+        ]
+    }
+
+    #[test]
+    fn test_review_state_groups_files_by_period() {
+        let files = create_files();
+        let state = ReviewState::new(&files);
+
+        assert_eq!(state.buckets.iter().map(|bucket| bucket.name.as_str()).collect::<Vec<_>>(), ["2025-05", "2025-06"]);
+        assert_eq!(state.buckets[1].files, [0, 2]);
+        assert_eq!(state.rows(), [Row::Bucket(0), Row::Bucket(1)]);
+        assert_eq!(state.included, [true, true, true]);
+    }
+
+    #[test]
+    fn test_review_state_toggle_expanded_shows_files() {
+        let files = create_files();
+        let mut state = ReviewState::new(&files);
+
+        state.move_cursor(1);
+        state.toggle_expanded();
+        assert_eq!(state.rows(), [Row::Bucket(0), Row::Bucket(1), Row::File(1, 0), Row::File(1, 2)]);
+
+        // Collapsing from a file row moves the cursor back to its bucket
+        state.move_cursor(2);
+        state.toggle_expanded();
+        assert_eq!(state.rows(), [Row::Bucket(0), Row::Bucket(1)]);
+        assert_eq!(state.cursor, 1);
+    }
+
+    #[test]
+    fn test_review_state_toggle_included() {
+        let files = create_files();
+        let mut state = ReviewState::new(&files);
+        state.move_cursor(1);
+        state.toggle_expanded();
+
+        // Excluding a file leaves its bucket partially included
+        state.move_cursor(1);
+        state.toggle_included();
+        assert_eq!(state.included, [false, true, true]);
+        assert!(state.render_row(Row::Bucket(1)).starts_with("[-] ▾ 2025-06  (1/2 file(s)"));
+
+        // Toggling a partially included bucket includes all of its files, then toggling it again excludes them
+        state.move_cursor(-1);
+        state.toggle_included();
+        assert_eq!(state.included, [true, true, true]);
+        state.toggle_included();
+        assert_eq!(state.included, [false, true, false]);
+        assert_eq!(state.count_included(0..files.len()), (1, 10));
+    }
+
+    #[test]
+    fn test_review_state_move_cursor_is_clamped() {
+        let files = create_files();
+        let mut state = ReviewState::new(&files);
+
+        state.move_cursor(-5);
+        assert_eq!(state.cursor, 0);
+        state.move_cursor(isize::MAX);
+        assert_eq!(state.cursor, 1);
+    }
+}
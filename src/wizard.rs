@@ -0,0 +1,279 @@
+use crate::cli::InitArgs;
+use crate::config::{get_default_config_path, render_starter_config, write_new_config};
+use chronomover_core::file::get_sample_files_to_move;
+use chronomover_core::model::{enrich_arguments, get_reference_time, parse_older_than, Args, GroupBy};
+use chronomover_core::storage::LocalStorage;
+use clap::{Args as _, FromArgMatches, ValueEnum};
+use color_eyre::eyre::{bail, Context, Result};
+use std::ffi::OsString;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::PathBuf;
+use std::process;
+
+/// Number of files of the source folder evaluated for the example moves
+const SAMPLE_CANDIDATE_COUNT: usize = 200;
+/// Number of example moves shown before writing the config
+const SAMPLE_MOVE_COUNT: usize = 5;
+
+/// How often the scheduler entry created by the wizard runs the rules
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+enum Schedule {
+    Daily,
+    Weekly,
+    Monthly,
+    Never,
+}
+
+/// Asks questions on `output` and reads the answers from `input`
+struct Prompter<R, W> {
+    input: R,
+    output: W,
+}
+
+impl<R: BufRead, W: Write> Prompter<R, W> {
+    /// Ask until `parse` accepts the answer, an empty answer standing for `default` when there is one
+    fn ask<T>(&mut self, question: &str, default: Option<&str>, parse: impl Fn(&str) -> Result<T, String>) -> Result<T> {
+        loop {
+            match default {
+                Some(default) if !default.is_empty() => write!(self.output, "{question} [{default}]: ")?,
+                _ => write!(self.output, "{question}: ")?,
+            }
+            self.output.flush()?;
+
+            let mut answer = String::new();
+            if self.input.read_line(&mut answer).context("Failed to read the answer")? == 0 {
+                bail!("Setup cancelled, no answer was given");
+            }
+            let answer = match (answer.trim(), default) {
+                ("", Some(default)) => default,
+                (answer, _) => answer,
+            };
+            match parse(answer) {
+                Ok(value) => return Ok(value),
+                Err(e) => writeln!(self.output, "  {e}")?,
+            }
+        }
+    }
+
+    fn confirm(&mut self, question: &str, default: bool) -> Result<bool> {
+        let hint = if default { "Y/n" } else { "y/N" };
+        self.ask(&format!("{question} ({hint})"), Some(""), |answer| match answer.to_lowercase().as_str() {
+            "" => Ok(default),
+            "y" | "yes" => Ok(true),
+            "n" | "no" => Ok(false),
+            _ => Err("Please answer yes or no".to_string()),
+        })
+    }
+}
+
+/// Interactively ask for a rule, show a few of the moves it would make in the real source folder, then write it to a
+/// new config file and optionally schedule it
+pub fn run_setup_wizard(init_args: &InitArgs) -> Result<()> {
+    let config_path = match &init_args.config {
+        Some(config_path) => config_path.clone(),
+        None => get_default_config_path()?,
+    };
+    if config_path.exists() {
+        bail!("Config file already exists: {} (edit it, or pass --config to create another one)", config_path.display());
+    }
+
+    let mut prompter = Prompter { input: io::stdin().lock(), output: io::stdout() };
+    writeln!(prompter.output, "This wizard creates a config file with one rule. Press Enter to accept the [default] answers.\n")?;
+    let (rule_name, command_line) = ask_rule(&mut prompter)?;
+
+    let command = Args::augment_args(clap::Command::new("chronomover"));
+    let matches = command.try_get_matches_from(&command_line).context("Invalid answers")?;
+    let args = enrich_arguments(&Args::from_arg_matches(&matches)?);
+    print_example_moves(&mut prompter.output, &args)?;
+
+    if !prompter.confirm(&format!("\nWrite this rule to {}?", config_path.display()), true)? {
+        writeln!(prompter.output, "Nothing was written")?;
+        return Ok(());
+    }
+    if let Some(config_dir) = config_path.parent() {
+        fs::create_dir_all(config_dir)
+            .with_context(|| format!("Failed to create directory: {}", config_dir.display()))?;
+    }
+    write_new_config(&config_path, &render_starter_config(&rule_name, &matches))?;
+    writeln!(prompter.output, "Config written to {}, run it with `chronomover run --all`", config_path.display())?;
+
+    let schedule = prompter.ask("Run it automatically (daily, weekly, monthly or never)", Some("never"), |answer| {
+        Schedule::from_str(answer, true).map_err(|_| "Please answer daily, weekly, monthly or never".to_string())
+    })?;
+    if schedule != Schedule::Never {
+        let executable = std::env::current_exe().context("Failed to find the path of the chronomover executable")?;
+        let run_command = format!("\"{}\" run --all --config \"{}\"", executable.display(), config_path.display());
+        match install_scheduler_entry(schedule, &run_command) {
+            Ok(description) => writeln!(prompter.output, "Scheduled: {description}")?,
+            Err(e) => writeln!(prompter.output, "Failed to schedule the rule ({e:#}), schedule this command manually: {run_command}")?,
+        }
+    }
+    Ok(())
+}
+
+/// Ask for the rule name and options, returning them as a command line
+fn ask_rule(prompter: &mut Prompter<impl BufRead, impl Write>) -> Result<(String, Vec<OsString>)> {
+    let source = prompter.ask("Folder to archive files from", None, |answer| match PathBuf::from(answer) {
+        _ if answer.is_empty() => Err("Please enter a folder".to_string()),
+        path if !path.is_dir() => Err(format!("Folder does not exist: {}", path.display())),
+        path => Ok(path),
+    })?;
+    let default_destination = source.join("Archive").display().to_string();
+    let destination = prompter.ask("Folder to move the files into", Some(&default_destination), |answer| Ok(PathBuf::from(answer)))?;
+    let group_by = prompter.ask(
+        "Group files into period folders (week, biweekly, month, trimester, quadrimester, semester, year or none)",
+        Some("month"),
+        |answer| match answer {
+            "none" => Ok(None),
+            _ => GroupBy::from_str(answer, true).map(Some).map_err(|_| format!("Unknown grouping: {answer}")),
+        },
+    )?;
+    let previous_period_only = group_by.is_some()
+        && prompter.confirm("Leave the files of the current period in place", true)?;
+    let older_than = prompter.ask("Only move files older than (e.g., 30d or 2025-01-15, empty for any age)", Some(""), |answer| {
+        if answer.is_empty() {
+            return Ok(None);
+        }
+        parse_older_than(answer).map(|_| Some(answer.to_string())).map_err(|e| format!("{e:#}"))
+    })?;
+    let rule_name = prompter.ask("Rule name", Some("default"), |answer| Ok(answer.to_string()))?;
+
+    let mut command_line = vec!["chronomover".into(), "--source".into(), source.into_os_string(), "--destination".into(), destination.into_os_string()];
+    if let Some(group_by) = group_by {
+        // Safe to unwrap: `GroupBy` has no skipped variants
+        command_line.extend(["--group-by".into(), group_by.to_possible_value().unwrap().get_name().into()]);
+    }
+    if previous_period_only {
+        command_line.push("--previous-period-only".into());
+    }
+    if let Some(older_than) = older_than {
+        command_line.extend(["--older-than".into(), older_than.into()]);
+    }
+    Ok((rule_name, command_line))
+}
+
+fn print_example_moves(output: &mut impl Write, args: &Args) -> Result<()> {
+    let sample = get_sample_files_to_move(args, &LocalStorage, get_reference_time(), SAMPLE_CANDIDATE_COUNT);
+    if sample.files_to_move.is_empty() {
+        writeln!(output, "\nNone of the first {} file(s) of {} would be moved by this rule", SAMPLE_CANDIDATE_COUNT, args.sources[0].display())?;
+        return Ok(());
+    }
+
+    writeln!(output, "\nExample moves:")?;
+    for file in sample.files_to_move.iter().take(SAMPLE_MOVE_COUNT) {
+        writeln!(output, "  {}\n    ↳ {}", file.source.display(), file.destination.display())?;
+    }
+    Ok(())
+}
+
+/// Add a scheduler entry running `run_command`, returning a description of it
+fn install_scheduler_entry(schedule: Schedule, run_command: &str) -> Result<String> {
+    if cfg!(windows) {
+        let mut schtasks = process::Command::new("schtasks");
+        schtasks.args(["/Create", "/TN", "ChronoMover", "/TR", run_command, "/ST", "09:00"]).args(get_schtasks_schedule(schedule));
+        let status = schtasks.status().context("Failed to run schtasks")?;
+        if !status.success() {
+            bail!("schtasks exited with {}", status);
+        }
+        Ok("Task Scheduler task 'ChronoMover'".to_string())
+    } else {
+        let cron_line = get_cron_line(schedule, run_command);
+        install_cron_line(&cron_line)?;
+        Ok(format!("crontab entry '{cron_line}'"))
+    }
+}
+
+/// Crontab line running `run_command`, with its `%` escaped since cron turns them into newlines
+fn get_cron_line(schedule: Schedule, run_command: &str) -> String {
+    let time = match schedule {
+        Schedule::Daily => "0 9 * * *",
+        Schedule::Weekly => "0 9 * * 1",
+        Schedule::Monthly | Schedule::Never => "0 9 1 * *",
+    };
+    format!("{time} {}", run_command.replace('%', "\\%"))
+}
+
+fn get_schtasks_schedule(schedule: Schedule) -> [&'static str; 4] {
+    match schedule {
+        Schedule::Daily => ["/SC", "DAILY", "/MO", "1"],
+        Schedule::Weekly => ["/SC", "WEEKLY", "/D", "MON"],
+        Schedule::Monthly | Schedule::Never => ["/SC", "MONTHLY", "/D", "1"],
+    }
+}
+
+/// Append a line to the crontab of the current user
+fn install_cron_line(cron_line: &str) -> Result<()> {
+    let current = process::Command::new("crontab").arg("-l").output().context("Failed to run crontab")?;
+    let stderr = String::from_utf8_lossy(&current.stderr);
+    // `crontab -l` fails when the user has no crontab yet, any other failure must not wipe the existing one
+    let mut crontab = if current.status.success() {
+        String::from_utf8_lossy(&current.stdout).into_owned()
+    } else if stderr.contains("no crontab for") {
+        String::new()
+    } else {
+        bail!("crontab -l exited with {}: {}", current.status, stderr.trim());
+    };
+    if !crontab.is_empty() && !crontab.ends_with('\n') {
+        crontab.push('\n');
+    }
+    crontab.push_str(cron_line);
+    crontab.push('\n');
+
+    let mut child = process::Command::new("crontab").arg("-").stdin(process::Stdio::piped()).spawn()
+        .context("Failed to run crontab")?;
+    // Safe to unwrap: stdin was piped
+    child.stdin.take().unwrap().write_all(crontab.as_bytes()).context("Failed to write the crontab")?;
+    let status = child.wait().context("Failed to run crontab")?;
+    if !status.success() {
+        bail!("crontab exited with {}", status);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn create_prompter(answers: &str) -> Prompter<Cursor<String>, Vec<u8>> {
+        Prompter { input: Cursor::new(answers.to_string()), output: Vec::new() }
+    }
+
+    #[test]
+    fn test_ask_uses_default_for_empty_answer() {
+        let mut prompter = create_prompter("\n");
+        let answer = prompter.ask("Rule name", Some("default"), |answer| Ok(answer.to_string())).unwrap();
+        assert_eq!(answer, "default");
+        assert_eq!(String::from_utf8(prompter.output).unwrap(), "Rule name [default]: ");
+    }
+
+    #[test]
+    fn test_ask_repeats_until_answer_is_valid() {
+        let mut prompter = create_prompter("fortnight\nweek\n");
+        let answer = prompter.ask("Grouping", None, |answer| GroupBy::from_str(answer, true)).unwrap();
+        assert_eq!(answer, GroupBy::Week);
+        assert_eq!(String::from_utf8(prompter.output).unwrap().matches("Grouping: ").count(), 2);
+    }
+
+    #[test]
+    fn test_ask_fails_without_answer() {
+        let mut prompter = create_prompter("");
+        assert!(prompter.ask("Rule name", Some("default"), |answer| Ok(answer.to_string())).is_err());
+    }
+
+    #[test]
+    fn test_confirm() {
+        assert!(create_prompter("\n").confirm("Continue", true).unwrap());
+        assert!(!create_prompter("\n").confirm("Continue", false).unwrap());
+        assert!(create_prompter("maybe\nYes\n").confirm("Continue", false).unwrap());
+        assert!(!create_prompter("n\n").confirm("Continue", true).unwrap());
+    }
+
+    #[test]
+    fn test_get_cron_line() {
+        assert_eq!(get_cron_line(Schedule::Weekly, "/bin/chronomover run --all"), "0 9 * * 1 /bin/chronomover run --all");
+        assert_eq!(get_cron_line(Schedule::Monthly, "chronomover"), "0 9 1 * * chronomover");
+        assert_eq!(get_cron_line(Schedule::Daily, "chronomover run '/home/me/100%'"), "0 9 * * * chronomover run '/home/me/100\\%'");
+    }
+}